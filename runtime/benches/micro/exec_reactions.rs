@@ -76,7 +76,7 @@ pub fn r(u: u32) -> GlobalReactionId {
 struct TestCase(&'static str, HashMap<LevelIx, HashSet<GlobalReactionId>>);
 
 impl TestCase {
-    fn get_exec(&self) -> ExecutableReactions {
+    fn get_exec(&self) -> ExecutableReactions<'_> {
         let mut result = ExecutableReactions::new();
         for (level, hset) in &self.1 {
             for r in hset {
@@ -89,25 +89,19 @@ impl TestCase {
 
 fn test_cases() -> Vec<TestCase> {
     vec![
-        TestCase(
-            "single",
-            HashMap::from([(LevelIx::from(0), (0..10).into_iter().map(r).collect())]),
-        ),
+        TestCase("single", HashMap::from([(LevelIx::from(0), (0..10).map(r).collect())])),
         TestCase(
             "sparse",
             HashMap::from([
-                (LevelIx::from(0), (0..10).into_iter().map(r).collect()),
-                (LevelIx::from(10), (0..10).into_iter().map(r).collect()),
+                (LevelIx::from(0), (0..10).map(r).collect()),
+                (LevelIx::from(10), (0..10).map(r).collect()),
             ]),
         ),
         TestCase(
             "wide-compact",
             // This is compact so the hashmap fun doesn't suffer from sparsity.
             // ExecutableReaction iteration should be 75 * sparse
-            (0..150)
-                .into_iter()
-                .map(|i| (LevelIx::from(i), (0..10).into_iter().map(r).collect()))
-                .collect(),
+            (0..150).map(|i| (LevelIx::from(i), (0..10).map(r).collect())).collect(),
         ),
     ]
 }
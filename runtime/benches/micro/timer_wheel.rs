@@ -0,0 +1,94 @@
+/*
+ * Copyright (c) 2021, TU Dresden.
+ *
+ * Redistribution and use in source and binary forms, with or without modification,
+ * are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL
+ * THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+ * SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+ * PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT,
+ * STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF
+ * THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+//! Compares the event queue with and without the timer wheel,
+//! for programs with many periodic timers. Each timer has
+//! a distinct period, so that few events share a tag.
+
+#![allow(unused, non_snake_case, non_camel_case_types)]
+#[macro_use]
+extern crate reactor_rt;
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use reactor_rt::internals::*;
+use reactor_rt::{Duration, EventTag};
+
+/// Simulate the firing of the given number of timers, until
+/// the given number of events has been processed.
+fn run_timers(num_timers: u64, num_events: usize, use_wheel: bool) {
+    let reactions = ExecutableReactions::new();
+    let period = |i: u64| Duration::from_micros(1000 + 37 * i);
+
+    let mut queue = EventQueue::default();
+    // timers that fire at each tag
+    let mut firings = HashMap::<EventTag, Vec<u64>>::new();
+    for i in 0..num_timers {
+        schedule(&mut queue, &mut firings, &reactions, use_wheel, i, period(i));
+    }
+    for _ in 0..num_events {
+        let evt = queue.take_earliest().unwrap();
+        let now = evt.tag.duration_since_start();
+        for i in firings.remove(&evt.tag).unwrap() {
+            schedule(&mut queue, &mut firings, &reactions, use_wheel, i, now + period(i));
+        }
+        black_box(evt);
+    }
+}
+
+fn schedule<'x>(
+    queue: &mut EventQueue<'x>,
+    firings: &mut HashMap<EventTag, Vec<u64>>,
+    reactions: &'x ExecutableReactions<'x>,
+    use_wheel: bool,
+    timer: u64,
+    offset: Duration,
+) {
+    let tag = EventTag::offset(offset, 0);
+    firings.entry(tag).or_default().push(timer);
+    let evt = Event::execute(tag, Cow::Borrowed(reactions));
+    if use_wheel {
+        queue.push_timer(evt)
+    } else {
+        queue.push(evt)
+    }
+}
+
+fn bench_timers(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Periodic timers");
+    for num_timers in [100, 1000, 5000] {
+        group.bench_with_input(BenchmarkId::new("sorted queue", num_timers), &num_timers, |b, n| {
+            b.iter(|| run_timers(*n, 2000, false))
+        });
+        group.bench_with_input(BenchmarkId::new("timer wheel", num_timers), &num_timers, |b, n| {
+            b.iter(|| run_timers(*n, 2000, true))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_timers);
+criterion_main!(benches);
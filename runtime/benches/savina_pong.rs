@@ -23,6 +23,7 @@
  */
 
 #![allow(unused, non_snake_case, non_camel_case_types)]
+#![allow(clippy::redundant_locals, clippy::match_single_binding, clippy::eq_op)]
 #[macro_use]
 extern crate reactor_rt;
 
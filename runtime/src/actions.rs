@@ -39,7 +39,68 @@ pub struct LogicalAction<T: Sync>(pub(crate) Action<Logical, T>);
 /// A physical action. Physical actions may only be used with
 /// the API of [AsyncCtx](crate::AsyncCtx).
 /// See [ReactionCtx::spawn_physical_thread](crate::ReactionCtx::spawn_physical_thread).
-pub struct PhysicalAction<T: Sync>(pub(crate) Action<Physical, T>);
+pub struct PhysicalAction<T: Sync> {
+    pub(crate) inner: Action<Physical, T>,
+    /// See [PhysicalActionRef::with_admission_priority].
+    pub(crate) priority: AdmissionPriority,
+    /// See [PhysicalActionRef::with_min_spacing].
+    spacing: Option<Spacing>,
+}
+
+/// Admission priority of a physical action, see [AdmissionPolicy].
+pub(crate) type AdmissionPriority = u32;
+
+/// What happens to an event of a physical action that is
+/// scheduled less than its min spacing after the previous one.
+/// See [PhysicalActionRef::with_min_spacing].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SpacingPolicy {
+    /// The new event is dropped, with its value.
+    Drop,
+    /// The new event is delayed to the previous tag plus
+    /// the min spacing.
+    Defer,
+    /// If the previous event has not been processed yet, the new
+    /// value replaces its value, and no new event is scheduled.
+    /// Otherwise the new event is deferred.
+    Replace,
+}
+
+/// What happens when an action is scheduled at a tag
+/// at which it is already scheduled, with another value.
+/// See [LogicalAction::with_collision_policy].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CollisionPolicy {
+    /// The new value replaces the previous one. This is the default.
+    Replace,
+    /// The previous value is kept, and the new one is dropped.
+    KeepFirst,
+    /// The new value is rejected with [ScheduleError::AlreadyScheduled](crate::ScheduleError::AlreadyScheduled).
+    /// Physical events sent by [AsyncCtx](crate::AsyncCtx) are
+    /// rejected like those that the scheduler does not admit.
+    Reject,
+}
+
+impl Default for CollisionPolicy {
+    fn default() -> Self {
+        CollisionPolicy::Replace
+    }
+}
+
+/// Min spacing of a physical action, and the tag of its latest event.
+struct Spacing {
+    min: Duration,
+    policy: SpacingPolicy,
+    latest: Option<EventTag>,
+}
+
+/// Where to schedule an event of a physical action, see [PhysicalAction::space].
+pub(crate) enum Spaced {
+    At(EventTag),
+    /// Replace the value of the pending event at this tag.
+    Replace(EventTag),
+    Drop,
+}
 
 pub(crate) struct Logical;
 pub(crate) struct Physical;
@@ -55,6 +116,9 @@ pub(crate) struct Action<Kind, T: Sync> {
     /// will be cleaned up after that tag. Otherwise the map will
     /// blow up the heap.
     map: VecMap<Reverse<EventTag>, Option<T>>,
+
+    /// Applied by [Self::schedule_value].
+    collision: CollisionPolicy,
 }
 
 impl<K, T: Sync> Action<K, T> {
@@ -75,11 +139,49 @@ impl<K, T: Sync> Action<K, T> {
         }
     }
 
+    /// Record a future value like [Self::schedule_future_value],
+    /// unless there is already one at that tag, in which case
+    /// the collision policy applies. Gives the value back if
+    /// it is rejected.
+    #[inline]
+    pub(crate) fn schedule_value(&mut self, time: EventTag, value: Option<T>) -> Result<(), Option<T>> {
+        match self.map.entry(Reverse(time)) {
+            Entry::Vacant(e) => e.insert(value),
+            Entry::Occupied(ref mut e) => match self.collision {
+                CollisionPolicy::Replace => e.replace(value),
+                CollisionPolicy::KeepFirst => trace!("Action already scheduled at {}, keeping its first value", time),
+                CollisionPolicy::Reject => return Err(value),
+            },
+        }
+        Ok(())
+    }
+
     #[inline]
     pub(crate) fn forget_value(&mut self, time: &EventTag) -> Option<T> {
         self.map.remove(&Reverse(*time)).flatten()
     }
 
+    /// Forget the values at the given tag and before it. Those
+    /// of events that were not processed, eg because they were
+    /// scheduled after the shutdown tag, are released that way.
+    #[inline]
+    pub(crate) fn forget_values_until(&mut self, time: &EventTag) {
+        // the earliest tag is the max key
+        while let Some(Reverse(earliest)) = self.map.max_key().copied() {
+            if earliest > *time {
+                break;
+            }
+            self.map.remove(&Reverse(earliest));
+        }
+    }
+
+    /// Whether the action is scheduled at a tag
+    /// in `(after, until]`.
+    #[inline]
+    pub(crate) fn is_pending_within(&self, after: EventTag, until: EventTag) -> bool {
+        self.map.iter().any(|(Reverse(tag), _)| after < *tag && *tag <= until)
+    }
+
     fn new_impl(id: TriggerId, min_delay: Option<Duration>, _is_logical: bool) -> Self {
         Action {
             min_delay: min_delay.unwrap_or(Duration::ZERO),
@@ -87,6 +189,7 @@ impl<K, T: Sync> Action<K, T> {
             id,
             _logical: PhantomData,
             map: VecMap::new(),
+            collision: CollisionPolicy::Replace,
         }
     }
 }
@@ -116,7 +219,7 @@ impl<T: Sync, K> ReactionTrigger<T> for Action<K, T> {
 #[cfg(not(feature = "no-unsafe"))]
 impl<T: Sync, K> triggers::ReactionTriggerWithRefAccess<T> for Action<K, T> {
     fn get_value_ref(&self, now: &EventTag, _start: &Instant) -> Option<&T> {
-        self.map.get(&Reverse(*now)).map(|a| a.as_ref()).flatten()
+        self.map.get(&Reverse(*now)).and_then(|a| a.as_ref())
     }
 }
 
@@ -151,17 +254,73 @@ impl<T: Sync> LogicalAction<T> {
     pub(crate) fn new(id: TriggerId, min_delay: Option<Duration>) -> Self {
         Self(Action::new_impl(id, min_delay, true))
     }
+
+    /// Set what happens when the action is scheduled several
+    /// times at the same tag, see [CollisionPolicy]. The default
+    /// is to replace the value. This is meant to be called at
+    /// assembly time.
+    pub fn with_collision_policy(mut self, policy: CollisionPolicy) -> Self {
+        self.0.collision = policy;
+        self
+    }
+}
+
+/// Saving pending values in checkpoints, see
+/// [ReactorBehavior::serialize_state](crate::ReactorBehavior::serialize_state).
+#[cfg(feature = "checkpoint")]
+impl<T: Sync + Clone> LogicalAction<T> {
+    /// The tags at which the action is scheduled, and
+    /// the values it carries then, in no particular order.
+    pub fn pending_values(&self) -> Vec<(EventTag, Option<T>)> {
+        self.0.map.iter().map(|(Reverse(tag), v)| (*tag, v.clone())).collect()
+    }
+
+    /// Restore values saved with [Self::pending_values]. The
+    /// events that trigger the action are restored separately.
+    pub fn restore_pending_values(&mut self, values: Vec<(EventTag, Option<T>)>) {
+        for (tag, value) in values {
+            self.0.schedule_future_value(tag, value);
+        }
+    }
 }
 
 impl<T: Sync> PhysicalAction<T> {
     fn new(id: TriggerId, min_delay: Option<Duration>) -> Self {
-        Self(Action::new_impl(id, min_delay, false))
+        Self {
+            inner: Action::new_impl(id, min_delay, false),
+            priority: 0,
+            spacing: None,
+        }
+    }
+
+    /// Apply the min spacing of the action, if any, to an
+    /// event that is about to be scheduled at the given tag.
+    pub(crate) fn space(&mut self, tag: EventTag) -> Spaced {
+        let spacing = match &mut self.spacing {
+            Some(spacing) => spacing,
+            None => return Spaced::At(tag),
+        };
+        if let Some(latest) = spacing.latest {
+            let earliest = EventTag::offset(latest.offset_from_t0 + spacing.min, 0);
+            if tag < earliest {
+                match spacing.policy {
+                    SpacingPolicy::Drop => return Spaced::Drop,
+                    SpacingPolicy::Replace if self.inner.map.contains_key(&Reverse(latest)) => return Spaced::Replace(latest),
+                    SpacingPolicy::Replace | SpacingPolicy::Defer => {
+                        spacing.latest = Some(earliest);
+                        return Spaced::At(earliest);
+                    }
+                }
+            }
+        }
+        spacing.latest = Some(tag);
+        Spaced::At(tag)
     }
 }
 
 impl<T: Sync> TriggerLike for PhysicalAction<T> {
     fn get_id(&self) -> TriggerId {
-        self.0.id
+        self.inner.id
     }
 }
 
@@ -227,14 +386,51 @@ mod test {
 /// on the action are
 ///
 /// See [crate::ReactionCtx::spawn_physical_thread].
-#[derive(Clone)]
 pub struct PhysicalActionRef<T: Sync>(Arc<Mutex<PhysicalAction<T>>>);
 
+// Derive would require T: Clone.
+impl<T: Sync> Clone for PhysicalActionRef<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
 impl<T: Sync> PhysicalActionRef<T> {
     pub(crate) fn new(id: TriggerId, min_delay: Option<Duration>) -> Self {
         Self(Arc::new(Mutex::new(PhysicalAction::new(id, min_delay))))
     }
 
+    /// Set the priority of this action for admission control
+    /// (see [AdmissionPolicy]). The default priority is zero.
+    /// This is meant to be called at assembly time.
+    pub fn with_admission_priority(self, priority: u32) -> Self {
+        self.configure(|a| a.priority = priority)
+    }
+
+    /// Enforce a min spacing between the events of this action, in
+    /// logical time. An event scheduled sooner than that after the
+    /// previous one is handled according to the policy. This is
+    /// meant to be called at assembly time.
+    pub fn with_min_spacing(self, min_spacing: Duration, policy: SpacingPolicy) -> Self {
+        let spacing = Spacing { min: min_spacing, policy, latest: None };
+        self.configure(|a| a.spacing = Some(spacing))
+    }
+
+    /// Set what happens when the action is scheduled several
+    /// times at the same tag, see [CollisionPolicy]. Events of
+    /// physical actions have the same tag if they are sent at
+    /// the same physical time, or with a [tag window](crate::SchedulerOptions::physical_tag_window).
+    /// This is meant to be called at assembly time.
+    pub fn with_collision_policy(self, policy: CollisionPolicy) -> Self {
+        self.configure(|a| a.inner.collision = policy)
+    }
+
+    fn configure(self, f: impl FnOnce(&mut PhysicalAction<T>)) -> Self {
+        self.use_mut(f)
+            .expect("Cannot configure a physical action whose lock is poisoned");
+        self
+    }
+
     pub(crate) fn use_mut<O>(&self, f: impl FnOnce(&mut PhysicalAction<T>) -> O) -> Result<O, ()> {
         let mut refmut = self.0.deref().lock().map_err(|_| ())?;
 
@@ -263,17 +459,17 @@ impl<T: Sync> TriggerLike for PhysicalActionRef<T> {
 
 impl<T: Sync> ReactionTrigger<T> for PhysicalActionRef<T> {
     fn is_present(&self, now: &EventTag, start: &Instant) -> bool {
-        self.use_value(|a| a.0.is_present(now, start)).unwrap()
+        self.use_value(|a| a.inner.is_present(now, start)).unwrap()
     }
 
     fn get_value(&self, now: &EventTag, start: &Instant) -> Option<T>
     where
         T: Copy,
     {
-        self.use_value(|a| a.0.get_value(now, start)).unwrap()
+        self.use_value(|a| a.inner.get_value(now, start)).unwrap()
     }
 
     fn use_value_ref<O>(&self, now: &EventTag, start: &Instant, action: impl FnOnce(Option<&T>) -> O) -> O {
-        self.use_value(|a| a.0.use_value_ref(now, start, action)).unwrap()
+        self.use_value(|a| a.inner.use_value_ref(now, start, action)).unwrap()
     }
 }
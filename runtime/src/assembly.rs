@@ -1,12 +1,15 @@
 //! Module containing the API to initialize a reactor program.
 
+use std::fmt::{Display, Formatter};
+
 use AssemblyErrorImpl::*;
 
 pub use crate::ids::GlobalReactionId;
 // this is where most of the stuff is implemented
 pub use crate::scheduler::assembly_impl::*;
+pub use crate::scheduler::validation::*;
 pub use crate::triggers::{TriggerId, TriggerLike};
-use crate::{DebugInfoRegistry, LocalReactionId, ReactorBehavior};
+use crate::{join_to, DebugInfoRegistry, LocalReactionId, ReactorBehavior};
 pub(crate) type PortId = TriggerId;
 
 /// Wrapper around the user struct for safe dispatch.
@@ -44,13 +47,27 @@ impl AssemblyError {
     pub(crate) fn lift(self, debug: &DebugInfoRegistry) -> String {
         self.display(debug)
     }
+
+    pub(crate) fn to_diagnostic(&self, debug: &DebugInfoRegistry) -> Diagnostic {
+        let path = match self.0 {
+            CyclicDependency(_, port) | CannotBind(_, port) | CannotSetLastWill(port) => {
+                Some(debug.fmt_component(port).to_string())
+            }
+            CannotPrioritize(reaction) => Some(debug.fmt_reaction(reaction).to_string()),
+            CyclicDependencyGraph(_) | IdOverflow => None,
+        };
+        Diagnostic { path, message: self.display(debug) }
+    }
 }
 
 pub(crate) enum AssemblyErrorImpl {
     CyclicDependency(PortId, PortId),
-    CyclicDependencyGraph,
+    /// A cycle of instantaneous dependencies, in order.
+    CyclicDependencyGraph(Vec<ProgramNode>),
     CannotBind(PortId, PortId),
     IdOverflow,
+    CannotSetLastWill(PortId),
+    CannotPrioritize(GlobalReactionId),
 }
 
 impl AssemblyError {
@@ -61,13 +78,71 @@ impl AssemblyError {
                 debug.fmt_component(upstream),
                 debug.fmt_component(downstream)
             ),
-            CyclicDependencyGraph => "Cyclic dependency graph".to_string(),
+            CyclicDependencyGraph(ref cycle) => {
+                let mut message = "Cyclic dependency graph: ".to_string();
+                let closed = cycle.iter().chain(cycle.first());
+                join_to!(&mut message, closed, " -> ", "", "", |node| node.path(debug)).unwrap();
+                message
+            }
             CannotBind(upstream, downstream) => format!(
                 "Cannot bind {} to {}, downstream is already bound",
                 debug.fmt_component(upstream),
                 debug.fmt_component(downstream)
             ),
             IdOverflow => "Overflow when allocating component ID".to_string(),
+            CannotSetLastWill(port) => format!(
+                "Cannot set the last will of {}, it is bound to an upstream port",
+                debug.fmt_component(port)
+            ),
+            CannotPrioritize(reaction) => format!(
+                "Cannot set the priority of {}, it is synthetic or belongs to another reactor",
+                debug.fmt_reaction(reaction)
+            ),
+        }
+    }
+}
+
+/// Errors and warnings collected over a whole program
+/// by [SyncScheduler::validate](crate::SyncScheduler::validate).
+#[derive(Clone, Debug, Default)]
+pub struct ValidationReport {
+    pub errors: Vec<Diagnostic>,
+    pub warnings: Vec<Diagnostic>,
+}
+
+impl ValidationReport {
+    /// Returns true if the program has no errors. It may
+    /// still have warnings.
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for error in &self.errors {
+            writeln!(f, "error: {}", error)?;
+        }
+        for warning in &self.warnings {
+            writeln!(f, "warning: {}", warning)?;
+        }
+        Ok(())
+    }
+}
+
+/// An error or warning about a program.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Diagnostic {
+    /// Path of the component concerned, if any, eg `/main/child.out`.
+    pub path: Option<String>,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.path {
+            Some(path) => write!(f, "{}: {}", path, self.message),
+            None => write!(f, "{}", self.message),
         }
     }
 }
@@ -80,3 +155,42 @@ pub enum PortKind {
     ChildInputReference,
     ChildOutputReference,
 }
+
+/// A group of ports that is declared once, and instantiated
+/// on several reactors, eg a request and its response. Bundles
+/// are declared with [port_bundle](crate::port_bundle), created
+/// with [ComponentCreator::new_bundle], and connected with a
+/// single call to [DependencyDeclarator::connect_bundles].
+pub trait PortBundle: Sized {
+    /// Create the ports of the bundle, named `name.port`. The
+    /// side determines which ports are inputs and outputs.
+    fn new_bundle<S: ReactorInitializer>(creator: &mut ComponentCreator<S>, name: &'static str, side: BundleSide) -> Self;
+
+    /// Bind the ports of two bundles: ports declared `out` flow
+    /// from `a` to `b`, and ports declared `in` from `b` to `a`.
+    fn connect<S: ReactorInitializer>(decl: &mut DependencyDeclarator<S>, a: &mut Self, b: &mut Self) -> AssemblyResult<()>;
+}
+
+/// Which end of a connection a [PortBundle] is on.
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum BundleSide {
+    /// Ports declared `out` are outputs, eg the
+    /// side that sends requests.
+    Initiator,
+    /// Ports declared `out` are inputs, eg the
+    /// side that answers requests.
+    Responder,
+}
+
+impl BundleSide {
+    /// Kind of a port declared `out` (if `out` is true)
+    /// or `in` in a bundle on this side.
+    #[doc(hidden)]
+    pub fn port_kind(self, out: bool) -> PortKind {
+        if out == (self == BundleSide::Initiator) {
+            PortKind::Output
+        } else {
+            PortKind::Input
+        }
+    }
+}
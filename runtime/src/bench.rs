@@ -0,0 +1,212 @@
+//! A harness to benchmark whole programs, see [Harness].
+//!
+//! Unlike the micro benchmarks of this crate, which use
+//! criterion, the harness measures a program as the scheduler
+//! sees it: besides the wall-clock time of each run, it records
+//! how many tags and reactions were processed, and how far
+//! physical time lagged behind logical time. Results can be
+//! written as CSV or JSON lines, to compare runtimes or versions:
+//!
+//! ```no_run
+//! # use reactor_rt::*;
+//! # use reactor_rt::assembly::ReactorInitializer;
+//! # use reactor_rt::bench::Harness;
+//! # fn bench<SavinaPong: ReactorInitializer + 'static>(params: impl FnMut() -> SavinaPong::Params) {
+//! let report = Harness::new("savina_pong")
+//!     .warmup(2)
+//!     .iterations(10)
+//!     .run::<SavinaPong>(SchedulerOptions::default, params);
+//! report.write_csv(std::io::stdout()).unwrap();
+//! # }
+//! ```
+
+use std::fmt::{Display, Formatter};
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::ReactorInitializer;
+use crate::util::json_string;
+use crate::*;
+
+/// Measurements of one run of a program.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Iteration {
+    /// Time from the start of the assembly to the end of shutdown.
+    pub wall_time: Duration,
+    /// Number of tags processed.
+    pub tags: usize,
+    /// Number of reactions executed.
+    pub reactions: usize,
+    /// Mean delay between the logical time of a tag and the
+    /// physical time at which it started being processed.
+    pub mean_lag: Duration,
+    /// Max of the same delay.
+    pub max_lag: Duration,
+}
+
+impl Iteration {
+    /// Number of reactions executed per second of wall-clock time.
+    pub fn throughput(&self) -> f64 {
+        self.reactions as f64 / self.wall_time.as_secs_f64()
+    }
+}
+
+/// Runs a program several times, and measures each run.
+/// Runs are sequential, and share nothing but the harness.
+pub struct Harness {
+    name: String,
+    warmup: usize,
+    iterations: usize,
+}
+
+impl Harness {
+    /// A harness that runs the program 10 times, without warmup.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), warmup: 0, iterations: 10 }
+    }
+
+    /// Run the program this number of times before measuring it.
+    pub fn warmup(mut self, runs: usize) -> Self {
+        self.warmup = runs;
+        self
+    }
+
+    /// Number of measured runs.
+    pub fn iterations(mut self, runs: usize) -> Self {
+        self.iterations = runs;
+        self
+    }
+
+    /// Run the program with main reactor `R`. The options and
+    /// parameters of each run are created by the given closures.
+    ///
+    /// The lag is measured with a [PerfTrace], which replaces
+    /// the one of the options, if any. It times every reaction,
+    /// which adds a small overhead per reaction to the runs.
+    pub fn run<R: ReactorInitializer + 'static>(
+        &self,
+        mut options: impl FnMut() -> SchedulerOptions,
+        mut params: impl FnMut() -> R::Params,
+    ) -> BenchReport {
+        for _ in 0..self.warmup {
+            SyncScheduler::run_main::<R>(options(), params());
+        }
+        let iterations = (0..self.iterations)
+            .map(|i| {
+                let iteration = Self::measure::<R>(options(), params());
+                debug!("{} #{}: {:?}", self.name, i, iteration.wall_time);
+                iteration
+            })
+            .collect();
+        BenchReport { name: self.name.clone(), iterations }
+    }
+
+    fn measure<R: ReactorInitializer + 'static>(mut options: SchedulerOptions, params: R::Params) -> Iteration {
+        let result: Arc<Mutex<Iteration>> = Default::default();
+        let sink = result.clone();
+        options.perf_trace = Some(PerfTrace::new(move |recording| {
+            let lags = recording
+                .tags
+                .iter()
+                .map(|span| span.start.saturating_sub(span.tag.offset_from_t0))
+                .collect::<Vec<_>>();
+            let mut result = sink.lock().unwrap();
+            result.tags = lags.len();
+            result.reactions = recording.reactions.len();
+            result.max_lag = lags.iter().copied().max().unwrap_or_default();
+            if !lags.is_empty() {
+                result.mean_lag = lags.iter().sum::<Duration>() / lags.len() as u32;
+            }
+        }));
+
+        let start = Instant::now();
+        SyncScheduler::run_main::<R>(options, params);
+        let wall_time = start.elapsed();
+
+        let mut result = result.lock().unwrap();
+        result.wall_time = wall_time;
+        std::mem::take(&mut *result)
+    }
+}
+
+/// Measurements of the runs of a [Harness].
+#[derive(Clone, Debug, PartialEq)]
+pub struct BenchReport {
+    pub name: String,
+    /// Measured runs, in order.
+    pub iterations: Vec<Iteration>,
+}
+
+impl BenchReport {
+    /// Median of the wall-clock time of the runs.
+    pub fn median_wall_time(&self) -> Duration {
+        let mut times = self.iterations.iter().map(|i| i.wall_time).collect::<Vec<_>>();
+        times.sort();
+        times.get(times.len() / 2).copied().unwrap_or_default()
+    }
+
+    /// Mean number of reactions executed per second.
+    pub fn mean_throughput(&self) -> f64 {
+        let sum: f64 = self.iterations.iter().map(Iteration::throughput).sum();
+        sum / self.iterations.len() as f64
+    }
+
+    /// Max lag over all runs, see [Iteration::max_lag].
+    pub fn max_lag(&self) -> Duration {
+        self.iterations.iter().map(|i| i.max_lag).max().unwrap_or_default()
+    }
+
+    /// Write the report as CSV, with one line per run.
+    /// Times are in nanoseconds.
+    pub fn write_csv(&self, mut w: impl Write) -> io::Result<()> {
+        writeln!(w, "name,iteration,wall_time_ns,tags,reactions,mean_lag_ns,max_lag_ns")?;
+        for (i, it) in self.iterations.iter().enumerate() {
+            writeln!(
+                w,
+                "{},{},{},{},{},{},{}",
+                self.name,
+                i,
+                it.wall_time.as_nanos(),
+                it.tags,
+                it.reactions,
+                it.mean_lag.as_nanos(),
+                it.max_lag.as_nanos()
+            )?;
+        }
+        w.flush()
+    }
+
+    /// Write the report as JSON lines, with one object per run.
+    /// Times are in nanoseconds.
+    pub fn write_json(&self, mut w: impl Write) -> io::Result<()> {
+        let name = json_string(&self.name);
+        for (i, it) in self.iterations.iter().enumerate() {
+            writeln!(
+                w,
+                "{{\"name\":{},\"iteration\":{},\"wall_time_ns\":{},\"tags\":{},\"reactions\":{},\"mean_lag_ns\":{},\"max_lag_ns\":{}}}",
+                name,
+                i,
+                it.wall_time.as_nanos(),
+                it.tags,
+                it.reactions,
+                it.mean_lag.as_nanos(),
+                it.max_lag.as_nanos()
+            )?;
+        }
+        w.flush()
+    }
+}
+
+impl Display for BenchReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} runs, median {:?}, {:.0} reactions/s, max lag {:?}",
+            self.name,
+            self.iterations.len(),
+            self.median_wall_time(),
+            self.mean_throughput(),
+            self.max_lag()
+        )
+    }
+}
@@ -0,0 +1,306 @@
+//! Immutable string and byte buffers, that are cheap to
+//! clone, for programs that send many strings or messages
+//! through ports.
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+/// Storage of a [Str] or [Bytes].
+enum Buf<T: ?Sized + 'static> {
+    /// Not allocated, eg a literal.
+    Static(&'static T),
+    /// Reference counted.
+    Shared(Arc<T>),
+}
+
+// Derive would require T: Clone.
+impl<T: ?Sized> Clone for Buf<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Buf::Static(v) => Buf::Static(v),
+            Buf::Shared(v) => Buf::Shared(Arc::clone(v)),
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for Buf<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        match self {
+            Buf::Static(v) => v,
+            Buf::Shared(v) => v,
+        }
+    }
+}
+
+macro_rules! shared_buffer {
+    ($(#[$attr:meta])* $Name:ident($Slice:ty), $Owned:ty) => {
+        $(#[$attr])*
+        #[derive(Clone)]
+        pub struct $Name(Buf<$Slice>);
+
+        impl $Name {
+            /// Wrap a static value. This does not allocate.
+            pub const fn from_static(value: &'static $Slice) -> Self {
+                Self(Buf::Static(value))
+            }
+
+            /// Copy the given value into a new buffer. If the value
+            /// is static, use [Self::from_static] instead, if it is
+            /// frequently repeated, see [Interner].
+            pub fn copy_from(value: &$Slice) -> Self {
+                Self(Buf::Shared(Arc::from(value)))
+            }
+
+            /// Returns true if both buffers share the same
+            /// storage, ie if one is a clone of the other.
+            pub fn ptr_eq(&self, other: &Self) -> bool {
+                std::ptr::eq::<$Slice>(&*self.0, &*other.0)
+            }
+        }
+
+        impl Deref for $Name {
+            type Target = $Slice;
+
+            #[inline]
+            fn deref(&self) -> &$Slice {
+                &self.0
+            }
+        }
+
+        impl AsRef<$Slice> for $Name {
+            fn as_ref(&self) -> &$Slice {
+                self
+            }
+        }
+
+        impl Borrow<$Slice> for $Name {
+            fn borrow(&self) -> &$Slice {
+                self
+            }
+        }
+
+        impl Default for $Name {
+            fn default() -> Self {
+                Self::from_static(Default::default())
+            }
+        }
+
+        impl From<$Owned> for $Name {
+            /// This does not copy the value, but may reallocate
+            /// it if it has excess capacity.
+            fn from(value: $Owned) -> Self {
+                Self(Buf::Shared(Arc::from(value)))
+            }
+        }
+
+        impl From<Arc<$Slice>> for $Name {
+            fn from(value: Arc<$Slice>) -> Self {
+                Self(Buf::Shared(value))
+            }
+        }
+
+        impl PartialEq for $Name {
+            fn eq(&self, other: &Self) -> bool {
+                **self == **other
+            }
+        }
+
+        impl Eq for $Name {}
+
+        impl PartialOrd for $Name {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $Name {
+            fn cmp(&self, other: &Self) -> Ordering {
+                (**self).cmp(&**other)
+            }
+        }
+
+        // Must be consistent with the Borrow impl.
+        impl Hash for $Name {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                (**self).hash(state)
+            }
+        }
+
+        impl Interner<$Name> {
+            /// Returns a buffer with the given value, which shares the
+            /// storage of the previous buffers interned with that value.
+            /// This only allocates the first time a value is seen.
+            pub fn intern(&self, value: &$Slice) -> $Name {
+                let mut values = self.values.lock().unwrap();
+                if let Some(interned) = values.get(value) {
+                    return interned.clone();
+                }
+                let interned = $Name::copy_from(value);
+                values.insert(interned.clone());
+                interned
+            }
+        }
+    };
+}
+
+shared_buffer! {
+    /// An immutable string, that is cheap to clone. Clones share
+    /// the same storage, so that a string sent to several reactions
+    /// through ports is not copied for each of them:
+    /// ```no_run
+    /// # use reactor_rt::prelude::*;
+    /// # let ctx: &mut ReactionCtx = panic!();
+    /// # let out: &mut Port<Str> = panic!();
+    /// # let inp: &Port<Str> = panic!();
+    /// ctx.set(out, Str::from_static("ready"));
+    /// // in a reaction downstream
+    /// let message: Option<Str> = ctx.use_ref_opt(inp, Str::clone);
+    /// ```
+    Str(str), String
+}
+
+shared_buffer! {
+    /// An immutable byte buffer, that is cheap to clone, like [Str].
+    Bytes([u8]), Vec<u8>
+}
+
+impl From<&'static str> for Str {
+    /// This does not allocate.
+    fn from(value: &'static str) -> Self {
+        Self::from_static(value)
+    }
+}
+
+impl From<&'static [u8]> for Bytes {
+    /// This does not allocate.
+    fn from(value: &'static [u8]) -> Self {
+        Self::from_static(value)
+    }
+}
+
+impl Display for Str {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&**self, f)
+    }
+}
+
+impl Debug for Str {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+impl Debug for Bytes {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Str {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Str {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self::from)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Bytes {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Bytes {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<u8>::deserialize(deserializer).map(Self::from)
+    }
+}
+
+/// Deduplicates [Str] or [Bytes] values, so that a value
+/// that is repeated, like a message kind or a topic, is
+/// allocated only once. It may be shared between reactors
+/// and threads.
+///
+/// Interned values are kept until [Self::clear] is called,
+/// so that the interner grows with the number of distinct
+/// values. Only intern values from a small set.
+pub struct Interner<T> {
+    values: Mutex<HashSet<T>>,
+}
+
+impl<T: Eq + Hash> Interner<T> {
+    pub fn new() -> Self {
+        Self { values: Default::default() }
+    }
+
+    /// Number of distinct values interned.
+    pub fn len(&self) -> usize {
+        self.values.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Forget the interned values. Buffers already handed
+    /// out are not affected.
+    pub fn clear(&self) {
+        self.values.lock().unwrap().clear()
+    }
+}
+
+impl<T: Eq + Hash> Default for Interner<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_interning_shares_storage() {
+        let interner = Interner::<Str>::new();
+        let a = interner.intern(&format!("topic-{}", 1));
+        let b = interner.intern("topic-1");
+        let c = interner.intern("topic-2");
+        assert!(a.ptr_eq(&b));
+        assert!(!a.ptr_eq(&c));
+        assert_eq!(interner.len(), 2);
+        assert_eq!(a, Str::from("topic-1"));
+        assert_eq!(&*c, "topic-2");
+
+        let bytes = Interner::<Bytes>::new();
+        assert!(bytes.intern(b"ab").ptr_eq(&bytes.intern(b"ab")));
+    }
+
+    #[test]
+    fn test_static_values_are_not_copied() {
+        static LITERAL: &str = "hello";
+        let s = Str::from(LITERAL);
+        assert!(std::ptr::eq(&*s, LITERAL));
+        assert!(s.clone().ptr_eq(&s));
+        assert_eq!(&*Bytes::from(LITERAL.as_bytes()), b"hello");
+
+        let owned = Str::from(String::from("hello"));
+        assert_eq!(owned, s);
+        assert!(!owned.ptr_eq(&s));
+    }
+}
@@ -0,0 +1,204 @@
+/*
+ * Copyright (c) 2021, TU Dresden.
+ *
+ * Redistribution and use in source and binary forms, with or without modification,
+ * are permitted provided that the following conditions are met:
+ *
+ * 1. Redistributions of source code must retain the above copyright notice,
+ *    this list of conditions and the following disclaimer.
+ *
+ * 2. Redistributions in binary form must reproduce the above copyright notice,
+ *    this list of conditions and the following disclaimer in the documentation
+ *    and/or other materials provided with the distribution.
+ *
+ * THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY
+ * EXPRESS OR IMPLIED WARRANTIES, INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF
+ * MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE DISCLAIMED. IN NO EVENT SHALL
+ * THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+ * SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO,
+ * PROCUREMENT OF SUBSTITUTE GOODS OR SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS
+ * INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY, WHETHER IN CONTRACT,
+ * STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF
+ * THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+ */
+
+//! Capabilities restrict which reactor may mutate a component.
+//!
+//! Code generated by LFC only ever passes a `&mut` reference to
+//! a port or action to the reactions that declared an effect on
+//! it, so it doesn't need this. Hand-written programs however
+//! can freely pass mutable references around. Wrapping the
+//! components of a reactor `S` into [Owned]`<S, _>` ensures
+//! that they can only be mutated at runtime by code that holds
+//! the [Capability]`<S>`, which is only handed to `S` when it is
+//! assembled.
+//!
+//! ```no_run
+//! # use reactor_rt::*;
+//! struct Source {
+//!     cap: Capability<Source>,
+//!     out: Owned<Source, Port<u32>>,
+//! }
+//!
+//! impl Source {
+//!     fn react_0(&mut self, ctx: &mut ReactionCtx) {
+//!         // reading needs no capability
+//!         let _ = ctx.get(&self.out);
+//!         // writing does
+//!         ctx.set(self.out.get_mut(&self.cap), 1);
+//!     }
+//! }
+//! ```
+//!
+//! The capability and owned components are created with
+//! [ComponentCreator::capability] and [ComponentCreator::own].
+//!
+//! Components cannot be mutated without a capability, and the
+//! capability of another reactor type is rejected at compile time:
+//! ```compile_fail
+//! # use reactor_rt::*;
+//! # struct Source;
+//! fn react(ctx: &mut ReactionCtx, out: &mut Owned<Source, Port<u32>>) {
+//!     ctx.set(out, 1);
+//! }
+//! ```
+//! ```compile_fail
+//! # use reactor_rt::*;
+//! # struct Source;
+//! # struct Sink;
+//! fn react(ctx: &mut ReactionCtx, cap: &Capability<Sink>, out: &mut Owned<Source, Port<u32>>) {
+//!     ctx.set(out.get_mut(cap), 1);
+//! }
+//! ```
+//! Several instances of a reactor type, eg the members of a bank,
+//! share the type of their capability, but not the capability
+//! itself: using the capability of one instance to mutate the
+//! components of another panics at runtime.
+
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::time::Instant;
+
+use crate::assembly::*;
+use crate::triggers::ReactionTrigger;
+#[cfg(not(feature = "no-unsafe"))]
+use crate::triggers::ReactionTriggerWithRefAccess;
+use crate::{EventTag, ReactorId};
+
+/// The right to mutate the [Owned] components of reactor `S`.
+/// It is created by [ComponentCreator::capability], which is
+/// only available while `S` is being assembled. It cannot be
+/// cloned, so a reactor keeps it to itself unless it explicitly
+/// hands out a reference.
+pub struct Capability<S> {
+    owner: ReactorId,
+    _owner: PhantomData<fn() -> S>,
+}
+
+impl<S> Capability<S> {
+    pub(crate) fn new(owner: ReactorId) -> Self {
+        Self { owner, _owner: PhantomData }
+    }
+}
+
+/// A component that only reactor `S` can mutate at runtime.
+/// Shared access is not restricted, so that other reactors (and
+/// the runtime) can read the component, and [Self::assembly_mut]
+/// allows binding ports of other reactors during assembly.
+pub struct Owned<S, C> {
+    component: C,
+    owner: ReactorId,
+    _owner: PhantomData<fn() -> S>,
+}
+
+impl<S, C> Owned<S, C> {
+    pub(crate) fn new(component: C, owner: ReactorId) -> Self {
+        Self { component, owner, _owner: PhantomData }
+    }
+
+    /// Access the component mutably, eg to set a port or
+    /// schedule an action. This requires the capability of its owner.
+    ///
+    /// # Panics
+    ///
+    /// If the capability belongs to another instance of `S`.
+    #[inline]
+    pub fn get_mut(&mut self, capability: &Capability<S>) -> &mut C {
+        assert_eq!(
+            capability.owner, self.owner,
+            "Capability of reactor {} used on a component of reactor {}",
+            capability.owner, self.owner
+        );
+        &mut self.component
+    }
+
+    /// Access the component mutably during assembly, eg to bind
+    /// ports of child reactors. The declarator is only available
+    /// during assembly, so this cannot be used at runtime.
+    #[inline]
+    pub fn assembly_mut<P: ReactorInitializer>(&mut self, _declarator: &DependencyDeclarator<P>) -> &mut C {
+        &mut self.component
+    }
+}
+
+impl<S, C> Deref for Owned<S, C> {
+    type Target = C;
+
+    #[inline]
+    fn deref(&self) -> &C {
+        &self.component
+    }
+}
+
+impl<S, C: TriggerLike> TriggerLike for Owned<S, C> {
+    #[inline]
+    fn get_id(&self) -> TriggerId {
+        self.component.get_id()
+    }
+}
+
+impl<S, T, C: ReactionTrigger<T>> ReactionTrigger<T> for Owned<S, C> {
+    #[inline]
+    fn get_value(&self, now: &EventTag, start: &Instant) -> Option<T>
+    where
+        T: Copy,
+    {
+        self.component.get_value(now, start)
+    }
+
+    #[inline]
+    fn use_value_ref<O>(&self, now: &EventTag, start: &Instant, action: impl FnOnce(Option<&T>) -> O) -> O {
+        self.component.use_value_ref(now, start, action)
+    }
+}
+
+#[cfg(not(feature = "no-unsafe"))]
+impl<S, T, C: ReactionTriggerWithRefAccess<T>> ReactionTriggerWithRefAccess<T> for Owned<S, C> {
+    #[inline]
+    fn get_value_ref(&self, now: &EventTag, start: &Instant) -> Option<&T> {
+        self.component.get_value_ref(now, start)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Bank;
+
+    #[test]
+    fn test_capability_of_the_owner_is_accepted() {
+        let cap = Capability::<Bank>::new(ReactorId::new(1));
+        let mut owned = Owned::<Bank, u32>::new(0, ReactorId::new(1));
+        *owned.get_mut(&cap) = 2;
+        assert_eq!(*owned, 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Capability of reactor 0 used on a component of reactor 1")]
+    fn test_capability_of_another_instance_is_rejected() {
+        let cap = Capability::<Bank>::new(ReactorId::new(0));
+        let mut owned = Owned::<Bank, u32>::new(0, ReactorId::new(1));
+        owned.get_mut(&cap);
+    }
+}
@@ -35,6 +35,7 @@ macro_rules! simple_idx_type {
 
 $(#[$($attrs)*])*
 #[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
 #[repr(transparent)]
 pub struct $id($impl_t);
 
@@ -98,6 +99,7 @@ macro_rules! global_id_newtype {
     {$(#[$m:meta])* $id:ident} => {
         $(#[$m])*
         #[derive(Eq, Ord, PartialOrd, PartialEq, Hash, Copy, Clone)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
         pub struct $id(pub(crate) GlobalId);
 
         impl $id {
@@ -128,6 +130,7 @@ global_id_newtype! {
 /// Identifies a component of a reactor using the ID of its container
 /// and a local component ID.
 #[derive(Eq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) struct GlobalId {
     container: ReactorId,
     local: LocalReactionId,
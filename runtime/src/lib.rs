@@ -32,24 +32,52 @@
 //!
 //! Crate-level features include:
 //! - `parallel-runtime`: use Rayon to execute reactions in parallel
-//! when possible. This is not yet the default. For some applications,
-//! where there is no data parallelism, this may harm performance
-//! (as well as pull in unneeded dependencies) and should be off.
+//!   when possible. This is not yet the default. For some applications,
+//!   where there is no data parallelism, this may harm performance
+//!   (as well as pull in unneeded dependencies) and should be off.
 //! - `wide-ids`: Enables 64-bit wide reaction ids on 64-bit
-//! architectures. This may reduce performance, but allows for
-//! 2^32 reactor instances compared to the default of 2^16,
-//! which may feel a bit tight for some applications. On machines
-//! with a pointer-width of less than 64 bits, ID types are
-//! always 32 bits. The feature also widens trigger ids to 64 bits
-//! if possible, which enables 2^64 individual trigger components
-//! (ports, actions, etc.) instead of 2^32.
+//!   architectures. This may reduce performance, but allows for
+//!   2^32 reactor instances compared to the default of 2^16,
+//!   which may feel a bit tight for some applications. On machines
+//!   with a pointer-width of less than 64 bits, ID types are
+//!   always 32 bits. The feature also widens trigger ids to 64 bits
+//!   if possible, which enables 2^64 individual trigger components
+//!   (ports, actions, etc.) instead of 2^32.
 //! - `vec-id-sets`: Change the implementation of reaction sets
-//! to be a sorted vector instead of a hash set. This has a positive
-//! performance impact, as reaction sets are typically very small.
-//! More testing is required to determine pathological cases.
-//! This is a default feature.
+//!   to be a sorted vector instead of a hash set. This has a positive
+//!   performance impact, as reaction sets are typically very small.
+//!   More testing is required to determine pathological cases.
+//!   This is a default feature.
 //! - `no-unsafe`: disable optimisations that use unsafe code in this runtime.
-//! Just provided for comparison, should probably be removed (unsafe code is fine).
+//!   Just provided for comparison, should probably be removed (unsafe code is fine).
+//! - `metrics`: enables exporting runtime metrics (tags processed, lag,
+//!   queue depth, reaction throughput) in the Prometheus text format,
+//!   either over HTTP or to a file. See [SchedulerOptions::metrics].
+//! - `visualization`: enables serving a live view of the running
+//!   program over HTTP, with its dependency graph, the triggers
+//!   present at sampled tags, and reaction timings.
+//!   See [SchedulerOptions::visualization].
+//! - `fault-injection`: enables injecting failures into the program,
+//!   like dropped physical events or failing reactions, to test
+//!   how it copes with them. See [SchedulerOptions::faults].
+//! - `test-harness`: enables running programs in logical time,
+//!   without waiting for physical time, and recording what happens
+//!   at each tag to test reactors. See the [testing] module.
+//! - `serde`: implements `Serialize` and `Deserialize` for public
+//!   value types, like [EventTag], [GlobalReactionId], [ShutdownReason],
+//!   [TagAnomaly] and [Str], so that they can be persisted or transmitted as is.
+//! - `checkpoint`: enables saving the state of reactors and the
+//!   pending events to a file at tag boundaries, and resuming the
+//!   execution from it. See `SchedulerOptions::checkpoint`.
+//! - `async`: lets async tasks schedule physical actions, through
+//!   an `AsyncSchedulerLink`, and spawns tokio tasks whose output
+//!   schedules a physical action with `ReactionCtx::spawn_task`.
+//! - `introspection`: enables inspecting the pending events of
+//!   a running program through a [SchedulerControl].
+//! - `signals`: enables the `stdlib::signals` module, to shut
+//!   down gracefully on SIGINT and SIGTERM (Unix only).
+//! - `io-driver`: enables the `stdlib::io_driver` module, to
+//!   react to the readiness of sockets without a thread per socket.
 
 // #![deny(unused_crate_dependencies)]
 #![deny(unused_extern_crates)]
@@ -62,26 +90,30 @@
 extern crate array_macro;
 #[cfg(test)]
 #[macro_use]
-#[allow(unused)]
+#[allow(unused_imports)]
 extern crate assert_matches;
 #[macro_use]
-extern crate index_vec;
-#[macro_use]
 extern crate log;
 #[cfg(feature = "parallel-runtime")]
 extern crate rayon;
 #[macro_use]
-extern crate smallvec;
-#[macro_use]
 extern crate static_assertions;
 #[macro_use]
 extern crate cfg_if;
 
 pub use std::time::{Duration, Instant};
 
+// used by the logging macros, eg log_info
+#[doc(hidden)]
+pub mod __log {
+    pub use log::{log, Level};
+}
+
 pub(crate) use scheduler::debug::*;
 
 pub use self::actions::*;
+pub use self::buffers::{Bytes, Interner, Str};
+pub use self::capabilities::*;
 pub use self::ids::*;
 pub use self::ports::*;
 pub use self::scheduler::*;
@@ -89,28 +121,37 @@ pub use self::time::*;
 pub use self::timers::*;
 pub use self::triggers::ReactionTrigger;
 pub use self::util::*;
+pub use self::watchdog::Watchdog;
 
 #[cfg(test)]
 pub mod test;
 
 mod actions;
-pub(self) mod ids;
+mod buffers;
+mod capabilities;
+mod ids;
 mod ports;
 mod scheduler;
 mod time;
 mod timers;
-pub(self) mod triggers;
+mod triggers;
 mod util;
+mod watchdog;
 
 pub mod assembly;
+pub mod bench;
+pub mod stdlib;
+#[cfg(feature = "test-harness")]
+pub mod testing;
 
 /// The prelude that is imported at the top of reactor files
 /// generated by LFC.
 pub mod prelude {
     pub use crate::Offset::*;
     pub use crate::{
-        after, assert_tag_is, delay, tag, AsyncCtx, Duration, EventTag, Instant, LogicalAction, Multiport, PhysicalActionRef,
-        Port, ReactionCtx, Timer,
+        after, assert_tag_is, delay, log_debug, log_error, log_info, log_trace, log_warn, tag, AsyncCtx, Bytes, Duration,
+        EventTag, Instant, LogicalAction, Multiport, PhysicalActionRef, Port, ReactionCtx, ReadCtx, ReadHandle, ScheduleCtx, Str,
+        TimeValue, Timer, Watchdog, WriteCtx,
     };
 
     /// Alias for the unit type, so that it can be written without quotes in LF.
@@ -144,6 +185,36 @@ pub trait ReactorBehavior {
     /// Acknowledge that the given tag is done executing and
     /// free resources if need be.
     fn cleanup_tag(&mut self, ctx: &CleanupCtx);
+
+    /// Called once before the first tag, outside of logical
+    /// time, to acquire resources such as files, sockets or
+    /// hardware handles. Reactors are called in the order of
+    /// their ids, ie containers before their children. This
+    /// is not called by [SyncScheduler::dry_run].
+    fn on_startup_sync(&mut self) {}
+
+    /// Called once after the last tag, outside of logical time,
+    /// to release the resources acquired in [Self::on_startup_sync].
+    /// Reactors are called in the reverse order. This is not
+    /// called if a reaction panics, the resources are then only
+    /// released when the reactors are dropped.
+    fn on_shutdown_sync(&mut self) {}
+
+    /// Save the state of this reactor into a [Checkpoint], including
+    /// the values of its pending actions. Returns `None` if the reactor
+    /// has no state, which is the default.
+    #[cfg(feature = "checkpoint")]
+    fn serialize_state(&self) -> Result<Option<ReactorState>, CheckpointError> {
+        Ok(None)
+    }
+
+    /// Restore the state saved by [Self::serialize_state],
+    /// when resuming from a [Checkpoint]. This is called after
+    /// assembly, instead of executing the startup reactions.
+    #[cfg(feature = "checkpoint")]
+    fn deserialize_state(&mut self, _state: &ReactorState) -> Result<(), CheckpointError> {
+        Ok(())
+    }
 }
 assert_obj_safe!(ReactorBehavior);
 
@@ -31,10 +31,11 @@ use std::fmt::Debug;
 use std::ops::Deref;
 use std::ops::{DerefMut, Index, IndexMut};
 use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 
 use atomic_refcell::AtomicRefCell;
-use AssemblyErrorImpl::{CannotBind, CyclicDependency};
+use AssemblyErrorImpl::{CannotBind, CannotSetLastWill, CyclicDependency};
 
 use crate::assembly::{AssemblyError, AssemblyErrorImpl, PortId, PortKind, TriggerId, TriggerLike};
 use crate::{EventTag, ReactionTrigger};
@@ -56,6 +57,16 @@ use crate::{EventTag, ReactionTrigger};
 /// able to add conditional compilation flags that enable
 /// runtime checks.
 ///
+/// Ports bound together share a single value, so a value set
+/// on a port is not copied to the ports downstream. Values that
+/// are not `Copy` can be read by reference with
+/// [use_ref](super::ReactionCtx::use_ref), or moved out by
+/// their last reader with [take](super::ReactionCtx::take)
+/// or [take_if_last](super::ReactionCtx::take_if_last).
+/// Large values sent to several readers can be wrapped into
+/// an [Arc](std::sync::Arc), so that readers that need their
+/// own copy share it instead.
+///
 pub struct Port<T: Sync> {
     id: TriggerId,
     kind: PortKind,
@@ -88,9 +99,9 @@ impl<T: Sync> Port<T> {
             kind,
             bind_status: BindStatus::Free,
             #[cfg(feature = "no-unsafe")]
-            upstream_binding: Rc::new(AtomicRefCell::new(Default::default())),
+            upstream_binding: Rc::new(AtomicRefCell::new(Rc::new(PortCell::new(id)))),
             #[cfg(not(feature = "no-unsafe"))]
-            upstream_binding: Rc::new(UnsafeCell::new(Default::default())),
+            upstream_binding: Rc::new(UnsafeCell::new(Rc::new(PortCell::new(id)))),
         }
     }
 
@@ -135,6 +146,34 @@ impl<T: Sync> Port<T> {
                 *class_cell.value.borrow_mut() = new_value;
             }
 
+            /// Move the value out, see [super::ReactionCtx::take].
+            /// Unlike [Self::set_impl], this may be called on bound
+            /// ports, as the value is not observed by other ports.
+            pub(crate) fn take_impl(&mut self) -> Option<T> {
+                use atomic_refcell::AtomicRef;
+
+                let cell_ref: AtomicRef<Rc<PortCell<T>>> = AtomicRefCell::borrow(&self.upstream_binding);
+                let class_cell: &PortCell<T> = Rc::borrow(cell_ref.deref());
+                let value = class_cell.value.borrow_mut().take();
+                value
+            }
+
+            /// Access the value of the previous tag, see [PortCell::last].
+            pub(crate) fn use_last<R>(&self, f: impl FnOnce(&mut LastValue<T>) -> R) -> R {
+                use atomic_refcell::AtomicRef;
+
+                let cell_ref: AtomicRef<Rc<PortCell<T>>> = AtomicRefCell::borrow(&self.upstream_binding);
+                let class_cell: &PortCell<T> = Rc::borrow(cell_ref.deref());
+                let mut last = class_cell.last.borrow_mut();
+                f(&mut last)
+            }
+
+            /// Id of the port that owns the value cell, ie the
+            /// upstream-most port of those bound to this one.
+            pub(crate) fn origin_id(&self) -> TriggerId {
+                AtomicRefCell::borrow(&self.upstream_binding).origin
+            }
+
         } else {
 
              /// Returns a reference to the value. It is not possible to
@@ -172,6 +211,32 @@ impl<T: Sync> Port<T> {
                     cell.value.get().replace(new_value);
                 }
             }
+
+             #[inline]
+             pub(crate) fn take_impl(&mut self) -> Option<T> {
+                let binding: &UnsafeCell<Rc<PortCell<T>>> = Rc::borrow(&self.upstream_binding);
+
+                unsafe {
+                    let cell: &Rc<PortCell<T>> = &*binding.get();
+                    cell.value.get().replace(None)
+                }
+            }
+
+             #[inline]
+             pub(crate) fn use_last<R>(&self, f: impl FnOnce(&mut LastValue<T>) -> R) -> R {
+                let binding: &UnsafeCell<Rc<PortCell<T>>> = Rc::borrow(&self.upstream_binding);
+
+                unsafe {
+                    let cell: &Rc<PortCell<T>> = &*binding.get();
+                    f(&mut *cell.last.get())
+                }
+            }
+
+             pub(crate) fn origin_id(&self) -> TriggerId {
+                let binding: &UnsafeCell<Rc<PortCell<T>>> = Rc::borrow(&self.upstream_binding);
+                let cell: &Rc<PortCell<T>> = unsafe { &*binding.get() };
+                cell.origin
+            }
         }
     }
 
@@ -181,10 +246,87 @@ impl<T: Sync> Port<T> {
         // If this port is bound, then some other port has
         // a reference to the same cell but is not bound.
         if self.bind_status != BindStatus::Bound {
-            self.set_impl(None)
+            let value = self.take_impl();
+            if value.is_some() {
+                self.use_last(|last| {
+                    if let Some(last) = last {
+                        **last = value;
+                    }
+                })
+            }
         }
     }
 
+    /// Returns false if the value is equal to the value of the
+    /// port at the previous tag at which it was present, see
+    /// [super::ReactionCtx::set_if_changed]. From the first call
+    /// on, the port keeps the value of the previous tag.
+    pub(crate) fn changed_since_last_tag(&self, new_value: &T, eq: impl FnOnce(&T, &T) -> bool) -> bool {
+        self.use_last(|last| match last {
+            Some(last) => (**last).as_ref().map_or(true, |last| !eq(last, new_value)),
+            None => {
+                *last = Some(Box::new(None));
+                true
+            }
+        })
+    }
+
+    /// Whether the port keeps the value of the previous tag,
+    /// which must then not be moved out by readers.
+    pub(crate) fn keeps_last(&self) -> bool {
+        self.use_last(|last| last.is_some())
+    }
+
+    /// Create the last will of this port, see [DependencyDeclarator::set_last_will](crate::assembly::DependencyDeclarator::set_last_will).
+    pub(crate) fn last_will(&self, value: T) -> Result<LastWill, AssemblyError>
+    where
+        T: 'static,
+    {
+        if self.bind_status == BindStatus::Bound {
+            return Err(AssemblyError(CannotSetLastWill(self.id)));
+        }
+        // a handle on the same cell, like the Rc clones made
+        // when binding, this is only done during assembly.
+        let mut handle = Port {
+            id: self.id,
+            kind: self.kind,
+            bind_status: BindStatus::Free,
+            upstream_binding: Rc::clone(&self.upstream_binding),
+        };
+        Ok(LastWill {
+            port: self.id,
+            emit: Box::new(move || handle.set_impl(Some(value))),
+        })
+    }
+
+    /// Export the values of this port to other threads, see [DependencyDeclarator::export_port](crate::assembly::DependencyDeclarator::export_port).
+    pub(crate) fn export(&self) -> (ReadHandle<T>, PortExport)
+    where
+        T: Clone + Send + 'static,
+    {
+        // a handle on the same cell, see last_will. It also
+        // sees the bindings made after the export.
+        let handle = Port {
+            id: self.id,
+            kind: self.kind,
+            bind_status: BindStatus::Bound,
+            upstream_binding: Rc::clone(&self.upstream_binding),
+        };
+        let read_handle = ReadHandle(Default::default());
+        let latest = Arc::clone(&read_handle.0);
+        let export = PortExport {
+            publish: Box::new(move |tag| {
+                handle.use_ref(|value| {
+                    if let Some(value) = value {
+                        *latest.lock().unwrap() = Some((tag, value.clone()));
+                    }
+                })
+            }),
+        };
+        (read_handle, export)
+    }
+
+    #[allow(clippy::needless_borrow)] // the borrows are needed with feature no-unsafe
     pub(crate) fn forward_to(&mut self, downstream: &mut Port<T>) -> Result<(), AssemblyError> {
         let mut mut_downstream_cell = {
             cfg_if! {
@@ -260,6 +402,63 @@ impl<T: Sync> TriggerLike for Port<T> {
     }
 }
 
+/// A value that a port takes at the shutdown tag.
+pub(crate) struct LastWill {
+    pub(crate) port: TriggerId,
+    emit: Box<dyn FnOnce()>,
+}
+
+impl LastWill {
+    /// Set the value of the port.
+    pub(crate) fn emit(self) {
+        (self.emit)()
+    }
+}
+
+/// The latest value of a port, which can be read from any
+/// thread, eg from a thread spawned with [ReactionCtx::spawn_physical_thread](crate::ReactionCtx::spawn_physical_thread).
+/// It is created at assembly time with [DependencyDeclarator::export_port](crate::assembly::DependencyDeclarator::export_port).
+///
+/// The handle is updated at the end of each tag at which the
+/// port is present, and keeps its value at the other tags. It
+/// is not a dependency of any reaction: a thread that reads it
+/// may observe the value of a tag, then of a later one, at any
+/// physical time.
+pub struct ReadHandle<T>(Arc<Mutex<Option<(EventTag, T)>>>);
+
+// Derive would require T: Clone.
+impl<T> Clone for ReadHandle<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<T: Clone> ReadHandle<T> {
+    /// The latest value of the port, or None if it
+    /// has never been present.
+    pub fn get(&self) -> Option<T> {
+        self.get_tagged().map(|(_, value)| value)
+    }
+
+    /// The latest value of the port, with the tag at which it was set.
+    pub fn get_tagged(&self) -> Option<(EventTag, T)> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+/// Copies the value of a port into its [ReadHandle].
+pub(crate) struct PortExport {
+    publish: Box<dyn FnMut(EventTag)>,
+}
+
+impl PortExport {
+    /// Called at the end of every tag, before
+    /// the values of ports are cleared.
+    pub(crate) fn publish(&mut self, tag: EventTag) {
+        (self.publish)(tag)
+    }
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 enum BindStatus {
     /// A bindable port is also writable explicitly (with set)
@@ -284,11 +483,26 @@ cfg_if! {
     }
 }
 
+/// See [PortCell::last].
+type LastValue<T> = Option<Box<Option<T>>>;
+
 /// This is the internal cell type that is shared by ports.
 struct PortCell<T: Sync> {
     /// Cell for the value.
     value: UncheckedCell<Option<T>>,
 
+    /// Value of the port at the previous tag at which it was
+    /// present. It is None, until the port is first set with
+    /// [super::ReactionCtx::set_if_changed], and the value is
+    /// then moved in when the port is cleared at the end of a
+    /// tag. It is boxed so that ports which never use it only
+    /// pay for a null pointer.
+    last: UncheckedCell<LastValue<T>>,
+
+    /// Id of the port that created this cell. Once ports are
+    /// bound, this is the upstream-most port of the equiv class.
+    origin: TriggerId,
+
     /// This is the set of ports that are "forwarded to".
     /// When you bind 2 ports A -> B, then the binding of B
     /// is updated to point to the equiv class of A. The downstream
@@ -304,7 +518,7 @@ struct PortCell<T: Sync> {
     /// - say you have bound A -> B, then B -> C
     /// - so all three refer to the equiv class of A, whose downstream is now {B, C}
     /// - if you then try binding C -> A, then we can know
-    /// that C is in the downstream of A, indicating that there is a cycle.
+    ///   that C is in the downstream of A, indicating that there is a cycle.
     downstreams: Downstreams<T>,
 }
 
@@ -334,10 +548,12 @@ impl<T: Sync> PortCell<T> {
     }
 }
 
-impl<T: Sync> Default for PortCell<T> {
-    fn default() -> Self {
+impl<T: Sync> PortCell<T> {
+    fn new(origin: TriggerId) -> Self {
         PortCell {
             value: Default::default(),
+            last: Default::default(),
+            origin,
             downstreams: Default::default(),
         }
     }
@@ -345,6 +561,18 @@ impl<T: Sync> Default for PortCell<T> {
 
 /// A multiport is a vector of independent ports (its _channels_)
 /// Multiports have special Lingua Franca syntax, similar to reactor banks.
+///
+/// All the iterators of a multiport yield channels by increasing
+/// index, whatever the channels that are present, so that reactions
+/// that iterate over them behave deterministically. They can all be
+/// reversed to yield channels by decreasing index:
+/// ```no_run
+/// # use reactor_rt::prelude::*;
+/// # let ctx: &mut ReactionCtx = panic!();
+/// # let inputs: &Multiport<u32> = panic!();
+/// // the present channel with the highest index wins
+/// let winner = inputs.enumerate_values().next_back();
+/// ```
 pub struct Multiport<T: Sync> {
     ports: Vec<Port<T>>,
     id: TriggerId,
@@ -371,27 +599,40 @@ impl<T: Sync> Multiport<T> {
 
     /// Iterate over the multiport and return mutable references to individual channels.
     #[inline(always)]
-    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Port<T>> {
+    pub fn iter_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut Port<T>> + ExactSizeIterator {
         self.ports.iter_mut()
     }
 
     /// Iterate over the channels of this multiport. Returns read-only
     /// references to individual ports.
     #[inline(always)]
-    pub fn iter(&self) -> impl Iterator<Item = &Port<T>> {
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Port<T>> + ExactSizeIterator {
         self.into_iter()
     }
 
+    /// Iterate over the channels of this multiport, with their index.
+    #[inline(always)]
+    pub fn enumerate(&self) -> impl DoubleEndedIterator<Item = (usize, &Port<T>)> + ExactSizeIterator {
+        self.iter().enumerate()
+    }
+
+    /// Iterate over mutable references to the channels of
+    /// this multiport, with their index.
+    #[inline(always)]
+    pub fn enumerate_mut(&mut self) -> impl DoubleEndedIterator<Item = (usize, &mut Port<T>)> + ExactSizeIterator {
+        self.iter_mut().enumerate()
+    }
+
     /// Iterate over only those channels that are set (have a value).
     /// Returns a tuple with their index (not necessarily contiguous).
-    pub fn enumerate_set(&self) -> impl Iterator<Item = (usize, &Port<T>)> {
-        self.iter().enumerate().filter(|&(_, p)| p.is_present_now())
+    pub fn enumerate_set(&self) -> impl DoubleEndedIterator<Item = (usize, &Port<T>)> {
+        self.enumerate().filter(|&(_, p)| p.is_present_now())
     }
 
     /// Iterate over only those channels that are set (have a value).
     /// The returned ports are not necessarily contiguous. See
     /// [Self::enumerate_set] to get access to their index.
-    pub fn iterate_set(&self) -> impl Iterator<Item = &Port<T>> {
+    pub fn iterate_set(&self) -> impl DoubleEndedIterator<Item = &Port<T>> {
         self.iter().filter(|&p| p.is_present_now())
     }
 
@@ -399,7 +640,7 @@ impl<T: Sync> Multiport<T> {
     /// and return a copy of the value.
     /// The returned ports are not necessarily contiguous. See
     /// [Self::enumerate_values] to get access to their index.
-    pub fn iterate_values(&self) -> impl Iterator<Item = T> + '_
+    pub fn iterate_values(&self) -> impl DoubleEndedIterator<Item = T> + '_
     where
         T: Copy,
     {
@@ -411,24 +652,24 @@ impl<T: Sync> Multiport<T> {
     /// The returned ports are not necessarily contiguous. See
     /// [Self::enumerate_values] to get access to their index.
     #[cfg(not(feature = "no-unsafe"))]
-    pub fn iterate_values_ref(&self) -> impl Iterator<Item = &T> + '_ {
+    pub fn iterate_values_ref(&self) -> impl DoubleEndedIterator<Item = &T> + '_ {
         self.iter().filter_map(|p| p.get_ref())
     }
 
     /// Iterate over only those channels that are set (have a value),
     /// yielding a tuple with their index in the bank and a copy of the value.
-    pub fn enumerate_values(&self) -> impl Iterator<Item = (usize, T)> + '_
+    pub fn enumerate_values(&self) -> impl DoubleEndedIterator<Item = (usize, T)> + '_
     where
         T: Copy,
     {
-        self.iter().enumerate().filter_map(|(i, p)| p.get().map(|v| (i, v)))
+        self.enumerate().filter_map(|(i, p)| p.get().map(|v| (i, v)))
     }
 
     /// Iterate over only those channels that are set (have a value),
     /// yielding a tuple with their index in the bank and a reference to the value.
     #[cfg(not(feature = "no-unsafe"))]
-    pub fn enumerate_values_ref(&self) -> impl Iterator<Item = (usize, &T)> + '_ {
-        self.iter().enumerate().filter_map(|(i, p)| p.get_ref().map(|v| (i, v)))
+    pub fn enumerate_values_ref(&self) -> impl DoubleEndedIterator<Item = (usize, &T)> + '_ {
+        self.enumerate().filter_map(|(i, p)| p.get_ref().map(|v| (i, v)))
     }
 }
 
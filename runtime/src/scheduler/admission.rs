@@ -0,0 +1,90 @@
+//! Admission control of physical events while the scheduler is overloaded.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Policy to reject physical events of low priority when
+/// the event queue of the scheduler grows too long. This
+/// protects the timeline of critical inputs under overload.
+/// See [SchedulerOptions::admission](crate::SchedulerOptions::admission).
+///
+/// Each physical action has an admission priority, which
+/// is zero by default and can be set at assembly time with
+/// [PhysicalActionRef::with_admission_priority](crate::PhysicalActionRef::with_admission_priority).
+/// While the queue holds at least [Self::queue_threshold] events,
+/// only actions with a priority of at least [Self::min_priority]
+/// may be scheduled, and [AsyncCtx::schedule_physical_with_v](crate::AsyncCtx::schedule_physical_with_v)
+/// returns an error for other actions. The value that was
+/// to be scheduled is then given back to the sender.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct AdmissionPolicy {
+    /// Number of pending events from which the scheduler
+    /// is considered overloaded.
+    pub queue_threshold: usize,
+    /// Minimum priority of the physical actions that are
+    /// still admitted while the scheduler is overloaded.
+    pub min_priority: u32,
+}
+
+/// Admission state shared between the scheduler and
+/// asynchronous threads.
+#[derive(Debug)]
+pub(crate) struct AdmissionControl {
+    policy: Option<AdmissionPolicy>,
+    /// Number of pending events from which all physical
+    /// events are rejected, see [BudgetPolicy::RejectPhysical](super::BudgetPolicy::RejectPhysical).
+    max_events: Option<usize>,
+    /// Number of events pending in the event queue, as last
+    /// observed by the scheduler.
+    queue_depth: AtomicUsize,
+}
+
+impl AdmissionControl {
+    pub(super) fn new(policy: Option<AdmissionPolicy>, max_events: Option<usize>) -> Self {
+        Self {
+            policy,
+            max_events,
+            queue_depth: AtomicUsize::new(0),
+        }
+    }
+
+    pub(super) fn set_queue_depth(&self, depth: usize) {
+        self.queue_depth.store(depth, Ordering::Relaxed);
+    }
+
+    /// Whether an event for a physical action with the
+    /// given priority may be sent to the scheduler.
+    pub(super) fn admits(&self, priority: u32) -> bool {
+        let depth = self.queue_depth.load(Ordering::Relaxed);
+        let overloaded = |policy: &AdmissionPolicy| priority < policy.min_priority && depth >= policy.queue_threshold;
+        !self.policy.as_ref().map_or(false, overloaded) && self.max_events.map_or(true, |max| depth < max)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_reject_low_priority_when_overloaded() {
+        let admission = AdmissionControl::new(Some(AdmissionPolicy { queue_threshold: 10, min_priority: 5 }), None);
+        assert!(admission.admits(0));
+
+        admission.set_queue_depth(10);
+        assert!(!admission.admits(0));
+        assert!(!admission.admits(4));
+        assert!(admission.admits(5));
+        assert!(admission.admits(u32::MAX));
+
+        admission.set_queue_depth(9);
+        assert!(admission.admits(0));
+    }
+
+    #[test]
+    fn test_reject_all_when_budget_is_exhausted() {
+        let admission = AdmissionControl::new(None, Some(4));
+        admission.set_queue_depth(3);
+        assert!(admission.admits(0));
+        admission.set_queue_depth(4);
+        assert!(!admission.admits(u32::MAX));
+    }
+}
@@ -0,0 +1,221 @@
+//! On-line detection of tags that take unusually long to process.
+
+use std::fmt::{Display, Formatter};
+
+use super::DebugInfoProvider;
+use crate::*;
+
+/// Detects reaction waves whose execution time deviates
+/// significantly from the recent past, and reports them
+/// to a user-provided callback.
+///
+/// The detector maintains an exponentially weighted moving
+/// average (EWMA) of the wall-clock time taken to process
+/// each tag, along with an exponentially weighted variance.
+/// A tag is reported if its processing time exceeds the
+/// average by more than [Self::threshold] standard deviations,
+/// and by at least [Self::min_excess].
+///
+/// Install it with [SchedulerOptions::anomaly_detector].
+/// While a detector is installed, the scheduler times every
+/// reaction individually, so that reports can name the reactions
+/// that dominated the wave.
+pub struct AnomalyDetector {
+    /// Smoothing factor of the moving average, in `(0, 1]`.
+    /// Higher values make the detector forget the past faster.
+    pub alpha: f64,
+    /// Number of standard deviations above the average
+    /// beyond which a tag is reported.
+    pub threshold: f64,
+    /// Number of tags to observe before any report is issued,
+    /// so that the average has time to settle.
+    pub warmup: u32,
+    /// Minimum absolute difference between the measured
+    /// and the expected processing time for a tag to be reported.
+    /// This filters out noise on very short waves.
+    pub min_excess: Duration,
+    /// Max number of reactions to include in a report.
+    pub max_reported_reactions: usize,
+
+    mean: f64,
+    variance: f64,
+    samples: u32,
+    callback: Box<dyn FnMut(&TagAnomaly) + Send>,
+}
+
+impl AnomalyDetector {
+    /// Create a detector with default parameters, which calls
+    /// the given function every time an anomaly is detected.
+    /// The callback runs on the scheduler thread, between two tags,
+    /// so it should return quickly.
+    pub fn new(callback: impl FnMut(&TagAnomaly) + Send + 'static) -> Self {
+        Self {
+            alpha: 0.1,
+            threshold: 3.0,
+            warmup: 20,
+            min_excess: Duration::from_micros(100),
+            max_reported_reactions: 3,
+            mean: 0.0,
+            variance: 0.0,
+            samples: 0,
+            callback: Box::new(callback),
+        }
+    }
+
+    /// Record the processing time of a tag. Returns the expected
+    /// processing time and the deviation (in standard deviations)
+    /// if the sample is anomalous. The statistics are updated
+    /// in any case.
+    pub(super) fn observe(&mut self, elapsed: Duration) -> Option<(Duration, f64)> {
+        let x = elapsed.as_nanos() as f64;
+        let std_dev = self.variance.sqrt();
+        let excess = x - self.mean;
+
+        let anomaly =
+            if self.samples >= self.warmup && excess > self.threshold * std_dev && excess >= self.min_excess.as_nanos() as f64 {
+                let deviation = if std_dev > 0.0 { excess / std_dev } else { f64::INFINITY };
+                Some((Duration::from_nanos(self.mean as u64), deviation))
+            } else {
+                None
+            };
+
+        if self.samples == 0 {
+            self.mean = x;
+        } else {
+            // incremental EWMA and EW variance
+            let incr = self.alpha * excess;
+            self.mean += incr;
+            self.variance = (1.0 - self.alpha) * (self.variance + excess * incr);
+        }
+        self.samples = self.samples.saturating_add(1);
+
+        anomaly
+    }
+
+    /// Observe the wave for the given tag, and invoke the
+    /// callback if it is anomalous.
+    pub(super) fn observe_tag(
+        &mut self,
+        tag: EventTag,
+        elapsed: Duration,
+        mut timings: Vec<(GlobalReactionId, Duration)>,
+        debug: &DebugInfoProvider<'_>,
+    ) {
+        if let Some((expected, deviation)) = self.observe(elapsed) {
+            timings.sort_by(|(_, a), (_, b)| b.cmp(a));
+            timings.truncate(self.max_reported_reactions);
+            let anomaly = TagAnomaly {
+                tag,
+                elapsed,
+                expected,
+                deviation,
+                dominant_reactions: timings
+                    .into_iter()
+                    .map(|(reaction, elapsed)| ReactionTiming {
+                        reaction,
+                        name: debug.display_reaction(reaction).to_string(),
+                        elapsed,
+                    })
+                    .collect(),
+            };
+            warn!("{}", anomaly);
+            (self.callback)(&anomaly)
+        }
+    }
+}
+
+/// Report produced by an [AnomalyDetector].
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TagAnomaly {
+    /// The tag whose processing was anomalous.
+    pub tag: EventTag,
+    /// Wall-clock time taken to process the tag.
+    pub elapsed: Duration,
+    /// Processing time the detector expected, ie the moving average.
+    pub expected: Duration,
+    /// How far the processing time was from the expected
+    /// time, in standard deviations. This may be infinite
+    /// if all previous tags took exactly the same time.
+    pub deviation: f64,
+    /// The reactions that took the most time during this tag,
+    /// slowest first.
+    pub dominant_reactions: Vec<ReactionTiming>,
+}
+
+/// Execution time of a single reaction.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReactionTiming {
+    pub reaction: GlobalReactionId,
+    /// Human-readable name of the reaction.
+    pub name: String,
+    pub elapsed: Duration,
+}
+
+impl Display for TagAnomaly {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Tag {} took {} µs, expected {} µs ({:.1} std devs)",
+            self.tag,
+            self.elapsed.as_micros(),
+            self.expected.as_micros(),
+            self.deviation
+        )?;
+        if self.dominant_reactions.is_empty() {
+            return Ok(());
+        }
+        join_to!(
+            f,
+            self.dominant_reactions.iter(),
+            ", ",
+            "; slowest reactions: ",
+            "",
+            |t| format!("{} ({} µs)", t.name, t.elapsed.as_micros())
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn detector() -> AnomalyDetector {
+        let mut d = AnomalyDetector::new(|_| {});
+        d.warmup = 5;
+        d.min_excess = Duration::from_micros(10);
+        d
+    }
+
+    #[test]
+    fn test_no_report_during_warmup() {
+        let mut d = detector();
+        for _ in 0..4 {
+            assert!(d.observe(Duration::from_micros(100)).is_none());
+        }
+        assert!(d.observe(Duration::from_secs(1)).is_none());
+    }
+
+    #[test]
+    fn test_report_outlier() {
+        let mut d = detector();
+        for i in 0..50 {
+            assert!(d.observe(Duration::from_micros(100 + i % 5)).is_none());
+        }
+        let (expected, deviation) = d.observe(Duration::from_millis(5)).expect("should be reported");
+        assert!(expected < Duration::from_micros(110));
+        assert!(deviation > d.threshold);
+    }
+
+    #[test]
+    fn test_min_excess_filters_noise() {
+        let mut d = detector();
+        for _ in 0..50 {
+            d.observe(Duration::from_micros(100));
+        }
+        // infinitely many std devs, but below min_excess
+        assert!(d.observe(Duration::from_micros(105)).is_none());
+        assert!(d.observe(Duration::from_micros(200)).is_some());
+    }
+}
@@ -30,6 +30,8 @@ use index_vec::{Idx, IndexVec};
 use super::{ReactorBox, ReactorVec};
 use crate::assembly::*;
 use crate::scheduler::dependencies::DepGraph;
+use crate::scheduler::startup_budget::StartupProfiler;
+use crate::scheduler::validation::BUILTIN_RULES;
 use crate::*;
 
 /// Globals shared by all assemblers.
@@ -40,20 +42,48 @@ pub(super) struct RootAssembler {
     pub(super) graph: DepGraph,
     /// Debug infos
     pub(super) debug_info: DebugInfoRegistry,
+    /// Values that ports take at the shutdown tag.
+    pub(super) last_wills: Vec<LastWill>,
+    /// Ports whose values are read by other threads.
+    pub(super) exports: Vec<PortExport>,
 
     /// Next reactor ID to assign
     reactor_id: ReactorId,
     /// Next trigger ID to assign
     cur_trigger: TriggerId,
+
+    /// If the program is being validated, collects diagnostics
+    /// instead of failing on the first error.
+    report: Option<ValidationReport>,
+
+    /// If the startup has a budget, measures the
+    /// time spent assembling each subtree.
+    profiler: Option<StartupProfiler>,
+}
+
+/// A program assembled by [RootAssembler::assemble_tree].
+pub(super) struct AssembledTree {
+    pub(super) reactors: ReactorVec<'static>,
+    pub(super) graph: DepGraph,
+    pub(super) id_registry: DebugInfoRegistry,
+    pub(super) last_wills: Vec<LastWill>,
+    pub(super) exports: Vec<PortExport>,
+    /// Given back so that the analysis passes can be measured too.
+    pub(super) profiler: Option<StartupProfiler>,
 }
 
 impl RootAssembler {
     /// Register a reactor into the global data structure that owns them during execution.
     fn register_reactor<R: ReactorInitializer + 'static>(&mut self, child: R) {
+        self.register_boxed(Box::new(child))
+    }
+
+    /// Register a reactor whose type is erased, see [AssemblyCtx::with_child_dyn].
+    fn register_boxed(&mut self, child: ReactorBox<'static>) {
         if child.id().index() >= self.reactors.len() {
             self.reactors.resize_with(child.id().index() + 1, || None)
         }
-        let prev = self.reactors[child.id()].replace(Box::new(child));
+        let prev = self.reactors[child.id()].replace(child);
         // this is impossible because we control how we allocate IDs entirely
         debug_assert!(prev.is_none(), "Overwrote a reactor during initialization")
     }
@@ -68,21 +98,99 @@ impl RootAssembler {
     /// Top level fun that assembles the main reactor
     pub fn assemble_tree<R: ReactorInitializer + 'static>(
         main_args: R::Params,
-    ) -> (ReactorVec<'static>, DepGraph, DebugInfoRegistry) {
-        let mut root = RootAssembler::default();
-        let assembler = AssemblyCtx::new(&mut root, ReactorDebugInfo::root::<R::Wrapped>());
+        profiler: Option<StartupProfiler>,
+    ) -> AssembledTree {
+        let mut root = RootAssembler { profiler, ..Default::default() };
+        let start = Instant::now();
+        let assembler = AssemblyCtx::new(&mut root, ReactorDebugInfo::root::<R::Wrapped>(), None);
 
         let main_reactor = match R::assemble(main_args, assembler) {
             Ok(main) => main.finish(),
             Err(e) => std::panic::panic_any(e.lift(&root.debug_info)),
         };
         root.debug_info.record_main_reactor(main_reactor.id());
+        root.subtree_done(main_reactor.id(), start);
         root.register_reactor(main_reactor);
 
-        let RootAssembler { graph, reactors, debug_info: id_registry, .. } = root;
+        let RootAssembler {
+            graph,
+            reactors,
+            debug_info: id_registry,
+            last_wills,
+            exports,
+            mut profiler,
+            ..
+        } = root;
+        if let Some(profiler) = &mut profiler {
+            profiler.pass_done("assembly", start);
+        }
 
         let reactors = reactors.into_iter().map(|r| r.expect("Uninitialized reactor!")).collect();
-        (reactors, graph, id_registry)
+        AssembledTree {
+            reactors,
+            graph,
+            id_registry,
+            last_wills,
+            exports,
+            profiler,
+        }
+    }
+
+    /// Assemble the main reactor, collecting all errors and
+    /// warnings instead of stopping at the first error. The
+    /// given rules are checked after the built-in ones.
+    pub fn validate_tree<R: ReactorInitializer + 'static>(
+        main_args: R::Params,
+        rules: &[&dyn ValidationRule],
+    ) -> ValidationReport {
+        let mut root = RootAssembler {
+            report: Some(Default::default()),
+            ..Default::default()
+        };
+        let assembler = AssemblyCtx::new(&mut root, ReactorDebugInfo::root::<R::Wrapped>(), None);
+
+        let result = R::assemble(main_args, assembler).map(FinishedReactor::finish);
+        let mut report = root.report.take().unwrap();
+        match result {
+            Ok(main) => {
+                root.debug_info.record_main_reactor(main.id());
+                let program = ProgramGraph::new(&root.graph, &root.debug_info);
+                for rule in BUILTIN_RULES.iter().chain(rules) {
+                    rule.check(&program, &mut report);
+                }
+            }
+            // errors that cannot be recovered from
+            Err(e) => report.errors.push(e.to_diagnostic(&root.debug_info)),
+        }
+        report
+    }
+
+    /// Handle an error after which assembly can continue.
+    /// When validating, the error is recorded, otherwise
+    /// it is returned.
+    fn recover(&mut self, error: AssemblyError) -> AssemblyResult<()> {
+        match &mut self.report {
+            Some(report) => {
+                report.errors.push(error.to_diagnostic(&self.debug_info));
+                Ok(())
+            }
+            None => Err(error),
+        }
+    }
+
+    /// Record the time spent assembling a reactor and its
+    /// children, if the startup has a budget.
+    fn subtree_done(&mut self, reactor: ReactorId, since: Instant) {
+        if let Some(profiler) = &mut self.profiler {
+            profiler.subtree_done(reactor, since.elapsed(), &self.debug_info);
+        }
+    }
+
+    fn warn(&mut self, warning: Diagnostic) {
+        match &mut self.report {
+            Some(report) => report.warnings.push(warning),
+            None => warn!("{}", warning),
+        }
     }
 }
 
@@ -92,8 +200,12 @@ impl Default for RootAssembler {
             reactor_id: ReactorId::new(0),
             graph: DepGraph::new(),
             debug_info: DebugInfoRegistry::new(),
+            last_wills: Vec::new(),
+            exports: Vec::new(),
             reactors: Default::default(),
             cur_trigger: TriggerId::FIRST_REGULAR,
+            report: None,
+            profiler: None,
         }
     }
 }
@@ -114,12 +226,18 @@ where
     debug: Option<ReactorDebugInfo>,
     /// IDs of children used for debug info.
     children_ids: Vec<ReactorId>,
+    /// Priority of the non-synthetic reactions of this reactor,
+    /// in declaration order. They are chained by priority edges
+    /// once dependencies are declared.
+    priorities: Vec<(GlobalReactionId, u32)>,
+    /// Index of this reactor in its bank, if it is part of one.
+    bank_index: Option<usize>,
 
     _phantom: PhantomData<S>,
 }
 
 /// Final result of the assembly of a reactor.
-pub struct FinishedReactor<'x, S>(AssemblyCtx<'x, S>, S)
+pub struct FinishedReactor<'x, S>(#[allow(unused)] AssemblyCtx<'x, S>, S)
 where
     S: ReactorInitializer;
 
@@ -132,7 +250,7 @@ impl<'x, S> AssemblyCtx<'x, S>
 where
     S: ReactorInitializer,
 {
-    fn new(globals: &'x mut RootAssembler, debug: ReactorDebugInfo) -> Self {
+    fn new(globals: &'x mut RootAssembler, debug: ReactorDebugInfo, bank_index: Option<usize>) -> Self {
         Self {
             globals,
             // this is not zero, so that reaction ids and component ids are disjoint
@@ -140,9 +258,20 @@ where
             debug: Some(debug),
             _phantom: PhantomData,
             children_ids: Vec::default(),
+            priorities: Vec::default(),
+            bank_index,
         }
     }
 
+    /// Index of this reactor in its bank, if it was created
+    /// by [Self::with_child_bank]. This is the `bank_index`
+    /// of LF, which members can read without the parent
+    /// passing it in their parameters.
+    #[inline]
+    pub fn bank_index(&self) -> Option<usize> {
+        self.bank_index
+    }
+
     /// top level function
     pub fn assemble(
         self,
@@ -174,7 +303,7 @@ where
         // makes this whole debug info recording very complicated.
         let id = self.globals.reactor_id.get_and_incr();
         let debug = self.debug.take().expect("unreachable - can only call assemble_self once");
-        trace!("Children of {}: {:?}", debug.to_string(), self.children_ids);
+        trace!("Children of {}: {:?}", debug, self.children_ids);
         self.globals.debug_info.record_reactor(id, debug);
         for child in self.children_ids.drain(..) {
             self.globals.debug_info.record_reactor_container(id, child);
@@ -182,7 +311,7 @@ where
 
         let first_trigger_id = self.globals.cur_trigger;
 
-        let mut ich = create_self(&mut ComponentCreator { assembler: &mut self }, id)?;
+        let mut ich = create_self(&mut ComponentCreator { assembler: &mut self, id }, id)?;
         // after creation, globals.cur_trigger has been mutated
         // record proper debug info.
         self.globals
@@ -192,12 +321,14 @@ where
         // declare dependencies
         let reactions = self.new_reactions(id, num_non_synthetic_reactions, reaction_names);
         declare_dependencies(&mut DependencyDeclarator { assembler: &mut self }, &mut ich, reactions)?;
+        self.chain_priorities();
         Ok(AssemblyIntermediate(self, ich))
     }
 
     /// Create N reactions. The first `num_non_synthetic` get
     /// priority edges, as they are taken to be those declared
-    /// in LF by the user.
+    /// in LF by the user. Their priority is their index, unless
+    /// overridden with [DependencyDeclarator::set_priority].
     /// The rest do not have priority edges, and their
     /// implementation must hence have no observable side-effect.
     fn new_reactions<const N: usize>(
@@ -210,26 +341,32 @@ where
 
         let result = array![i => GlobalReactionId::new(my_id, LocalReactionId::from_usize(i)); N];
 
-        let mut prev: Option<GlobalReactionId> = None;
         for (i, r) in result.iter().cloned().enumerate() {
             if let Some(label) = names[i] {
                 self.globals.debug_info.record_reaction(r, Cow::Borrowed(label))
             }
             self.globals.graph.record_reaction(r);
             if i < num_non_synthetic {
-                if let Some(prev) = prev {
-                    // Add an edge that represents that the
-                    // previous reaction takes precedence
-                    self.globals.graph.reaction_priority(prev, r);
-                }
+                self.priorities.push((r, i as u32));
             }
-            prev = Some(r);
         }
 
         self.cur_local = self.cur_local.plus(N);
         result
     }
 
+    /// Add priority edges between the non-synthetic reactions,
+    /// by increasing priority. Ties keep the declaration order.
+    fn chain_priorities(&mut self) {
+        let mut ordered = std::mem::take(&mut self.priorities);
+        ordered.sort_by_key(|&(_, priority)| priority);
+        for pair in ordered.windows(2) {
+            // Add an edge that represents that the
+            // previous reaction takes precedence
+            self.globals.graph.reaction_priority(pair[0].0, pair[1].0);
+        }
+    }
+
     /// Assembles a child reactor and makes it available in
     /// the scope of a function.
     #[inline]
@@ -252,20 +389,24 @@ where
     }
 
     /// Assembles a bank of children reactor and makes it
-    /// available in the scope of a function.
+    /// available in the scope of a function. Members are
+    /// ordered by bank index: the member at index `i` was
+    /// created with the parameters `arg_maker(i)`, so ports
+    /// bound member by member to a multiport keep that order.
+    /// Members can also read their index with [Self::bank_index].
     #[inline]
     pub fn with_child_bank<Sub, A, F>(
         mut self,
         inst_name: &'static str,
         bank_width: usize,
-        arg_maker: A,
+        mut arg_maker: A,
         action: F,
     ) -> AssemblyResult<AssemblyIntermediate<'x, S>>
     where
         Sub: ReactorInitializer + 'static,
         // we can't use impl Fn(...) because we want to specify explicit type parameters in the calle
         F: FnOnce(Self, &mut Vec<Sub>) -> AssemblyResult<AssemblyIntermediate<'x, S>>,
-        A: Fn(/*bank_index:*/ usize) -> Sub::Params,
+        A: FnMut(/*bank_index:*/ usize) -> Sub::Params,
     {
         trace!("Assembling bank {}", inst_name);
 
@@ -280,6 +421,53 @@ where
         Ok(AssemblyIntermediate(ich, r))
     }
 
+    /// Assembles a child reactor whose type is only known at
+    /// runtime, eg because it is chosen by a configuration file,
+    /// and makes it available in the scope of a function through
+    /// its interface `I`. The interface is typically a trait that
+    /// gives access to the ports of the child, so that the parent
+    /// can bind them like those of other children:
+    ///
+    /// ```ignore
+    /// trait Filter {
+    ///     fn ports(&mut self) -> (&mut Port<u32>, &mut Port<u32>);
+    /// }
+    ///
+    /// // eg looked up in a registry by name
+    /// let filter: Box<dyn DynReactorInitializer<dyn Filter>> = match config.filter {
+    ///     "double" => DynReactor::<Doubler, dyn Filter>::boxed((), |r| r),
+    ///     _ => DynReactor::<Identity, dyn Filter>::boxed((), |r| r),
+    /// };
+    /// ctx.with_child_dyn("filter", filter, |mut ctx, filter| {
+    ///     let (input, output) = filter.ports();
+    ///     ctx.bind_ports(&mut source.out, input)?;
+    ///     ctx.bind_ports(output, &mut sink.inp)?;
+    ///     // ...
+    /// })
+    /// ```
+    pub fn with_child_dyn<I: ?Sized + 'static, F>(
+        mut self,
+        inst_name: &'static str,
+        initializer: Box<dyn DynReactorInitializer<I>>,
+        action: F,
+    ) -> AssemblyResult<AssemblyIntermediate<'x, S>>
+    where
+        F: FnOnce(Self, &mut I) -> AssemblyResult<AssemblyIntermediate<'x, S>>,
+    {
+        trace!("Assembling {}", inst_name);
+        let parent = self.debug.as_ref().expect("should assemble sub-reactors before self");
+        let start = Instant::now();
+        let slot = ChildSlot { globals: self.globals, parent, inst_name };
+        let DynChild(mut sub) = initializer.assemble_dyn(slot)?;
+        self.globals.subtree_done(sub.id(), start);
+        self.children_ids.push(sub.id());
+
+        let AssemblyIntermediate(ich, s) = action(self, sub.interface())?;
+        trace!("Registering {}", inst_name);
+        ich.globals.register_boxed(sub.into_behavior());
+        Ok(AssemblyIntermediate(ich, s))
+    }
+
     /// Assemble a child reactor. The child needs to be registered
     /// using [Self::register_reactor] later.
     #[inline(always)]
@@ -296,8 +484,10 @@ where
             Some(i) => my_debug.derive_bank_item::<Sub>(inst_name, i),
         };
 
-        let subctx = AssemblyCtx::new(self.globals, debug_info);
+        let start = Instant::now();
+        let subctx = AssemblyCtx::new(self.globals, debug_info, bank_idx);
         let subinst = Sub::assemble(args, subctx)?.finish();
+        self.globals.subtree_done(subinst.id(), start);
         self.children_ids.push(subinst.id());
         Ok(subinst)
     }
@@ -313,6 +503,88 @@ where
     }
 }
 
+/// The object-safe counterpart of [ReactorInitializer], for
+/// reactors that are seen through an interface `I`, see
+/// [AssemblyCtx::with_child_dyn]. It is implemented by [DynReactor].
+pub trait DynReactorInitializer<I: ?Sized> {
+    /// Assemble the reactor, usually with [ChildSlot::assemble].
+    fn assemble_dyn(self: Box<Self>, slot: ChildSlot<'_>) -> AssemblyResult<DynChild<I>>;
+}
+
+/// Where a [DynReactorInitializer] assembles its reactor.
+pub struct ChildSlot<'a> {
+    globals: &'a mut RootAssembler,
+    parent: &'a ReactorDebugInfo,
+    inst_name: &'static str,
+}
+
+impl ChildSlot<'_> {
+    /// Assemble a reactor of type `R`, which the parent will
+    /// see through the given view.
+    pub fn assemble<R: ReactorInitializer + 'static, I: ?Sized + 'static>(
+        self,
+        params: R::Params,
+        view: fn(&mut R) -> &mut I,
+    ) -> AssemblyResult<DynChild<I>> {
+        let debug = self.parent.derive::<R>(self.inst_name);
+        let reactor = R::assemble(params, AssemblyCtx::new(self.globals, debug, None))?.finish();
+        Ok(DynChild(Box::new(Viewed { reactor, view })))
+    }
+}
+
+/// A reactor assembled by a [DynReactorInitializer],
+/// whose type is erased.
+pub struct DynChild<I: ?Sized>(Box<dyn ErasedChild<I>>);
+
+trait ErasedChild<I: ?Sized> {
+    fn id(&self) -> ReactorId;
+    fn interface(&mut self) -> &mut I;
+    fn into_behavior(self: Box<Self>) -> ReactorBox<'static>;
+}
+
+struct Viewed<R, I: ?Sized> {
+    reactor: R,
+    view: fn(&mut R) -> &mut I,
+}
+
+impl<R: ReactorInitializer + 'static, I: ?Sized> ErasedChild<I> for Viewed<R, I> {
+    fn id(&self) -> ReactorId {
+        self.reactor.id()
+    }
+
+    fn interface(&mut self) -> &mut I {
+        (self.view)(&mut self.reactor)
+    }
+
+    fn into_behavior(self: Box<Self>) -> ReactorBox<'static> {
+        Box::new(self.reactor)
+    }
+}
+
+/// A [DynReactorInitializer] for reactors of type `R`.
+pub struct DynReactor<R: ReactorInitializer, I: ?Sized> {
+    params: R::Params,
+    view: fn(&mut R) -> &mut I,
+}
+
+impl<R: ReactorInitializer + 'static, I: ?Sized + 'static> DynReactor<R, I> {
+    /// The view usually just coerces the reactor to the
+    /// interface, eg `|r| r` if `I` is a trait object.
+    pub fn boxed(params: R::Params, view: fn(&mut R) -> &mut I) -> Box<dyn DynReactorInitializer<I>>
+    where
+        R::Params: 'static,
+    {
+        Box::new(Self { params, view })
+    }
+}
+
+impl<R: ReactorInitializer + 'static, I: ?Sized + 'static> DynReactorInitializer<I> for DynReactor<R, I> {
+    fn assemble_dyn(self: Box<Self>, slot: ChildSlot<'_>) -> AssemblyResult<DynChild<I>> {
+        let DynReactor { params, view } = *self;
+        slot.assemble::<R, I>(params, view)
+    }
+}
+
 /// Declares dependencies between components and reactions.
 pub struct DependencyDeclarator<'a, 'x, S: ReactorInitializer> {
     assembler: &'a mut AssemblyCtx<'x, S>,
@@ -325,6 +597,22 @@ impl<S: ReactorInitializer> DependencyDeclarator<'_, '_, S> {
         Ok(())
     }
 
+    /// Declare that the reaction is triggered at startup.
+    /// This is the same as declaring [TriggerId::STARTUP]
+    /// as a trigger.
+    #[inline]
+    pub fn triggered_by_startup(&mut self, reaction: GlobalReactionId) -> AssemblyResult<()> {
+        self.declare_triggers(TriggerId::STARTUP, reaction)
+    }
+
+    /// Declare that the reaction is triggered at shutdown.
+    /// This is the same as declaring [TriggerId::SHUTDOWN]
+    /// as a trigger.
+    #[inline]
+    pub fn triggered_by_shutdown(&mut self, reaction: GlobalReactionId) -> AssemblyResult<()> {
+        self.declare_triggers(TriggerId::SHUTDOWN, reaction)
+    }
+
     #[inline]
     pub fn effects_port<T: Sync>(&mut self, reaction: GlobalReactionId, port: &Port<T>) -> AssemblyResult<()> {
         self.effects_instantaneous(reaction, port.get_id())
@@ -340,40 +628,137 @@ impl<S: ReactorInitializer> DependencyDeclarator<'_, '_, S> {
         self.effects_instantaneous(reaction, timer.get_id())
     }
 
+    /// Declare that the reaction may set or schedule the given
+    /// component. This dispatches on the kind of component,
+    /// see [EffectLike].
+    #[inline]
+    pub fn declare_effect(&mut self, reaction: GlobalReactionId, component: &impl EffectLike) -> AssemblyResult<()> {
+        component.declare_effect(self, reaction)
+    }
+
+    #[inline]
+    fn effects_delayed(&mut self, reaction: GlobalReactionId, action: TriggerId) -> AssemblyResult<()> {
+        self.graph().reaction_schedules(reaction, action);
+        Ok(())
+    }
+
     #[inline]
     fn effects_instantaneous(&mut self, reaction: GlobalReactionId, trigger: TriggerId) -> AssemblyResult<()> {
         self.graph().reaction_effects(reaction, trigger);
         Ok(())
     }
 
+    /// Declare a deadline on the reaction. The reaction must
+    /// start executing at most this long (in physical time) after
+    /// the logical time of the tag. If it starts later, it is still
+    /// executed, but [ReactionCtx::deadline_violated] returns true,
+    /// so that it can run its violation handler instead of its body.
+    #[inline]
+    pub fn declare_deadline(&mut self, reaction: GlobalReactionId, deadline: Duration) -> AssemblyResult<()> {
+        self.graph().reaction_deadline(reaction, deadline);
+        Ok(())
+    }
+
     #[inline]
     pub fn declare_uses(&mut self, reaction: GlobalReactionId, trigger: TriggerId) -> AssemblyResult<()> {
         self.graph().reaction_uses(reaction, trigger);
         Ok(())
     }
 
+    /// Override the priority of a reaction of this reactor. When
+    /// several reactions of the reactor are triggered at the same
+    /// tag, those with a lower priority execute first. By default,
+    /// the priority of a reaction is its index in declaration order,
+    /// so that reactions execute in the order they are declared.
+    /// Reactions with equal priorities keep that order. Synthetic
+    /// reactions have no priority.
+    pub fn set_priority(&mut self, reaction: GlobalReactionId, priority: u32) -> AssemblyResult<()> {
+        match self.assembler.priorities.iter_mut().find(|(r, _)| *r == reaction) {
+            Some((_, p)) => {
+                *p = priority;
+                Ok(())
+            }
+            None => self
+                .assembler
+                .globals
+                .recover(AssemblyError(AssemblyErrorImpl::CannotPrioritize(reaction))),
+        }
+    }
+
+    /// Set the value that the port takes at the shutdown tag.
+    /// Reactions downstream of the port observe it, even if no
+    /// shutdown reaction sets the port, so that they see a defined
+    /// terminal state. A shutdown reaction that sets the port
+    /// overrides it. The port must not be bound to an upstream port.
+    pub fn set_last_will<T: Sync + 'static>(&mut self, port: &Port<T>, value: T) -> AssemblyResult<()> {
+        match port.last_will(value) {
+            Ok(will) => {
+                self.assembler.globals.last_wills.push(will);
+                Ok(())
+            }
+            Err(e) => self.assembler.globals.recover(e),
+        }
+    }
+
+    /// Export the values of the port to other threads. The
+    /// returned handle can be stored in the reactor, and moved
+    /// into the threads it spawns, see [ReadHandle].
+    pub fn export_port<T: Sync + Send + Clone + 'static>(&mut self, port: &Port<T>) -> ReadHandle<T> {
+        let (handle, export) = port.export();
+        self.assembler.globals.exports.push(export);
+        // the value must still be there when it is published
+        self.graph().port_observed(port.get_id());
+        handle
+    }
+
     /// Bind two ports together.
     #[inline]
     pub fn bind_ports<T: Sync>(&mut self, upstream: &mut Port<T>, downstream: &mut Port<T>) -> AssemblyResult<()> {
-        upstream.forward_to(downstream)?;
-        self.graph().port_bind(upstream, downstream);
-        Ok(())
+        match upstream.forward_to(downstream) {
+            Ok(()) => {
+                self.graph().port_bind(upstream, downstream);
+                Ok(())
+            }
+            Err(e) => self.assembler.globals.recover(e),
+        }
+    }
+
+    /// Bind the ports of two bundles, see [PortBundle::connect].
+    /// To connect peers, `a` is the initiator and `b` the responder.
+    /// To forward the bundle of a child to a bundle of its parent
+    /// on the same side, `a` is the one that sends the ports declared
+    /// `out`, ie the child for an initiator, and the parent for a
+    /// responder.
+    #[inline]
+    pub fn connect_bundles<B: PortBundle>(&mut self, a: &mut B, b: &mut B) -> AssemblyResult<()> {
+        B::connect(self, a, b)
     }
 
     /// Bind the ports of the upstream to those of the downstream,
-    /// as if zipping both iterators.
-    /// todo this will just throw away bindings if both iterators are not of the same size
-    ///  normally this should be reported by LFC as a warning, maybe we should implement the same thing here
+    /// as if zipping both iterators. If both iterators are not
+    /// of the same size, the remaining ports are left unbound,
+    /// and a warning is issued.
     #[inline]
     pub fn bind_ports_zip<'a, T: Sync + 'a>(
         &mut self,
-        upstream: impl Iterator<Item = &'a mut Port<T>>,
-        downstream: impl Iterator<Item = &'a mut Port<T>>,
+        mut upstream: impl Iterator<Item = &'a mut Port<T>>,
+        mut downstream: impl Iterator<Item = &'a mut Port<T>>,
     ) -> AssemblyResult<()> {
-        for (upstream, downstream) in upstream.zip(downstream) {
-            self.bind_ports(upstream, downstream)?;
+        loop {
+            match (upstream.next(), downstream.next()) {
+                (Some(upstream), Some(downstream)) => self.bind_ports(upstream, downstream)?,
+                (None, None) => return Ok(()),
+                (Some(unbound), None) | (None, Some(unbound)) => {
+                    let globals = &mut self.assembler.globals;
+                    let path = globals.debug_info.fmt_component(unbound.get_id()).to_string();
+                    globals.warn(Diagnostic {
+                        path: Some(path),
+                        message: "Port left unbound, both sides of the connection have a different number of ports".into(),
+                    });
+                    return Ok(());
+                }
+            }
         }
-        Ok(())
     }
 
     #[inline]
@@ -398,18 +783,84 @@ impl<S: ReactorInitializer> DependencyDeclarator<'_, '_, S> {
         Ok(())
     }
 
+    /// Bind the ports whose path matches the pattern to the
+    /// channels of the downstream multiport, in order. This is
+    /// typically used to connect the ports to observe to a
+    /// [Monitor](crate::stdlib::monitor::Monitor). Paths look
+    /// like `main/sensor/out`, and `*` matches any sequence of
+    /// characters in the pattern. Returns the number of ports
+    /// that were bound.
+    pub fn bind_matching<'a, T: Sync + 'a>(
+        &mut self,
+        pattern: &str,
+        candidates: impl IntoIterator<Item = &'a mut Port<T>>,
+        downstream: &mut Multiport<T>,
+    ) -> AssemblyResult<usize> {
+        let mut channels = downstream.iter_mut();
+        let mut num_bound = 0;
+        for port in candidates {
+            let path = self.assembler.globals.debug_info.fmt_component(port.get_id()).to_string();
+            if !matches_pattern(pattern, &path) {
+                continue;
+            }
+            match channels.next() {
+                Some(channel) => {
+                    self.bind_ports(port, channel)?;
+                    num_bound += 1;
+                }
+                None => self.assembler.globals.warn(Diagnostic {
+                    path: Some(path),
+                    message: format!("Port matches {}, but there is no channel left to bind it to", pattern),
+                }),
+            }
+        }
+        Ok(num_bound)
+    }
+
     #[inline]
     fn graph(&mut self) -> &mut DepGraph {
         &mut self.assembler.globals.graph
     }
 }
 
+/// Whether the path matches the pattern, where `*`
+/// matches any sequence of characters.
+fn matches_pattern(pattern: &str, path: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // there is always a first part, which must be a prefix
+    let mut rest = match path.strip_prefix(parts.next().unwrap()) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let mut parts = parts.peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            // the last part must be a suffix
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(ix) => rest = &rest[ix + part.len()..],
+            None => return false,
+        }
+    }
+    // no wildcard
+    rest.is_empty()
+}
+
 /// Creates the components of a reactor.
 pub struct ComponentCreator<'a, 'x, S: ReactorInitializer> {
     assembler: &'a mut AssemblyCtx<'x, S>,
+    /// Id of the reactor being assembled.
+    id: ReactorId,
 }
 
 impl<S: ReactorInitializer> ComponentCreator<'_, '_, S> {
+    /// See [AssemblyCtx::bank_index].
+    #[inline]
+    pub fn bank_index(&self) -> Option<usize> {
+        self.assembler.bank_index
+    }
+
     pub fn new_port<T: Sync>(&mut self, lf_name: &'static str, kind: PortKind) -> Port<T> {
         self.new_port_impl(Cow::Borrowed(lf_name), kind)
     }
@@ -420,6 +871,18 @@ impl<S: ReactorInitializer> ComponentCreator<'_, '_, S> {
         Port::new(id, kind)
     }
 
+    /// Create a port of a bundle, named `bundle.port`.
+    /// This is used by [port_bundle](crate::port_bundle).
+    #[doc(hidden)]
+    pub fn new_bundle_port<T: Sync>(&mut self, bundle: &'static str, port: &'static str, kind: PortKind) -> Port<T> {
+        self.new_port_impl(Cow::Owned(format!("{}.{}", bundle, port)), kind)
+    }
+
+    /// Create the ports of a bundle, see [PortBundle].
+    pub fn new_bundle<B: PortBundle>(&mut self, lf_name: &'static str, side: BundleSide) -> B {
+        B::new_bundle(self, lf_name, side)
+    }
+
     pub fn new_multiport<T: Sync>(
         &mut self,
         lf_name: &'static str,
@@ -460,12 +923,37 @@ impl<S: ReactorInitializer> ComponentCreator<'_, '_, S> {
         PhysicalActionRef::new(id, min_delay)
     }
 
+    /// Create a watchdog, see [Watchdog].
+    pub fn new_watchdog(&mut self, lf_name: &'static str) -> Watchdog {
+        Watchdog::new(self.new_physical_action(lf_name, None))
+    }
+
+    /// Create a timer. A period of zero means that the timer
+    /// triggers only once, see also [Self::new_periodic_timer].
     pub fn new_timer(&mut self, lf_name: &'static str, offset: Duration, period: Duration) -> Timer {
         let id = self.next_comp_id(Cow::Borrowed(lf_name));
         self.graph().record_timer(id);
         Timer::new(id, offset, period)
     }
 
+    /// Create a timer that triggers periodically. Unlike
+    /// with [Self::new_timer], the period cannot be zero.
+    pub fn new_periodic_timer(&mut self, lf_name: &'static str, offset: Delay, period: Period) -> Timer {
+        self.new_timer(lf_name, offset.into(), period.into())
+    }
+
+    /// Returns the capability to mutate the [Owned] components
+    /// of the reactor being assembled.
+    pub fn capability(&self) -> Capability<S> {
+        Capability::new(self.id)
+    }
+
+    /// Wrap a component so that only the reactor being assembled
+    /// may mutate it at runtime. See [Capability].
+    pub fn own<C>(&self, component: C) -> Owned<S, C> {
+        Owned::new(component, self.id)
+    }
+
     /// Create and return a new id for a trigger component.
     fn next_comp_id(&mut self, debug_name: Cow<'static, str>) -> TriggerId {
         let id = self
@@ -484,6 +972,332 @@ impl<S: ReactorInitializer> ComponentCreator<'_, '_, S> {
     }
 }
 
+/// A component that a reaction may declare as an effect,
+/// see [DependencyDeclarator::declare_effect].
+pub trait EffectLike {
+    #[doc(hidden)]
+    fn declare_effect<S: ReactorInitializer>(
+        &self,
+        declarator: &mut DependencyDeclarator<S>,
+        reaction: GlobalReactionId,
+    ) -> AssemblyResult<()>;
+}
+
+impl<T: Sync> EffectLike for Port<T> {
+    fn declare_effect<S: ReactorInitializer>(
+        &self,
+        decl: &mut DependencyDeclarator<S>,
+        reaction: GlobalReactionId,
+    ) -> AssemblyResult<()> {
+        decl.effects_port(reaction, self)
+    }
+}
+
+impl<T: Sync> EffectLike for Multiport<T> {
+    fn declare_effect<S: ReactorInitializer>(
+        &self,
+        decl: &mut DependencyDeclarator<S>,
+        reaction: GlobalReactionId,
+    ) -> AssemblyResult<()> {
+        decl.effects_multiport(reaction, self)
+    }
+}
+
+impl EffectLike for Timer {
+    fn declare_effect<S: ReactorInitializer>(
+        &self,
+        decl: &mut DependencyDeclarator<S>,
+        reaction: GlobalReactionId,
+    ) -> AssemblyResult<()> {
+        decl.effects_timer(reaction, self)
+    }
+}
+
+// Scheduling an action never triggers reactions at the
+// current tag, so those dependencies are delayed, and are
+// ignored when ordering the reactions of a tag.
+
+impl<T: Sync> EffectLike for LogicalAction<T> {
+    fn declare_effect<S: ReactorInitializer>(
+        &self,
+        decl: &mut DependencyDeclarator<S>,
+        reaction: GlobalReactionId,
+    ) -> AssemblyResult<()> {
+        decl.effects_delayed(reaction, self.get_id())
+    }
+}
+
+impl<T: Sync> EffectLike for PhysicalActionRef<T> {
+    fn declare_effect<S: ReactorInitializer>(
+        &self,
+        decl: &mut DependencyDeclarator<S>,
+        reaction: GlobalReactionId,
+    ) -> AssemblyResult<()> {
+        decl.effects_delayed(reaction, self.get_id())
+    }
+}
+
+impl<O, C: EffectLike> EffectLike for Owned<O, C> {
+    fn declare_effect<S: ReactorInitializer>(
+        &self,
+        decl: &mut DependencyDeclarator<S>,
+        reaction: GlobalReactionId,
+    ) -> AssemblyResult<()> {
+        (**self).declare_effect(decl, reaction)
+    }
+}
+
+/// Declares the triggers, uses and effects of reactions, for
+/// programs that are written by hand. This must be called
+/// within the dependency declaration closure of [AssemblyCtx::assemble_self],
+/// as it uses the `?` operator to propagate errors.
+///
+/// The first parameter is a pair of the [DependencyDeclarator]
+/// and of the reactor. Then follows a list of reactions, whose
+/// names are the variables bound to the [GlobalReactionId]
+/// of each reaction. Each reaction lists its triggers, uses,
+/// effects and deadline, each clause being optional. Components are named
+/// by the field of the reactor that contains them. The special
+/// triggers `startup` and `shutdown` are also recognized. Arbitrary
+/// expressions, eg referring to ports of child reactors, may be
+/// used if they are parenthesized.
+///
+/// ```ignore
+/// |__assembler, __self, [react_0, react_1]| {
+///     declare_reactions! {
+///         (__assembler, __self)
+///         react_0: triggers(startup, serve) effects(send);
+///         react_1: triggers(receive) uses((child.out)) effects(serve) deadline(delay!(5 ms));
+///     }
+///     Ok(())
+/// }
+/// ```
+#[macro_export]
+macro_rules! declare_reactions {
+    (
+        ($decl:ident, $this:ident)
+        $(
+            $reaction:ident :
+            $(triggers($($trigger:tt),* $(,)?))?
+            $(uses($($used:tt),* $(,)?))?
+            $(effects($($effect:tt),* $(,)?))?
+            $(deadline($deadline:expr))?
+            ;
+        )*
+    ) => {
+        $(
+            $($( $decl.declare_triggers($crate::__declared_trigger!($this, $trigger), $reaction)?; )*)?
+            $($( $decl.declare_uses($reaction, $crate::__declared_trigger!($this, $used))?; )*)?
+            $($( $decl.declare_effect($reaction, $crate::__declared_component!($this, $effect))?; )*)?
+            $( $decl.declare_deadline($reaction, $deadline)?; )?
+        )*
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __declared_trigger {
+    ($this:ident, startup) => {
+        $crate::assembly::TriggerId::STARTUP
+    };
+    ($this:ident, shutdown) => {
+        $crate::assembly::TriggerId::SHUTDOWN
+    };
+    ($this:ident, $component:tt) => {
+        $crate::assembly::TriggerLike::get_id($crate::__declared_component!($this, $component))
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __declared_component {
+    ($this:ident, ($e:expr)) => {
+        &$e
+    };
+    ($this:ident, $field:ident) => {
+        &$this.$field
+    };
+}
+
+/// Declares a reactor that only contains child reactors and
+/// connections between their ports, eg the main reactor of a
+/// hand-written program. This expands to the [ReactorInitializer]
+/// and [ReactorBehavior] implementations that a code generator
+/// would produce for such a reactor.
+///
+/// Each instance is declared with its name, its type, and the
+/// expression of its construction parameters. The parameters of
+/// the reactor itself may be bound to a variable, which is in
+/// scope of these expressions, eg `struct Main(args: Args);`.
+/// Each connection
+/// binds an upstream port to a downstream port, named by the
+/// instance and the field that contains the port.
+///
+/// ```ignore
+/// reactor_program! {
+///     /// Prints a greeting.
+///     pub struct Main;
+///     instances {
+///         p: Prompt = (),
+///         g: Greeter = GreeterParams::new("hello"),
+///     }
+///     connections {
+///         p.out -> g.prompt;
+///     }
+/// }
+///
+/// SyncScheduler::run_main::<Main>(Default::default(), ());
+/// ```
+#[macro_export]
+macro_rules! reactor_program {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident;
+        $($rest:tt)*
+    ) => {
+        $crate::reactor_program! {
+            $(#[$meta])*
+            $vis struct $name(_params: ());
+            $($rest)*
+        }
+    };
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident($params:ident : $params_ty:ty);
+        instances {
+            $($inst:ident : $inst_ty:ty = $inst_args:expr),* $(,)?
+        }
+        connections {
+            $($upstream:ident . $up_port:ident -> $downstream:ident . $down_port:ident;)*
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            id: $crate::ReactorId,
+        }
+
+        impl $crate::assembly::ReactorInitializer for $name {
+            type Wrapped = Self;
+            type Params = $params_ty;
+            const MAX_REACTION_ID: $crate::LocalReactionId = $crate::LocalReactionId::new(0);
+
+            fn assemble(
+                $params: Self::Params,
+                __ctx: $crate::assembly::AssemblyCtx<Self>,
+            ) -> $crate::assembly::AssemblyResult<$crate::assembly::FinishedReactor<Self>> {
+                __ctx.assemble(|__ctx| {
+                    $crate::__reactor_program_children!(__ctx [$($inst : $inst_ty = $inst_args),*] {
+                        __ctx.assemble_self(|_, id| Ok(Self { id }), 0, [], |__assembler, _, []| {
+                            $( __assembler.bind_ports(&mut $upstream.$up_port, &mut $downstream.$down_port)?; )*
+                            Ok(())
+                        })
+                    })
+                })
+            }
+        }
+
+        impl $crate::ReactorBehavior for $name {
+            fn id(&self) -> $crate::ReactorId {
+                self.id
+            }
+
+            fn react(&mut self, _: &mut $crate::ReactionCtx, rid: $crate::LocalReactionId) {
+                unreachable!("{} has no reaction {}", stringify!($name), rid)
+            }
+
+            fn cleanup_tag(&mut self, _: &$crate::CleanupCtx) {}
+        }
+    };
+}
+
+/// Declares a [PortBundle], a struct whose fields are ports.
+/// Fields are declared `out` or `in`, from the point of view
+/// of the [initiator](BundleSide::Initiator) of the connection.
+///
+/// ```ignore
+/// port_bundle! {
+///     /// A request and its response.
+///     pub struct Rpc {
+///         out request: u32,
+///         in response: u32,
+///     }
+/// }
+///
+/// // in the creator of the client, resp. server
+/// rpc: cc.new_bundle("rpc", BundleSide::Initiator),
+/// rpc: cc.new_bundle("rpc", BundleSide::Responder),
+/// // in the parent
+/// decl.connect_bundles(&mut client.rpc, &mut server.rpc)?;
+/// ```
+#[macro_export]
+macro_rules! port_bundle {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($(#[$field_meta:meta])* $dir:tt $field:ident : $ty:ty),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis struct $name {
+            $($(#[$field_meta])* pub $field: $crate::Port<$ty>,)*
+        }
+
+        impl $crate::assembly::PortBundle for $name {
+            fn new_bundle<S: $crate::assembly::ReactorInitializer>(
+                creator: &mut $crate::assembly::ComponentCreator<S>,
+                name: &'static str,
+                side: $crate::assembly::BundleSide,
+            ) -> Self {
+                Self {
+                    $($field: creator.new_bundle_port(name, stringify!($field), side.port_kind($crate::__bundle_dir!($dir))),)*
+                }
+            }
+
+            fn connect<S: $crate::assembly::ReactorInitializer>(
+                decl: &mut $crate::assembly::DependencyDeclarator<S>,
+                a: &mut Self,
+                b: &mut Self,
+            ) -> $crate::assembly::AssemblyResult<()> {
+                $(
+                    if $crate::__bundle_dir!($dir) {
+                        decl.bind_ports(&mut a.$field, &mut b.$field)?;
+                    } else {
+                        decl.bind_ports(&mut b.$field, &mut a.$field)?;
+                    }
+                )*
+                Ok(())
+            }
+        }
+    };
+}
+
+/// Whether a field of [port_bundle] is declared `out`.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __bundle_dir {
+    (out) => {
+        true
+    };
+    (in) => {
+        false
+    };
+}
+
+/// Nests the [AssemblyCtx::with_child] calls of [reactor_program],
+/// so that all instances are in scope of the innermost body.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __reactor_program_children {
+    ($ctx:ident [] $body:block) => {
+        $body
+    };
+    ($ctx:ident [$inst:ident : $inst_ty:ty = $inst_args:expr $(, $rest:ident : $rest_ty:ty = $rest_args:expr)*] $body:block) => {
+        $ctx.with_child::<$inst_ty, _>(stringify!($inst), $inst_args, |$ctx, #[allow(unused_variables)] $inst| {
+            $crate::__reactor_program_children!($ctx [$($rest : $rest_ty = $rest_args),*] $body)
+        })
+    };
+}
+
 /// Iterates a bank, produces an `Iterator<Item=&mut Port<_>>`.
 /// Does not explicitly borrow the bank, which is unsafe, but
 /// we trust the code generator to fail if a port is both on
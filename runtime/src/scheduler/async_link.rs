@@ -0,0 +1,94 @@
+//! Scheduling physical actions from async tasks,
+//! with the feature `async`.
+
+use std::future::Future;
+
+use crossbeam_channel::reconnectable::SendError;
+use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
+
+use crate::*;
+
+/// A link to the event queue of the scheduler, for async code.
+/// Unlike [AsyncCtx], which belongs to a thread started with
+/// [ReactionCtx::spawn_physical_thread], it may be cloned and
+/// shared between tasks, and its methods take `&self`. Sending
+/// an event never waits for the scheduler, so they may be
+/// called from an async task without blocking its executor.
+///
+/// Create it with [ReactionCtx::async_link]. Like [AsyncCtx],
+/// a live link keeps the program alive, see
+/// [SchedulerOptions::keep_alive].
+#[derive(Clone)]
+pub struct AsyncSchedulerLink {
+    ctx: AsyncCtx,
+}
+
+static_assertions::assert_impl_all!(AsyncSchedulerLink: Send, Sync, Clone);
+
+impl AsyncSchedulerLink {
+    /// See [AsyncCtx::was_terminated].
+    pub fn was_terminated(&self) -> bool {
+        self.ctx.was_terminated()
+    }
+
+    /// See [AsyncCtx::request_stop].
+    pub fn request_stop(&self, offset: Offset) -> Result<(), SendError<()>> {
+        self.ctx.send_stop(offset)
+    }
+
+    /// See [AsyncCtx::schedule_physical].
+    pub fn schedule_physical<T: Sync>(&self, action: &PhysicalActionRef<T>, offset: Offset) -> Result<(), SendError<Option<T>>> {
+        self.ctx.send_physical(action, None, offset)
+    }
+
+    /// See [AsyncCtx::schedule_physical_with_v].
+    pub fn schedule_physical_with_v<T: Sync>(
+        &self,
+        action: &PhysicalActionRef<T>,
+        value: Option<T>,
+        offset: Offset,
+    ) -> Result<(), SendError<Option<T>>> {
+        self.ctx.send_physical(action, value, offset)
+    }
+}
+
+impl ReactionCtx<'_, '_> {
+    /// Returns a link to the event queue, which async
+    /// tasks can use to schedule physical actions.
+    pub fn async_link(&self) -> AsyncSchedulerLink {
+        AsyncSchedulerLink { ctx: self.new_async_ctx() }
+    }
+
+    /// Spawn a task on the given tokio runtime, and schedule
+    /// the physical action with the output of the future when
+    /// it completes. The returned handle resolves to the result
+    /// of scheduling, which fails if the program has shut down
+    /// in the meantime.
+    ///
+    /// ```no_run
+    /// # use reactor_rt::prelude::*;
+    /// # let ctx: &mut ReactionCtx = panic!();
+    /// # let runtime: &tokio::runtime::Handle = panic!();
+    /// # let response: &PhysicalActionRef<String> = panic!();
+    /// # async fn fetch() -> String { String::new() }
+    /// ctx.spawn_task(runtime, response, async { fetch().await });
+    /// ```
+    pub fn spawn_task<T, F>(
+        &mut self,
+        runtime: &Handle,
+        action: &PhysicalActionRef<T>,
+        future: F,
+    ) -> JoinHandle<Result<(), SendError<Option<T>>>>
+    where
+        T: Sync + Send + 'static,
+        F: Future<Output = T> + Send + 'static,
+    {
+        let link = self.async_link();
+        let action = action.clone();
+        runtime.spawn(async move {
+            let value = future.await;
+            link.schedule_physical_with_v(&action, Some(value), Offset::Asap)
+        })
+    }
+}
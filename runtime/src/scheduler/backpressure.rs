@@ -0,0 +1,196 @@
+//! Bounding the channel through which asynchronous
+//! threads send physical events, see [ChannelBound].
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+/// What a sender does when the channel of physical
+/// events is full, see [ChannelBound].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum OverflowPolicy {
+    /// The sender waits until the scheduler receives an event,
+    /// or until the program shuts down. This slows the producer
+    /// down to the pace of the scheduler.
+    Block,
+    /// The new event is sent, and the oldest event of the
+    /// channel is discarded when the scheduler receives it.
+    /// This keeps the most recent inputs.
+    DropOldest,
+    /// The new event is rejected, and [AsyncCtx::schedule_physical_with_v](crate::AsyncCtx::schedule_physical_with_v)
+    /// returns an error, which gives the value back to the sender.
+    DropNewest,
+}
+
+/// A bound on the number of physical events that have been
+/// sent by asynchronous threads, but not yet received by the
+/// scheduler, see [SchedulerOptions::physical_channel](crate::SchedulerOptions::physical_channel).
+///
+/// The scheduler receives physical events whenever it waits,
+/// and between tags. Events pile up in the channel while tags
+/// are being processed, and a producer that is faster than the
+/// scheduler would make it grow without bound. Requests to stop
+/// the program are not counted, and are never dropped.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ChannelBound {
+    /// Max number of events in the channel, at least 1.
+    pub capacity: usize,
+    pub policy: OverflowPolicy,
+}
+
+/// State of a bounded channel, shared between the
+/// scheduler and asynchronous threads.
+#[derive(Debug)]
+pub(crate) struct PhysicalChannel {
+    bound: ChannelBound,
+    state: Mutex<ChannelState>,
+    /// Notified when an event is received.
+    space: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct ChannelState {
+    /// Number of events sent and not yet received.
+    in_flight: usize,
+    /// Number of events to discard when they are received,
+    /// with [OverflowPolicy::DropOldest]. They are not
+    /// counted in [Self::in_flight].
+    displaced: usize,
+}
+
+/// A place in the channel, reserved for an event that is
+/// about to be sent. It is given back if it is dropped
+/// before [Self::sent] is called.
+pub(super) struct Permit<'a> {
+    channel: &'a PhysicalChannel,
+    sent: bool,
+}
+
+impl Permit<'_> {
+    pub(super) fn sent(mut self) {
+        self.sent = true;
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if !self.sent {
+            // whether it displaced an event or not, there is one
+            // event less in the channel than was accounted for
+            let mut state = self.channel.state.lock().unwrap();
+            if state.displaced > 0 {
+                state.displaced -= 1;
+            } else {
+                state.in_flight -= 1;
+                self.channel.space.notify_one();
+            }
+        }
+    }
+}
+
+impl PhysicalChannel {
+    pub(super) fn new(bound: ChannelBound) -> Self {
+        assert!(bound.capacity > 0, "The capacity of the channel must be at least 1");
+        Self {
+            bound,
+            state: Default::default(),
+            space: Condvar::new(),
+        }
+    }
+
+    /// Reserve a place for an event, according to the policy.
+    /// Returns None if the event must not be sent, because the
+    /// channel is full or the scheduler has been shut down.
+    pub(super) fn reserve(&self, was_terminated: &AtomicBool) -> Option<Permit<'_>> {
+        let mut state = self.state.lock().unwrap();
+        while state.in_flight >= self.bound.capacity {
+            match self.bound.policy {
+                OverflowPolicy::DropNewest => return None,
+                OverflowPolicy::DropOldest => {
+                    state.displaced += 1;
+                    return Some(Permit { channel: self, sent: false });
+                }
+                OverflowPolicy::Block => {
+                    if was_terminated.load(Ordering::SeqCst) {
+                        return None;
+                    }
+                    // the timeout covers a scheduler that stops without waking us
+                    state = self.space.wait_timeout(state, Duration::from_millis(100)).unwrap().0;
+                }
+            }
+        }
+        state.in_flight += 1;
+        Some(Permit { channel: self, sent: false })
+    }
+
+    /// Called by the scheduler when it receives an event.
+    /// Returns true if the event was displaced by a newer
+    /// one, and must be discarded.
+    pub(super) fn received(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.displaced > 0 {
+            state.displaced -= 1;
+            return true;
+        }
+        state.in_flight -= 1;
+        self.space.notify_one();
+        false
+    }
+
+    /// Wake up the senders that wait for space, when
+    /// the scheduler shuts down.
+    pub(super) fn wake_all(&self) {
+        self.space.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn channel(capacity: usize, policy: OverflowPolicy) -> PhysicalChannel {
+        PhysicalChannel::new(ChannelBound { capacity, policy })
+    }
+
+    #[test]
+    fn test_drop_newest_rejects_when_full() {
+        let channel = channel(2, OverflowPolicy::DropNewest);
+        let terminated = AtomicBool::new(false);
+        channel.reserve(&terminated).unwrap().sent();
+        channel.reserve(&terminated).unwrap().sent();
+        assert!(channel.reserve(&terminated).is_none());
+
+        assert!(!channel.received());
+        channel.reserve(&terminated).unwrap().sent();
+    }
+
+    #[test]
+    fn test_drop_oldest_discards_the_oldest_events() {
+        let channel = channel(2, OverflowPolicy::DropOldest);
+        let terminated = AtomicBool::new(false);
+        for _ in 0..5 {
+            channel.reserve(&terminated).unwrap().sent();
+        }
+        // the 3 oldest events are discarded
+        let discarded: Vec<bool> = (0..5).map(|_| channel.received()).collect();
+        assert_eq!(discarded, vec![true, true, true, false, false]);
+    }
+
+    #[test]
+    fn test_unsent_permit_is_given_back() {
+        let channel = channel(1, OverflowPolicy::DropNewest);
+        let terminated = AtomicBool::new(false);
+        drop(channel.reserve(&terminated).unwrap());
+        channel.reserve(&terminated).unwrap().sent();
+        assert!(channel.reserve(&terminated).is_none());
+    }
+
+    #[test]
+    fn test_block_gives_up_on_shutdown() {
+        let channel = channel(1, OverflowPolicy::Block);
+        let terminated = AtomicBool::new(false);
+        channel.reserve(&terminated).unwrap().sent();
+        terminated.store(true, Ordering::SeqCst);
+        assert!(channel.reserve(&terminated).is_none());
+    }
+}
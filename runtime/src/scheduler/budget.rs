@@ -0,0 +1,36 @@
+//! Bound on the number of events pending in the scheduler.
+
+/// Bounds the number of events pending in the event queue
+/// of the scheduler, including those of timers, so that the
+/// memory used by the event system stays bounded on constrained
+/// deployments. See [SchedulerOptions::event_budget](crate::SchedulerOptions::event_budget).
+///
+/// The budget is checked before each tag is processed, so the
+/// events that a single tag schedules may exceed it briefly.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct EventBudget {
+    /// Max number of pending events.
+    pub max_events: usize,
+    /// What to do when the budget is exceeded.
+    pub policy: BudgetPolicy,
+}
+
+/// What to do when an [EventBudget] is exceeded.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BudgetPolicy {
+    /// Physical events are rejected while the budget is
+    /// exhausted, regardless of their admission priority (see
+    /// [AdmissionPolicy](crate::AdmissionPolicy)).
+    /// [AsyncCtx::schedule_physical_with_v](crate::AsyncCtx::schedule_physical_with_v)
+    /// then returns an error. Events scheduled by reactions
+    /// are still accepted.
+    RejectPhysical,
+    /// Pending events with the latest tags are dropped until
+    /// the queue fits the budget. Their reactions never run.
+    /// Timer events and the event that requests shutdown are
+    /// never dropped.
+    DropLatest,
+    /// The program shuts down, with reason
+    /// [ShutdownReason::EventBudgetExceeded](crate::ShutdownReason::EventBudgetExceeded).
+    Shutdown,
+}
@@ -0,0 +1,138 @@
+//! Snapshots of the state of a program, from which its
+//! execution can be resumed, see [Checkpoint].
+
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::assembly::TriggerId;
+use crate::*;
+
+/// The saved state of a reactor, see [ReactorBehavior::serialize_state].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ReactorState(serde_json::Value);
+
+impl ReactorState {
+    /// Save the given value.
+    pub fn of<T: Serialize>(state: &T) -> Result<Self, CheckpointError> {
+        serde_json::to_value(state).map(Self).map_err(CheckpointError::Format)
+    }
+
+    /// Read back the value that was saved.
+    pub fn get<T: DeserializeOwned>(&self) -> Result<T, CheckpointError> {
+        T::deserialize(&self.0).map_err(CheckpointError::Format)
+    }
+}
+
+/// An error while writing, reading, or
+/// resuming from a [Checkpoint].
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(std::io::Error),
+    Format(serde_json::Error),
+    /// The checkpoint was taken from another program.
+    Mismatch(String),
+}
+
+impl Display for CheckpointError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckpointError::Io(e) => write!(f, "checkpoint I/O error: {}", e),
+            CheckpointError::Format(e) => write!(f, "malformed checkpoint: {}", e),
+            CheckpointError::Mismatch(msg) => write!(f, "checkpoint does not match the program: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+/// An event that was pending when a [Checkpoint] was taken.
+/// The reactions it triggers are recomputed from its triggers
+/// when resuming.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PendingEvent {
+    pub tag: EventTag,
+    /// The actions and timers that produced the event.
+    pub triggers: Vec<TriggerId>,
+    /// Whether the event requests shutdown.
+    pub terminate: bool,
+}
+
+/// The state of a program at the end of a tag: the state
+/// of its reactors, and its pending events. Execution may be
+/// resumed from it with [SchedulerOptions::resume_from], by
+/// the same program.
+///
+/// Reactors save their state with [ReactorBehavior::serialize_state].
+/// The values carried by pending actions are part of the state of
+/// the reactor that owns the action, see [LogicalAction::pending_values].
+/// The values of pending physical actions are not saved: those
+/// events are restored, but the action is present without a value.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// The latest processed tag.
+    pub tag: EventTag,
+    /// The path and saved state of every reactor, in
+    /// the order of their ids. Paths are checked when
+    /// resuming, to detect a different program.
+    pub reactors: Vec<(String, Option<ReactorState>)>,
+    /// Pending events, in tag order.
+    pub events: Vec<PendingEvent>,
+}
+
+impl Checkpoint {
+    /// Read a checkpoint written with [Self::save].
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CheckpointError> {
+        let text = std::fs::read_to_string(path).map_err(CheckpointError::Io)?;
+        serde_json::from_str(&text).map_err(CheckpointError::Format)
+    }
+
+    /// Write the checkpoint to a file, as JSON. The file is
+    /// replaced atomically, so that a crash while writing
+    /// leaves the previous checkpoint intact.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CheckpointError> {
+        let path = path.as_ref();
+        let text = serde_json::to_string(self).map_err(CheckpointError::Format)?;
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        std::fs::write(&tmp, text).map_err(CheckpointError::Io)?;
+        std::fs::rename(&tmp, path).map_err(CheckpointError::Io)
+    }
+}
+
+/// When and where to write checkpoints,
+/// see [SchedulerOptions::checkpoint].
+pub struct CheckpointPolicy {
+    path: PathBuf,
+    period: Duration,
+    /// Logical time from which the next checkpoint is due.
+    next: Duration,
+}
+
+impl CheckpointPolicy {
+    /// Write a checkpoint to the given file at the end of the
+    /// first tag at or after every multiple of the period, in
+    /// logical time. Each checkpoint replaces the previous one.
+    pub fn every(period: Duration, path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into(), period, next: period }
+    }
+
+    pub(super) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether a checkpoint is due at the end of the given tag.
+    pub(super) fn is_due(&mut self, tag: EventTag) -> bool {
+        let elapsed = tag.offset_from_t0;
+        if elapsed < self.next || self.period.is_zero() {
+            return false;
+        }
+        while self.next <= elapsed {
+            self.next += self.period;
+        }
+        true
+    }
+}
@@ -1,8 +1,10 @@
 use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 use std::hash::{Hash, Hasher};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use std::thread::JoinHandle;
+use std::sync::{Arc, Mutex};
+use std::thread::{JoinHandle, Thread, ThreadId};
 
 use crossbeam_channel::reconnectable::{Receiver, SendError, Sender};
 use smallvec::SmallVec;
@@ -16,7 +18,7 @@ use crate::*;
 /// allows mutating the event queue of the scheduler.
 /// Only the interactions declared at assembly time
 /// are allowed.
-
+//
 // Implementation details:
 // ReactionCtx is an API built around a ReactionWave. A single
 // ReactionCtx may be used for multiple ReactionWaves, but
@@ -49,6 +51,38 @@ pub struct ReactionCtx<'a, 'x> {
     /// It duplicates [Self::was_terminated_atomic], to avoid an atomic
     /// operation within [Self::is_shutdown].
     was_terminated: bool,
+    /// Why the program is shutting down, only set at the shutdown tag.
+    pub(super) shutdown_reason: Option<ShutdownReason>,
+    /// Whether to record the execution time of each reaction
+    /// into [RContextForwardableStuff::reaction_timings].
+    pub(super) record_timings: bool,
+    /// Whether to record when each reaction starts and ends
+    /// into [RContextForwardableStuff::reaction_spans].
+    pub(super) record_spans: bool,
+    /// Admission control, shared with asynchronous threads.
+    pub(super) admission: Option<&'a Arc<AdmissionControl>>,
+    /// Bound on the channel of physical events, shared with asynchronous threads.
+    pub(super) channel: Option<&'a Arc<PhysicalChannel>>,
+    /// Window to which asynchronous threads round up the tags
+    /// of physical events, see [SchedulerOptions::physical_tag_window](crate::SchedulerOptions::physical_tag_window).
+    pub(super) physical_tag_window: Option<Duration>,
+    /// See [SchedulerOptions::seed](crate::SchedulerOptions::seed).
+    pub(super) seed: u64,
+    /// Tags of physical events, when they are recorded or replayed.
+    pub(super) physical_tags: Option<&'a Arc<PhysicalTags>>,
+    /// Threads blocked in [AsyncCtx::sleep], shared with asynchronous threads.
+    pub(super) sleepers: Option<&'a Arc<Sleepers>>,
+    /// Whether to record the reactions that schedule events at
+    /// the current time point into [RContextForwardableStuff::injections].
+    pub(super) track_injections: bool,
+    /// Whether the current reaction has missed its deadline.
+    pub(super) deadline_violated: bool,
+    /// Whether to record the ports and timers that become present
+    /// at the current tag into [RContextForwardableStuff::present].
+    pub(super) record_present: bool,
+    /// Failures to inject into reactions, if any.
+    #[cfg(feature = "fault-injection")]
+    pub(super) faults: Option<&'a FaultInjector>,
 }
 
 impl<'a, 'x> ReactionCtx<'a, 'x> {
@@ -101,6 +135,59 @@ impl<'a, 'x> ReactionCtx<'a, 'x> {
         self.was_terminated
     }
 
+    /// Returns why the program is shutting down, if this
+    /// is the shutdown tag (see [Self::is_shutdown]). Reactions
+    /// triggered by `shutdown` can use this to tell normal
+    /// completion apart from other cases.
+    #[inline]
+    pub fn shutdown_reason(&self) -> Option<ShutdownReason> {
+        self.shutdown_reason
+    }
+
+    /// Returns a pseudo-random generator, whose sequence is
+    /// determined by the [seed](crate::SchedulerOptions::seed)
+    /// of the program, the current tag, and the executing reaction.
+    /// Reruns and replays of the program therefore draw the same
+    /// numbers, whatever the order in which reactions execute.
+    ///
+    /// Every call within a reaction returns a generator with the
+    /// same sequence: to draw several numbers, keep the generator.
+    ///
+    /// ```no_run
+    /// # use reactor_rt::prelude::*;
+    /// # let ctx: &mut ReactionCtx = panic!();
+    /// let mut rng = ctx.random();
+    /// let jitter = Duration::from_micros(rng.next_in(0, 500));
+    /// let heads = rng.next_bool(0.5);
+    /// ```
+    pub fn random(&self) -> TagRng {
+        TagRng::keyed(self.seed, (self.tag, self.current_reaction))
+    }
+
+    /// Returns the prefix of the messages logged with [log_info](crate::log_info)
+    /// and its siblings: the current tag, and the path and label
+    /// of the executing reaction, eg `[(T0 + 0 ns = 0 ms, 0)] /child/0@on_input:`.
+    pub fn log_prefix(&self) -> LogPrefix<'_, 'a, 'x> {
+        LogPrefix(self)
+    }
+
+    /// Returns whether the current reaction started executing
+    /// after its deadline, see [DependencyDeclarator::declare_deadline].
+    /// Generated code uses this to run the deadline violation
+    /// handler of the reaction instead of its body:
+    /// ```ignore
+    /// if ctx.deadline_violated() {
+    ///     // violation handler
+    /// } else {
+    ///     // body
+    /// }
+    /// ```
+    /// This is always false for reactions without a deadline.
+    #[inline]
+    pub fn deadline_violated(&self) -> bool {
+        self.deadline_violated
+    }
+
     /// Returns the amount of logical time elapsed since the
     /// start of the program. This does not take microsteps
     /// into account.
@@ -229,6 +316,76 @@ impl<'a, 'x> ReactionCtx<'a, 'x> {
         self.use_ref(container, |c| c.map(action))
     }
 
+    /// Returns the value of the given port, moving it out of the
+    /// port if the current reaction is the last one to read it
+    /// at this tag, and cloning it otherwise. This avoids copying
+    /// large values through a chain of ports:
+    /// ```no_run
+    /// # use reactor_rt::prelude::*;
+    /// # let ctx: &mut ReactionCtx = panic!();
+    /// # let inp: &mut Port<Vec<u8>> = panic!();
+    /// # let out: &mut Port<Vec<u8>> = panic!();
+    /// if let Some(mut buf) = ctx.take(inp) {
+    ///     buf.push(0);
+    ///     ctx.set(out, buf);
+    /// }
+    /// ```
+    ///
+    /// Whether the value is moved is decided at assembly time:
+    /// the last reader is the reaction that triggers on or uses
+    /// the port (or the ports bound to it) with the highest
+    /// level, provided no other reader has that level. Values
+    /// that are read by several reactions at the same level, for
+    /// instance on a fan-out connection, are cloned. Wrap them
+    /// into an [Arc](std::sync::Arc) to make that cheap, and
+    /// use [Arc::try_unwrap](std::sync::Arc::try_unwrap) to get
+    /// the value back once the other readers are done.
+    ///
+    /// Once the value is moved, the port is absent for the rest
+    /// of the tag, including for [Self::is_present]. Exported
+    /// ports, see [DependencyDeclarator::export_port](crate::assembly::DependencyDeclarator::export_port),
+    /// have no last reader, as their value is published at the
+    /// end of the tag: their value is always cloned. Traces and
+    /// event logs only record that the port was set, so they are
+    /// not affected.
+    ///
+    /// Values that are not `Clone` can be moved out with
+    /// [Self::take_if_last].
+    #[inline]
+    pub fn take<T>(&mut self, port: &mut Port<T>) -> Option<T>
+    where
+        T: Sync + Clone,
+    {
+        if self.is_last_reader(port) {
+            port.take_impl()
+        } else {
+            port.use_ref(Option::<T>::clone)
+        }
+    }
+
+    /// Moves the value out of the port, if the current reaction
+    /// is its last reader, see [Self::take]. Returns None if the
+    /// port is absent, or if the reaction is not the last reader,
+    /// in which case the value is left in the port. This does not
+    /// require `T: Clone`.
+    #[inline]
+    pub fn take_if_last<T: Sync>(&mut self, port: &mut Port<T>) -> Option<T> {
+        if self.is_last_reader(port) {
+            port.take_impl()
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if the current reaction is the last one to
+    /// read the port at this tag, ie if [Self::take] moves its
+    /// value out.
+    #[inline]
+    pub fn is_last_reader<T: Sync>(&self, port: &Port<T>) -> bool {
+        let last_reader = self.dataflow.last_reader_of(&port.origin_id());
+        last_reader.is_some() && last_reader == self.current_reaction && !port.keeps_last()
+    }
+
     /// Sets the value of the given port.
     ///
     /// The change is visible at the same logical time, i.e.
@@ -244,6 +401,9 @@ impl<'a, 'x> ReactionCtx<'a, 'x> {
             self.check_set_port_is_legal(port)
         }
         port.set_impl(Some(value));
+        if self.record_present {
+            self.insides.present.push(port.get_id());
+        }
         self.enqueue_now(Cow::Borrowed(self.reactions_triggered_by(port.get_id())));
     }
 
@@ -308,6 +468,86 @@ impl<'a, 'x> ReactionCtx<'a, 'x> {
         }
     }
 
+    /// Sets the value of the given port, unless it is equal to
+    /// the value of the port at the previous tag at which it was
+    /// present. In that case the port is not set and no
+    /// reactions are triggered, which avoids spurious wake-ups
+    /// in pipelines that only react to changes. Returns true
+    /// if the port was set.
+    ///
+    /// The port keeps the value of the previous tag from the
+    /// first call on, including values set with [Self::set].
+    /// The first call always sets the port. Readers of the port
+    /// then cannot move its value out with [Self::take].
+    ///
+    /// ```no_run
+    /// # use reactor_rt::{ReactionCtx, Port};
+    /// # let ctx: &mut ReactionCtx = unimplemented!();
+    /// # let mode: &mut Port<bool> = unimplemented!();
+    /// # let temperature = 20;
+    /// // downstream reactions only run when the mode flips
+    /// ctx.set_if_changed(mode, temperature > 25);
+    /// ```
+    #[inline]
+    pub fn set_if_changed<T>(&mut self, port: &mut Port<T>, value: T) -> bool
+    where
+        T: Sync + PartialEq,
+    {
+        self.set_if_changed_by(port, value, T::eq)
+    }
+
+    /// Like [Self::set_if_changed], but values are compared with
+    /// the given function, which returns true if they are equal.
+    /// This allows comparing values that are not `PartialEq`, or
+    /// floats with a tolerance:
+    ///
+    /// ```no_run
+    /// # use reactor_rt::{ReactionCtx, Port};
+    /// # let ctx: &mut ReactionCtx = unimplemented!();
+    /// # let temperature: &mut Port<f64> = unimplemented!();
+    /// # let measured = 20.0;
+    /// ctx.set_if_changed_by(temperature, measured, |a, b| (a - b).abs() < 0.1);
+    /// ```
+    #[inline]
+    pub fn set_if_changed_by<T>(&mut self, port: &mut Port<T>, value: T, eq: impl FnOnce(&T, &T) -> bool) -> bool
+    where
+        T: Sync,
+    {
+        if port.changed_since_last_tag(&value, eq) {
+            self.set(port, value);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the number of reactions downstream of the given
+    /// component, ie reactions that are triggered by it, or use
+    /// it, possibly through port bindings. This is fixed at
+    /// assembly time.
+    #[inline]
+    pub fn downstream_count(&self, component: &impl TriggerLike) -> usize {
+        self.dataflow.readers_of(&component.get_id())
+    }
+
+    /// Returns true if any reaction may observe the value of the
+    /// given component, see [Self::downstream_count]. Reactions
+    /// can use this to skip computing values for ports that
+    /// are not connected:
+    /// ```no_run
+    /// # use reactor_rt::prelude::*;
+    /// # let ctx: &mut ReactionCtx = panic!();
+    /// # let diagnostics: &mut Port<String> = panic!();
+    /// # fn expensive_report() -> String { unimplemented!() }
+    /// if ctx.has_downstream(diagnostics) {
+    ///     ctx.set(diagnostics, expensive_report());
+    /// }
+    /// ```
+    #[inline]
+    pub fn has_downstream(&self, component: &impl TriggerLike) -> bool {
+        self.downstream_count(component) > 0
+    }
+
     /// Returns true if the given action was triggered at the
     /// current logical time.
     ///
@@ -345,7 +585,7 @@ impl<'a, 'x> ReactionCtx<'a, 'x> {
     ///
     /// The action will carry the given value at the time it
     /// is triggered, unless it is overwritten by another call
-    /// to this method. The value can be cleared by using `None`
+    /// to this method at the same tag (see [CollisionPolicy]). The value can be cleared by using `None`
     /// as a value. Note that even if the value is absent, the
     /// *action* will still be present at the time it is triggered
     /// (see [Self::is_present]).
@@ -368,8 +608,42 @@ impl<'a, 'x> ReactionCtx<'a, 'x> {
     /// // that's equivalent to
     /// ctx.schedule(action, Asap);
     /// ```
+    ///
+    /// ### Panics
+    ///
+    /// If the offset designates a tag that is not after the
+    /// current tag, or if the action is already scheduled at that
+    /// tag and rejects the value, see [Self::try_schedule_with_v].
     #[inline]
     pub fn schedule_with_v<T: Sync>(&mut self, action: &mut impl SchedulableAsAction<T>, value: Option<T>, offset: Offset) {
+        if let Err(e) = action.schedule_with_v(self, value, offset) {
+            panic!("{}", e)
+        }
+    }
+
+    /// Like [Self::schedule_with_v], but returns an error if the
+    /// offset designates a tag that is not after the current tag,
+    /// which may happen with [Offset::AfterTag] and [Offset::AtPhysicalTime],
+    /// or if the action rejects a second value at the same tag
+    /// (see [CollisionPolicy]). The action is then not scheduled.
+    ///
+    /// ```no_run
+    /// # use reactor_rt::prelude::*;
+    /// # let ctx: &mut ReactionCtx = panic!();
+    /// # let action: &mut LogicalAction<u32> = panic!();
+    /// # let deadline: Instant = panic!();
+    /// if let Err(e) = ctx.try_schedule_with_v(action, Some(0), AtPhysicalTime(deadline)) {
+    ///     // the deadline is already behind logical time
+    ///     ctx.schedule_with_v(action, Some(0), Asap);
+    /// }
+    /// ```
+    #[inline]
+    pub fn try_schedule_with_v<T: Sync>(
+        &mut self,
+        action: &mut impl SchedulableAsAction<T>,
+        value: Option<T>,
+        offset: Offset,
+    ) -> Result<(), ScheduleError> {
         action.schedule_with_v(self, value, offset)
     }
 
@@ -377,19 +651,32 @@ impl<'a, 'x> ReactionCtx<'a, 'x> {
     ///
     /// This is used for actions.
     #[inline]
-    pub(crate) fn enqueue_later(&mut self, downstream: &'x ExecutableReactions, tag: EventTag) {
+    pub(crate) fn enqueue_later(&mut self, trigger: TriggerId, tag: EventTag) {
         debug_assert!(tag > self.get_tag());
+        if self.track_injections && tag.offset_from_t0 == self.tag.offset_from_t0 {
+            self.insides.injections.extend(self.current_reaction);
+        }
 
-        let evt = Event::execute(tag, Cow::Borrowed(downstream));
+        let downstream = self.reactions_triggered_by(trigger);
+        let evt = Event::execute(tag, Cow::Borrowed(downstream)).triggered_by(trigger);
         self.insides.future_events.push(evt);
     }
 
+    #[inline]
+    fn enqueue_timer(&mut self, trigger: TriggerId, tag: EventTag) {
+        debug_assert!(tag > self.get_tag());
+
+        let downstream = self.reactions_triggered_by(trigger);
+        let evt = Event::execute(tag, Cow::Borrowed(downstream)).triggered_by(trigger);
+        self.insides.timer_events.push(evt);
+    }
+
     #[inline]
     pub(crate) fn enqueue_now(&mut self, downstream: Cow<'x, ExecutableReactions<'x>>) {
-        match &mut self.insides.todo_now {
-            Some(ref mut do_next) => do_next.to_mut().absorb_after(downstream.as_ref(), self.cur_level.next()),
-            None => self.insides.todo_now = Some(downstream),
-        }
+        // Plans are borrowed from the dataflow as long as possible,
+        // so that setting a port does not allocate in the common case.
+        let todo = self.insides.todo_now.take();
+        self.insides.todo_now = ExecutableReactions::merge_plans_after(todo, Some(downstream), self.cur_level.next());
     }
 
     fn reactions_triggered_by(&self, trigger: TriggerId) -> &'x ExecutableReactions<'x> {
@@ -400,6 +687,51 @@ impl<'a, 'x> ReactionCtx<'a, 'x> {
         self.get_tag().successor(offset_from_now)
     }
 
+    /// The tag at which a logical event scheduled with the
+    /// given offset and minimum delay occurs.
+    fn resolve_logical_tag(&self, offset: Offset, min_delay: Duration) -> Result<EventTag, ScheduleError> {
+        let requested = match offset {
+            Offset::After(_) | Offset::Asap => return Ok(self.make_successor_tag(min_delay + offset.to_duration())),
+            Offset::AfterTag(tag) => tag,
+            Offset::AtPhysicalTime(instant) => EventTag::absolute(self.initial_time, instant.max(self.initial_time)),
+        };
+        // a zero delay keeps the requested tag, it does not add a microstep
+        let requested = if min_delay.is_zero() {
+            requested
+        } else {
+            requested.successor(min_delay)
+        };
+        if requested <= self.get_tag() {
+            return Err(ScheduleError::NotInTheFuture { requested, current: self.get_tag() });
+        }
+        Ok(requested)
+    }
+
+    /// Schedule the action with the given offset, unless it is
+    /// already scheduled after the current tag, and at the latest
+    /// at the tag at which it would occur. Returns true if it
+    /// was scheduled. This is meant for actions that request some
+    /// processing soon, so that requests do not pile up:
+    ///
+    /// ```no_run
+    /// # use reactor_rt::prelude::*;
+    /// # let ctx: &mut ReactionCtx = panic!();
+    /// # let flush: &mut LogicalAction<()> = panic!();
+    /// // on each new message, make sure a flush happens within 10 ms
+    /// ctx.schedule_if_absent(flush, after!(10 ms));
+    /// ```
+    ///
+    /// An occurrence scheduled later than that tag does not prevent
+    /// scheduling. The action is scheduled without a value.
+    #[inline]
+    pub fn schedule_if_absent<T: Sync>(&mut self, action: &mut impl SchedulableAsAction<T>, offset: Offset) -> bool {
+        if action.is_pending_within(self, offset) {
+            return false;
+        }
+        self.schedule(action, offset);
+        true
+    }
+
     /// Spawn a new thread that can use a [AsyncCtx]
     /// to push asynchronous events to the reaction queue. This is
     /// only useful with [physical actions](crate::PhysicalAction).
@@ -410,7 +742,8 @@ impl<'a, 'x> ReactionCtx<'a, 'x> {
     /// thread to finish its task. For that reason, the thread's
     /// closure should not execute an infinite loop, it should at
     /// least check that the scheduler has not been terminated by
-    /// polling [AsyncCtx::was_terminated].
+    /// polling [AsyncCtx::was_terminated], or by waiting
+    /// with [AsyncCtx::sleep], which is interrupted at shutdown.
     ///
     /// ### Example
     ///
@@ -435,14 +768,35 @@ impl<'a, 'x> ReactionCtx<'a, 'x> {
         F: Send + 'static,
         R: Send + 'static,
     {
-        let tx = self.rx.new_sender();
-        let initial_time = self.initial_time;
-        let was_terminated = self.was_terminated_atomic.clone();
+        let mut link = self.new_async_ctx();
+        std::thread::spawn(move || f(&mut link))
+    }
 
-        std::thread::spawn(move || {
-            let mut link = AsyncCtx { tx, initial_time, was_terminated };
-            f(&mut link)
-        })
+    /// Create a new link to the event queue.
+    pub(super) fn new_async_ctx(&self) -> AsyncCtx {
+        AsyncCtx {
+            tx: self.rx.new_sender(),
+            initial_time: self.initial_time,
+            was_terminated: self.was_terminated_atomic.clone(),
+            admission: self.admission.cloned(),
+            channel: self.channel.cloned(),
+            tag_window: self.physical_tag_window,
+            physical_tags: self.physical_tags.cloned(),
+            sleepers: self.sleepers.cloned(),
+        }
+    }
+
+    /// Arm the watchdog, so that it expires after the given
+    /// timeout in physical time, unless it is started again or
+    /// stopped before. If it is already armed, this moves its
+    /// deadline. See [Watchdog].
+    pub fn start_watchdog(&mut self, watchdog: &Watchdog, timeout: Duration) {
+        watchdog.start(self, timeout)
+    }
+
+    /// Disarm the watchdog, if it is armed.
+    pub fn stop_watchdog(&mut self, watchdog: &Watchdog) {
+        watchdog.stop()
     }
 
     /// Request that the application shutdown, possibly with
@@ -464,7 +818,10 @@ impl<'a, 'x> ReactionCtx<'a, 'x> {
     /// ```
     #[inline]
     pub fn request_stop(&mut self, offset: Offset) {
-        let tag = self.make_successor_tag(offset.to_duration());
+        let tag = self.resolve_logical_tag(offset, Duration::ZERO).unwrap_or_else(|e| {
+            warn!("Stop requested at a past tag, stopping on the next microstep instead ({})", e);
+            self.make_successor_tag(Duration::ZERO)
+        });
 
         let evt = Event::terminate_at(tag);
         self.insides.future_events.push(evt);
@@ -477,8 +834,7 @@ impl<'a, 'x> ReactionCtx<'a, 'x> {
     #[inline]
     pub fn reschedule_timer(&mut self, timer: &mut Timer) {
         if timer.is_periodic() {
-            let downstream = self.reactions_triggered_by(timer.get_id());
-            self.enqueue_later(downstream, self.make_successor_tag(timer.period));
+            self.enqueue_timer(timer.get_id(), self.make_successor_tag(timer.period));
         }
     }
 
@@ -489,12 +845,14 @@ impl<'a, 'x> ReactionCtx<'a, 'x> {
     #[inline]
     pub fn bootstrap_timer(&mut self, timer: &mut Timer) {
         // we're in startup
-        let downstream = self.reactions_triggered_by(timer.get_id());
         if timer.offset.is_zero() {
             // no offset
-            self.enqueue_now(Cow::Borrowed(downstream))
+            if self.record_present {
+                self.insides.present.push(timer.get_id());
+            }
+            self.enqueue_now(Cow::Borrowed(self.reactions_triggered_by(timer.get_id())))
         } else {
-            self.enqueue_later(downstream, self.make_successor_tag(timer.offset))
+            self.enqueue_timer(timer.get_id(), self.make_successor_tag(timer.offset))
         }
     }
 
@@ -508,7 +866,34 @@ impl<'a, 'x> ReactionCtx<'a, 'x> {
         );
         debug_assert_eq!(reactor.id(), reaction_id.0.container(), "Wrong reactor");
         self.current_reaction.replace(reaction_id);
-        reactor.react(self, reaction_id.0.local());
+        #[cfg(feature = "fault-injection")]
+        if let Some(faults) = self.faults {
+            faults.before_reaction(self.tag, reaction_id, &self.debug_info);
+        }
+        self.deadline_violated = self
+            .dataflow
+            .deadline_of(reaction_id)
+            .map_or(false, |deadline| Instant::now() > self.get_logical_time() + deadline);
+        if self.deadline_violated {
+            debug!(
+                "  - Deadline of {} is violated",
+                self.debug_info.display_reaction(reaction_id)
+            );
+        }
+        if self.record_timings || self.record_spans {
+            let start = Instant::now();
+            reactor.react(self, reaction_id.0.local());
+            let elapsed = start.elapsed();
+            if self.record_timings {
+                self.insides.reaction_timings.push((reaction_id, elapsed));
+            }
+            if self.record_spans {
+                let worker = super::perf_trace::current_worker();
+                self.insides.reaction_spans.push((reaction_id, start, elapsed, worker));
+            }
+        } else {
+            reactor.react(self, reaction_id.0.local());
+        }
         self.current_reaction.take();
     }
 
@@ -523,7 +908,7 @@ impl<'a, 'x> ReactionCtx<'a, 'x> {
         was_terminated: bool,
     ) -> Self {
         Self {
-            insides: RContextForwardableStuff { todo_now: todo, future_events: Default::default() },
+            insides: RContextForwardableStuff { todo_now: todo, ..Default::default() },
             cur_level: Default::default(),
             tag,
             current_reaction: None,
@@ -533,6 +918,20 @@ impl<'a, 'x> ReactionCtx<'a, 'x> {
             was_terminated_atomic,
             debug_info,
             was_terminated,
+            shutdown_reason: None,
+            record_timings: false,
+            record_spans: false,
+            admission: None,
+            channel: None,
+            physical_tag_window: None,
+            seed: 0,
+            physical_tags: None,
+            sleepers: None,
+            track_injections: false,
+            record_present: false,
+            deadline_violated: false,
+            #[cfg(feature = "fault-injection")]
+            faults: None,
         }
     }
 
@@ -553,6 +952,20 @@ impl<'a, 'x> ReactionCtx<'a, 'x> {
             was_terminated_atomic: self.was_terminated_atomic,
             debug_info: self.debug_info.clone(),
             current_reaction: self.current_reaction,
+            shutdown_reason: self.shutdown_reason,
+            record_timings: self.record_timings,
+            record_spans: self.record_spans,
+            admission: self.admission,
+            channel: self.channel,
+            physical_tag_window: self.physical_tag_window,
+            seed: self.seed,
+            physical_tags: self.physical_tags,
+            sleepers: self.sleepers,
+            track_injections: self.track_injections,
+            record_present: self.record_present,
+            deadline_violated: false,
+            #[cfg(feature = "fault-injection")]
+            faults: self.faults,
         }
     }
 }
@@ -573,6 +986,26 @@ pub(super) struct RContextForwardableStuff<'x> {
     /// Events that were produced for a strictly greater
     /// logical time than a current one.
     pub(super) future_events: SmallVec<[Event<'x>; 4]>,
+
+    /// Events produced by timers, which are pushed with
+    /// [EventQueue::push_timer].
+    pub(super) timer_events: SmallVec<[Event<'x>; 4]>,
+
+    /// Execution time of each reaction that was executed,
+    /// only recorded if [ReactionCtx::record_timings] is set.
+    pub(super) reaction_timings: Vec<(GlobalReactionId, Duration)>,
+
+    /// Start, duration and thread of each reaction that was executed,
+    /// only recorded if [ReactionCtx::record_spans] is set.
+    pub(super) reaction_spans: Vec<super::perf_trace::RawSpan>,
+
+    /// Reactions that scheduled an event at the current time
+    /// point, only recorded if [ReactionCtx::track_injections] is set.
+    pub(super) injections: SmallVec<[GlobalReactionId; 2]>,
+
+    /// Ports and timers that became present at the current tag,
+    /// only recorded if [ReactionCtx::record_present] is set.
+    pub(super) present: SmallVec<[TriggerId; 4]>,
 }
 
 #[cfg(feature = "parallel-runtime")]
@@ -585,6 +1018,44 @@ impl RContextForwardableStuff<'_> {
     pub(super) fn absorb(&mut self, mut other: Self) {
         self.todo_now = ExecutableReactions::merge_cows(self.todo_now.take(), other.todo_now);
         self.future_events.append(&mut other.future_events);
+        self.timer_events.append(&mut other.timer_events);
+        self.reaction_timings.append(&mut other.reaction_timings);
+        self.reaction_spans.append(&mut other.reaction_spans);
+        self.injections.append(&mut other.injections);
+        self.present.append(&mut other.present);
+    }
+}
+
+/// Threads blocked in [AsyncCtx::sleep]. Threads are only
+/// registered while they sleep, so that the scheduler can wake
+/// them up when it shuts down without keeping a handle on
+/// every thread that ever sent an event.
+#[derive(Default)]
+pub(super) struct Sleepers {
+    threads: Mutex<HashMap<ThreadId, Thread>>,
+}
+
+impl Sleepers {
+    fn enter(&self) {
+        let thread = std::thread::current();
+        self.threads.lock().unwrap().insert(thread.id(), thread);
+    }
+
+    fn leave(&self) {
+        self.threads.lock().unwrap().remove(&std::thread::current().id());
+    }
+
+    /// Unpark all sleeping threads.
+    pub(super) fn wake_all(&self) {
+        for thread in self.threads.lock().unwrap().values() {
+            thread.unpark();
+        }
+    }
+
+    /// Number of sleeping threads.
+    #[cfg(test)]
+    pub(super) fn len(&self) -> usize {
+        self.threads.lock().unwrap().len()
     }
 }
 
@@ -600,16 +1071,64 @@ pub struct AsyncCtx {
     initial_time: Instant,
     /// Whether the scheduler has been terminated.
     was_terminated: Arc<AtomicBool>,
+    /// Admission control of the scheduler, if enabled.
+    admission: Option<Arc<AdmissionControl>>,
+    /// Bound on the channel of physical events, if any.
+    channel: Option<Arc<PhysicalChannel>>,
+    /// Window to which tags of physical events are rounded up, if any.
+    tag_window: Option<Duration>,
+    /// Tags of physical events, when they are recorded or replayed.
+    physical_tags: Option<Arc<PhysicalTags>>,
+    /// Threads blocked in [Self::sleep], if the scheduler wakes them up.
+    sleepers: Option<Arc<Sleepers>>,
 }
 
 impl AsyncCtx {
     /// Returns true if the scheduler has been shutdown. When
     /// that's true, calls to other methods of this type will
-    /// fail with [SendError].
+    /// fail with [SendError]. This is set as soon as the
+    /// scheduler decides to shut down, ie before the shutdown
+    /// reactions execute, as events sent after that point
+    /// would never be processed.
     pub fn was_terminated(&self) -> bool {
         self.was_terminated.load(Ordering::SeqCst)
     }
 
+    /// Block the current thread for the given duration, or
+    /// until the scheduler shuts down, whichever comes first.
+    /// Returns false if the scheduler has been shut down. This
+    /// can be used by threads that produce events periodically:
+    /// ```no_run
+    /// # use reactor_rt::prelude::*;
+    /// # let link: &mut AsyncCtx = panic!();
+    /// # let action: &PhysicalActionRef<u32> = panic!();
+    /// while link.sleep(delay!(100 ms)) {
+    ///     let _ = link.schedule_physical_with_v(action, Some(0), Asap);
+    /// }
+    /// ```
+    pub fn sleep(&self, duration: Duration) -> bool {
+        let deadline = Instant::now() + duration;
+        // the scheduler unparks this thread when it shuts down,
+        // if it is registered before the termination flag is read
+        if let Some(sleepers) = &self.sleepers {
+            sleepers.enter();
+        }
+        let slept = loop {
+            if self.was_terminated() {
+                break false;
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                break true;
+            }
+            std::thread::park_timeout(deadline - now);
+        };
+        if let Some(sleepers) = &self.sleepers {
+            sleepers.leave();
+        }
+        slept
+    }
+
     /// Request that the application shutdown, possibly with
     /// a particular offset from the current physical time.
     ///
@@ -620,9 +1139,17 @@ impl AsyncCtx {
     /// or its shutdown might be programmed for a logical
     /// time which precedes the current physical time.
     pub fn request_stop(&mut self, offset: Offset) -> Result<(), SendError<()>> {
+        self.send_stop(offset)
+    }
+
+    pub(super) fn send_stop(&self, offset: Offset) -> Result<(), SendError<()>> {
+        if self.was_terminated() {
+            return Err(SendError(()));
+        }
         // physical time must be ahead of logical time so
         // this event is scheduled for the future
-        let tag = EventTag::absolute(self.initial_time, Instant::now() + offset.to_duration());
+        let now = EventTag::now(self.initial_time);
+        let tag = offset.physical_tag(self.initial_time).max(now);
 
         let evt = PhysicalEvent::terminate_at(tag);
         self.tx.send(evt).map_err(|e| {
@@ -665,24 +1192,92 @@ impl AsyncCtx {
     /// or its shutdown might be programmed for a logical
     /// time which precedes the current physical time.
     ///
+    /// This also fails if the scheduler is overloaded and
+    /// the priority of the action is too low to be admitted,
+    /// see [AdmissionPolicy].
+    ///
     pub fn schedule_physical_with_v<T: Sync>(
         &mut self,
         action: &PhysicalActionRef<T>,
         value: Option<T>,
         offset: Offset,
     ) -> Result<(), SendError<Option<T>>> {
+        self.send_physical(action, value, offset)
+    }
+
+    pub(super) fn send_physical<T: Sync>(
+        &self,
+        action: &PhysicalActionRef<T>,
+        value: Option<T>,
+        offset: Offset,
+    ) -> Result<(), SendError<Option<T>>> {
+        // reserve before locking the action, as the scheduler
+        // may need to lock it before it receives the next event
+        let permit = match &self.channel {
+            Some(channel) => match channel.reserve(&self.was_terminated) {
+                Some(permit) => Some(permit),
+                None => {
+                    debug!("Channel of physical events is full, rejecting physical event");
+                    return Err(SendError(value));
+                }
+            },
+            None => None,
+        };
         // physical time must be ahead of logical time so
         // this event is scheduled for the future
         action
             .use_mut_p(value, |action, value| {
-                let tag = EventTag::absolute(self.initial_time, Instant::now() + offset.to_duration());
-                action.0.schedule_future_value(tag, value);
+                if self.was_terminated() {
+                    debug!("Scheduler has been shut down, rejecting physical event");
+                    return Err(SendError(value));
+                }
+                if let Some(admission) = &self.admission {
+                    if !admission.admits(action.priority) {
+                        debug!(
+                            "Scheduler is overloaded, rejecting physical event of priority {}",
+                            action.priority
+                        );
+                        return Err(SendError(value));
+                    }
+                }
+
+                let now = EventTag::now(self.initial_time);
+                let mut tag = offset.physical_tag(self.initial_time);
+                if tag < now {
+                    warn!("Physical event requested at {}, which is in the past, rejecting it", tag);
+                    return Err(SendError(value));
+                }
+                if let Some(window) = self.tag_window {
+                    tag = tag.round_up_to(window);
+                }
+                tag = match action.space(tag) {
+                    Spaced::At(tag) => tag,
+                    Spaced::Replace(pending) => {
+                        action.inner.schedule_future_value(pending, value);
+                        return Ok(());
+                    }
+                    Spaced::Drop => {
+                        trace!("Dropping physical event at {}, it is too close to the previous one", tag);
+                        return Ok(());
+                    }
+                };
+                if let Some(tags) = &self.physical_tags {
+                    tag = tags.tag_of(action.get_id(), tag);
+                }
+                if let Err(value) = action.inner.schedule_value(tag, value) {
+                    debug!("Physical action is already scheduled at {}, rejecting physical event", tag);
+                    return Err(SendError(value));
+                }
 
                 let evt = PhysicalEvent::trigger(tag, action.get_id());
                 self.tx.send(evt).map_err(|e| {
                     warn!("Event could not be sent! {:?}", e);
-                    SendError(action.0.forget_value(&tag))
-                })
+                    SendError(action.inner.forget_value(&tag))
+                })?;
+                if let Some(permit) = permit {
+                    permit.sent();
+                }
+                Ok(())
             })
             .unwrap_or_else(|value| Err(SendError(value)))
     }
@@ -692,27 +1287,76 @@ impl AsyncCtx {
 /// to give access to [ReactionCtx::schedule] and variants.
 pub trait SchedulableAsAction<T: Sync> {
     #[doc(hidden)]
-    fn schedule_with_v(&mut self, ctx: &mut ReactionCtx, value: Option<T>, offset: Offset);
+    fn schedule_with_v(&mut self, ctx: &mut ReactionCtx, value: Option<T>, offset: Offset) -> Result<(), ScheduleError>;
+
+    /// Whether the action is already scheduled after the current
+    /// tag, and at the latest at the tag at which scheduling it
+    /// with this offset would make it occur.
+    #[doc(hidden)]
+    fn is_pending_within(&self, ctx: &ReactionCtx, offset: Offset) -> bool;
 }
 
 impl<T: Sync> SchedulableAsAction<T> for LogicalAction<T> {
-    fn schedule_with_v(&mut self, ctx: &mut ReactionCtx, value: Option<T>, offset: Offset) {
-        let eta = ctx.make_successor_tag(self.0.min_delay + offset.to_duration());
-        self.0.schedule_future_value(eta, value);
-        let downstream = ctx.dataflow.reactions_triggered_by(&self.get_id());
-        ctx.enqueue_later(downstream, eta);
+    fn schedule_with_v(&mut self, ctx: &mut ReactionCtx, value: Option<T>, offset: Offset) -> Result<(), ScheduleError> {
+        let eta = ctx.resolve_logical_tag(offset, self.0.min_delay)?;
+        if self.0.schedule_value(eta, value).is_err() {
+            return Err(ScheduleError::AlreadyScheduled { tag: eta });
+        }
+        ctx.enqueue_later(self.get_id(), eta);
+        Ok(())
+    }
+
+    fn is_pending_within(&self, ctx: &ReactionCtx, offset: Offset) -> bool {
+        match ctx.resolve_logical_tag(offset, self.0.min_delay) {
+            Ok(eta) => self.0.is_pending_within(ctx.get_tag(), eta),
+            Err(_) => false,
+        }
     }
 }
 
 impl<T: Sync> SchedulableAsAction<T> for PhysicalActionRef<T> {
-    fn schedule_with_v(&mut self, ctx: &mut ReactionCtx, value: Option<T>, offset: Offset) {
+    fn schedule_with_v(&mut self, ctx: &mut ReactionCtx, value: Option<T>, offset: Offset) -> Result<(), ScheduleError> {
+        let requested = offset.physical_tag(ctx.initial_time);
+        if requested <= ctx.get_tag() {
+            return Err(ScheduleError::NotInTheFuture { requested, current: ctx.get_tag() });
+        }
         self.use_mut_p(value, |action, value| {
-            let tag = EventTag::absolute(ctx.initial_time, Instant::now() + offset.to_duration());
-            action.0.schedule_future_value(tag, value);
-            let downstream = ctx.dataflow.reactions_triggered_by(&action.get_id());
-            ctx.enqueue_later(downstream, tag);
+            let mut tag = match action.space(requested) {
+                Spaced::At(tag) => tag,
+                Spaced::Replace(pending) => {
+                    action.inner.schedule_future_value(pending, value);
+                    return Ok(());
+                }
+                Spaced::Drop => return Ok(()),
+            };
+            if let Some(tags) = ctx.physical_tags {
+                tag = tags.tag_of(action.get_id(), tag);
+            }
+            if action.inner.schedule_value(tag, value).is_err() {
+                return Err(ScheduleError::AlreadyScheduled { tag });
+            }
+            ctx.enqueue_later(action.get_id(), tag);
+            Ok(())
         })
-        .ok();
+        .unwrap_or(Ok(()))
+    }
+
+    fn is_pending_within(&self, ctx: &ReactionCtx, offset: Offset) -> bool {
+        let eta = offset.physical_tag(ctx.initial_time);
+        self.use_value(|action| action.inner.is_pending_within(ctx.get_tag(), eta))
+            .unwrap_or(false)
+    }
+}
+
+/// The prefix of log messages, see [ReactionCtx::log_prefix].
+pub struct LogPrefix<'c, 'a, 'x>(&'c ReactionCtx<'a, 'x>);
+
+impl Display for LogPrefix<'_, '_, '_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.0.current_reaction {
+            Some(reaction) => write!(f, "[{}] {}:", self.0.tag, self.0.debug_info.display_reaction(reaction)),
+            None => write!(f, "[{}]:", self.0.tag),
+        }
     }
 }
 
@@ -748,21 +1392,86 @@ pub enum Offset {
     /// assert_eq!(Asap, After(Duration::ZERO));
     /// ```
     Asap,
+
+    /// Specify that the trigger will fire at the given tag, plus the
+    /// action's inherent minimum delay. Unlike the other offsets, this
+    /// does not depend on the current tag, so that several reactions
+    /// may schedule events at a tag they agreed on. The tag must be
+    /// after the current tag, see [ReactionCtx::try_schedule_with_v].
+    ///
+    /// For physical actions, the tag is used as is.
+    AfterTag(EventTag),
+
+    /// Specify that the trigger will fire at the tag of the given
+    /// physical instant, plus the action's inherent minimum delay.
+    /// This aligns events with an external clock. The instant must
+    /// be after the current logical time, see [ReactionCtx::try_schedule_with_v].
+    AtPhysicalTime(Instant),
 }
 
 impl Offset {
+    /// The delay of a relative offset, zero for the others.
     #[inline]
     pub(crate) fn to_duration(self) -> Duration {
         match self {
             Offset::After(d) => d,
-            Offset::Asap => Duration::ZERO,
+            Offset::Asap | Offset::AfterTag(_) | Offset::AtPhysicalTime(_) => Duration::ZERO,
         }
     }
+
+    /// The tag of a physical event scheduled with this offset.
+    /// Relative offsets are counted from the current physical time.
+    pub(crate) fn physical_tag(self, t0: Instant) -> EventTag {
+        match self {
+            Offset::After(_) | Offset::Asap => EventTag::absolute(t0, Instant::now() + self.to_duration()),
+            Offset::AfterTag(tag) => tag,
+            Offset::AtPhysicalTime(instant) => EventTag::absolute(t0, instant.max(t0)),
+        }
+    }
+}
+
+/// An error returned by [ReactionCtx::try_schedule_with_v].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ScheduleError {
+    /// The offset designates a tag that is not
+    /// after the current tag.
+    NotInTheFuture { requested: EventTag, current: EventTag },
+    /// The action is already scheduled at that tag, and
+    /// its [CollisionPolicy] is [Reject](CollisionPolicy::Reject).
+    AlreadyScheduled { tag: EventTag },
+}
+
+impl Display for ScheduleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScheduleError::NotInTheFuture { requested, current } => {
+                write!(
+                    f,
+                    "Cannot schedule an event at {}, which is not after the current tag {}",
+                    requested, current
+                )
+            }
+            ScheduleError::AlreadyScheduled { tag } => write!(f, "The action is already scheduled at {}", tag),
+        }
+    }
+}
+
+impl std::error::Error for ScheduleError {}
+
+impl From<Delay> for Offset {
+    fn from(d: Delay) -> Self {
+        Offset::After(d.as_duration())
+    }
 }
 
 impl PartialEq<Self> for Offset {
     fn eq(&self, other: &Self) -> bool {
-        self.to_duration() == other.to_duration()
+        match (self, other) {
+            (Offset::AfterTag(a), Offset::AfterTag(b)) => a == b,
+            (Offset::AtPhysicalTime(a), Offset::AtPhysicalTime(b)) => a == b,
+            (Offset::After(_) | Offset::Asap, Offset::After(_) | Offset::Asap) => self.to_duration() == other.to_duration(),
+            _ => false,
+        }
     }
 }
 
@@ -770,7 +1479,11 @@ impl Eq for Offset {}
 
 impl Hash for Offset {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.to_duration().hash(state);
+        match self {
+            Offset::After(_) | Offset::Asap => self.to_duration().hash(state),
+            Offset::AfterTag(tag) => tag.hash(state),
+            Offset::AtPhysicalTime(instant) => instant.hash(state),
+        }
     }
 }
 
@@ -782,6 +1495,9 @@ impl Hash for Offset {
 pub struct CleanupCtx {
     /// Tag we're cleaning up
     pub tag: EventTag,
+    /// Physical events that were discarded since the
+    /// previous tag, whose values must be forgotten.
+    pub(crate) displaced: Vec<(EventTag, TriggerId)>,
 }
 
 impl CleanupCtx {
@@ -797,10 +1513,48 @@ impl CleanupCtx {
     }
 
     pub fn cleanup_logical_action<T: Sync>(&self, action: &mut LogicalAction<T>) {
-        action.0.forget_value(&self.tag);
+        action.0.forget_values_until(&self.tag);
     }
 
     pub fn cleanup_physical_action<T: Sync>(&self, action: &mut PhysicalActionRef<T>) {
-        action.use_mut(|a| a.0.forget_value(&self.tag)).ok();
+        action
+            .use_mut(|a| {
+                a.inner.forget_value(&self.tag);
+                let id = a.get_id();
+                for (tag, _) in self.displaced.iter().filter(|(_, t)| *t == id) {
+                    a.inner.forget_value(tag);
+                }
+            })
+            .ok();
+    }
+
+    pub fn cleanup_watchdog(&self, watchdog: &mut Watchdog) {
+        self.cleanup_physical_action(watchdog.action_mut())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sleepers_are_only_kept_while_they_sleep() {
+        let sleepers = Arc::new(Sleepers::default());
+        let woken = Arc::new(AtomicBool::new(false));
+        let (shared, flag) = (sleepers.clone(), woken.clone());
+        let thread = std::thread::spawn(move || {
+            shared.enter();
+            while !flag.load(Ordering::SeqCst) {
+                std::thread::park();
+            }
+            shared.leave();
+        });
+        while sleepers.len() == 0 {
+            std::thread::yield_now();
+        }
+        woken.store(true, Ordering::SeqCst);
+        sleepers.wake_all();
+        thread.join().unwrap();
+        assert_eq!(sleepers.len(), 0);
     }
 }
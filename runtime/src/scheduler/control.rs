@@ -0,0 +1,273 @@
+//! Adjustment of the options of a running scheduler,
+//! pausing of its execution, and inspection of its
+//! event queue.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+
+use crate::*;
+
+/// A handle to adjust a subset of the [SchedulerOptions] while
+/// the program runs, eg to administer a long-running service
+/// without restarting it. Install it with [SchedulerOptions::control],
+/// and keep a clone of it, which may be sent to other threads,
+/// or passed to reactors as a parameter.
+///
+/// Changes are applied by the scheduler between two tags, ie
+/// before it processes its next event. A scheduler that is
+/// waiting for a physical event applies them once it wakes up.
+/// If several changes to the same option are made before they
+/// are applied, they are composed in order.
+///
+/// The handle can also [pause](Self::pause) the execution, eg
+/// for interactive debugging, or to drive the program one
+/// [step](Self::step) at a time in hardware-in-the-loop tests.
+#[derive(Clone, Default)]
+pub struct SchedulerControl {
+    shared: Arc<Shared>,
+}
+
+#[derive(Default)]
+struct Shared {
+    /// Whether [Self::changes] contains changes that have
+    /// not been applied, so that the scheduler does not
+    /// need to lock on every tag.
+    pending: AtomicBool,
+    changes: Mutex<Changes>,
+    /// Mirrors [RunState::paused], so that the scheduler
+    /// does not need to lock on every tag either.
+    paused: AtomicBool,
+    run_state: Mutex<RunState>,
+    /// Notified when the execution is resumed, or a step is allowed.
+    unpaused: Condvar,
+    /// Tags of the pending events, in order, as of the
+    /// last time the scheduler published them.
+    #[cfg(feature = "introspection")]
+    pending_tags: Mutex<Vec<EventTag>>,
+}
+
+#[derive(Default)]
+struct RunState {
+    paused: bool,
+    /// Number of tags that may still be processed while paused.
+    steps: usize,
+}
+
+/// Changes that the scheduler has not applied yet.
+#[derive(Default)]
+pub(super) struct Changes {
+    pub(super) timeout: Option<TimeoutChange>,
+    pub(super) anomaly_detection: Option<bool>,
+    #[cfg(feature = "metrics")]
+    pub(super) metrics_period: Option<Duration>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(super) enum TimeoutChange {
+    /// Replace the timeout, which is relative to the start of the program.
+    Set(Option<Duration>),
+    /// Push back the current timeout, if any.
+    Extend(Duration),
+}
+
+impl SchedulerControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the [timeout](SchedulerOptions::timeout) of the
+    /// program. None removes the timeout. If the new timeout is
+    /// already past, the program shuts down at the next tag.
+    pub fn set_timeout(&self, timeout: Option<Duration>) {
+        self.change(|c| c.timeout = Some(TimeoutChange::Set(timeout)))
+    }
+
+    /// Push back the [timeout](SchedulerOptions::timeout) of the
+    /// program by the given duration. This has no effect if the
+    /// program has no timeout.
+    pub fn extend_timeout(&self, by: Duration) {
+        self.change(|c| {
+            c.timeout = Some(match c.timeout {
+                None => TimeoutChange::Extend(by),
+                Some(TimeoutChange::Extend(d)) => TimeoutChange::Extend(d + by),
+                Some(TimeoutChange::Set(t)) => TimeoutChange::Set(t.map(|t| t + by)),
+            })
+        })
+    }
+
+    /// Pause or resume the [anomaly detector](SchedulerOptions::anomaly_detector),
+    /// if one is installed. While it is paused, reactions are not
+    /// timed, which removes the overhead of the detector.
+    pub fn set_anomaly_detection(&self, enabled: bool) {
+        self.change(|c| c.anomaly_detection = Some(enabled))
+    }
+
+    /// Change the period at which [metrics](SchedulerOptions::metrics)
+    /// are written to a file. This has no effect on other kinds of export.
+    #[cfg(feature = "metrics")]
+    pub fn set_metrics_period(&self, period: Duration) {
+        self.change(|c| c.metrics_period = Some(period))
+    }
+
+    /// Stop advancing logical time. The tag being processed, if
+    /// any, completes, then the scheduler waits for [Self::resume]
+    /// or [Self::step]. Physical time is not paused: once resumed,
+    /// the program is late, and catches up like after any lag.
+    ///
+    /// Pausing before the program starts lets the startup tag
+    /// execute, but no later tag.
+    ///
+    /// Physical events are still received while paused, and
+    /// processed once resumed. A request to stop the program
+    /// from an asynchronous thread (see [AsyncCtx::request_stop]),
+    /// or reaching the timeout, ends the pause, so that the
+    /// program shuts down.
+    pub fn pause(&self) {
+        let mut state = self.shared.run_state.lock().unwrap();
+        state.paused = true;
+        self.shared.paused.store(true, Ordering::Release);
+    }
+
+    /// Resume the execution after [Self::pause]. Steps that were
+    /// not taken yet are forgotten.
+    pub fn resume(&self) {
+        let mut state = self.shared.run_state.lock().unwrap();
+        *state = RunState::default();
+        self.shared.paused.store(false, Ordering::Release);
+        self.shared.unpaused.notify_all();
+    }
+
+    /// Let a paused scheduler process exactly one more tag,
+    /// possibly after waiting for it. This has no effect if
+    /// the scheduler is not paused.
+    pub fn step(&self) {
+        let mut state = self.shared.run_state.lock().unwrap();
+        if state.paused {
+            state.steps += 1;
+            self.shared.unpaused.notify_all();
+        }
+    }
+
+    /// Whether the execution is paused, see [Self::pause].
+    pub fn is_paused(&self) -> bool {
+        self.shared.paused.load(Ordering::Acquire)
+    }
+
+    /// Tags at which events are pending, in order. This is
+    /// a snapshot taken by the scheduler before it processes
+    /// each tag, so it may be outdated by one tag. The events
+    /// of physical actions appear once the scheduler has
+    /// received them.
+    ///
+    /// Taking that snapshot costs time proportional to the
+    /// size of the queue, and is only done if the feature
+    /// `introspection` is enabled.
+    #[cfg(feature = "introspection")]
+    pub fn pending_tags(&self) -> Vec<EventTag> {
+        self.shared.pending_tags.lock().unwrap().clone()
+    }
+
+    /// The earliest tag of [Self::pending_tags], ie the
+    /// tag that will be processed next, unless an earlier
+    /// event is scheduled meanwhile.
+    #[cfg(feature = "introspection")]
+    pub fn next_tag(&self) -> Option<EventTag> {
+        self.shared.pending_tags.lock().unwrap().first().copied()
+    }
+
+    /// The number of [Self::pending_tags]. Events at the same
+    /// tag are processed together, so they are counted once.
+    #[cfg(feature = "introspection")]
+    pub fn queue_len(&self) -> usize {
+        self.shared.pending_tags.lock().unwrap().len()
+    }
+
+    #[cfg(feature = "introspection")]
+    pub(super) fn publish_pending_tags(&self, tags: Vec<EventTag>) {
+        *self.shared.pending_tags.lock().unwrap() = tags;
+    }
+
+    /// Block while the execution is paused and no step is allowed,
+    /// for at most the given duration. Returns whether the execution
+    /// is still paused.
+    pub(super) fn wait_while_paused(&self, timeout: Duration) -> bool {
+        if !self.is_paused() {
+            return false;
+        }
+        let state = self.shared.run_state.lock().unwrap();
+        let (_state, result) = self
+            .shared
+            .unpaused
+            .wait_timeout_while(state, timeout, |s| s.paused && s.steps == 0)
+            .unwrap();
+        result.timed_out()
+    }
+
+    /// Called when a tag has been processed, to use up a step.
+    pub(super) fn tag_processed(&self) {
+        if !self.is_paused() {
+            return;
+        }
+        let mut state = self.shared.run_state.lock().unwrap();
+        state.steps = state.steps.saturating_sub(1);
+    }
+
+    fn change(&self, f: impl FnOnce(&mut Changes)) {
+        f(&mut self.shared.changes.lock().unwrap());
+        self.shared.pending.store(true, Ordering::Release);
+    }
+
+    /// Take the changes that were not applied yet, if any.
+    pub(super) fn take_changes(&self) -> Option<Changes> {
+        if !self.shared.pending.swap(false, Ordering::Acquire) {
+            return None;
+        }
+        Some(std::mem::take(&mut *self.shared.changes.lock().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_timeout_changes_are_composed() {
+        let ms = Duration::from_millis;
+        let control = SchedulerControl::new();
+        assert!(control.take_changes().is_none());
+
+        control.extend_timeout(ms(10));
+        control.extend_timeout(ms(5));
+        assert_eq!(control.take_changes().unwrap().timeout, Some(TimeoutChange::Extend(ms(15))));
+        assert!(control.take_changes().is_none());
+
+        control.set_timeout(Some(ms(100)));
+        control.extend_timeout(ms(5));
+        control.set_anomaly_detection(false);
+        let changes = control.take_changes().unwrap();
+        assert_eq!(changes.timeout, Some(TimeoutChange::Set(Some(ms(105)))));
+        assert_eq!(changes.anomaly_detection, Some(false));
+    }
+
+    #[test]
+    fn test_steps_are_only_counted_while_paused() {
+        let control = SchedulerControl::new();
+        control.step();
+        assert!(!control.wait_while_paused(Duration::ZERO));
+
+        control.pause();
+        control.step();
+        control.step();
+        assert!(!control.wait_while_paused(Duration::ZERO));
+        control.tag_processed();
+        assert!(!control.wait_while_paused(Duration::ZERO));
+        control.tag_processed();
+        assert!(control.wait_while_paused(Duration::ZERO));
+        assert_eq!(control.shared.run_state.lock().unwrap().steps, 0);
+
+        control.step();
+        control.resume();
+        assert!(!control.is_paused());
+        assert_eq!(control.shared.run_state.lock().unwrap().steps, 0);
+    }
+}
@@ -0,0 +1,153 @@
+//! Dumps of the state of the scheduler when the
+//! program panics, see [CrashDump].
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, Once};
+
+use crate::*;
+
+/// Default of [CrashDump::keep_recent].
+const DEFAULT_RECENT_REACTIONS: usize = 64;
+
+/// Writes the state of the scheduler to a file if the program
+/// panics, so that failures in the field can be diagnosed
+/// without reproducing them. The dump contains the panic
+/// message and the thread that panicked, the tag being
+/// processed, the latest reactions that were executed, and
+/// the pending events with the reactions they would trigger.
+///
+/// Install it with [SchedulerOptions::crash_dump]. The dump is
+/// written before the [PanicPolicy] is applied. To describe the
+/// panic, a panic hook is installed the first time a program with
+/// a crash dump runs. It calls the hook that was installed before,
+/// and only applies to the threads of the scheduler.
+pub struct CrashDump {
+    path: PathBuf,
+    capacity: usize,
+    /// Latest executed reactions, oldest first.
+    recent: VecDeque<(EventTag, GlobalReactionId)>,
+    panic: PanicSlot,
+}
+
+impl CrashDump {
+    pub fn to_file(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            capacity: DEFAULT_RECENT_REACTIONS,
+            recent: VecDeque::new(),
+            panic: Default::default(),
+        }
+    }
+
+    /// Set the number of executed reactions that
+    /// are kept for the dump, 64 by default.
+    pub fn keep_recent(mut self, reactions: usize) -> Self {
+        self.capacity = reactions;
+        self
+    }
+
+    pub(super) fn panic_slot(&self) -> PanicSlot {
+        self.panic.clone()
+    }
+
+    /// Record that the given reactions are executed.
+    pub(super) fn record_batch(&mut self, tag: EventTag, batch: impl IntoIterator<Item = GlobalReactionId>) {
+        if self.capacity == 0 {
+            return;
+        }
+        for reaction in batch {
+            if self.recent.len() == self.capacity {
+                self.recent.pop_front();
+            }
+            self.recent.push_back((tag, reaction));
+        }
+    }
+
+    /// Write the dump. Errors are logged.
+    pub(super) fn write(
+        &self,
+        payload: &(dyn Any + Send),
+        current_tag: Option<EventTag>,
+        pending: &[String],
+        debug: &DebugInfoRegistry,
+    ) {
+        let mut dump = String::from("reactor program crashed\n");
+        let panic = self.panic.0.lock().ok().and_then(|p| p.clone());
+        let panic = panic.unwrap_or_else(|| payload_message(payload).to_string());
+        let _ = writeln!(dump, "panic: {}", panic);
+        let _ = match current_tag {
+            Some(tag) => writeln!(dump, "current tag: {}", tag),
+            None => writeln!(dump, "current tag: none, startup was not reached"),
+        };
+        let _ = writeln!(dump, "\nrecent reactions ({}), oldest first:", self.recent.len());
+        for (tag, reaction) in &self.recent {
+            let _ = writeln!(dump, "  {} {}", tag, debug.fmt_reaction(*reaction));
+        }
+        let _ = writeln!(dump, "\npending events ({}):", pending.len());
+        for event in pending {
+            let _ = writeln!(dump, "  {}", event);
+        }
+
+        match std::fs::write(&self.path, dump) {
+            Ok(()) => error!("Wrote crash dump to {}", self.path.display()),
+            Err(e) => error!("Could not write crash dump to {}: {}", self.path.display(), e),
+        }
+    }
+}
+
+/// Where the panic hook describes the panics of the
+/// threads that are watched, see [Self::watch_current_thread].
+#[derive(Clone, Default)]
+pub(super) struct PanicSlot(Arc<Mutex<Option<String>>>);
+
+thread_local! {
+    static WATCHED: RefCell<Option<PanicSlot>> = RefCell::new(None);
+}
+
+static INSTALL_HOOK: Once = Once::new();
+
+impl PanicSlot {
+    /// Describe the panics of the current thread into this slot,
+    /// until [Self::unwatch_current_thread] is called. Only the
+    /// first panic is kept.
+    pub(super) fn watch_current_thread(&self) {
+        INSTALL_HOOK.call_once(|| {
+            let previous = std::panic::take_hook();
+            std::panic::set_hook(Box::new(move |info| {
+                let _ = WATCHED.try_with(|watched| {
+                    if let Some(PanicSlot(slot)) = &*watched.borrow() {
+                        let thread = std::thread::current();
+                        let mut desc = format!("thread '{}' panicked", thread.name().unwrap_or("<unnamed>"));
+                        if let Some(location) = info.location() {
+                            let _ = write!(desc, " at {}", location);
+                        }
+                        let _ = write!(desc, ": {}", payload_message(info.payload()));
+                        if let Ok(mut slot) = slot.lock() {
+                            slot.get_or_insert(desc);
+                        }
+                    }
+                });
+                previous(info)
+            }));
+        });
+        WATCHED.with(|watched| *watched.borrow_mut() = Some(self.clone()));
+    }
+
+    pub(super) fn unwatch_current_thread() {
+        WATCHED.with(|watched| watched.borrow_mut().take());
+    }
+}
+
+fn payload_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg
+    } else {
+        "<non-string panic payload>"
+    }
+}
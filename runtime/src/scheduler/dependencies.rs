@@ -24,22 +24,23 @@
 
 use std::borrow::Cow;
 use std::collections::hash_map::Entry as HEntry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::default::Default;
 use std::fmt::{Debug, Display, Formatter};
 use std::ops::Range;
 use std::sync::Arc;
 
 use index_vec::{Idx, IndexVec};
-use petgraph::graph::{DiGraph, NodeIndex};
-use petgraph::visit::EdgeRef;
-use petgraph::Direction::Outgoing;
+use petgraph::graph::{DiGraph, EdgeReference, NodeIndex};
+use petgraph::visit::{EdgeFiltered, EdgeRef, IntoNeighborsDirected};
+use petgraph::Direction::{Incoming, Outgoing};
 use vecmap::{Entry as VEntry, KeyRef, VecMap};
 
 use super::ReactionPlan;
 use crate::assembly::*;
 use crate::impl_types::GlobalIdImpl;
 use crate::scheduler::dependencies::NodeKind::MultiportUpstream;
+use crate::scheduler::startup_budget::StartupProfiler;
 use crate::*;
 
 type GraphIx = NodeIndex<GlobalIdImpl>;
@@ -86,30 +87,37 @@ impl From<GlobalReactionId> for GraphId {
     }
 }
 
-type DepGraphImpl = DiGraph<GraphNode, EdgeWeight, GlobalIdImpl>;
+impl From<GraphId> for ProgramNode {
+    fn from(id: GraphId) -> Self {
+        match id {
+            GraphId::Trigger(id) => ProgramNode::Trigger(id),
+            GraphId::Reaction(id) => ProgramNode::Reaction(id),
+        }
+    }
+}
 
-/// Dependency graph representing "instantaneous" dependencies,
-/// ie read- and write-dependencies of reactions to ports, and
-/// their trigger dependencies. This must be a DAG.
+type DepGraphImpl = DiGraph<GraphNode, EdgeKind, GlobalIdImpl>;
+type DepEdgeRef<'a> = EdgeReference<'a, EdgeKind, GlobalIdImpl>;
+
+/// Dependency graph of the program. Its edges are typed with
+/// an [EdgeKind]. All edges except [EdgeKind::Delayed] represent
+/// "instantaneous" dependencies, ie read- and write-dependencies
+/// of reactions to ports, their trigger dependencies, and priority
+/// of reactions. Those must form a DAG. Analysis passes only
+/// consider those edges, see [Self::instantaneous].
 ///
 /// One global instance is built during the assembly process (see [RootAssembler]).
 /// Initialization completes when that instance is turned into
 /// a [DataflowInfo], which is the data structure used at runtime.
 ///
 pub(super) struct DepGraph {
-    /// Instantaneous data flow. Must be acyclic. Edges from
-    /// reactions to actions are not represented, as they are
+    /// Data flow and control flow. Edges from reactions to
+    /// actions are [delayed](EdgeKind::Delayed), as they are
     /// not actually a data dependency that could cause a
     /// scheduling conflict. Conveniently those edges are the
     /// only way control flow may be cyclic in the reactor model,
-    /// so a stock check for cycles can be used.
-    ///
-    /// There are several kind of edges:
-    /// - reaction -> port: the reaction effects the port/action
-    /// - port/action -> reaction: the port/action triggers the reaction
-    /// - port -> port: a binding of a port to another
-    /// - reaction n -> reaction m: means n has higher priority
-    /// than m, only filled in for reactions of the same reactor.
+    /// so a stock check for cycles can be used once they are
+    /// filtered out.
     dataflow: DepGraphImpl,
 
     /// Maps global IDs back to graph indices.
@@ -120,6 +128,13 @@ pub(super) struct DepGraph {
     multiport_containment: HashMap<GraphId, TriggerId>,
     /// Map of multiport ID -> range of IDs for its channels
     multiport_ranges: VecMap<TriggerId, Range<TriggerId>>,
+
+    /// Deadlines of the reactions that have one.
+    deadlines: HashMap<GlobalReactionId, Duration>,
+
+    /// Ports whose value is read at the end of each tag
+    /// by something else than a reaction, eg an export.
+    observed: HashSet<TriggerId>,
 }
 
 impl Debug for GraphNode {
@@ -140,6 +155,8 @@ impl DepGraph {
             ix_by_id: Default::default(),
             multiport_containment: Default::default(),
             multiport_ranges: Default::default(),
+            deadlines: Default::default(),
+            observed: Default::default(),
         };
         ich.record_special(TriggerId::STARTUP);
         ich.record_special(TriggerId::SHUTDOWN);
@@ -162,10 +179,51 @@ impl DepGraph {
                 GraphId::Trigger(TriggerId::SHUTDOWN) => "shutdown".to_string(),
                 GraphId::Trigger(id) => format!("{:?}({})", n.kind, id_registry.fmt_component(id)),
             },
-            |_, _| "",
+            |_, kind| *kind,
         );
 
-        format!("{}", Dot::with_config(&labeled, &[Config::EdgeNoLabel]))
+        let edge_style = |_, e: DepEdgeRef<'_>| match e.weight() {
+            EdgeKind::Trigger | EdgeKind::Effect | EdgeKind::Binding => String::new(),
+            EdgeKind::Use => "style = dashed".to_string(),
+            EdgeKind::Priority => "style = dotted".to_string(),
+            EdgeKind::Delayed => "style = dashed, color = gray".to_string(),
+        };
+        let dot = Dot::with_attr_getters(&labeled, &[Config::EdgeNoLabel], &edge_style, &|_, _| String::new());
+        format!("{}", dot)
+    }
+
+    /// Produce a JSON representation of the graph. Nodes
+    /// are referred to by their index in the list of nodes.
+    #[cold]
+    #[inline(never)]
+    pub fn format_json(&self, id_registry: &DebugInfoRegistry) -> String {
+        use std::fmt::Write;
+
+        use crate::util::json_string;
+
+        let mut json = "{\"nodes\":[".to_string();
+        for (i, ix) in self.dataflow.node_indices().enumerate() {
+            let sep = if i == 0 { "" } else { "," };
+            let kind = &self.dataflow[ix].kind;
+            let label = json_string(&self.fmt_node(ix, id_registry));
+            write!(json, "{}{{\"kind\":\"{:?}\",\"label\":{}}}", sep, kind, label).unwrap();
+        }
+        json += "],\"edges\":[";
+        for (i, e) in self.dataflow.edge_references().enumerate() {
+            let sep = if i == 0 { "" } else { "," };
+            let (from, to) = (e.source().index(), e.target().index());
+            write!(
+                json,
+                "{}{{\"from\":{},\"to\":{},\"kind\":\"{:?}\"}}",
+                sep,
+                from,
+                to,
+                e.weight()
+            )
+            .unwrap();
+        }
+        json += "]}";
+        json
     }
 
     pub(super) fn record_port(&mut self, id: TriggerId) {
@@ -200,7 +258,7 @@ impl DepGraph {
         {
             self.multiport_containment.insert(GraphId::Trigger(channel_id), id);
 
-            // self.dataflow.add_edge(upstream_ix, channel_ix, EdgeWeight::Default);
+            // self.dataflow.add_edge(upstream_ix, channel_ix, EdgeKind::Binding);
         }
         self.multiport_ranges.insert(id, id.next_range(len).unwrap());
         Ok(())
@@ -209,7 +267,7 @@ impl DepGraph {
     pub(super) fn record_port_bank_component(&mut self, bank_id: TriggerId, channel_id: TriggerId) {
         let channel_ix = self.record_port_impl(channel_id);
         self.dataflow
-            .add_edge(self.get_ix(bank_id.into()), channel_ix, EdgeWeight::Default);
+            .add_edge(self.get_ix(bank_id.into()), channel_ix, EdgeKind::Binding);
     }
 
     pub(super) fn record_laction(&mut self, id: TriggerId) {
@@ -231,7 +289,7 @@ impl DepGraph {
     /// Records that n > m, ie it will execute always before m.
     pub fn reaction_priority(&mut self, n: GlobalReactionId, m: GlobalReactionId) {
         self.dataflow
-            .add_edge(self.get_ix(n.into()), self.get_ix(m.into()), EdgeWeight::Default);
+            .add_edge(self.get_ix(n.into()), self.get_ix(m.into()), EdgeKind::Priority);
     }
 
     pub fn port_bind<T: Sync>(&mut self, p1: &Port<T>, p2: &Port<T>) {
@@ -239,7 +297,7 @@ impl DepGraph {
         self.dataflow.add_edge(
             self.get_ix(p1.get_id().into()),
             self.get_ix(p2.get_id().into()),
-            EdgeWeight::Default,
+            EdgeKind::Binding,
         );
     }
 
@@ -247,37 +305,53 @@ impl DepGraph {
     pub fn port_bind_untyped(&mut self, p1: TriggerId, p2: TriggerId) {
         // upstream (settable) -> downstream (bound)
         self.dataflow
-            .add_edge(self.get_ix(p1.into()), self.get_ix(p2.into()), EdgeWeight::Default);
+            .add_edge(self.get_ix(p1.into()), self.get_ix(p2.into()), EdgeKind::Binding);
     }
 
     pub fn triggers_reaction(&mut self, trigger: TriggerId, reaction: GlobalReactionId) {
-        self.trigger_to_reaction_edge(trigger, reaction, EdgeWeight::Default);
+        self.trigger_to_reaction_edge(trigger, reaction, EdgeKind::Trigger);
     }
 
     pub fn reaction_uses(&mut self, reaction: GlobalReactionId, trigger: TriggerId) {
-        self.trigger_to_reaction_edge(trigger, reaction, EdgeWeight::Use);
+        self.trigger_to_reaction_edge(trigger, reaction, EdgeKind::Use);
     }
 
-    fn trigger_to_reaction_edge(&mut self, trigger: TriggerId, reaction: GlobalReactionId, weight: EdgeWeight) {
+    fn trigger_to_reaction_edge(&mut self, trigger: TriggerId, reaction: GlobalReactionId, kind: EdgeKind) {
         let trigger_ix = self.get_ix(trigger.into());
         let reaction_ix = self.get_ix(reaction.into());
 
         if self.dataflow[trigger_ix].kind == MultiportUpstream {
             for channel_id in TriggerId::iter_range(self.multiport_ranges.get(&trigger).unwrap()) {
                 // trigger -> reaction
-                self.dataflow.add_edge(self.get_ix(channel_id.into()), reaction_ix, weight);
+                self.dataflow.add_edge(self.get_ix(channel_id.into()), reaction_ix, kind);
             }
             return;
         }
 
         // trigger -> reaction
-        self.dataflow.add_edge(trigger_ix, reaction_ix, weight);
+        self.dataflow.add_edge(trigger_ix, reaction_ix, kind);
+    }
+
+    /// Record that the value of the port is read at the end
+    /// of each tag, so no reaction may move it out.
+    pub fn port_observed(&mut self, port: TriggerId) {
+        self.observed.insert(port);
+    }
+
+    pub fn reaction_deadline(&mut self, reaction: GlobalReactionId, deadline: Duration) {
+        self.deadlines.insert(reaction, deadline);
     }
 
     pub fn reaction_effects(&mut self, reaction: GlobalReactionId, trigger: TriggerId) {
         // reaction -> trigger
         self.dataflow
-            .add_edge(self.get_ix(reaction.into()), self.get_ix(trigger.into()), EdgeWeight::Default);
+            .add_edge(self.get_ix(reaction.into()), self.get_ix(trigger.into()), EdgeKind::Effect);
+    }
+
+    pub fn reaction_schedules(&mut self, reaction: GlobalReactionId, action: TriggerId) {
+        // reaction -> action
+        self.dataflow
+            .add_edge(self.get_ix(reaction.into()), self.get_ix(action.into()), EdgeKind::Delayed);
     }
 
     fn get_ix(&self, id: GraphId) -> GraphIx {
@@ -303,16 +377,152 @@ impl DepGraph {
 }
 
 impl DepGraph {
+    /// The subgraph of instantaneous dependencies, which
+    /// must be acyclic.
+    fn instantaneous(&self) -> EdgeFiltered<&DepGraphImpl, fn(DepEdgeRef<'_>) -> bool> {
+        EdgeFiltered(&self.dataflow, |e| e.weight().is_instantaneous())
+    }
+
+    /// Describe the cycles of the graph, one diagnostic
+    /// per strongly connected component.
+    pub(super) fn cycle_diagnostics(&self, debug: &DebugInfoRegistry) -> Vec<Diagnostic> {
+        let is_self_loop = |ix: GraphIx| self.dataflow.edges_connecting(ix, ix).any(|e| e.weight().is_instantaneous());
+        petgraph::algo::tarjan_scc(&self.instantaneous())
+            .into_iter()
+            .filter(|scc| scc.len() > 1 || is_self_loop(scc[0]))
+            .map(|mut scc| {
+                scc.sort();
+                let mut message = "Cyclic dependency between ".to_string();
+                join_to!(&mut message, scc.iter(), ", ", "", "", |ix| self.fmt_node(*ix, debug)).unwrap();
+                Diagnostic { path: None, message }
+            })
+            .collect()
+    }
+
+    /// A shortest cycle of instantaneous dependencies that
+    /// goes through the given node, which must be on a cycle.
+    /// The cycle starts with its smallest node, and does not
+    /// repeat it at the end.
+    fn cycle_through(&self, start: GraphIx) -> Vec<GraphIx> {
+        let instantaneous = self.instantaneous();
+        let mut predecessors = HashMap::<GraphIx, GraphIx>::new();
+        let mut queue = std::collections::VecDeque::from(vec![start]);
+        'search: while let Some(ix) = queue.pop_front() {
+            for succ in instantaneous.neighbors_directed(ix, Outgoing) {
+                if let HEntry::Vacant(e) = predecessors.entry(succ) {
+                    e.insert(ix);
+                    if succ == start {
+                        break 'search;
+                    }
+                    queue.push_back(succ);
+                }
+            }
+        }
+
+        let mut cycle = vec![start];
+        let mut ix = predecessors[&start];
+        while ix != start {
+            cycle.push(ix);
+            ix = predecessors[&ix];
+        }
+        cycle.reverse();
+        let min = (0..cycle.len()).min_by_key(|i| cycle[*i]).unwrap();
+        cycle.rotate_left(min);
+        cycle
+    }
+
+    /// Warn about reactions that have no trigger, as they
+    /// are never executed.
+    pub(super) fn untriggered_reaction_diagnostics(&self, debug: &DebugInfoRegistry) -> Vec<Diagnostic> {
+        self.dataflow
+            .node_indices()
+            .filter(|ix| matches!(self.dataflow[*ix].id, GraphId::Reaction(_)))
+            .filter(|ix| {
+                !self
+                    .dataflow
+                    .edges_directed(*ix, Incoming)
+                    .any(|e| *e.weight() == EdgeKind::Trigger)
+            })
+            .map(|ix| Diagnostic {
+                path: Some(self.fmt_node(ix, debug)),
+                message: "Reaction has no trigger and will never be executed".into(),
+            })
+            .collect()
+    }
+
+    /// Report ports that are set by reactions of several
+    /// reactors, or both set by reactions and bound to an
+    /// upstream port.
+    pub(super) fn multiple_writer_diagnostics(&self, debug: &DebugInfoRegistry) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for ix in self
+            .dataflow
+            .node_indices()
+            .filter(|ix| self.dataflow[*ix].kind == NodeKind::Port)
+        {
+            let mut writers = Vec::new();
+            let mut upstream = None;
+            for e in self.dataflow.edges_directed(ix, Incoming) {
+                match (e.weight(), &self.dataflow[e.source()]) {
+                    (EdgeKind::Effect, GraphNode { id: GraphId::Reaction(r), .. }) => writers.push(*r),
+                    (EdgeKind::Binding, GraphNode { kind: NodeKind::Port, .. }) => upstream = Some(e.source()),
+                    _ => {}
+                }
+            }
+            writers.sort();
+            let mut message = match upstream {
+                Some(up) if !writers.is_empty() => format!("Port is bound to {}, but also set by ", self.fmt_node(up, debug)),
+                _ if writers.iter().any(|w| w.0.container() != writers[0].0.container()) => {
+                    "Port is set by reactions of several reactors: ".to_string()
+                }
+                _ => continue,
+            };
+            join_to!(&mut message, writers.iter(), ", ", "", "", |r| debug
+                .fmt_reaction(*r)
+                .to_string())
+            .unwrap();
+            diagnostics.push(Diagnostic { path: Some(self.fmt_node(ix, debug)), message });
+        }
+        diagnostics
+    }
+
+    /// All nodes and edges of the graph, for [ProgramGraph].
+    pub(super) fn nodes(&self) -> impl Iterator<Item = ProgramNode> + '_ {
+        self.dataflow.node_weights().map(|n| n.id.into())
+    }
+
+    pub(super) fn edges(&self) -> impl Iterator<Item = (ProgramNode, ProgramNode, EdgeKind)> + '_ {
+        self.dataflow.edge_references().map(move |e| {
+            let source = self.dataflow[e.source()].id.into();
+            let target = self.dataflow[e.target()].id.into();
+            (source, target, *e.weight())
+        })
+    }
+
+    fn fmt_node(&self, ix: GraphIx, debug: &DebugInfoRegistry) -> String {
+        match self.dataflow[ix].id {
+            GraphId::Reaction(id) => debug.fmt_reaction(id).to_string(),
+            GraphId::Trigger(TriggerId::STARTUP) => "startup".to_string(),
+            GraphId::Trigger(TriggerId::SHUTDOWN) => "shutdown".to_string(),
+            GraphId::Trigger(id) => debug.fmt_component(id).to_string(),
+        }
+    }
+
     pub(self) fn number_reactions_by_level(&self) -> AssemblyResult<HashMap<GlobalReactionId, LevelIx>> {
-        let toposorted = petgraph::algo::toposort(&self.dataflow, None)
-            .map_err(|_| AssemblyError(AssemblyErrorImpl::CyclicDependencyGraph))?;
+        let instantaneous = self.instantaneous();
+        let toposorted = petgraph::algo::toposort(&instantaneous, None).map_err(|cycle| {
+            let path = self.cycle_through(cycle.node_id()).into_iter();
+            AssemblyError(AssemblyErrorImpl::CyclicDependencyGraph(
+                path.map(|ix| self.dataflow[ix].id.into()).collect(),
+            ))
+        })?;
 
         let mut levels = HashMap::<GraphIx, LevelIx>::with_capacity(self.dataflow.node_count());
 
         for ix in &toposorted {
             let cur_level = *levels.entry(*ix).or_insert(LevelIx::ZERO);
 
-            let successors = self.dataflow.edges_directed(*ix, Outgoing).map(|e| e.target());
+            let successors = instantaneous.neighbors_directed(*ix, Outgoing);
 
             for succ_ix in successors {
                 let succ_level = levels.entry(succ_ix).or_insert(LevelIx::ZERO);
@@ -334,16 +544,40 @@ impl DepGraph {
     }
 }
 
+/// Kind of a dependency of the program graph, see [ProgramGraph].
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
-enum EdgeWeight {
-    /// Default semantics for this edge (determined by the
-    /// kind of source and target vertex). This only makes a
-    /// difference for edges from a port/action to a reaction:
-    /// if they're labeled `Default`, they're trigger dependencies,
-    /// otherwise use dependencies.
-    Default,
-    ///
+pub enum EdgeKind {
+    /// port/action/timer -> reaction: the component triggers
+    /// the reaction.
+    Trigger,
+    /// port/action -> reaction: the reaction reads the component,
+    /// but is not triggered by it.
     Use,
+    /// reaction -> port/timer: the reaction sets the component
+    /// at the current tag.
+    Effect,
+    /// port -> port: a binding of a port to another, or of a
+    /// port bank to one of its channels.
+    Binding,
+    /// reaction n -> reaction m: means n has higher priority
+    /// than m, only filled in for reactions of the same reactor.
+    Priority,
+    /// reaction -> action: the reaction schedules the action,
+    /// which takes effect at a later tag.
+    Delayed,
+}
+
+impl EdgeKind {
+    /// Whether this edge is a dependency within a single tag.
+    pub fn is_instantaneous(self) -> bool {
+        self != EdgeKind::Delayed
+    }
+}
+
+impl Display for EdgeKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self, f)
+    }
 }
 
 /// Stores the level of each reaction. This is transient info
@@ -370,6 +604,16 @@ impl ReactionLevelInfo {
     }
 }
 
+/// The reactions that may read a trigger at a tag.
+#[derive(Copy, Clone, Debug, Default)]
+struct Readers {
+    count: usize,
+    /// The reader that is executed after all the others, if
+    /// any. This is the reader with the highest level, if no
+    /// other reader shares that level.
+    last: Option<GlobalReactionId>,
+}
+
 /// Pre-calculated dependency information,
 /// using the dependency graph
 pub(super) struct DataflowInfo {
@@ -377,21 +621,48 @@ pub(super) struct DataflowInfo {
     /// to be scheduled when it is triggered.
     /// Todo: many of those are never asked for, eg those of bound ports
     trigger_to_plan: IndexVec<TriggerId, Arc<ExecutableReactions<'static>>>,
+    /// Maps each trigger to the reactions that it
+    /// triggers, or that use it.
+    trigger_to_readers: IndexVec<TriggerId, Readers>,
+    /// Deadlines of the reactions that have one.
+    deadlines: HashMap<GlobalReactionId, Duration>,
 }
 
 impl DataflowInfo {
-    pub fn new(mut graph: DepGraph) -> Result<Self, AssemblyError> {
+    pub fn new(graph: DepGraph) -> Result<Self, AssemblyError> {
+        Self::analyze(graph, None)
+    }
+
+    /// Like [Self::new], recording the time
+    /// spent in each pass into the profiler.
+    pub(super) fn analyze(mut graph: DepGraph, mut profiler: Option<&mut StartupProfiler>) -> Result<Self, AssemblyError> {
+        let start = Instant::now();
         let level_info = ReactionLevelInfo::new(graph.number_reactions_by_level()?);
-        let trigger_to_plan = Self::collect_trigger_to_plan(&mut graph, &level_info);
+        if let Some(profiler) = &mut profiler {
+            profiler.pass_done("reaction levels", start);
+        }
+        let start = Instant::now();
+        let (trigger_to_plan, trigger_to_readers) = Self::collect_trigger_to_plan(&mut graph, &level_info);
+        if let Some(profiler) = &mut profiler {
+            profiler.pass_done("reaction plans", start);
+        }
 
-        Ok(DataflowInfo { trigger_to_plan })
+        Ok(DataflowInfo {
+            trigger_to_plan,
+            trigger_to_readers,
+            deadlines: std::mem::take(&mut graph.deadlines),
+        })
     }
 
     fn collect_trigger_to_plan(
-        DepGraph { dataflow, .. }: &mut DepGraph,
+        DepGraph { dataflow, observed, .. }: &mut DepGraph,
         level_info: &ReactionLevelInfo,
-    ) -> IndexVec<TriggerId, Arc<ExecutableReactions<'static>>> {
+    ) -> (
+        IndexVec<TriggerId, Arc<ExecutableReactions<'static>>>,
+        IndexVec<TriggerId, Readers>,
+    ) {
         let mut result = IndexVec::with_capacity(dataflow.node_count() / 2);
+        let mut readers_by_trigger = IndexVec::with_capacity(dataflow.node_count() / 2);
 
         for trigger in dataflow.node_indices() {
             if let GraphId::Trigger(trigger_id) = dataflow[trigger].id {
@@ -409,12 +680,45 @@ impl DataflowInfo {
                 // }
 
                 let mut reactions = ExecutableReactions::new();
-                Self::collect_reactions_rec(dataflow, trigger, level_info, &mut reactions);
+                let mut readers = Vec::new();
+                let mut is_observed = false;
+                Self::collect_reactions_rec(dataflow, trigger, level_info, &mut reactions, &mut readers);
+                Self::collect_observed_rec(dataflow, trigger, observed, &mut is_observed);
                 result.insert(trigger_id, Arc::new(reactions));
+                // a reaction may be reached through several bindings
+                readers.sort();
+                readers.dedup();
+                let mut readers = Self::readers(&readers, level_info);
+                if is_observed {
+                    readers.last = None;
+                }
+                readers_by_trigger.insert(trigger_id, readers);
             }
         }
 
-        result
+        (result, readers_by_trigger)
+    }
+
+    fn readers(readers: &[GlobalReactionId], level_info: &ReactionLevelInfo) -> Readers {
+        let level = |r: &GlobalReactionId| level_info.level_numbers[r];
+        let max_level = readers.iter().map(level).max();
+        let mut last = readers.iter().filter(|r| Some(level(r)) == max_level);
+        Readers {
+            count: readers.len(),
+            last: last.next().filter(|_| last.next().is_none()).copied(),
+        }
+    }
+
+    /// Whether the trigger, or one of the ports bound to it, is observed.
+    fn collect_observed_rec(dataflow: &DepGraphImpl, trigger: GraphIx, observed: &HashSet<TriggerId>, is_observed: &mut bool) {
+        if let GraphId::Trigger(id) = dataflow[trigger].id {
+            *is_observed |= observed.contains(&id);
+        }
+        for downstream in dataflow.edges_directed(trigger, Outgoing) {
+            if dataflow[downstream.target()].kind == NodeKind::Port {
+                Self::collect_observed_rec(dataflow, downstream.target(), observed, is_observed)
+            }
+        }
     }
 
     fn collect_reactions_rec(
@@ -422,21 +726,23 @@ impl DataflowInfo {
         trigger: GraphIx,
         level_info: &ReactionLevelInfo,
         reactions: &mut ExecutableReactions<'static>,
+        readers: &mut Vec<GlobalReactionId>,
     ) {
         for downstream in dataflow.edges_directed(trigger, Outgoing) {
             let node = &dataflow[downstream.target()];
             match node.kind {
                 NodeKind::Port => {
-                    // this is necessarily a port->port binding
-                    Self::collect_reactions_rec(dataflow, downstream.target(), level_info, reactions)
+                    debug_assert_eq!(downstream.weight(), &EdgeKind::Binding);
+                    Self::collect_reactions_rec(dataflow, downstream.target(), level_info, reactions, readers)
                 }
                 NodeKind::Reaction => {
                     let rid = match node.id {
                         GraphId::Reaction(rid) => rid,
                         _ => unreachable!("this is a reaction"),
                     };
+                    readers.push(rid);
                     // trigger->reaction
-                    if downstream.weight() != &EdgeWeight::Use {
+                    if downstream.weight() == &EdgeKind::Trigger {
                         // so it's a trigger dependency
                         level_info.augment(reactions, rid)
                     }
@@ -458,6 +764,40 @@ impl DataflowInfo {
     pub fn reactions_triggered_by(&self, trigger: &TriggerId) -> &ExecutableReactions<'static> {
         &self.trigger_to_plan[*trigger]
     }
+
+    /// Returns the deadline of the reaction, if it has one.
+    #[inline]
+    pub fn deadline_of(&self, reaction: GlobalReactionId) -> Option<Duration> {
+        self.deadlines.get(&reaction).copied()
+    }
+
+    /// Returns the reactions that have a deadline, with their deadline.
+    pub(crate) fn deadlines(&self) -> impl Iterator<Item = (GlobalReactionId, Duration)> + '_ {
+        self.deadlines.iter().map(|(r, d)| (*r, *d))
+    }
+
+    /// Returns the number of reactions that are triggered by,
+    /// or use the given trigger, directly or through bindings.
+    ///
+    /// # Panics
+    ///
+    /// If the trigger id is not registered
+    pub fn readers_of(&self, trigger: &TriggerId) -> usize {
+        self.trigger_to_readers[*trigger].count
+    }
+
+    /// Returns the reaction that reads the given trigger after
+    /// all other readers, if there is one. Other readers are
+    /// at lower levels, so they are done when it executes.
+    /// Ports that are observed at the end of the tag, eg by an
+    /// export, have no last reader.
+    ///
+    /// # Panics
+    ///
+    /// If the trigger id is not registered
+    pub fn last_reader_of(&self, trigger: &TriggerId) -> Option<GlobalReactionId> {
+        self.trigger_to_readers[*trigger].last
+    }
 }
 
 cfg_if! {
@@ -604,7 +944,7 @@ impl<'x> ExecutableReactions<'x> {
     }
 
     #[inline]
-    pub fn next_batch<'a>(&'a self, min_level_exclusive: KeyRef<&LevelIx>) -> Option<(KeyRef<&'a LevelIx>, &Level)> {
+    pub fn next_batch<'a>(&'a self, min_level_exclusive: KeyRef<&LevelIx>) -> Option<(KeyRef<&'a LevelIx>, &'a Level)> {
         self.levels
             .next_mapping(min_level_exclusive)
             .map(|(ix, cow)| (ix, cow.as_ref()))
@@ -678,11 +1018,14 @@ impl<'x> ExecutableReactions<'x> {
     /// Levels below the `min_level` are not merged, and the caller
     /// shouldn't query them. For all levels >= `min_level`,
     /// the produced reaction plan has all the reactions of
-    /// `x` and `y` for that level.
+    /// `x` and `y` for that level. Borrowed plans are only copied
+    /// if the union is actually larger than both of them.
     pub(super) fn merge_plans_after(x: ReactionPlan<'x>, y: ReactionPlan<'x>, min_level: LevelIx) -> ReactionPlan<'x> {
         match (x, y) {
             (x, None) | (None, x) => x,
             (Some(x), y) | (y, Some(x)) if x.max_level() < min_level => y,
+            // eg a port set twice in a row
+            (Some(Cow::Borrowed(x)), Some(Cow::Borrowed(y))) if std::ptr::eq(x, y) => Some(Cow::Borrowed(x)),
             (Some(Cow::Owned(mut x)), Some(y)) | (Some(y), Some(Cow::Owned(mut x))) => {
                 x.absorb_after(&y, min_level);
                 Some(Cow::Owned(x))
@@ -731,7 +1074,7 @@ pub mod test {
             }
         }
 
-        fn new_reactor(&mut self, name: impl Into<String>) -> TestAssembler {
+        fn new_reactor(&mut self, name: impl Into<String>) -> TestAssembler<'_> {
             let reactor_id = self.next_reactor_id.get_and_incr();
             self.debug_info.record_reactor(reactor_id, ReactorDebugInfo::test_named(name));
             TestAssembler {
@@ -776,12 +1119,21 @@ pub mod test {
 
         fn new_ports<const N: usize>(&mut self, names: [&'static str; N]) -> [TriggerId; N] {
             let result = array![_ => self.fixture.next_trigger_id.get_and_incr().unwrap(); N];
-            for (i, p) in (&result).iter().enumerate() {
+            for (i, p) in result.iter().enumerate() {
                 self.fixture.graph.record_port(*p);
                 self.fixture.debug_info.record_trigger(*p, Cow::Borrowed(names[i]));
             }
             result
         }
+
+        fn new_actions<const N: usize>(&mut self, names: [&'static str; N]) -> [TriggerId; N] {
+            let result = array![_ => self.fixture.next_trigger_id.get_and_incr().unwrap(); N];
+            for (i, a) in result.iter().enumerate() {
+                self.fixture.graph.record_laction(*a);
+                self.fixture.debug_info.record_trigger(*a, Cow::Borrowed(names[i]));
+            }
+            result
+        }
     }
 
     impl Drop for TestAssembler<'_> {
@@ -833,6 +1185,24 @@ pub mod test {
         }
     }
 
+    #[test]
+    fn test_plan_merging_does_not_copy_borrowed_plans() {
+        let level1 = LevelIx::from(1);
+
+        let mut p1 = ExecutableReactions::new();
+        p1.insert(new_reaction(0, 2), level1);
+        let empty = ExecutableReactions::new();
+
+        let is_borrowed = |plan: ReactionPlan<'_>| matches!(plan, Some(Cow::Borrowed(_)));
+        let merged = ExecutableReactions::merge_plans_after(Some(Cow::Borrowed(&p1)), Some(Cow::Borrowed(&p1)), level1);
+        assert!(is_borrowed(merged));
+        let merged = ExecutableReactions::merge_plans_after(Some(Cow::Borrowed(&p1)), Some(Cow::Borrowed(&empty)), level1);
+        assert!(is_borrowed(merged));
+        // levels before the min level are ignored
+        let merged = ExecutableReactions::merge_plans_after(Some(Cow::Borrowed(&empty)), Some(Cow::Borrowed(&p1)), level1.next());
+        assert!(is_borrowed(merged));
+    }
+
     #[test]
     fn test_level_assignment_simple() {
         let mut test = TestGraphFixture::new();
@@ -967,7 +1337,7 @@ pub mod test {
     3 [ label = "Reaction(main/1)" ]
     4 [ label = "Port(main/p0)" ]
     5 [ label = "Port(main/p1)" ]
-    2 -> 3 [ ]
+    2 -> 3 [ style = dotted]
     2 -> 4 [ ]
     2 -> 5 [ ]
     4 -> 3 [ ]
@@ -976,4 +1346,77 @@ pub mod test {
 "#
         );
     }
+
+    #[test]
+    fn test_cycle_diagnostics() {
+        let mut test = TestGraphFixture::new();
+
+        let mut builder = test.new_reactor("main");
+        let [n1, n2] = builder.new_reactions();
+        let [p0, p1] = builder.new_ports(["p0", "p1"]);
+        drop(builder);
+
+        test.graph.triggers_reaction(p0, n1);
+        test.graph.reaction_effects(n1, p1);
+        test.graph.triggers_reaction(p1, n2);
+        test.graph.reaction_effects(n2, p0);
+
+        let diagnostics = test.graph.cycle_diagnostics(&test.debug_info);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Cyclic dependency between main/0, main/1, main/p0, main/p1"
+        );
+    }
+
+    #[test]
+    fn test_cycle_error_reports_the_path() {
+        let mut test = TestGraphFixture::new();
+
+        let mut builder = test.new_reactor("main");
+        let [n1, n2] = builder.new_reactions();
+        let [p0, p1] = builder.new_ports(["p0", "p1"]);
+        drop(builder);
+
+        test.graph.triggers_reaction(p0, n1);
+        test.graph.reaction_effects(n1, p1);
+        test.graph.triggers_reaction(p1, n2);
+        test.graph.reaction_effects(n2, p0);
+
+        let error = test.graph.number_reactions_by_level().err().unwrap();
+        assert_eq!(
+            error.lift(&test.debug_info),
+            // the priority edge between reactions is shorter than p1
+            "Cyclic dependency graph: main/0 -> main/1 -> main/p0 -> main/0"
+        );
+    }
+
+    #[test]
+    fn test_delayed_edges_are_not_instantaneous() {
+        let mut test = TestGraphFixture::new();
+
+        let mut builder = test.new_reactor("main");
+        let [n1, n2] = builder.new_reactions();
+        let [a0] = builder.new_actions(["a0"]);
+        let [p0] = builder.new_ports(["p0"]);
+        drop(builder);
+
+        // n1 reschedules the action that triggers it
+        test.graph.triggers_reaction(a0, n1);
+        test.graph.reaction_schedules(n1, a0);
+        test.graph.reaction_effects(n1, p0);
+        test.graph.reaction_uses(n2, p0);
+
+        assert!(test.graph.cycle_diagnostics(&test.debug_info).is_empty());
+        let levels = test.number_reactions_by_level();
+        assert!(levels[&n1] < levels[&n2]);
+        // n2 is only triggered by a use edge
+        let diagnostics = test.graph.untriggered_reaction_diagnostics(&test.debug_info);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path.as_deref(), Some("main/1"));
+        assert!(test
+            .graph
+            .format_dot(&test.debug_info)
+            .contains("2 -> 4 [ style = dashed, color = gray]"));
+    }
 }
@@ -0,0 +1,247 @@
+//! Detection of the divergence of an execution from a recorded trace.
+
+use std::collections::VecDeque;
+
+use super::DebugInfoProvider;
+use crate::triggers::TriggerId;
+use crate::*;
+
+/// What happened at a tag, in a recorded trace or in
+/// the live execution.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TagRecord {
+    pub tag: EventTag,
+    /// Reactions executed at the tag, sorted.
+    pub reactions: Vec<GlobalReactionId>,
+    /// Triggers present at the tag, sorted.
+    pub present: Vec<TriggerId>,
+}
+
+/// The first point where an execution diverged from
+/// the recorded trace, reported by a [DivergenceDetector].
+#[derive(Clone, Debug)]
+pub struct Divergence {
+    /// Latest tag at which both executions agreed, if any.
+    pub last_agreed: Option<EventTag>,
+    /// What the trace recorded at the tag of the divergence.
+    /// None if the live execution processed a tag that was
+    /// not recorded.
+    pub expected: Option<TagRecord>,
+    /// What the live execution did at that tag. None if
+    /// it skipped a recorded tag, or stopped before it.
+    pub actual: Option<TagRecord>,
+    /// Human-readable description, with the names of the
+    /// reactions and triggers that differ.
+    pub description: String,
+}
+
+type DivergenceCallback = Box<dyn FnOnce(&Divergence) + Send>;
+
+/// Compares a live execution with a recorded trace, tag by tag,
+/// and reports the first tag where they differ, either by the set
+/// of reactions that were executed, or by the set of triggers that
+/// were present. Replaying the inputs of a recorded run, eg with a
+/// [ReplaySource](crate::stdlib::replay::ReplaySource), then tells
+/// whether a change in the program altered its behavior.
+///
+/// Install it with [SchedulerOptions::divergence]. The divergence
+/// is logged, and passed to the callback.
+pub struct DivergenceDetector {
+    reference: VecDeque<TagRecord>,
+    last_agreed: Option<EventTag>,
+    callback: Option<DivergenceCallback>,
+}
+
+impl DivergenceDetector {
+    /// Create a detector for the given trace. Returns None
+    /// if the trace is not complete (see [SampledTrace::is_complete]),
+    /// as a sample cannot be compared tag by tag.
+    pub fn new(trace: &SampledTrace, callback: impl FnOnce(&Divergence) + Send + 'static) -> Option<Self> {
+        if !trace.is_complete() {
+            return None;
+        }
+        let mut reference = VecDeque::<TagRecord>::new();
+        for record in &trace.records {
+            match reference.back_mut() {
+                Some(last) if last.tag == record.tag => last.reactions.push(record.reaction),
+                _ => reference.push_back(TagRecord {
+                    tag: record.tag,
+                    reactions: vec![record.reaction],
+                    present: vec![],
+                }),
+            }
+        }
+        for (record, present) in reference.iter_mut().zip(&trace.present) {
+            debug_assert_eq!(record.tag, present.tag);
+            record.reactions.sort();
+            record.present = present.triggers.clone();
+        }
+        Some(Self {
+            reference,
+            last_agreed: None,
+            callback: Some(Box::new(callback)),
+        })
+    }
+
+    /// Whether the divergence has not been found yet.
+    pub(super) fn is_active(&self) -> bool {
+        self.callback.is_some()
+    }
+
+    /// Compare a processed tag with the trace.
+    pub(super) fn observe_tag(
+        &mut self,
+        tag: EventTag,
+        reactions: impl IntoIterator<Item = GlobalReactionId>,
+        present: impl IntoIterator<Item = TriggerId>,
+        debug: &DebugInfoProvider<'_>,
+    ) {
+        let reactions = reactions.into_iter().collect::<Vec<_>>();
+        // traces have no record of tags without reactions
+        if !self.is_active() || reactions.is_empty() {
+            return;
+        }
+        let mut actual = TagRecord {
+            tag,
+            reactions,
+            present: present.into_iter().collect(),
+        };
+        actual.reactions.sort();
+        actual.present.sort();
+        actual.present.dedup();
+
+        match self.reference.front() {
+            Some(expected) if expected.tag == tag => {
+                let expected = self.reference.pop_front().unwrap();
+                if expected == actual {
+                    self.last_agreed = Some(tag);
+                } else {
+                    self.report(Some(expected), Some(actual), debug)
+                }
+            }
+            // a recorded tag was skipped
+            Some(expected) if expected.tag < tag => {
+                let expected = self.reference.pop_front();
+                self.report(expected, None, debug)
+            }
+            _ => self.report(None, Some(actual), debug),
+        }
+    }
+
+    /// Check that no recorded tag remains, at shutdown.
+    pub(super) fn finish(&mut self, debug: &DebugInfoProvider<'_>) {
+        if self.is_active() {
+            if let Some(expected) = self.reference.pop_front() {
+                self.report(Some(expected), None, debug)
+            }
+        }
+    }
+
+    fn report(&mut self, expected: Option<TagRecord>, actual: Option<TagRecord>, debug: &DebugInfoProvider<'_>) {
+        let description = describe(&expected, &actual, debug);
+        let divergence = Divergence {
+            last_agreed: self.last_agreed,
+            expected,
+            actual,
+            description,
+        };
+        error!("Execution diverged from the recorded trace: {}", divergence.description);
+        if let Some(callback) = self.callback.take() {
+            callback(&divergence)
+        }
+    }
+}
+
+fn describe(expected: &Option<TagRecord>, actual: &Option<TagRecord>, debug: &DebugInfoProvider<'_>) -> String {
+    let (expected, actual) = match (expected, actual) {
+        (Some(e), Some(a)) => (e, a),
+        (Some(e), None) => return format!("recorded tag {} was not processed", e.tag),
+        (None, Some(a)) => return format!("tag {} was not recorded", a.tag),
+        (None, None) => unreachable!(),
+    };
+    let mut msg = format!("at {}", actual.tag);
+    let missing = |x: &[GlobalReactionId], y: &[GlobalReactionId]| {
+        x.iter()
+            .filter(|r| !y.contains(r))
+            .map(|r| debug.display_reaction(*r).to_string())
+            .collect::<Vec<_>>()
+    };
+    let missing_triggers = |x: &[TriggerId], y: &[TriggerId]| {
+        x.iter()
+            .filter(|t| !y.contains(t))
+            .map(|t| debug.id_registry.fmt_component(*t).to_string())
+            .collect::<Vec<_>>()
+    };
+    for (what, names) in [
+        ("reactions not executed", missing(&expected.reactions, &actual.reactions)),
+        ("unexpected reactions", missing(&actual.reactions, &expected.reactions)),
+        ("triggers not present", missing_triggers(&expected.present, &actual.present)),
+        ("unexpected triggers", missing_triggers(&actual.present, &expected.present)),
+    ] {
+        if !names.is_empty() {
+            msg += &format!("; {}: {}", what, names.join(", "));
+        }
+    }
+    msg
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn reaction(i: u16) -> GlobalReactionId {
+        GlobalReactionId::new(ReactorId::new(0), LocalReactionId::new(i as _))
+    }
+
+    fn tag(ms: u64) -> EventTag {
+        EventTag::offset(Duration::from_millis(ms), 0)
+    }
+
+    fn trace(tags: &[(u64, &[u16])]) -> SampledTrace {
+        let mut sampler = TraceSampler::new(TraceSampling::EveryNthTag(1), 100, |_| {});
+        for (ms, reactions) in tags {
+            let timings = reactions.iter().map(|r| (reaction(*r), Duration::ZERO)).collect::<Vec<_>>();
+            sampler.observe_tag(tag(*ms), &timings, [TriggerId::STARTUP]);
+        }
+        sampler.trace()
+    }
+
+    #[test]
+    fn test_first_divergence_is_reported() {
+        let mut registry = DebugInfoRegistry::new();
+        registry.record_reactor(ReactorId::new(0), ReactorDebugInfo::test_named("main"));
+        for i in 0..2 {
+            registry.record_reaction(reaction(i), format!("r{}", i).into());
+        }
+        let debug = DebugInfoProvider { id_registry: &registry };
+        let reference = trace(&[(0, &[0, 1]), (10, &[1]), (20, &[0])]);
+        let reported = std::sync::Arc::new(std::sync::Mutex::new(None));
+        let sink = reported.clone();
+        let mut detector = DivergenceDetector::new(&reference, move |d| *sink.lock().unwrap() = Some(d.clone())).unwrap();
+
+        detector.observe_tag(tag(0), vec![reaction(1), reaction(0)], [TriggerId::STARTUP], &debug);
+        assert!(detector.is_active());
+        detector.observe_tag(tag(10), vec![reaction(0)], [TriggerId::STARTUP], &debug);
+        assert!(!detector.is_active());
+        // only the first divergence is reported
+        detector.observe_tag(tag(15), vec![reaction(0)], [], &debug);
+
+        let divergence = reported.lock().unwrap().take().unwrap();
+        assert_eq!(divergence.last_agreed, Some(tag(0)));
+        assert_eq!(divergence.expected.unwrap().reactions, vec![reaction(1)]);
+        assert_eq!(divergence.actual.unwrap().reactions, vec![reaction(0)]);
+        assert_eq!(
+            divergence.description,
+            "at (T0 + 10000000 ns = 10 ms, 0); reactions not executed: main/1@r1; unexpected reactions: main/0@r0"
+        );
+    }
+
+    #[test]
+    fn test_incomplete_trace_is_rejected() {
+        let mut sampler = TraceSampler::new(TraceSampling::EveryNthTag(2), 100, |_| {});
+        for ms in [0, 10] {
+            sampler.observe_tag(tag(ms), &[(reaction(0), Duration::ZERO)], []);
+        }
+        assert!(DivergenceDetector::new(&sampler.trace(), |_| {}).is_none());
+    }
+}
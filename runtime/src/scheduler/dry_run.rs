@@ -0,0 +1,52 @@
+//! Report of a dry run, see [SyncScheduler::dry_run].
+
+use std::fmt::{Display, Formatter};
+
+use crate::assembly::ValidationReport;
+use crate::*;
+
+/// What a program would do after startup, as found
+/// by [SyncScheduler::dry_run].
+#[derive(Clone, Debug, Default)]
+pub struct DryRunReport {
+    pub validation: ValidationReport,
+    /// Reactions executed at startup. Empty if the
+    /// program is invalid, as it is not started then.
+    pub reactions: Vec<String>,
+    /// Timers armed at startup, with the tag at
+    /// which they would trigger next.
+    pub timers: Vec<(String, EventTag)>,
+    /// Actions scheduled at startup, with the tag
+    /// of their event.
+    pub actions: Vec<(String, EventTag)>,
+    /// Tag at which a reaction requested to stop, if any.
+    pub stop_requested: Option<EventTag>,
+}
+
+impl DryRunReport {
+    /// Returns true if the program is valid. It
+    /// may still have warnings.
+    pub fn is_ok(&self) -> bool {
+        self.validation.is_ok()
+    }
+}
+
+impl Display for DryRunReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.validation)?;
+        if !self.is_ok() {
+            return writeln!(f, "startup was not executed");
+        }
+        writeln!(f, "startup executed {} reactions", self.reactions.len())?;
+        for (timer, tag) in &self.timers {
+            writeln!(f, "timer {} armed for {}", timer, tag)?;
+        }
+        for (action, tag) in &self.actions {
+            writeln!(f, "action {} pending at {}", action, tag)?;
+        }
+        if let Some(tag) = self.stop_requested {
+            writeln!(f, "stop requested at {}", tag)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,392 @@
+//! Recording of the events of an execution, and their replay.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use index_vec::Idx;
+
+use super::DebugInfoProvider;
+use crate::triggers::TriggerId;
+use crate::*;
+
+/// Reactions executed at a tag, as recorded in an [EventLog].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LoggedTag {
+    pub tag: EventTag,
+    /// Reactions executed at the tag, sorted.
+    pub reactions: Vec<GlobalReactionId>,
+}
+
+/// A physical event, as recorded in an [EventLog].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct LoggedEvent {
+    /// Tag assigned to the event when it was scheduled.
+    pub tag: EventTag,
+    /// The physical action that was scheduled.
+    pub trigger: TriggerId,
+}
+
+/// The events of an execution, recorded by an [EventRecorder].
+/// Replaying the log with [SchedulerOptions::replay_events]
+/// reproduces the timing of the physical events, and hence the
+/// sequence of tags and reactions of the recorded execution,
+/// provided the program and its inputs are unchanged.
+///
+/// Logs are saved in a compact binary format, which is only
+/// meant to be read back by the same version of the program,
+/// as it refers to components by their id.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct EventLog {
+    /// Tags at which reactions were executed, in order.
+    pub tags: Vec<LoggedTag>,
+    /// Physical events, in the order they were scheduled.
+    pub physical_events: Vec<LoggedEvent>,
+}
+
+const MAGIC: &[u8; 8] = b"RTEVLOG1";
+
+impl EventLog {
+    /// Write the log in binary format.
+    pub fn write_to(&self, mut w: impl Write) -> io::Result<()> {
+        w.write_all(MAGIC)?;
+        write_u64(&mut w, self.tags.len() as u64)?;
+        for logged in &self.tags {
+            write_tag(&mut w, logged.tag)?;
+            write_u64(&mut w, logged.reactions.len() as u64)?;
+            for reaction in &logged.reactions {
+                write_u64(&mut w, reaction.0.container().index() as u64)?;
+                write_u64(&mut w, reaction.0.local().index() as u64)?;
+            }
+        }
+        write_u64(&mut w, self.physical_events.len() as u64)?;
+        for evt in &self.physical_events {
+            write_tag(&mut w, evt.tag)?;
+            write_u64(&mut w, evt.trigger.index() as u64)?;
+        }
+        w.flush()
+    }
+
+    /// Read a log written by [Self::write_to].
+    pub fn read_from(mut r: impl Read) -> io::Result<Self> {
+        let mut magic = [0u8; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not an event log"));
+        }
+        let mut log = EventLog::default();
+        for _ in 0..read_u64(&mut r)? {
+            let tag = read_tag(&mut r)?;
+            let mut reactions = Vec::new();
+            for _ in 0..read_u64(&mut r)? {
+                let container = ReactorId::from_usize(read_u64(&mut r)? as usize);
+                let local = LocalReactionId::from_usize(read_u64(&mut r)? as usize);
+                reactions.push(GlobalReactionId::new(container, local));
+            }
+            log.tags.push(LoggedTag { tag, reactions });
+        }
+        for _ in 0..read_u64(&mut r)? {
+            let tag = read_tag(&mut r)?;
+            let trigger = TriggerId::from_usize(read_u64(&mut r)? as usize);
+            log.physical_events.push(LoggedEvent { tag, trigger });
+        }
+        Ok(log)
+    }
+
+    /// Write the log to a file, see [Self::write_to].
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_to(BufWriter::new(File::create(path)?))
+    }
+
+    /// Read a log from a file, see [Self::read_from].
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+}
+
+fn write_u64(w: &mut impl Write, u: u64) -> io::Result<()> {
+    w.write_all(&u.to_le_bytes())
+}
+
+fn write_tag(w: &mut impl Write, tag: EventTag) -> io::Result<()> {
+    write_u64(w, tag.offset_from_t0.as_nanos() as u64)?;
+    w.write_all(&tag.microstep.raw().to_le_bytes())
+}
+
+fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_tag(r: &mut impl Read) -> io::Result<EventTag> {
+    let nanos = read_u64(r)?;
+    let mut bytes = [0u8; 4];
+    r.read_exact(&mut bytes)?;
+    Ok(EventTag::offset(Duration::from_nanos(nanos), u32::from_le_bytes(bytes)))
+}
+
+type LogCallback = Box<dyn FnOnce(EventLog) + Send>;
+
+/// Records the events of an execution into an [EventLog],
+/// which is passed to a callback at shutdown. Install it with
+/// [SchedulerOptions::record_events].
+///
+/// The log records the tag of every physical event, and the
+/// reactions executed at every tag. The values of physical
+/// actions are not recorded: a replay reuses the values
+/// produced by the live sources, only their timing is
+/// reproduced.
+pub struct EventRecorder {
+    tags: Vec<LoggedTag>,
+    callback: Option<LogCallback>,
+}
+
+impl EventRecorder {
+    pub fn new(callback: impl FnOnce(EventLog) + Send + 'static) -> Self {
+        Self {
+            tags: Vec::new(),
+            callback: Some(Box::new(callback)),
+        }
+    }
+
+    /// Save the log to the given file at shutdown. Errors
+    /// are logged.
+    pub fn to_file(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self::new(move |log| match log.save(&path) {
+            Ok(()) => info!("Wrote event log to {}", path.display()),
+            Err(e) => error!("Could not write event log to {}: {}", path.display(), e),
+        })
+    }
+
+    pub(super) fn observe_tag(&mut self, tag: EventTag, reactions: impl IntoIterator<Item = GlobalReactionId>) {
+        let mut reactions = reactions.into_iter().collect::<Vec<_>>();
+        if reactions.is_empty() {
+            return;
+        }
+        reactions.sort();
+        self.tags.push(LoggedTag { tag, reactions });
+    }
+
+    /// Tags recorded so far.
+    pub(super) fn tags(&self) -> &[LoggedTag] {
+        &self.tags
+    }
+
+    /// Pass the log to the callback, with the given physical events.
+    pub(super) fn finish(&mut self, physical_events: Vec<LoggedEvent>) {
+        if let Some(callback) = self.callback.take() {
+            callback(EventLog {
+                tags: std::mem::take(&mut self.tags),
+                physical_events,
+            })
+        }
+    }
+}
+
+/// Assigns tags to physical events, when they are recorded or
+/// replayed. This is shared with asynchronous threads.
+#[derive(Default)]
+pub(crate) struct PhysicalTags {
+    /// Physical events scheduled so far, if recording.
+    recorded: Option<Mutex<Vec<LoggedEvent>>>,
+    /// Tags of the physical events to replay, by action.
+    replayed: Option<Mutex<HashMap<TriggerId, VecDeque<EventTag>>>>,
+}
+
+impl PhysicalTags {
+    pub(super) fn new(record: bool, replay: Option<&EventLog>) -> Self {
+        Self {
+            recorded: record.then(Default::default),
+            replayed: replay.map(|log| {
+                let mut by_trigger = HashMap::<TriggerId, VecDeque<EventTag>>::new();
+                for evt in &log.physical_events {
+                    by_trigger.entry(evt.trigger).or_default().push_back(evt.tag);
+                }
+                Mutex::new(by_trigger)
+            }),
+        }
+    }
+
+    /// The tag of a new physical event of the given action,
+    /// which has the given tag in a live execution. When
+    /// replaying, this is the next recorded tag of the action,
+    /// if any remains.
+    pub(super) fn tag_of(&self, trigger: TriggerId, live: EventTag) -> EventTag {
+        let replayed = self.replayed.as_ref().and_then(|r| {
+            let tag = r.lock().unwrap().get_mut(&trigger).and_then(VecDeque::pop_front);
+            if tag.is_none() {
+                warn!("No recorded event left to replay for {:?}, using the physical time", trigger);
+            }
+            tag
+        });
+        let tag = replayed.unwrap_or(live);
+        if let Some(recorded) = &self.recorded {
+            recorded.lock().unwrap().push(LoggedEvent { tag, trigger });
+        }
+        tag
+    }
+
+    /// The physical events scheduled so far, if recording.
+    pub(super) fn take_recorded(&self) -> Vec<LoggedEvent> {
+        self.recorded
+            .as_ref()
+            .map(|r| std::mem::take(&mut *r.lock().unwrap()))
+            .unwrap_or_default()
+    }
+}
+
+/// State of the scheduler during a replay.
+pub(super) struct EventReplay {
+    /// Recorded physical events that have not been received yet.
+    /// The scheduler must not process their tag before they are.
+    expected: BTreeMap<EventTag, Vec<TriggerId>>,
+    /// Recorded tags that have not been processed yet.
+    tags: VecDeque<LoggedTag>,
+    /// Whether a difference with the recorded reactions was reported.
+    diverged: bool,
+}
+
+impl EventReplay {
+    pub(super) fn new(log: &EventLog) -> Self {
+        let mut expected = BTreeMap::<EventTag, Vec<TriggerId>>::new();
+        for evt in &log.physical_events {
+            expected.entry(evt.tag).or_default().push(evt.trigger);
+        }
+        Self {
+            expected,
+            tags: log.tags.iter().cloned().collect(),
+            diverged: false,
+        }
+    }
+
+    /// Returns the earliest recorded physical event, if it
+    /// has not been received and its tag is not later than
+    /// the given one.
+    pub(super) fn awaited_before(&self, tag: EventTag) -> Option<EventTag> {
+        self.expected.keys().next().copied().filter(|t| *t <= tag)
+    }
+
+    /// Record that a physical event was received.
+    pub(super) fn received(&mut self, tag: EventTag, trigger: TriggerId) {
+        if let Some(triggers) = self.expected.get_mut(&tag) {
+            if let Some(i) = triggers.iter().position(|t| *t == trigger) {
+                triggers.swap_remove(i);
+                if triggers.is_empty() {
+                    self.expected.remove(&tag);
+                }
+            }
+        }
+    }
+
+    /// Stop waiting for recorded physical events, eg because
+    /// no thread can send them anymore.
+    pub(super) fn abandon(&mut self) {
+        if !self.expected.is_empty() {
+            warn!(
+                "{} recorded physical events were not replayed",
+                self.expected.values().map(Vec::len).sum::<usize>()
+            );
+            self.expected.clear();
+        }
+    }
+
+    /// Compare the reactions executed at a tag with the recording.
+    /// Only the first difference is logged.
+    pub(super) fn observe_tag(
+        &mut self,
+        tag: EventTag,
+        reactions: impl IntoIterator<Item = GlobalReactionId>,
+        debug: &DebugInfoProvider<'_>,
+    ) {
+        let mut reactions = reactions.into_iter().collect::<Vec<_>>();
+        if self.diverged || reactions.is_empty() {
+            return;
+        }
+        reactions.sort();
+        if self.tags.front().map_or(false, |logged| logged.tag < tag) {
+            let skipped = self.tags.pop_front().unwrap();
+            return self.report(format!("recorded tag {} was not processed", skipped.tag));
+        }
+        match self.tags.pop_front() {
+            Some(logged) if logged.tag == tag && logged.reactions == reactions => {}
+            Some(logged) if logged.tag == tag => {
+                let names =
+                    |rs: &[GlobalReactionId]| rs.iter().map(|r| debug.display_reaction(*r).to_string()).collect::<Vec<_>>();
+                self.report(format!(
+                    "at {}, recorded reactions [{}], executed [{}]",
+                    tag,
+                    names(&logged.reactions).join(", "),
+                    names(&reactions).join(", ")
+                ))
+            }
+            _ => self.report(format!("tag {} was not recorded", tag)),
+        }
+    }
+
+    fn report(&mut self, msg: String) {
+        self.diverged = true;
+        warn!("Replay diverged from the event log: {}", msg);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tag(ms: u64, microstep: u32) -> EventTag {
+        EventTag::offset(Duration::from_millis(ms), microstep)
+    }
+
+    #[test]
+    fn test_binary_roundtrip() {
+        let reaction = |r, l| GlobalReactionId::new(ReactorId::new(r), LocalReactionId::new(l));
+        let log = EventLog {
+            tags: vec![
+                LoggedTag {
+                    tag: tag(0, 0),
+                    reactions: vec![reaction(0, 0), reaction(1, 2)],
+                },
+                LoggedTag { tag: tag(12, 3), reactions: vec![reaction(1, 1)] },
+            ],
+            physical_events: vec![LoggedEvent { tag: tag(12, 0), trigger: TriggerId::from_usize(4) }],
+        };
+        let mut bytes = Vec::new();
+        log.write_to(&mut bytes).unwrap();
+        assert_eq!(EventLog::read_from(bytes.as_slice()).unwrap(), log);
+
+        bytes[0] = b'X';
+        assert_eq!(
+            EventLog::read_from(bytes.as_slice()).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn test_replayed_tags_are_used_in_order() {
+        let action = TriggerId::from_usize(4);
+        let log = EventLog {
+            tags: vec![],
+            physical_events: vec![
+                LoggedEvent { tag: tag(5, 0), trigger: action },
+                LoggedEvent { tag: tag(9, 0), trigger: action },
+            ],
+        };
+        let tags = PhysicalTags::new(true, Some(&log));
+        assert_eq!(tags.tag_of(action, tag(1, 0)), tag(5, 0));
+        assert_eq!(tags.tag_of(action, tag(2, 0)), tag(9, 0));
+        // the log is exhausted
+        assert_eq!(tags.tag_of(action, tag(3, 0)), tag(3, 0));
+        assert_eq!(tags.take_recorded().len(), 3);
+
+        let mut replay = EventReplay::new(&log);
+        assert_eq!(replay.awaited_before(tag(4, 0)), None);
+        assert_eq!(replay.awaited_before(tag(7, 0)), Some(tag(5, 0)));
+        replay.received(tag(5, 0), action);
+        assert_eq!(replay.awaited_before(tag(7, 0)), None);
+        assert_eq!(replay.awaited_before(tag(9, 0)), Some(tag(9, 0)));
+    }
+}
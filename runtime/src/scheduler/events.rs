@@ -1,8 +1,12 @@
 use std::borrow::Cow;
-use std::collections::VecDeque;
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
 use std::fmt::{Display, Formatter};
 use std::time::Instant;
 
+use smallvec::SmallVec;
+
+use super::timer_wheel::TimerWheel;
 use super::ReactionPlan;
 use crate::scheduler::dependencies::{DataflowInfo, ExecutableReactions};
 use crate::triggers::TriggerId;
@@ -19,6 +23,7 @@ use crate::*;
 /// Use the [tag!](crate::tag) macro to create this struct with
 /// convenient syntax.
 #[derive(Copy, Clone, Hash, Eq, PartialEq, Debug, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EventTag {
     /// The time offset from the origin of the logical timeline.
     /// Knowing the start time of the application is necessary to
@@ -110,6 +115,22 @@ impl EventTag {
             microstep: MicroStep::ZERO,
         }
     }
+
+    /// Returns the zeroth microstep of the earliest time point
+    /// that is a whole multiple of the window, and not before
+    /// this tag. A zero window leaves the time point unchanged.
+    pub(crate) fn round_up_to(self, window: Duration) -> Self {
+        let window = window.as_nanos();
+        let nanos = self.offset_from_t0.as_nanos();
+        if window == 0 || (nanos % window == 0 && self.microstep == MicroStep::ZERO) {
+            return self;
+        }
+        let rounded = (nanos / window + 1) * window;
+        Self {
+            offset_from_t0: Duration::new((rounded / 1_000_000_000) as u64, (rounded % 1_000_000_000) as u32),
+            microstep: MicroStep::ZERO,
+        }
+    }
 }
 
 impl Display for EventTag {
@@ -132,16 +153,19 @@ impl Display for EventTag {
 /// [self::AsyncCtx] may only communicate with
 /// the scheduler by sending events.
 #[derive(Debug)]
-pub(super) struct Event<'x> {
+pub struct Event<'x> {
     /// The tag at which the reactions to this event must be executed.
     /// This is always > to the latest *processed* tag, by construction
     /// of the reactor application.
-    pub(super) tag: EventTag,
+    pub tag: EventTag,
     /// A set of reactions to execute.
     pub reactions: ReactionPlan<'x>,
     /// Whether we should terminate the application at
     /// the tag of this event (after processing the tag).
     pub terminate: bool,
+    /// The actions and timers that produced this event. They
+    /// are present at its tag. May contain duplicates.
+    pub triggers: SmallVec<[TriggerId; 1]>,
 }
 
 impl<'x> Event<'x> {
@@ -149,13 +173,30 @@ impl<'x> Event<'x> {
         debug_assert_eq!(self.tag, other.tag);
         self.reactions = ExecutableReactions::merge_cows(self.reactions.take(), other.reactions);
         self.terminate |= other.terminate;
+        self.triggers.extend(other.triggers);
     }
 
     pub fn execute(tag: EventTag, reactions: Cow<'x, ExecutableReactions<'x>>) -> Self {
-        Self { tag, reactions: Some(reactions), terminate: false }
+        Self {
+            tag,
+            reactions: Some(reactions),
+            terminate: false,
+            triggers: SmallVec::new(),
+        }
     }
     pub fn terminate_at(tag: EventTag) -> Self {
-        Self { tag, reactions: None, terminate: true }
+        Self {
+            tag,
+            reactions: None,
+            terminate: true,
+            triggers: SmallVec::new(),
+        }
+    }
+
+    /// Record that the given trigger produced this event.
+    pub fn triggered_by(mut self, trigger: TriggerId) -> Self {
+        self.triggers.push(trigger);
+        self
     }
 }
 
@@ -173,12 +214,13 @@ pub(super) struct PhysicalEvent {
 
 impl PhysicalEvent {
     /// Turn a [PhysicalEvent] into an [Event] within the scheduler.
-    pub(super) fn make_executable(self, dataflow: &DataflowInfo) -> Event {
+    pub(super) fn make_executable(self, dataflow: &DataflowInfo) -> Event<'_> {
         let PhysicalEvent { tag, trigger_id, terminate } = self;
         Event {
             tag,
             terminate,
             reactions: trigger_id.map(|id| Cow::Borrowed(dataflow.reactions_triggered_by(&id))),
+            triggers: trigger_id.into_iter().collect(),
         }
     }
 
@@ -193,41 +235,118 @@ impl PhysicalEvent {
 /// A queue of pending [Event]s. Events are ordered by tag,
 /// so this is not a FIFO queue.
 #[derive(Default)]
-pub(super) struct EventQueue<'x> {
-    /// This list is sorted by the tag of each event (in ascending order).
-    ///
-    /// But insertion is at worse O(n)... And it's easy to build
-    /// a pathological program where this worse case is always hit.
-    /// Theoretically using a tree/ heap would be useful here.
-    ///        .
-    ///       ..
-    ///      ...
-    ///     ....
-    value_list: VecDeque<Event<'x>>,
+pub struct EventQueue<'x> {
+    /// Pending events by tag. Events pushed at the same tag
+    /// are merged into one, so each tag is processed in a single
+    /// wave, where the union of their reactions is executed in
+    /// topological order.
+    events: BTreeMap<EventTag, Event<'x>>,
+
+    /// Events produced by timers, which are stored separately,
+    /// see [Self::push_timer].
+    timers: TimerWheel<'x>,
 }
 
 impl<'x> EventQueue<'x> {
     /// Removes and returns the earliest tag
     pub fn take_earliest(&mut self) -> Option<Event<'x>> {
-        self.value_list.pop_front()
-    }
-
-    // todo perf: we could make a more optimal function to push a
-    //  lot of events at once. Consider the following algorithm:
-    //  - start with a sorted `self.value_list` and a (non-sorted) `new_evts: Vec<Event>`
-    //  - sort the new events in place (in a Cow maybe). They'll
-    //  probably come in already sorted but we can't assume this.
-    //  Use an algorithm that best-cases for sorted data. (eg https://crates.io/crates/dmsort)
-    //  - take the earliest new event and binary search to insert it.
-    //  - then do the same thing but only on the remaining (to the right)
-    //  portion of `self.value_list`. Basically the routine of an insertion
-    //  sort.
-
-    /// Push an event into the heap.
-    pub(super) fn push(&mut self, evt: Event<'x>) {
-        match self.value_list.binary_search_by_key(&evt.tag, |e| e.tag) {
-            Ok(idx) => self.value_list[idx].absorb(evt),
-            Err(idx) => self.value_list.insert(idx, evt),
+        let timer_tag = self.timers.peek_tag();
+        match (self.events.keys().next().copied(), timer_tag) {
+            (Some(tag), Some(timer_tag)) if tag == timer_tag => {
+                let mut evt = self.events.remove(&tag).unwrap();
+                evt.absorb(self.timers.take_earliest().unwrap());
+                Some(evt)
+            }
+            (Some(tag), Some(timer_tag)) if timer_tag < tag => self.timers.take_earliest(),
+            (Some(tag), _) => self.events.remove(&tag),
+            (None, _) => self.timers.take_earliest(),
+        }
+    }
+
+    /// Number of pending events.
+    pub(super) fn len(&self) -> usize {
+        self.events.len() + self.timers.len()
+    }
+
+    /// Tags of the pending events, in order.
+    #[cfg(feature = "introspection")]
+    pub(super) fn pending_tags(&self) -> Vec<EventTag> {
+        let mut tags: Vec<EventTag> = self.events.keys().copied().collect();
+        self.timers.collect_tags(&mut tags);
+        tags.sort_unstable();
+        tags.dedup();
+        tags
+    }
+
+    /// Push an event into the queue. It is merged with
+    /// the pending event at the same tag, if any.
+    pub fn push(&mut self, evt: Event<'x>) {
+        match self.events.entry(evt.tag) {
+            Entry::Occupied(mut e) => e.get_mut().absorb(evt),
+            Entry::Vacant(e) => {
+                e.insert(evt);
+            }
+        }
+    }
+
+    /// Remove and return the pending event with the latest tag,
+    /// unless it is a timer event or requests termination.
+    pub(super) fn pop_latest(&mut self) -> Option<Event<'x>> {
+        match self.events.values().next_back() {
+            Some(evt) if !evt.terminate => {
+                let tag = evt.tag;
+                self.events.remove(&tag)
+            }
+            _ => None,
+        }
+    }
+
+    /// Remove all pending events, in tag order. Events
+    /// produced by timers are returned separately.
+    pub(super) fn drain(&mut self) -> (Vec<Event<'x>>, Vec<Event<'x>>) {
+        let mut timers = Vec::with_capacity(self.timers.len());
+        while let Some(evt) = self.timers.take_earliest() {
+            timers.push(evt);
         }
+        (std::mem::take(&mut self.events).into_values().collect(), timers)
+    }
+
+    /// Push an event produced by a timer. This is equivalent
+    /// to [Self::push], but cheaper when there are many timers.
+    pub fn push_timer(&mut self, evt: Event<'x>) {
+        self.timers.insert(evt)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn triggered(ms: u64, trigger: usize) -> Event<'static> {
+        let tag = EventTag::offset(Duration::from_millis(ms), 0);
+        Event {
+            tag,
+            reactions: None,
+            terminate: false,
+            triggers: SmallVec::new(),
+        }
+        .triggered_by(TriggerId::new(trigger as _))
+    }
+
+    #[test]
+    fn test_events_at_equal_tags_are_merged() {
+        let mut queue = EventQueue::default();
+        queue.push(triggered(20, 2));
+        queue.push(triggered(10, 3));
+        queue.push(triggered(20, 4));
+        queue.push(triggered(10, 5));
+        assert_eq!(queue.len(), 2);
+
+        let first = queue.take_earliest().unwrap();
+        assert_eq!(first.tag.offset_from_t0, Duration::from_millis(10));
+        assert_eq!(first.triggers.as_slice(), &[TriggerId::new(3), TriggerId::new(5)]);
+        let last = queue.pop_latest().unwrap();
+        assert_eq!(last.triggers.as_slice(), &[TriggerId::new(2), TriggerId::new(4)]);
+        assert!(queue.take_earliest().is_none());
     }
 }
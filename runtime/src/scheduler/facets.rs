@@ -0,0 +1,193 @@
+//! Restricted views of a [ReactionCtx], see [ReadCtx],
+//! [WriteCtx] and [ScheduleCtx].
+//!
+//! A reaction receives the whole [ReactionCtx], but code that
+//! it calls into may only need part of it. Passing a facet to
+//! a helper documents, in its signature, whether it only reads
+//! the current tag and values, sets ports, or schedules events,
+//! and the compiler checks that the helper does nothing else:
+//!
+//! ```no_run
+//! # use reactor_rt::prelude::*;
+//! fn is_overheating(ctx: ReadCtx, temperature: &Port<f64>) -> bool {
+//!     ctx.get(temperature).map_or(false, |t| t > 90.0)
+//! }
+//!
+//! fn raise_alarm(mut ctx: WriteCtx, alarm: &mut Port<bool>) {
+//!     ctx.set(alarm, true)
+//! }
+//!
+//! # let ctx: &mut ReactionCtx = panic!();
+//! # let temperature: &Port<f64> = panic!();
+//! # let alarm: &mut Port<bool> = panic!();
+//! if is_overheating(ctx.as_read(), temperature) {
+//!     raise_alarm(ctx.as_write(), alarm);
+//! }
+//! ```
+//!
+//! Each facet dereferences to a shared [ReactionCtx], whose
+//! methods all read, so that every facet can read.
+//!
+//! Facets are opt-in views, they are not granted according to
+//! the dependencies declared by a reaction. The reaction itself
+//! can still create any facet from its context, and nothing
+//! checks that the ports a [WriteCtx] sets are effects of the
+//! reaction, besides the debug assertions of [ReactionCtx::set].
+
+use std::ops::Deref;
+use std::thread::JoinHandle;
+
+use crate::*;
+
+/// A view of a [ReactionCtx] that can only read: the
+/// current tag, physical time, and the values of ports and
+/// actions. See [ReactionCtx::as_read].
+#[derive(Copy, Clone)]
+pub struct ReadCtx<'c, 'a, 'x> {
+    ctx: &'c ReactionCtx<'a, 'x>,
+}
+
+/// A view of a [ReactionCtx] that can read, and set or
+/// take the values of ports. See [ReactionCtx::as_write].
+pub struct WriteCtx<'c, 'a, 'x> {
+    ctx: &'c mut ReactionCtx<'a, 'x>,
+}
+
+/// A view of a [ReactionCtx] that can read, and schedule
+/// actions, start physical threads, arm watchdogs, or
+/// request shutdown. See [ReactionCtx::as_schedule].
+pub struct ScheduleCtx<'c, 'a, 'x> {
+    ctx: &'c mut ReactionCtx<'a, 'x>,
+}
+
+impl<'a, 'x> ReactionCtx<'a, 'x> {
+    /// Returns a view of this context that can only read.
+    #[inline]
+    pub fn as_read(&self) -> ReadCtx<'_, 'a, 'x> {
+        ReadCtx { ctx: self }
+    }
+
+    /// Returns a view of this context that can read, and
+    /// set ports but not schedule events.
+    #[inline]
+    pub fn as_write(&mut self) -> WriteCtx<'_, 'a, 'x> {
+        WriteCtx { ctx: self }
+    }
+
+    /// Returns a view of this context that can read, and
+    /// schedule events but not set ports.
+    #[inline]
+    pub fn as_schedule(&mut self) -> ScheduleCtx<'_, 'a, 'x> {
+        ScheduleCtx { ctx: self }
+    }
+}
+
+impl<'a, 'x> Deref for ReadCtx<'_, 'a, 'x> {
+    type Target = ReactionCtx<'a, 'x>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.ctx
+    }
+}
+
+impl<'a, 'x> Deref for WriteCtx<'_, 'a, 'x> {
+    type Target = ReactionCtx<'a, 'x>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.ctx
+    }
+}
+
+impl<'a, 'x> Deref for ScheduleCtx<'_, 'a, 'x> {
+    type Target = ReactionCtx<'a, 'x>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.ctx
+    }
+}
+
+impl WriteCtx<'_, '_, '_> {
+    /// See [ReactionCtx::set].
+    #[inline]
+    pub fn set<T: Sync>(&mut self, port: &mut Port<T>, value: T) {
+        self.ctx.set(port, value)
+    }
+
+    /// See [ReactionCtx::set_opt].
+    #[inline]
+    pub fn set_opt<T: Sync>(&mut self, port: &mut Port<T>, value: Option<T>) {
+        self.ctx.set_opt(port, value)
+    }
+
+    /// See [ReactionCtx::take].
+    #[inline]
+    pub fn take<T: Sync + Clone>(&mut self, port: &mut Port<T>) -> Option<T> {
+        self.ctx.take(port)
+    }
+
+    /// See [ReactionCtx::take_if_last].
+    #[inline]
+    pub fn take_if_last<T: Sync>(&mut self, port: &mut Port<T>) -> Option<T> {
+        self.ctx.take_if_last(port)
+    }
+}
+
+impl ScheduleCtx<'_, '_, '_> {
+    /// See [ReactionCtx::schedule].
+    #[inline]
+    pub fn schedule<T: Sync>(&mut self, action: &mut impl SchedulableAsAction<T>, offset: Offset) {
+        self.ctx.schedule(action, offset)
+    }
+
+    /// See [ReactionCtx::schedule_with_v].
+    #[inline]
+    pub fn schedule_with_v<T: Sync>(&mut self, action: &mut impl SchedulableAsAction<T>, value: Option<T>, offset: Offset) {
+        self.ctx.schedule_with_v(action, value, offset)
+    }
+
+    /// See [ReactionCtx::try_schedule_with_v].
+    #[inline]
+    pub fn try_schedule_with_v<T: Sync>(
+        &mut self,
+        action: &mut impl SchedulableAsAction<T>,
+        value: Option<T>,
+        offset: Offset,
+    ) -> Result<(), ScheduleError> {
+        self.ctx.try_schedule_with_v(action, value, offset)
+    }
+
+    /// See [ReactionCtx::schedule_if_absent].
+    #[inline]
+    pub fn schedule_if_absent<T: Sync>(&mut self, action: &mut impl SchedulableAsAction<T>, offset: Offset) -> bool {
+        self.ctx.schedule_if_absent(action, offset)
+    }
+
+    /// See [ReactionCtx::spawn_physical_thread].
+    pub fn spawn_physical_thread<F, R>(&mut self, f: F) -> JoinHandle<R>
+    where
+        F: FnOnce(&mut AsyncCtx) -> R,
+        F: Send + 'static,
+        R: Send + 'static,
+    {
+        self.ctx.spawn_physical_thread(f)
+    }
+
+    /// See [ReactionCtx::start_watchdog].
+    pub fn start_watchdog(&mut self, watchdog: &Watchdog, timeout: Duration) {
+        self.ctx.start_watchdog(watchdog, timeout)
+    }
+
+    /// See [ReactionCtx::stop_watchdog].
+    pub fn stop_watchdog(&mut self, watchdog: &Watchdog) {
+        self.ctx.stop_watchdog(watchdog)
+    }
+
+    /// See [ReactionCtx::request_stop].
+    #[inline]
+    pub fn request_stop(&mut self, offset: Offset) {
+        self.ctx.request_stop(offset)
+    }
+}
@@ -0,0 +1,119 @@
+//! Injection of controlled failures, to test how a program
+//! copes with them. This module is only available with
+//! feature `fault-injection`.
+//!
+//! Random decisions are derived from a hash of the seed and of
+//! the tag (and reaction or action) they apply to, so that they
+//! are reproducible across runs with the same seed, and
+//! independent of the order in which reactions are executed.
+
+use std::hash::{Hash, Hasher};
+
+use super::random::{splitmix64, Fnv};
+use super::DebugInfoProvider;
+use crate::assembly::TriggerId;
+use crate::*;
+
+/// Configuration of the failures to inject, see [SchedulerOptions::faults].
+///
+/// Deadline misses (see [ReactionCtx::deadline_violated]) can be provoked by configuring
+/// [Self::reaction_delay] for the reactions under test.
+#[derive(Clone, Debug, Default)]
+pub struct FaultInjector {
+    /// Seed for random decisions.
+    pub seed: u64,
+    /// Probability in `[0, 1]` that an event sent by a physical
+    /// action is dropped by the scheduler. Requests to stop
+    /// the program are never dropped.
+    pub physical_drop_rate: f64,
+    /// If set, each reaction is delayed with the given probability
+    /// (first component) by a random duration up to the given
+    /// maximum (second component).
+    pub reaction_delay: Option<(f64, Duration)>,
+    /// Reactions that panic when they are executed at the given tag.
+    pub reaction_failures: Vec<ReactionFailure>,
+}
+
+/// A reaction that should panic at a given tag.
+#[derive(Clone, Debug)]
+pub struct ReactionFailure {
+    /// Path of the reaction as it appears in trace messages,
+    /// eg `/ping/0`, or `/ping/0@label` if the reaction has a label.
+    /// The label is optional.
+    pub reaction: String,
+    pub tag: EventTag,
+}
+
+impl FaultInjector {
+    /// Returns whether a physical event for the given action
+    /// at the given tag should be dropped.
+    pub(super) fn drop_physical_event(&self, tag: EventTag, trigger: TriggerId) -> bool {
+        self.physical_drop_rate > 0.0 && self.random(|h| (tag, trigger).hash(h)) < self.physical_drop_rate
+    }
+
+    /// Called before the given reaction is executed.
+    pub(super) fn before_reaction(&self, tag: EventTag, reaction: GlobalReactionId, debug: &DebugInfoProvider<'_>) {
+        let reaction_key = |h: &mut Fnv| (tag, reaction).hash(h);
+
+        if let Some((probability, max_delay)) = self.reaction_delay {
+            if self.random(reaction_key) < probability {
+                let fraction = self.random(|h| {
+                    reaction_key(h);
+                    "delay".hash(h)
+                });
+                std::thread::sleep(max_delay.mul_f64(fraction));
+            }
+        }
+
+        for failure in &self.reaction_failures {
+            if failure.tag == tag {
+                let name = debug.display_reaction(reaction).to_string();
+                let unlabeled = name.split('@').next().unwrap();
+                if failure.reaction == name || failure.reaction == unlabeled {
+                    panic!("Injected failure of reaction {} at {}", name, tag);
+                }
+            }
+        }
+    }
+
+    /// Returns a number in `[0, 1)` that is determined by the
+    /// seed and the hashed key.
+    fn random(&self, key: impl FnOnce(&mut Fnv)) -> f64 {
+        let mut hasher = Fnv::new(self.seed);
+        key(&mut hasher);
+        // keep 53 bits, the precision of an f64
+        (splitmix64(hasher.finish()) >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_drop_rate() {
+        let faults = FaultInjector {
+            seed: 42,
+            physical_drop_rate: 0.25,
+            ..Default::default()
+        };
+        let trigger = TriggerId::FIRST_REGULAR;
+        let dropped = (0..10_000)
+            .filter(|&i| faults.drop_physical_event(EventTag::ORIGIN.successor(Duration::from_millis(i)), trigger))
+            .count();
+        assert!((2000..3000).contains(&dropped), "dropped {}", dropped);
+
+        // decisions are reproducible
+        let tag = EventTag::ORIGIN.successor(Duration::from_millis(3));
+        assert_eq!(
+            faults.drop_physical_event(tag, trigger),
+            faults.clone().drop_physical_event(tag, trigger)
+        );
+    }
+
+    #[test]
+    fn test_no_drop_by_default() {
+        let faults = FaultInjector::default();
+        assert!(!faults.drop_physical_event(EventTag::ORIGIN, TriggerId::FIRST_REGULAR));
+    }
+}
@@ -0,0 +1,247 @@
+//! Runtime metrics, exported in the Prometheus text format.
+//! This module is only available with feature `metrics`.
+
+use std::fmt::Write as _;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use crate::*;
+
+/// Where and how to export the metrics of the scheduler.
+/// See [SchedulerOptions::metrics].
+#[derive(Clone, Debug)]
+pub enum MetricsExport {
+    /// Serve metrics over HTTP on the given address. Any
+    /// request to the server is answered with the current metrics,
+    /// so this can be used directly as a Prometheus scrape target.
+    Http(SocketAddr),
+    /// Periodically overwrite the given file with the current
+    /// metrics, eg for the textfile collector of the Prometheus
+    /// node exporter. The file is also written once at shutdown.
+    File { path: PathBuf, period: Duration },
+}
+
+/// Counters and gauges updated by the scheduler while it runs.
+/// They are shared with the exporter thread.
+#[derive(Debug)]
+pub(super) struct Metrics {
+    start: Instant,
+    /// Number of tags processed.
+    events_processed: AtomicU64,
+    /// Number of reactions executed.
+    reactions_executed: AtomicU64,
+    /// Number of events pending in the event queue.
+    queue_depth: AtomicU64,
+    /// Max number of pending events, zero if unbounded,
+    /// see [EventBudget].
+    event_budget: u64,
+    /// Number of events dropped to fit the event budget.
+    events_dropped: AtomicU64,
+    /// Delay between the logical time of the latest tag
+    /// and the physical time at which it started being processed.
+    lag_ns: AtomicU64,
+    /// Period of the export to a file, which may be changed
+    /// while the program runs.
+    export_period_ns: AtomicU64,
+}
+
+impl Metrics {
+    pub(super) fn new(event_budget: Option<usize>) -> Self {
+        Self {
+            start: Instant::now(),
+            events_processed: Default::default(),
+            reactions_executed: Default::default(),
+            queue_depth: Default::default(),
+            event_budget: event_budget.unwrap_or(0) as u64,
+            events_dropped: Default::default(),
+            lag_ns: Default::default(),
+            export_period_ns: Default::default(),
+        }
+    }
+
+    pub(super) fn set_export_period(&self, period: Duration) {
+        self.export_period_ns.store(period.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    fn export_period(&self) -> Duration {
+        Duration::from_nanos(self.export_period_ns.load(Ordering::Relaxed))
+    }
+
+    pub(super) fn record_tag(&self, lag: Duration, queue_depth: usize) {
+        self.events_processed.fetch_add(1, Ordering::Relaxed);
+        self.lag_ns.store(lag.as_nanos() as u64, Ordering::Relaxed);
+        self.queue_depth.store(queue_depth as u64, Ordering::Relaxed);
+    }
+
+    pub(super) fn record_dropped_event(&self) {
+        self.events_dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn record_reactions(&self, num: usize) {
+        self.reactions_executed.fetch_add(num as u64, Ordering::Relaxed);
+    }
+
+    /// Format the metrics in the Prometheus text exposition format.
+    pub(super) fn render(&self) -> String {
+        let uptime = self.start.elapsed().as_secs_f64();
+        let reactions = self.reactions_executed.load(Ordering::Relaxed);
+        let reactions_per_sec = if uptime > 0.0 { reactions as f64 / uptime } else { 0.0 };
+
+        let mut str = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: &dyn std::fmt::Display| {
+            writeln!(str, "# HELP {} {}", name, help).unwrap();
+            writeln!(str, "# TYPE {} {}", name, kind).unwrap();
+            writeln!(str, "{} {}", name, value).unwrap();
+        };
+
+        metric(
+            "reactor_events_processed_total",
+            "counter",
+            "Number of tags processed by the scheduler.",
+            &self.events_processed.load(Ordering::Relaxed),
+        );
+        metric(
+            "reactor_reactions_executed_total",
+            "counter",
+            "Number of reactions executed.",
+            &reactions,
+        );
+        metric(
+            "reactor_reactions_per_second",
+            "gauge",
+            "Average number of reactions executed per second since startup.",
+            &reactions_per_sec,
+        );
+        metric(
+            "reactor_event_queue_depth",
+            "gauge",
+            "Number of events pending in the event queue.",
+            &self.queue_depth.load(Ordering::Relaxed),
+        );
+        metric(
+            "reactor_event_budget",
+            "gauge",
+            "Max number of pending events, zero if unbounded.",
+            &self.event_budget,
+        );
+        metric(
+            "reactor_events_dropped_total",
+            "counter",
+            "Number of events dropped to fit the event budget.",
+            &self.events_dropped.load(Ordering::Relaxed),
+        );
+        metric(
+            "reactor_lag_seconds",
+            "gauge",
+            "Delay of physical time over logical time when the latest tag was processed.",
+            &(self.lag_ns.load(Ordering::Relaxed) as f64 / 1e9),
+        );
+        metric(
+            "reactor_uptime_seconds",
+            "gauge",
+            "Time elapsed since the scheduler started.",
+            &uptime,
+        );
+        str
+    }
+}
+
+/// Start a thread that exports the metrics until the
+/// scheduler terminates.
+pub(super) fn spawn_exporter(export: MetricsExport, metrics: Arc<Metrics>, was_terminated: Arc<AtomicBool>) -> JoinHandle<()> {
+    /// Granularity at which the exporter checks for termination.
+    const POLL_PERIOD: Duration = Duration::from_millis(50);
+
+    if let MetricsExport::File { period, .. } = &export {
+        metrics.set_export_period(*period);
+    }
+    std::thread::spawn(move || match export {
+        MetricsExport::Http(addr) => {
+            let listener = match TcpListener::bind(addr).and_then(|l| l.set_nonblocking(true).map(|_| l)) {
+                Ok(listener) => listener,
+                Err(e) => {
+                    warn!("Cannot serve metrics on {}: {}", addr, e);
+                    return;
+                }
+            };
+            info!("Serving metrics on http://{}", addr);
+            while !was_terminated.load(Ordering::SeqCst) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        if let Err(e) = serve_metrics(stream, &metrics) {
+                            warn!("Error while serving metrics: {}", e);
+                        }
+                    }
+                    Err(e) if e.kind() == ErrorKind::WouldBlock => std::thread::sleep(POLL_PERIOD),
+                    Err(e) => warn!("Error while serving metrics: {}", e),
+                }
+            }
+        }
+        MetricsExport::File { path, .. } => loop {
+            // check before writing so that the last write happens after termination
+            let terminated = was_terminated.load(Ordering::SeqCst);
+            if let Err(e) = write_metrics_file(&path, &metrics) {
+                warn!("Cannot write metrics to {}: {}", path.display(), e);
+            }
+            if terminated {
+                break;
+            }
+            let period = metrics.export_period();
+            let next_write = Instant::now() + period;
+            while !was_terminated.load(Ordering::SeqCst) && Instant::now() < next_write {
+                std::thread::sleep(POLL_PERIOD.min(period));
+            }
+        },
+    })
+}
+
+fn serve_metrics(mut stream: TcpStream, metrics: &Metrics) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    // We don't care about the contents of the request, but
+    // the client may not read the response until the request is consumed.
+    let mut buf = [0u8; 1024];
+    let _ = stream.read(&mut buf)?;
+
+    let body = metrics.render();
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn write_metrics_file(path: &std::path::Path, metrics: &Metrics) -> std::io::Result<()> {
+    // write to a temp file and rename it, so that readers never see a partial file
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, metrics.render())?;
+    std::fs::rename(tmp, path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render_prometheus_format() {
+        let metrics = Metrics::new(Some(8));
+        metrics.record_tag(Duration::from_millis(1500), 4);
+        metrics.record_dropped_event();
+        metrics.record_tag(Duration::from_millis(2), 3);
+        metrics.record_reactions(10);
+
+        let text = metrics.render();
+        assert!(text.contains("# TYPE reactor_events_processed_total counter\nreactor_events_processed_total 2\n"));
+        assert!(text.contains("\nreactor_reactions_executed_total 10\n"));
+        assert!(text.contains("\nreactor_event_queue_depth 3\n"));
+        assert!(text.contains("\nreactor_event_budget 8\n"));
+        assert!(text.contains("\nreactor_events_dropped_total 1\n"));
+        assert!(text.contains("\nreactor_lag_seconds 0.002\n"));
+    }
+}
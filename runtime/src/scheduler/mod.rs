@@ -25,48 +25,116 @@
 use std::borrow::Cow;
 use std::fmt::Display;
 
+pub(crate) use admission::AdmissionControl;
+pub use admission::AdmissionPolicy;
+pub use anomaly::*;
+#[cfg(feature = "async")]
+pub use async_link::AsyncSchedulerLink;
+pub(crate) use backpressure::PhysicalChannel;
+pub use backpressure::{ChannelBound, OverflowPolicy};
+pub use budget::{BudgetPolicy, EventBudget};
+#[cfg(feature = "checkpoint")]
+pub use checkpoint::{Checkpoint, CheckpointError, CheckpointPolicy, PendingEvent, ReactorState};
 pub use context::*;
-pub use events::*;
+pub use control::SchedulerControl;
+pub use crash_dump::CrashDump;
+pub use divergence::*;
+pub use dry_run::DryRunReport;
+pub(crate) use event_log::PhysicalTags;
+pub use event_log::{EventLog, EventRecorder, LoggedEvent, LoggedTag};
+pub use events::EventTag;
+use events::{Event, EventQueue, PhysicalEvent};
+pub use facets::{ReadCtx, ScheduleCtx, WriteCtx};
+#[cfg(feature = "fault-injection")]
+pub use faults::{FaultInjector, ReactionFailure};
 use index_vec::IndexVec;
+#[cfg(feature = "metrics")]
+pub use metrics::MetricsExport;
+pub use perf_trace::{PerfRecording, PerfTrace, PerfTraceFormat, ReactionSpan, TagSpan};
+pub use policy::{EarliestDeadlineFirst, EarliestTagFirst, PeriodicRelease, SchedulingPolicy};
+pub use random::TagRng;
 pub use scheduler_impl::*;
+pub use startup_budget::{StartupBudget, StartupReport};
+pub use trace::*;
 
 use self::dependencies::ExecutableReactions;
+use self::starvation::MicrostepGuard;
 use crate::*;
 
+mod admission;
+mod anomaly;
 pub(crate) mod assembly_impl;
+#[cfg(feature = "async")]
+mod async_link;
+mod backpressure;
+mod budget;
+#[cfg(feature = "checkpoint")]
+mod checkpoint;
 mod context;
+mod control;
+mod crash_dump;
 pub(crate) mod debug;
 mod dependencies;
+mod divergence;
+mod dry_run;
+mod event_log;
 mod events;
+mod facets;
+#[cfg(feature = "fault-injection")]
+mod faults;
+#[cfg(feature = "metrics")]
+mod metrics;
+mod perf_trace;
+mod policy;
+mod random;
 mod scheduler_impl;
+mod startup_budget;
+mod starvation;
+mod timer_wheel;
+mod trace;
+pub(crate) mod validation;
+#[cfg(feature = "visualization")]
+mod visualization;
 
 #[cfg(feature = "public-internals")]
 pub mod internals {
     pub use super::dependencies::{ExecutableReactions, Level, LevelIx, ReactionLevelInfo};
+    pub use super::events::{Event, EventQueue};
 }
 
-pub(self) type ReactionPlan<'x> = Option<Cow<'x, ExecutableReactions<'x>>>;
-pub(self) type ReactorBox<'a> = Box<dyn ReactorBehavior + 'a>;
-pub(self) type ReactorVec<'a> = IndexVec<ReactorId, ReactorBox<'a>>;
+type ReactionPlan<'x> = Option<Cow<'x, ExecutableReactions<'x>>>;
+type ReactorBox<'a> = Box<dyn ReactorBehavior + 'a>;
+type ReactorVec<'a> = IndexVec<ReactorId, ReactorBox<'a>>;
 
 /// Can format stuff for trace messages.
 #[derive(Clone)]
-pub(self) struct DebugInfoProvider<'a> {
+struct DebugInfoProvider<'a> {
     id_registry: &'a DebugInfoRegistry,
 }
 
 impl DebugInfoProvider<'_> {
-    pub(self) fn display_event(&self, evt: &Event) -> String {
-        let Event { tag, reactions, terminate } = evt;
+    fn display_event(&self, evt: &Event) -> String {
+        let Event { tag, reactions, terminate, triggers } = evt;
         let mut str = format!("at {}: run {}", tag, self.display_reactions(reactions));
 
+        if !triggers.is_empty() {
+            let mut triggers = triggers.to_vec();
+            triggers.sort();
+            triggers.dedup();
+            join_to!(&mut str, triggers.iter(), ", ", " for {", "}", |t| format!(
+                "{}",
+                self.id_registry.fmt_component(*t)
+            ))
+            .unwrap();
+        }
+
         if *terminate {
             str += ", then terminate"
         }
         str
     }
 
-    pub(self) fn display_reactions(&self, reactions: &ReactionPlan) -> String {
+    fn display_reactions(&self, reactions: &ReactionPlan) -> String {
         use std::fmt::*;
 
         let mut str = "[".to_string();
@@ -87,7 +155,7 @@ impl DebugInfoProvider<'_> {
     }
 
     #[inline]
-    pub(self) fn display_reaction(&self, id: GlobalReactionId) -> impl Display + '_ {
+    fn display_reaction(&self, id: GlobalReactionId) -> impl Display + '_ {
         self.id_registry.fmt_reaction(id)
     }
 }
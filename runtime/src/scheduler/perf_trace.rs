@@ -0,0 +1,275 @@
+//! Recording of the activity of the scheduler, for
+//! performance analysis with external tools.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::util::json_string;
+use crate::*;
+
+/// Execution of a reaction, as recorded by a [PerfTrace].
+#[derive(Clone, Debug)]
+pub struct ReactionSpan {
+    pub reaction: GlobalReactionId,
+    pub tag: EventTag,
+    /// Physical time at which the reaction started,
+    /// relative to the start of the program.
+    pub start: Duration,
+    pub duration: Duration,
+    /// Thread that executed the reaction: 0 for the thread
+    /// of the scheduler, `i + 1` for the i-th worker thread.
+    pub worker: usize,
+}
+
+/// Processing of a tag, as recorded by a [PerfTrace].
+#[derive(Clone, Debug)]
+pub struct TagSpan {
+    pub tag: EventTag,
+    /// Physical time at which the processing started,
+    /// relative to the start of the program.
+    pub start: Duration,
+    pub duration: Duration,
+    /// Number of reactions executed at the tag.
+    pub wave_size: usize,
+    /// Number of pending events once the tag was processed.
+    pub queue_depth: usize,
+}
+
+/// Activity of the scheduler over a run, produced by a
+/// [PerfTrace] at shutdown.
+#[derive(Clone, Debug, Default)]
+pub struct PerfRecording {
+    /// Reaction executions, ordered by tag.
+    pub reactions: Vec<ReactionSpan>,
+    /// Processed tags, in order.
+    pub tags: Vec<TagSpan>,
+    /// Names of the reactions.
+    pub symbols: SymbolTable,
+}
+
+impl PerfRecording {
+    fn reaction_name(&self, span: &ReactionSpan) -> String {
+        self.symbols
+            .reaction(span.reaction)
+            .map_or_else(|| span.reaction.to_string(), str::to_string)
+    }
+
+    /// Write the recording in the JSON format of Chrome traces,
+    /// which can be opened with Perfetto or `chrome://tracing`.
+    /// Tags are shown on a track of their own, reactions on a
+    /// track per thread, and the wave size and queue depth as
+    /// counters.
+    pub fn write_chrome_trace(&self, mut w: impl Write) -> io::Result<()> {
+        fn micros(d: Duration) -> f64 {
+            d.as_nanos() as f64 / 1000.0
+        }
+        write!(w, "{{\"displayTimeUnit\":\"ms\",\"traceEvents\":[")?;
+        write!(
+            w,
+            "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":0,\"args\":{{\"name\":\"tags\"}}}}"
+        )?;
+        for span in &self.tags {
+            let tag = json_string(&span.tag.to_string());
+            let ts = micros(span.start);
+            write!(
+                w,
+                ",\n{{\"name\":{},\"cat\":\"tag\",\"ph\":\"X\",\"ts\":{},\"dur\":{},\"pid\":1,\"tid\":0}}",
+                tag,
+                ts,
+                micros(span.duration)
+            )?;
+            write!(
+                w,
+                ",\n{{\"name\":\"wave size\",\"ph\":\"C\",\"ts\":{},\"pid\":1,\"args\":{{\"reactions\":{}}}}}",
+                ts, span.wave_size
+            )?;
+            write!(
+                w,
+                ",\n{{\"name\":\"queue depth\",\"ph\":\"C\",\"ts\":{},\"pid\":1,\"args\":{{\"events\":{}}}}}",
+                micros(span.start + span.duration),
+                span.queue_depth
+            )?;
+        }
+        for span in &self.reactions {
+            write!(
+                w,
+                ",\n{{\"name\":{},\"cat\":\"reaction\",\"ph\":\"X\",\"ts\":{},\"dur\":{},\"pid\":1,\"tid\":{},\"args\":{{\"tag\":{}}}}}",
+                json_string(&self.reaction_name(span)),
+                micros(span.start),
+                micros(span.duration),
+                span.worker + 1,
+                json_string(&span.tag.to_string())
+            )?;
+        }
+        writeln!(w, "]}}")?;
+        w.flush()
+    }
+
+    /// Write the recording as CSV, with one line per tag and
+    /// per reaction execution. Times are in nanoseconds. Columns
+    /// that do not apply to a line are empty.
+    pub fn write_csv(&self, mut w: impl Write) -> io::Result<()> {
+        writeln!(
+            w,
+            "kind,name,tag_ns,microstep,start_ns,duration_ns,worker,wave_size,queue_depth"
+        )?;
+        for span in &self.tags {
+            writeln!(
+                w,
+                "tag,,{},{},{},{},,{},{}",
+                span.tag.offset_from_t0.as_nanos(),
+                span.tag.microstep,
+                span.start.as_nanos(),
+                span.duration.as_nanos(),
+                span.wave_size,
+                span.queue_depth
+            )?;
+        }
+        for span in &self.reactions {
+            writeln!(
+                w,
+                "reaction,{},{},{},{},{},{},,",
+                self.reaction_name(span),
+                span.tag.offset_from_t0.as_nanos(),
+                span.tag.microstep,
+                span.start.as_nanos(),
+                span.duration.as_nanos(),
+                span.worker
+            )?;
+        }
+        w.flush()
+    }
+}
+
+/// Format of the file written by [PerfTrace::to_file].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PerfTraceFormat {
+    /// See [PerfRecording::write_chrome_trace].
+    ChromeTrace,
+    /// See [PerfRecording::write_csv].
+    Csv,
+}
+
+type PerfCallback = Box<dyn FnOnce(&PerfRecording) + Send>;
+
+/// Records when each reaction starts and ends, how many reactions
+/// are executed at each tag, and how many events are pending
+/// after it. The recording is passed to a callback at shutdown,
+/// or written to a file for tools like Perfetto.
+///
+/// Install it with [SchedulerOptions::perf_trace]. Every tag of
+/// the run is recorded, so the recording grows with the length
+/// of the run.
+pub struct PerfTrace {
+    recording: PerfRecording,
+    callback: Option<PerfCallback>,
+}
+
+/// Execution of a reaction, as recorded by a [ReactionCtx](super::ReactionCtx).
+pub(super) type RawSpan = (GlobalReactionId, Instant, Duration, usize);
+
+impl PerfTrace {
+    pub fn new(callback: impl FnOnce(&PerfRecording) + Send + 'static) -> Self {
+        Self {
+            recording: Default::default(),
+            callback: Some(Box::new(callback)),
+        }
+    }
+
+    /// Write the recording to the given file at shutdown.
+    /// Errors are logged.
+    pub fn to_file(path: impl Into<PathBuf>, format: PerfTraceFormat) -> Self {
+        let path = path.into();
+        Self::new(move |recording| {
+            let written = File::create(&path).and_then(|f| match format {
+                PerfTraceFormat::ChromeTrace => recording.write_chrome_trace(BufWriter::new(f)),
+                PerfTraceFormat::Csv => recording.write_csv(BufWriter::new(f)),
+            });
+            match written {
+                Ok(()) => info!("Wrote performance trace to {}", path.display()),
+                Err(e) => error!("Could not write performance trace to {}: {}", path.display(), e),
+            }
+        })
+    }
+
+    pub(super) fn observe_tag(
+        &mut self,
+        tag: EventTag,
+        initial_time: Instant,
+        wave_start: Instant,
+        spans: Vec<RawSpan>,
+        queue_depth: usize,
+    ) {
+        let since_start = |t: Instant| t.saturating_duration_since(initial_time);
+        self.recording.tags.push(TagSpan {
+            tag,
+            start: since_start(wave_start),
+            duration: wave_start.elapsed(),
+            wave_size: spans.len(),
+            queue_depth,
+        });
+        let reactions = spans.into_iter().map(|(reaction, start, duration, worker)| ReactionSpan {
+            reaction,
+            tag,
+            start: since_start(start),
+            duration,
+            worker,
+        });
+        self.recording.reactions.extend(reactions);
+    }
+
+    /// Pass the recording to the callback, with the
+    /// names of the reactions it refers to.
+    pub(super) fn finish(&mut self, debug: &DebugInfoRegistry) {
+        if let Some(callback) = self.callback.take() {
+            let mut recording = std::mem::take(&mut self.recording);
+            let reactions = recording.reactions.iter().map(|s| s.reaction);
+            recording.symbols = SymbolTable::new(debug, reactions, []);
+            callback(&recording)
+        }
+    }
+}
+
+/// Index of the thread executing a reaction, see [ReactionSpan::worker].
+pub(super) fn current_worker() -> usize {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "parallel-runtime")] {
+            rayon::current_thread_index().map_or(0, |i| i + 1)
+        } else {
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_exports() {
+        let reaction = GlobalReactionId::new(ReactorId::new(0), LocalReactionId::new(1));
+        let t0 = Instant::now();
+        let mut perf = PerfTrace::new(|_| {});
+        let spans = vec![(reaction, t0 + Duration::from_micros(3), Duration::from_micros(2), 0)];
+        perf.observe_tag(EventTag::ORIGIN, t0, t0 + Duration::from_micros(1), spans, 4);
+
+        let mut csv = Vec::new();
+        perf.recording.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("tag,,0,0,1000,"), "{}", lines[1]);
+        assert!(lines[1].ends_with(",1,4"), "{}", lines[1]);
+        assert_eq!(lines[2], format!("reaction,{},0,0,3000,2000,0,,", reaction));
+
+        let mut json = Vec::new();
+        perf.recording.write_chrome_trace(&mut json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        let events = json["traceEvents"].as_array().unwrap();
+        // metadata, tag, two counters and a reaction
+        assert_eq!(events.len(), 5);
+        assert_eq!(events[4]["ts"], 3.0);
+        assert_eq!(events[4]["tid"], 1);
+    }
+}
@@ -0,0 +1,91 @@
+//! Pluggable scheduling decisions, see [SchedulingPolicy].
+
+use std::collections::HashMap;
+
+use crate::*;
+
+/// Decides when the scheduler processes each tag, and in which
+/// order it executes independent reactions. Install one with
+/// [SchedulerOptions::scheduling_policy]. Without it, the scheduler
+/// behaves like [EarliestTagFirst].
+///
+/// Pending events are always processed in tag order, as the
+/// semantics of logical time require. A policy may only delay
+/// the release of a tag past its logical time, and choose the
+/// order of the reactions of a level, which do not depend on
+/// each other. Both are deterministic with respect to logical
+/// time, but a policy can use them to shape the timing of a
+/// program, or to force an interleaving in a test.
+pub trait SchedulingPolicy: Send {
+    /// Returns the physical instant at which the tag may be
+    /// processed, given the start time of the program. The
+    /// scheduler never processes a tag before its logical time,
+    /// so earlier instants have no effect.
+    fn release_time(&mut self, tag: EventTag, t0: Instant) -> Instant {
+        tag.to_logical_time(t0)
+    }
+
+    /// Order the reactions of a level before they are executed.
+    /// They are passed in an arbitrary order. With the feature
+    /// `parallel-runtime`, levels that are large enough are
+    /// executed in parallel: the workers start the reactions in
+    /// this order as they become available, so reactions may
+    /// still complete in another order.
+    fn order_level(&mut self, _tag: EventTag, _reactions: &mut [GlobalReactionId]) {}
+
+    /// Called before startup for each reaction that declares
+    /// a deadline, see [ReactionCtx::deadline_violated].
+    fn declare_deadline(&mut self, _reaction: GlobalReactionId, _deadline: Duration) {}
+}
+
+/// The default policy, which processes each tag as soon as
+/// physical time reaches its logical time, and executes the
+/// reactions of a level in an unspecified order.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct EarliestTagFirst;
+
+impl SchedulingPolicy for EarliestTagFirst {}
+
+/// Processes tags like [EarliestTagFirst], but executes the
+/// reactions of a level by increasing deadline. The reactions of
+/// a level all execute at the same tag, so this is the order of
+/// their absolute deadlines. Reactions without a deadline execute
+/// last. Under load, this reduces the number of missed deadlines
+/// compared to executing reactions in an arbitrary order.
+#[derive(Clone, Debug, Default)]
+pub struct EarliestDeadlineFirst {
+    deadlines: HashMap<GlobalReactionId, Duration>,
+}
+
+impl SchedulingPolicy for EarliestDeadlineFirst {
+    fn order_level(&mut self, _tag: EventTag, reactions: &mut [GlobalReactionId]) {
+        // the sort is stable, so ties keep their order
+        reactions.sort_by_key(|r| self.deadlines.get(r).copied().unwrap_or(Duration::MAX));
+    }
+
+    fn declare_deadline(&mut self, reaction: GlobalReactionId, deadline: Duration) {
+        self.deadlines.insert(reaction, deadline);
+    }
+}
+
+/// Releases tags only at whole multiples of a period, counted
+/// from the start of the program. All the tags that fall within
+/// a period are processed together at its end, like in a
+/// time-triggered system. This bounds the jitter of the release
+/// of reactions, at the cost of up to one period of latency.
+#[derive(Copy, Clone, Debug)]
+pub struct PeriodicRelease {
+    pub period: Duration,
+}
+
+impl SchedulingPolicy for PeriodicRelease {
+    fn release_time(&mut self, tag: EventTag, t0: Instant) -> Instant {
+        let period = self.period.as_nanos();
+        let offset = tag.offset_from_t0.as_nanos();
+        if period == 0 || offset % period == 0 {
+            return tag.to_logical_time(t0);
+        }
+        let released = (offset / period + 1) * period;
+        t0 + Duration::from_nanos(released as u64)
+    }
+}
@@ -0,0 +1,104 @@
+//! Reproducible randomness for reactions, see [ReactionCtx::random].
+//!
+//! Numbers are derived from a hash of the seed of the program and
+//! of the key they are drawn for, eg a tag and a reaction, so that
+//! they do not depend on the order in which reactions are executed.
+
+use std::hash::{Hash, Hasher};
+
+/// A pseudo-random generator, returned by [ReactionCtx::random](crate::ReactionCtx::random).
+///
+/// This is a splitmix64 generator: it is fast and has good
+/// statistical properties, but it is not cryptographically secure.
+#[derive(Clone, Debug)]
+pub struct TagRng {
+    state: u64,
+}
+
+impl TagRng {
+    /// A generator whose sequence is determined by the seed and the key.
+    pub(super) fn keyed(seed: u64, key: impl Hash) -> Self {
+        let mut hasher = Fnv::new(seed);
+        key.hash(&mut hasher);
+        Self { state: hasher.finish() }
+    }
+
+    /// Returns a uniformly distributed `u64`.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        splitmix64(self.state)
+    }
+
+    /// Returns a number in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        // keep 53 bits, the precision of an f64
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns true with the given probability, in `[0, 1]`.
+    pub fn next_bool(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+
+    /// Returns a number in `[low, high)`. Panics if the range is empty.
+    pub fn next_in(&mut self, low: u64, high: u64) -> u64 {
+        assert!(low < high, "Empty range {}..{}", low, high);
+        // the modulo bias is negligible unless the range is huge
+        low + self.next_u64() % (high - low)
+    }
+}
+
+/// FNV-1a, which unlike the default hasher of the standard
+/// library is guaranteed to be stable.
+pub(super) struct Fnv(u64);
+
+impl Fnv {
+    pub(super) fn new(seed: u64) -> Self {
+        Self(0xcbf2_9ce4_8422_2325 ^ splitmix64(seed))
+    }
+}
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// Mixes the bits of the input, see https://prng.di.unimi.it/splitmix64.c
+pub(super) fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_ranges() {
+        let mut rng = TagRng::keyed(1, "range");
+        for _ in 0..1000 {
+            assert!((3..7).contains(&rng.next_in(3, 7)));
+            assert!((0.0..1.0).contains(&rng.next_f64()));
+        }
+        assert!(!rng.next_bool(0.0));
+        assert!(rng.next_bool(1.0));
+    }
+
+    #[test]
+    fn test_keys_give_distinct_sequences() {
+        let mut a = TagRng::keyed(1, "a");
+        let mut b = TagRng::keyed(1, "b");
+        assert_ne!(a.next_u64(), b.next_u64());
+        assert_eq!(TagRng::keyed(1, "a").next_u64(), TagRng::keyed(1, "a").next_u64());
+    }
+}
@@ -24,15 +24,22 @@
 
 //! Home of the scheduler component.
 
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crossbeam_channel::reconnectable::*;
 
-use super::assembly_impl::RootAssembler;
+use super::assembly_impl::{AssembledTree, RootAssembler};
+use super::control::TimeoutChange;
+use super::crash_dump::PanicSlot;
+use super::event_log::EventReplay;
+use super::startup_budget::StartupProfiler;
 use super::*;
 use crate::assembly::*;
-use crate::scheduler::dependencies::DataflowInfo;
+use crate::scheduler::dependencies::{DataflowInfo, DepGraph};
 use crate::*;
 
 /// Construction parameters for the scheduler.
@@ -45,22 +52,274 @@ pub struct SchedulerOptions {
     /// If true, we won't shut down the scheduler as soon as
     /// the event queue is empty, provided there are still
     /// live threads that can send messages to the scheduler
-    /// asynchronously.
+    /// asynchronously. The scheduler then waits for their
+    /// physical events, until the timeout if there is one,
+    /// and shuts down once all those threads have ended
+    /// (their [AsyncCtx] is dropped).
+    ///
+    /// The scheduler currently behaves like this even if this
+    /// is false, as existing programs rely on it to receive
+    /// physical events while no logical event is pending.
     pub keep_alive: bool,
 
     /// Timeout of reactor execution. If provided, the reactor
     /// program will be shut down *at the latest* at `T0 + timeout`.
     /// Calls to `request_stop` may make the program terminate earlier.
+    /// Shutdown reactions then execute at the tag `(T0 + timeout, 0)`,
+    /// once physical time has reached it, and events scheduled
+    /// later are discarded.
     pub timeout: Option<Duration>,
 
     /// Max number of threads to use in the thread pool.
     /// If zero, uses one thread per core. Ignored unless
     /// building with feature `parallel-runtime`.
+    ///
+    /// Worker threads are scoped to [SyncScheduler::run_main]:
+    /// they are all joined before it returns, including when
+    /// a reaction panics.
     pub threads: usize,
 
-    /// If true, dump the dependency graph to a file before
-    /// starting execution.
+    /// What to do when a reaction panics, on any thread.
+    pub on_reaction_panic: PanicPolicy,
+
+    /// If true, dump the dependency graph to a DOT file and
+    /// a JSON file in the temporary directory before starting
+    /// execution, see [SyncScheduler::dump_graph].
     pub dump_graph: bool,
+
+    /// If set, the start of the logical timeline is delayed
+    /// until the given wall-clock time. Independently started
+    /// programs can use this to align their timelines.
+    pub start_at: Option<StartTime>,
+
+    /// If set, the processing time of each tag is monitored,
+    /// and tags that take unusually long are reported to
+    /// the detector's callback. See [AnomalyDetector].
+    pub anomaly_detector: Option<AnomalyDetector>,
+
+    /// If set, physical events of low priority are rejected
+    /// while the event queue is too long, see [AdmissionPolicy].
+    pub admission: Option<AdmissionPolicy>,
+
+    /// If set, the number of pending events is bounded,
+    /// see [EventBudget].
+    pub event_budget: Option<EventBudget>,
+
+    /// If set, the number of physical events sent by asynchronous
+    /// threads and not yet received by the scheduler is bounded,
+    /// see [ChannelBound]. By default, the channel is unbounded.
+    pub physical_channel: Option<ChannelBound>,
+
+    /// If set, the tags of physical events are rounded up to the
+    /// next whole multiple of this window, counted from the start
+    /// of the program. All physical events sent within a window
+    /// then share a tag, which is processed at the end of the window.
+    /// This reduces the number of tags to process for high-rate
+    /// inputs, at the cost of up to one window of latency, and
+    /// of tag precision. If a physical action is scheduled several
+    /// times within a window, only the last value is kept.
+    pub physical_tag_window: Option<Duration>,
+
+    /// If set, decides when each tag is processed, and in which
+    /// order the reactions of a level are executed, see
+    /// [SchedulingPolicy]. By default, tags are processed as
+    /// soon as their logical time is reached. [EarliestDeadlineFirst]
+    /// executes the reactions with the earliest deadlines first.
+    pub scheduling_policy: Option<Box<dyn SchedulingPolicy>>,
+
+    /// If set, the program is shut down when logical time reaches
+    /// a microstep greater than this, ie when reactions keep
+    /// scheduling events at the same time point without delay.
+    /// The reactions that do so are then logged as an error.
+    pub max_microsteps: Option<u32>,
+
+    /// Seed of the numbers drawn with [ReactionCtx::random].
+    /// Runs with the same seed draw the same numbers.
+    pub seed: u64,
+
+    /// If set, a sample of the reaction executions is
+    /// recorded, see [TraceSampler].
+    pub trace: Option<TraceSampler>,
+
+    /// If set, the start and end of every reaction execution,
+    /// the number of reactions executed at each tag, and the
+    /// depth of the event queue are recorded, see [PerfTrace].
+    pub perf_trace: Option<PerfTrace>,
+
+    /// If set, the time spent assembling the program and
+    /// analysing its dependencies is compared with this
+    /// budget, and a report is logged if it is exceeded,
+    /// see [StartupBudget].
+    pub startup_budget: Option<StartupBudget>,
+
+    /// If set, the state of the scheduler is written to a
+    /// file if the program panics, see [CrashDump].
+    pub crash_dump: Option<CrashDump>,
+
+    /// If set, the execution is compared with a recorded trace,
+    /// and the first divergence is reported, see [DivergenceDetector].
+    pub divergence: Option<DivergenceDetector>,
+
+    /// If set, the events of the execution are recorded into
+    /// an [EventLog], see [EventRecorder].
+    pub record_events: Option<EventRecorder>,
+
+    /// If set, the physical events of the program are given the
+    /// tags recorded in this log instead of the current physical
+    /// time, and the scheduler waits for each recorded event before
+    /// processing later tags. This reproduces the execution that
+    /// was recorded, provided the physical actions are scheduled
+    /// in the same order. Actions scheduled more often than
+    /// recorded fall back to physical time.
+    pub replay_events: Option<EventLog>,
+
+    /// If set, some of these options can be changed while
+    /// the program runs, through this handle.
+    pub control: Option<SchedulerControl>,
+
+    /// If set, runtime metrics are exported as specified.
+    #[cfg(feature = "metrics")]
+    pub metrics: Option<MetricsExport>,
+
+    /// If set, a live view of the program is served over
+    /// HTTP on this address, for demos and debugging. The page
+    /// shows the dependency graph, the triggers present at
+    /// the latest sampled tag, the number of pending events,
+    /// and the execution time of reactions. Tags are sampled
+    /// at most every 100 ms.
+    #[cfg(feature = "visualization")]
+    pub visualization: Option<std::net::SocketAddr>,
+
+    /// If set, failures are injected into the execution
+    /// of the program, see [FaultInjector].
+    #[cfg(feature = "fault-injection")]
+    pub faults: Option<FaultInjector>,
+
+    /// If true, tags are processed as soon as possible, without
+    /// waiting for physical time to reach their logical time,
+    /// so that logical time runs ahead of physical time. This is
+    /// meant for tests, see [testing](crate::testing). Events of
+    /// physical actions are tagged with physical time, so they
+    /// would be late: programs that have some should not use it.
+    #[cfg(feature = "test-harness")]
+    pub fast: bool,
+
+    /// If set, the state of the program is saved to a file
+    /// at tag boundaries, see [CheckpointPolicy].
+    #[cfg(feature = "checkpoint")]
+    pub checkpoint: Option<CheckpointPolicy>,
+
+    /// If set, the program resumes from this checkpoint instead
+    /// of starting: the state of reactors and the pending events
+    /// are restored, and startup reactions are not executed. The
+    /// logical timeline continues from the tag of the checkpoint,
+    /// so that the timeout is still counted from the original start.
+    /// This panics if the checkpoint was taken from another program.
+    #[cfg(feature = "checkpoint")]
+    pub resume_from: Option<Checkpoint>,
+}
+
+/// When to fix the origin of the logical timeline,
+/// see [SchedulerOptions::start_at].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum StartTime {
+    /// Start at the given wall-clock time. If it is already
+    /// in the past, start immediately.
+    At(SystemTime),
+    /// Start after the given delay.
+    After(Duration),
+    /// Start at the next wall-clock time that is a whole
+    /// multiple of the given period, counted from the UNIX epoch.
+    /// For instance, with a period of one second, the program
+    /// starts on the next whole second. Starts immediately if
+    /// the current time is already aligned.
+    AlignTo(Duration),
+}
+
+impl StartTime {
+    /// Returns how long to wait before starting, given the
+    /// current wall-clock time.
+    fn delay_from(&self, now: SystemTime) -> Duration {
+        match *self {
+            StartTime::At(t) => t.duration_since(now).unwrap_or_default(),
+            StartTime::After(delay) => delay,
+            StartTime::AlignTo(period) => {
+                if period.is_zero() {
+                    return Duration::ZERO;
+                }
+                let since_epoch = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+                let period = period.as_nanos();
+                let remainder = since_epoch % period;
+                if remainder == 0 {
+                    Duration::ZERO
+                } else {
+                    Duration::from_nanos((period - remainder) as u64)
+                }
+            }
+        }
+    }
+}
+
+/// Format of the dependency graph written by [SyncScheduler::dump_graph].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum GraphFormat {
+    /// Graphviz DOT format. Use edges are dashed, and
+    /// priority edges between reactions are dotted.
+    Dot,
+    /// JSON, as an object with a list of `nodes`, each with
+    /// a `kind` and a `label`, and a list of `edges`, each with
+    /// a `kind` and the index of its `from` and `to` nodes.
+    Json,
+}
+
+fn write_graph(graph: &DepGraph, id_registry: &DebugInfoRegistry, path: &Path, format: GraphFormat) -> std::io::Result<()> {
+    let formatted = match format {
+        GraphFormat::Dot => graph.format_dot(id_registry),
+        GraphFormat::Json => graph.format_json(id_registry),
+    };
+    std::fs::write(path, formatted + "\n")
+}
+
+/// What to do when a reaction panics,
+/// see [SchedulerOptions::on_reaction_panic].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PanicPolicy {
+    /// The panic unwinds out of [SyncScheduler::run_main],
+    /// with its original payload, once worker threads have
+    /// been joined. Asynchronous threads are notified with
+    /// [AsyncCtx::was_terminated]. Shutdown reactions are not
+    /// executed, as the state of reactors may be inconsistent.
+    Propagate,
+    /// The process is aborted once the panic message has
+    /// been printed. This avoids running any more code, like
+    /// destructors or asynchronous threads, after a failure.
+    Abort,
+}
+
+impl Default for PanicPolicy {
+    fn default() -> Self {
+        Self::Propagate
+    }
+}
+
+/// Why the program is shutting down, see [ReactionCtx::shutdown_reason].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum ShutdownReason {
+    /// The [timeout](SchedulerOptions::timeout) of the program was reached.
+    Timeout,
+    /// A reaction or an asynchronous thread requested the
+    /// program to stop, with [ReactionCtx::request_stop] or
+    /// [AsyncCtx::request_stop].
+    Requested,
+    /// There are no more events to process, and none can
+    /// be received from asynchronous threads.
+    EventQueueEmpty,
+    /// Logical time stopped advancing, see [SchedulerOptions::max_microsteps].
+    MicrostepLimitExceeded,
+    /// Too many events were pending, see [SchedulerOptions::event_budget].
+    EventBudgetExceeded,
 }
 
 // Macros are placed a bit out of order to avoid exporting them
@@ -81,6 +340,60 @@ macro_rules! push_event {
     }};
 }
 
+/// Whether a physical event should be dropped by fault injection.
+#[cfg(feature = "fault-injection")]
+macro_rules! is_injected_drop {
+    ($scheduler:expr, $evt:expr) => {{
+        let evt: &PhysicalEvent = &$evt;
+        let dropped = match (&$scheduler.faults, evt.trigger_id) {
+            (Some(faults), Some(trigger)) if !evt.terminate => faults.drop_physical_event(evt.tag, trigger),
+            _ => false,
+        };
+        if dropped {
+            trace!("Dropping physical event at {} (fault injection)", evt.tag);
+            $scheduler.displaced.push((evt.tag, evt.trigger_id.unwrap()));
+        }
+        dropped
+    }};
+}
+
+#[cfg(not(feature = "fault-injection"))]
+macro_rules! is_injected_drop {
+    ($scheduler:expr, $evt:expr) => {{
+        let _ = &$evt;
+        false
+    }};
+}
+
+/// Whether a physical event should be discarded, because a newer
+/// one displaced it from the bounded channel, or by fault injection.
+macro_rules! is_discarded {
+    ($scheduler:expr, $evt:expr) => {{
+        let evt: &PhysicalEvent = &$evt;
+        let displaced = match (&$scheduler.channel, evt.trigger_id) {
+            (Some(channel), Some(trigger)) if !evt.terminate && channel.received() => {
+                trace!("Dropping physical event at {}, it was displaced by a newer one", evt.tag);
+                $scheduler.displaced.push((evt.tag, trigger));
+                true
+            }
+            _ => false,
+        };
+        displaced || is_injected_drop!($scheduler, evt)
+    }};
+}
+
+/// Record that a physical event was received, when replaying,
+/// and whether it requests to stop the program.
+macro_rules! mark_received {
+    ($scheduler:expr, $evt:expr) => {{
+        let evt: &PhysicalEvent = &$evt;
+        $scheduler.stop_requested |= evt.terminate;
+        if let (Some(replay), Some(trigger)) = (&mut $scheduler.replay, evt.trigger_id) {
+            replay.received(evt.tag, trigger);
+        }
+    }};
+}
+
 /// The runtime scheduler.
 ///
 /// Lifetime parameters: 'x and 't are carried around everywhere,
@@ -118,6 +431,14 @@ pub struct SyncScheduler<'x> {
     /// initialization if a timeout was specified.
     shutdown_time: Option<EventTag>,
 
+    /// Why the program is shutting down. Set when shutdown starts.
+    shutdown_reason: Option<ShutdownReason>,
+
+    /// Whether an asynchronous thread requested to stop the program.
+    /// The scheduler then does not [pause](SchedulerControl::pause)
+    /// anymore, so that the program shuts down.
+    stop_requested: bool,
+
     /// Whether the app has been terminated. Only used for
     /// communication with asynchronous threads. Set by the
     /// scheduler only.
@@ -125,30 +446,220 @@ pub struct SyncScheduler<'x> {
 
     /// Debug information.
     id_registry: DebugInfoRegistry,
+
+    /// Monitors the processing time of tags, if enabled.
+    anomaly_detector: Option<AnomalyDetector>,
+
+    /// Admission control of physical events, if enabled.
+    admission: Option<Arc<AdmissionControl>>,
+
+    /// Bound on the number of pending events, if any.
+    event_budget: Option<EventBudget>,
+
+    /// Bound on the channel of physical events, if any.
+    channel: Option<Arc<PhysicalChannel>>,
+
+    /// Physical events that were discarded by [Self::channel],
+    /// or by fault injection, whose values are forgotten at the end of the next tag.
+    displaced: Vec<(EventTag, TriggerId)>,
+
+    /// Window to which tags of physical events are rounded up, if any.
+    physical_tag_window: Option<Duration>,
+
+    /// See [SchedulerOptions::seed].
+    seed: u64,
+
+    /// Threads blocked in [AsyncCtx::sleep].
+    sleepers: Arc<Sleepers>,
+
+    /// Bounds the number of microsteps at a time point, if enabled.
+    microstep_guard: Option<MicrostepGuard>,
+
+    /// Records a sample of reaction executions, if enabled.
+    tracer: Option<TraceSampler>,
+
+    /// Records the activity of the scheduler, if enabled.
+    perf_trace: Option<PerfTrace>,
+
+    /// Written if the program panics.
+    crash_dump: Option<CrashDump>,
+
+    /// See [SchedulerOptions::scheduling_policy].
+    policy: Option<Box<dyn SchedulingPolicy>>,
+
+    /// Compares the execution with a recorded trace, if enabled.
+    divergence: Option<DivergenceDetector>,
+
+    /// Records the events of the execution, if enabled.
+    event_recorder: Option<EventRecorder>,
+
+    /// Tracks the recorded physical events, when replaying.
+    replay: Option<EventReplay>,
+
+    /// Tags of physical events, shared with asynchronous
+    /// threads, when recording or replaying.
+    physical_tags: Option<Arc<PhysicalTags>>,
+
+    /// Values that ports take at the shutdown tag.
+    last_wills: Vec<LastWill>,
+
+    /// See [DependencyDeclarator::export_port].
+    exports: Vec<PortExport>,
+
+    /// Handle through which options are changed at runtime, if any.
+    control: Option<SchedulerControl>,
+
+    /// Whether the anomaly detector is active, it may be paused
+    /// through the [Self::control] handle.
+    anomaly_detection_enabled: bool,
+
+    /// Runtime metrics, if they are exported.
+    #[cfg(feature = "metrics")]
+    metrics: Option<Arc<super::metrics::Metrics>>,
+
+    /// State shown by the visualization server, if it is enabled.
+    #[cfg(feature = "visualization")]
+    live_view: Option<Arc<super::visualization::LiveView>>,
+
+    /// Failures to inject, if any.
+    #[cfg(feature = "fault-injection")]
+    faults: Option<FaultInjector>,
+
+    /// See [SchedulerOptions::fast].
+    #[cfg(feature = "test-harness")]
+    fast: bool,
+
+    /// See [SchedulerOptions::checkpoint].
+    #[cfg(feature = "checkpoint")]
+    checkpoint: Option<CheckpointPolicy>,
+
+    /// See [SchedulerOptions::resume_from]. Taken at startup.
+    #[cfg(feature = "checkpoint")]
+    resume_from: Option<Checkpoint>,
 }
 
 impl<'x> SyncScheduler<'x> {
-    pub fn run_main<R: ReactorInitializer + 'static>(options: SchedulerOptions, args: R::Params) {
+    /// Assemble the program and check it for errors, without
+    /// executing it. Unlike [Self::run_main], which stops at
+    /// the first error, this reports all the errors and
+    /// warnings of the program at once.
+    pub fn validate<R: ReactorInitializer + 'static>(args: R::Params) -> ValidationReport {
+        RootAssembler::validate_tree::<R>(args, &[])
+    }
+
+    /// Like [Self::validate], but also checks the given
+    /// project-specific rules, see [ValidationRule].
+    pub fn validate_with<R: ReactorInitializer + 'static>(args: R::Params, rules: &[&dyn ValidationRule]) -> ValidationReport {
+        RootAssembler::validate_tree::<R>(args, rules)
+    }
+
+    /// Assemble and validate the program, then only execute
+    /// its startup tag, and report what it would have done next:
+    /// the timers it armed and the actions it scheduled. This
+    /// is a smoke test for programs whose full execution is
+    /// too long, eg in CI.
+    ///
+    /// Startup is not executed if the program is invalid. Threads
+    /// spawned by startup reactions see the scheduler as already
+    /// terminated, so the physical events they send are rejected,
+    /// and [AsyncCtx::sleep] returns immediately. Shutdown reactions,
+    /// and the lifecycle hooks of [ReactorBehavior], are not executed.
+    pub fn dry_run<R: ReactorInitializer + 'static>(args: R::Params) -> DryRunReport
+    where
+        R::Params: Clone,
+    {
+        let validation = Self::validate::<R>(args.clone());
+        if !validation.is_ok() {
+            return DryRunReport { validation, ..Default::default() };
+        }
+        let AssembledTree { reactors, graph, id_registry, .. } = RootAssembler::assemble_tree::<R>(args, None);
+        let dataflow_info = DataflowInfo::new(graph).map_err(|e| e.lift(&id_registry)).unwrap();
+        let options = SchedulerOptions {
+            record_events: Some(EventRecorder::new(|_| {})),
+            ..Default::default()
+        };
+        let mut scheduler = SyncScheduler::new(options, id_registry, &dataflow_info, reactors, Instant::now());
+        scheduler.was_terminated.store(true, Ordering::SeqCst);
+        scheduler.startup();
+        scheduler.sleepers.wake_all();
+
+        let debug = debug_info!(scheduler);
+        let name = |t: &TriggerId| debug.id_registry.fmt_component(*t).to_string();
+        let mut report = DryRunReport { validation, ..Default::default() };
+        if let Some(startup) = scheduler.event_recorder.as_ref().and_then(|r| r.tags().first()) {
+            report.reactions = startup
+                .reactions
+                .iter()
+                .map(|r| debug.display_reaction(*r).to_string())
+                .collect();
+        }
+        let (events, timer_events) = scheduler.event_queue.drain();
+        for evt in &events {
+            if evt.terminate {
+                report.stop_requested.get_or_insert(evt.tag);
+            }
+            report.actions.extend(evt.triggers.iter().map(|t| (name(t), evt.tag)));
+        }
+        for evt in &timer_events {
+            report.timers.extend(evt.triggers.iter().map(|t| (name(t), evt.tag)));
+        }
+        report
+    }
+
+    /// Assemble the program, and write its dependency graph to
+    /// the given file, without executing it. The graph is flattened:
+    /// it contains the reactions, ports, actions and timers of all
+    /// reactors, labeled with their path, eg `/child/out`.
+    ///
+    /// This panics if the program cannot be assembled, like
+    /// [Self::run_main]. See [Self::validate] to report errors.
+    pub fn dump_graph<R: ReactorInitializer + 'static>(
+        args: R::Params,
+        path: impl AsRef<Path>,
+        format: GraphFormat,
+    ) -> std::io::Result<()> {
+        let AssembledTree { graph, id_registry, .. } = RootAssembler::assemble_tree::<R>(args, None);
+        write_graph(&graph, &id_registry, path.as_ref(), format)
+    }
+
+    pub fn run_main<R: ReactorInitializer + 'static>(mut options: SchedulerOptions, args: R::Params) {
         let start = Instant::now();
         info!("Starting assembly...");
-        let (reactors, graph, id_registry) = RootAssembler::assemble_tree::<R>(args);
+        let profiler = options.startup_budget.take().map(StartupProfiler::new);
+        let AssembledTree {
+            reactors,
+            graph,
+            id_registry,
+            last_wills,
+            exports,
+            mut profiler,
+        } = RootAssembler::assemble_tree::<R>(args, profiler);
         let time = Instant::now() - start;
         info!("Assembly done in {} µs...", time.as_micros());
 
         if options.dump_graph {
-            use std::fs::File;
-            use std::io::Write;
-
-            let path = std::env::temp_dir().join("reactors.dot");
-
-            File::create(path.clone())
-                .and_then(|mut dot_file| writeln!(dot_file, "{}", graph.format_dot(&id_registry)))
-                .expect("Error while writing DOT file");
-            eprintln!("Wrote dot file to {}", path.to_string_lossy());
+            for (format, file) in [(GraphFormat::Dot, "reactors.dot"), (GraphFormat::Json, "reactors.json")] {
+                let path = std::env::temp_dir().join(file);
+                write_graph(&graph, &id_registry, &path, format).expect("Error while writing graph file");
+                eprintln!("Wrote graph to {}", path.to_string_lossy());
+            }
         }
 
+        #[cfg(feature = "visualization")]
+        let live_view = options.visualization.map(|addr| {
+            (
+                addr,
+                Arc::new(super::visualization::LiveView::new(graph.format_json(&id_registry))),
+            )
+        });
+
         // collect dependency information
-        let dataflow_info = DataflowInfo::new(graph).map_err(|e| e.lift(&id_registry)).unwrap();
+        let dataflow_info = DataflowInfo::analyze(graph, profiler.as_mut())
+            .map_err(|e| e.lift(&id_registry))
+            .unwrap();
+        if let Some(profiler) = profiler {
+            profiler.finish(&id_registry);
+        }
 
         // Using thread::scope here introduces an unnamed lifetime for
         // the scope, which is captured as 't by the SyncScheduler.
@@ -156,53 +667,224 @@ impl<'x> SyncScheduler<'x> {
         // dataflow_info outlives 't, so that physical contexts
         // can be spawned in threads that capture references
         // to 'x.
-        let initial_time = Instant::now();
+        let initial_time = match options.start_at {
+            Some(start) => {
+                let delay = start.delay_from(SystemTime::now());
+                let initial_time = Instant::now() + delay;
+                info!("Delaying startup by {} ms", delay.as_millis());
+                std::thread::sleep(delay);
+                initial_time
+            }
+            None => Instant::now(),
+        };
+        // continue the timeline of the checkpoint
+        #[cfg(feature = "checkpoint")]
+        let initial_time = match &options.resume_from {
+            Some(checkpoint) => initial_time
+                .checked_sub(checkpoint.tag.offset_from_t0)
+                .unwrap_or(initial_time),
+            None => initial_time,
+        };
         #[cfg(feature = "parallel-runtime")]
-        let rayon_thread_pool = rayon::ThreadPoolBuilder::new().num_threads(options.threads).build().unwrap();
-
-        let scheduler = SyncScheduler::new(options, id_registry, &dataflow_info, reactors, initial_time);
-
-        cfg_if::cfg_if! {
-            if #[cfg(feature = "parallel-runtime")] {
-                /// The unsafe impl is safe if scheduler instances
-                /// are only sent between threads like this (their Rc
-                /// internals are not copied).
-                /// So long as the framework entirely controls the lifetime
-                /// of SyncScheduler instances, this is enforceable.
-                unsafe impl Send for SyncScheduler<'_> {}
-
-                // install makes calls to parallel iterators use that thread pool
-                rayon_thread_pool.install(|| scheduler.launch_event_loop());
-            } else {
-                scheduler.launch_event_loop();
+        let worker_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(options.threads)
+            .thread_name(|i| format!("reactor-worker-{}", i));
+        let on_reaction_panic = options.on_reaction_panic;
+
+        #[cfg(feature = "metrics")]
+        let metrics_export = options.metrics.clone();
+
+        let mut scheduler = SyncScheduler::new(options, id_registry, &dataflow_info, reactors, initial_time);
+        scheduler.last_wills = last_wills;
+        scheduler.exports = exports;
+
+        #[cfg(feature = "visualization")]
+        let visualization_server = live_view.map(|(addr, view)| {
+            scheduler.live_view = Some(view.clone());
+            super::visualization::spawn_server(addr, view, scheduler.was_terminated.clone())
+        });
+
+        #[cfg(feature = "metrics")]
+        let metrics_exporter = metrics_export
+            .zip(scheduler.metrics.clone())
+            .map(|(export, metrics)| super::metrics::spawn_exporter(export, metrics, scheduler.was_terminated.clone()));
+
+        let was_terminated = scheduler.was_terminated.clone();
+        #[cfg(feature = "parallel-runtime")]
+        let panic_slot = scheduler.crash_dump.as_ref().map(CrashDump::panic_slot);
+
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            cfg_if::cfg_if! {
+                if #[cfg(feature = "parallel-runtime")] {
+                    // Workers are scoped threads, which are joined when
+                    // the event loop returns or unwinds. Install makes
+                    // calls to parallel iterators use that thread pool.
+                    worker_pool
+                        .build_scoped(
+                            |worker| {
+                                if let Some(slot) = &panic_slot {
+                                    slot.watch_current_thread();
+                                }
+                                worker.run()
+                            },
+                            |pool| pool.install(|| scheduler.launch_event_loop()))
+                        .expect("Could not start worker threads")
+                } else {
+                    scheduler.launch_event_loop()
+                }
             }
+        }));
+
+        if let Err(payload) = result {
+            // the scheduler did not get to notify concurrent threads
+            was_terminated.store(true, Ordering::SeqCst);
+            match on_reaction_panic {
+                PanicPolicy::Propagate => {
+                    error!("A reaction panicked, the scheduler is stopping");
+                    #[cfg(feature = "metrics")]
+                    if let Some(exporter) = metrics_exporter {
+                        let _ = exporter.join();
+                    }
+                    #[cfg(feature = "visualization")]
+                    if let Some(server) = visualization_server {
+                        let _ = server.join();
+                    }
+                    std::panic::resume_unwind(payload)
+                }
+                PanicPolicy::Abort => {
+                    error!("A reaction panicked, aborting");
+                    std::process::abort()
+                }
+            }
+        }
+
+        #[cfg(feature = "metrics")]
+        if let Some(exporter) = metrics_exporter {
+            // the exporter stops shortly after termination
+            let _ = exporter.join();
+        }
+        #[cfg(feature = "visualization")]
+        if let Some(server) = visualization_server {
+            let _ = server.join();
         }
     }
 
     /// Launch the event loop in this thread.
     fn launch_event_loop(mut self) {
+        for reactor in &mut self.reactors {
+            reactor.on_startup_sync();
+        }
+        match self.crash_dump.as_ref().map(CrashDump::panic_slot) {
+            None => self.run_event_loop(),
+            Some(slot) => {
+                slot.watch_current_thread();
+                let result = std::panic::catch_unwind(AssertUnwindSafe(|| self.run_event_loop()));
+                PanicSlot::unwatch_current_thread();
+                if let Err(payload) = result {
+                    self.write_crash_dump(&*payload);
+                    std::panic::resume_unwind(payload)
+                }
+            }
+        }
+        for reactor in self.reactors.iter_mut().rev() {
+            reactor.on_shutdown_sync();
+        }
+
+        // self destructor is called here
+    }
+
+    /// Write the crash dump, after a panic
+    /// unwound out of the event loop.
+    fn write_crash_dump(&mut self, payload: &(dyn std::any::Any + Send)) {
+        let (mut pending, timers) = self.event_queue.drain();
+        pending.extend(timers);
+        pending.sort_by_key(|evt| evt.tag);
+        let debug = debug_info!(self);
+        let pending = pending.iter().map(|evt| debug.display_event(evt)).collect::<Vec<_>>();
+        if let Some(dump) = &self.crash_dump {
+            dump.write(payload, self.latest_processed_tag, &pending, &self.id_registry);
+        }
+    }
+
+    fn run_event_loop(&mut self) {
         /************************************************
          * This is the main event loop of the scheduler *
          ************************************************/
 
+        #[cfg(feature = "checkpoint")]
+        match self.resume_from.take() {
+            Some(checkpoint) => self.resume(checkpoint),
+            None => self.startup(),
+        }
+        #[cfg(not(feature = "checkpoint"))]
         self.startup();
 
         loop {
+            #[cfg(feature = "introspection")]
+            if let Some(control) = &self.control {
+                control.publish_pending_tags(self.event_queue.pending_tags());
+            }
+            self.wait_while_paused();
+            self.apply_control();
+
             // flush pending events, this doesn't block
-            for evt in self.rx.try_iter() {
-                let evt = evt.make_executable(self.dataflow);
-                push_event!(self, evt);
+            self.flush_physical_events();
+
+            let over_budget = self.enforce_event_budget();
+            let next_evt = self.event_queue.take_earliest();
+            if let Some(admission) = &self.admission {
+                admission.set_queue_depth(self.event_queue.len());
             }
 
-            if let Some(evt) = self.event_queue.take_earliest() {
-                if self.is_after_shutdown(evt.tag) {
-                    trace!("Event is late, shutting down - event tag: {}", evt.tag);
-                    break;
+            if let Some(evt) = next_evt {
+                if let Some(shutdown_t) = self.shutdown_time.filter(|&t| t < evt.tag) {
+                    // The timeout tag is processed first, once physical time
+                    // reaches it, and physical events that arrive meanwhile
+                    // are processed before it. Later events are discarded.
+                    trace!(
+                        "Event is after the timeout, processing the timeout tag first - event tag: {}",
+                        evt.tag
+                    );
+                    push_event!(self, evt);
+                    push_event!(
+                        self,
+                        Event {
+                            tag: shutdown_t,
+                            reactions: None,
+                            terminate: false,
+                            triggers: Default::default(),
+                        }
+                    );
+                    continue;
+                }
+                if let Some(expected) = self.replay.as_ref().and_then(|r| r.awaited_before(evt.tag)) {
+                    // the recorded physical event must be processed first
+                    trace!("Waiting for the recorded physical event at {}", expected);
+                    push_event!(self, evt);
+                    match self.rx.recv() {
+                        Ok(async_event) => {
+                            mark_received!(self, async_event);
+                            if !is_discarded!(self, async_event) {
+                                let async_event = async_event.make_executable(self.dataflow);
+                                push_event!(self, async_event);
+                            }
+                        }
+                        Err(_) => self.replay.as_mut().unwrap().abandon(),
+                    }
+                    continue;
                 }
                 trace!("Processing event {}", self.debug().display_event(&evt));
-                match self.catch_up_physical_time(evt.tag.to_logical_time(self.initial_time)) {
+                let release = self.release_time(evt.tag);
+                match self.catch_up_physical_time(release) {
                     Ok(_) => {}
+                    Err(async_event) if is_discarded!(self, async_event) => {
+                        mark_received!(self, async_event);
+                        // the sleep was cut short, keep waiting
+                        push_event!(self, evt);
+                        continue;
+                    }
                     Err(async_event) => {
+                        mark_received!(self, async_event);
                         let async_event = async_event.make_executable(self.dataflow);
                         // an asynchronous event woke our sleep
                         if async_event.tag < evt.tag {
@@ -218,27 +900,72 @@ impl<'x> SyncScheduler<'x> {
                 };
                 // at this point we're at the correct time
 
+                #[cfg(feature = "metrics")]
+                if let Some(metrics) = &self.metrics {
+                    let lag = Instant::now().saturating_duration_since(evt.tag.to_logical_time(self.initial_time));
+                    metrics.record_tag(lag, self.event_queue.len());
+                }
+
                 if evt.terminate || self.shutdown_time == Some(evt.tag) {
-                    return self.shutdown(evt.tag, evt.reactions);
+                    let reason = if evt.terminate {
+                        ShutdownReason::Requested
+                    } else {
+                        ShutdownReason::Timeout
+                    };
+                    return self.shutdown(evt.tag, evt.reactions, &evt.triggers, reason);
+                }
+
+                if over_budget {
+                    error!(
+                        "{} events are pending, which exceeds the event budget",
+                        self.event_queue.len() + 1
+                    );
+                    return self.shutdown(evt.tag, None, &[], ShutdownReason::EventBudgetExceeded);
                 }
 
-                self.process_tag(false, evt.tag, evt.reactions);
+                if let Some(guard) = self.microstep_guard.as_ref().filter(|g| g.is_exceeded(evt.tag)) {
+                    error!("{}", guard.diagnostic(evt.tag, &debug_info!(self)));
+                    return self.shutdown(evt.tag, None, &[], ShutdownReason::MicrostepLimitExceeded);
+                }
+
+                let tag = evt.tag;
+                self.process_tag(false, tag, evt.reactions, &evt.triggers);
+                if let Some(control) = &self.control {
+                    control.tag_processed();
+                }
+                #[cfg(feature = "checkpoint")]
+                self.checkpoint_if_due(tag);
             } else if let Some(evt) = self.receive_event() {
+                mark_received!(self, evt);
+                if is_discarded!(self, evt) {
+                    continue;
+                }
                 let evt = evt.make_executable(self.dataflow);
                 // this may block
                 push_event!(self, evt);
                 continue;
             } else {
+                if self.apply_control() {
+                    // the timeout may have changed while we were waiting
+                    continue;
+                }
                 // all senders have hung up, or timeout
-                info!("Event queue is empty forever, shutting down.");
+                let timed_out = self
+                    .shutdown_time
+                    .map_or(false, |t| Instant::now() >= t.to_logical_time(self.initial_time));
+                if timed_out {
+                    self.shutdown_reason = Some(ShutdownReason::Timeout);
+                } else {
+                    info!("Event queue is empty forever, shutting down.");
+                    self.shutdown_reason = Some(ShutdownReason::EventQueueEmpty);
+                }
                 break;
             }
         } // end loop
 
         let shutdown_tag = self.shutdown_time.unwrap_or_else(|| EventTag::now(self.initial_time));
-        self.shutdown(shutdown_tag, None);
-
-        // self destructor is called here
+        let reason = self.shutdown_reason.unwrap_or(ShutdownReason::EventQueueEmpty);
+        self.shutdown(shutdown_tag, None, &[], reason);
     }
 
     /// Creates a new scheduler. An empty scheduler doesn't
@@ -255,10 +982,6 @@ impl<'x> SyncScheduler<'x> {
             warn!("'workers' runtime parameter has no effect unless feature 'parallel-runtime' is enabled")
         }
 
-        if options.keep_alive {
-            warn!("'keepalive' runtime parameter has no effect in the Rust target")
-        }
-
         let (_, rx) = unbounded::<PhysicalEvent>();
         Self {
             rx,
@@ -275,41 +998,323 @@ impl<'x> SyncScheduler<'x> {
             }),
             dataflow: dependency_info,
             id_registry,
+            shutdown_reason: None,
+            stop_requested: false,
             was_terminated: Default::default(),
+            anomaly_detector: options.anomaly_detector,
+            admission: Self::admission_control(options.admission, options.event_budget),
+            event_budget: options.event_budget,
+            channel: options.physical_channel.map(|bound| Arc::new(PhysicalChannel::new(bound))),
+            displaced: Vec::new(),
+            physical_tag_window: options.physical_tag_window,
+            seed: options.seed,
+            microstep_guard: options.max_microsteps.map(MicrostepGuard::new),
+            tracer: options.trace,
+            perf_trace: options.perf_trace,
+            crash_dump: options.crash_dump,
+            policy: options.scheduling_policy.map(|mut policy| {
+                for (reaction, deadline) in dependency_info.deadlines() {
+                    policy.declare_deadline(reaction, deadline);
+                }
+                policy
+            }),
+            divergence: options.divergence,
+            physical_tags: (options.record_events.is_some() || options.replay_events.is_some()).then(|| {
+                Arc::new(PhysicalTags::new(
+                    options.record_events.is_some(),
+                    options.replay_events.as_ref(),
+                ))
+            }),
+            replay: options.replay_events.as_ref().map(EventReplay::new),
+            event_recorder: options.record_events,
+            last_wills: Vec::new(),
+            exports: Vec::new(),
+            control: options.control,
+            anomaly_detection_enabled: true,
+            sleepers: Default::default(),
+            #[cfg(feature = "metrics")]
+            metrics: options
+                .metrics
+                .map(|_| Arc::new(super::metrics::Metrics::new(options.event_budget.map(|b| b.max_events)))),
+            #[cfg(feature = "visualization")]
+            live_view: None,
+            #[cfg(feature = "fault-injection")]
+            faults: options.faults,
+            #[cfg(feature = "test-harness")]
+            fast: options.fast,
+            #[cfg(feature = "checkpoint")]
+            checkpoint: options.checkpoint,
+            #[cfg(feature = "checkpoint")]
+            resume_from: options.resume_from,
         }
     }
 
     /// Fix the origin of the logical timeline to the current
     /// physical time, and runs the startup reactions
     /// of all reactors.
+    ///
+    /// The startup tag is processed like any other tag: values
+    /// set by startup reactions propagate to the reactions they
+    /// trigger or that use them, which execute later in the wave.
+    /// Startup reactions of a child may hence use values set by
+    /// the startup reactions of its parent.
     fn startup(&mut self) {
         info!("Triggering startup...");
         debug_assert!(!self.reactors.is_empty(), "No registered reactors");
 
         let startup_reactions = self.dataflow.reactions_triggered_by(&TriggerId::STARTUP);
-        self.process_tag(false, EventTag::ORIGIN, Some(Cow::Borrowed(startup_reactions)))
+        self.process_tag(
+            false,
+            EventTag::ORIGIN,
+            Some(Cow::Borrowed(startup_reactions)),
+            &[TriggerId::STARTUP],
+        )
     }
 
-    fn shutdown(&mut self, shutdown_tag: EventTag, reactions: ReactionPlan<'x>) {
-        info!("Scheduler is shutting down, at {}", shutdown_tag);
+    /// Restore the state of reactors and the pending events of
+    /// the checkpoint, instead of running startup reactions.
+    #[cfg(feature = "checkpoint")]
+    fn resume(&mut self, checkpoint: Checkpoint) {
+        info!("Resuming from checkpoint at {}", checkpoint.tag);
+        if let Err(e) = self.restore_reactors(&checkpoint) {
+            panic!("Cannot resume from checkpoint: {}", e)
+        }
+        for pending in checkpoint.events {
+            let mut evt = if pending.terminate {
+                Event::terminate_at(pending.tag)
+            } else {
+                Event::execute(pending.tag, Cow::Owned(ExecutableReactions::new()))
+            };
+            for trigger in pending.triggers {
+                let reactions = Cow::Borrowed(self.dataflow.reactions_triggered_by(&trigger));
+                evt.absorb(Event::execute(pending.tag, reactions).triggered_by(trigger));
+            }
+            push_event!(self, evt);
+        }
+        self.latest_processed_tag = Some(checkpoint.tag);
+    }
+
+    #[cfg(feature = "checkpoint")]
+    fn restore_reactors(&mut self, checkpoint: &Checkpoint) -> Result<(), CheckpointError> {
+        if checkpoint.reactors.len() != self.reactors.len() {
+            return Err(CheckpointError::Mismatch(format!(
+                "{} reactors were saved, the program has {}",
+                checkpoint.reactors.len(),
+                self.reactors.len()
+            )));
+        }
+        for (reactor, (path, state)) in self.reactors.iter_mut().zip(&checkpoint.reactors) {
+            let actual = self.id_registry.get_debug_info(reactor.id()).to_string();
+            if *path != actual {
+                return Err(CheckpointError::Mismatch(format!(
+                    "expected reactor {}, got {}",
+                    path, actual
+                )));
+            }
+            if let Some(state) = state {
+                reactor.deserialize_state(state)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Save a checkpoint at the end of the given tag, if one is
+    /// due. Errors are logged, and do not stop the program.
+    #[cfg(feature = "checkpoint")]
+    fn checkpoint_if_due(&mut self, tag: EventTag) {
+        if !self.checkpoint.as_mut().map_or(false, |c| c.is_due(tag)) {
+            return;
+        }
+        let result = self.take_checkpoint(tag).and_then(|checkpoint| {
+            let path = self.checkpoint.as_ref().unwrap().path();
+            checkpoint.save(path)
+        });
+        match result {
+            Ok(()) => debug!("Saved checkpoint at {}", tag),
+            Err(e) => error!("Could not save checkpoint at {}: {}", tag, e),
+        }
+    }
+
+    #[cfg(feature = "checkpoint")]
+    fn take_checkpoint(&mut self, tag: EventTag) -> Result<Checkpoint, CheckpointError> {
+        let mut reactors = Vec::with_capacity(self.reactors.len());
+        for reactor in &self.reactors {
+            let path = self.id_registry.get_debug_info(reactor.id()).to_string();
+            reactors.push((path, reactor.serialize_state()?));
+        }
+        // the queue does not support iteration, so it is rebuilt
+        let (events, timers) = self.event_queue.drain();
+        let mut pending = Vec::with_capacity(events.len() + timers.len());
+        for evt in events.iter().chain(&timers) {
+            pending.push(PendingEvent {
+                tag: evt.tag,
+                triggers: evt.triggers.to_vec(),
+                terminate: evt.terminate,
+            });
+        }
+        pending.sort_by_key(|p| p.tag);
+        events.into_iter().for_each(|evt| self.event_queue.push(evt));
+        timers.into_iter().for_each(|evt| self.event_queue.push_timer(evt));
+        Ok(Checkpoint { tag, reactors, events: pending })
+    }
+
+    fn shutdown(&mut self, shutdown_tag: EventTag, reactions: ReactionPlan<'x>, triggers: &[TriggerId], reason: ShutdownReason) {
+        info!("Scheduler is shutting down, at {} ({:?})", shutdown_tag, reason);
         self.shutdown_time = Some(shutdown_tag);
+        self.shutdown_reason = Some(reason);
         let default_plan: ReactionPlan<'x> = Some(Cow::Borrowed(self.dataflow.reactions_triggered_by(&TriggerId::SHUTDOWN)));
-        let reactions = ExecutableReactions::merge_cows(reactions, default_plan);
-
-        self.process_tag(true, shutdown_tag, reactions);
+        let mut reactions = ExecutableReactions::merge_cows(reactions, default_plan);
+        let mut triggers = triggers.iter().copied().chain(Some(TriggerId::SHUTDOWN)).collect::<Vec<_>>();
+
+        for will in self.last_wills.drain(..) {
+            let downstream = Some(Cow::Borrowed(self.dataflow.reactions_triggered_by(&will.port)));
+            reactions = ExecutableReactions::merge_cows(reactions, downstream);
+            triggers.push(will.port);
+            will.emit();
+        }
 
-        // notify concurrent threads.
+        // notify concurrent threads, events they send from
+        // now on would not be processed.
         self.was_terminated.store(true, Ordering::SeqCst);
+        self.sleepers.wake_all();
+        if let Some(channel) = &self.channel {
+            channel.wake_all();
+        }
+
+        self.process_tag(true, shutdown_tag, reactions, &triggers);
+        if let Some(tracer) = &mut self.tracer {
+            tracer.finish(&self.id_registry);
+        }
+        if let Some(perf) = &mut self.perf_trace {
+            perf.finish(&self.id_registry);
+        }
+        if let Some(divergence) = &mut self.divergence {
+            divergence.finish(&debug_info!(self));
+        }
+        if let Some(recorder) = &mut self.event_recorder {
+            let physical_events = self.physical_tags.as_ref().map(|t| t.take_recorded()).unwrap_or_default();
+            recorder.finish(physical_events);
+        }
         info!("Scheduler has been shut down")
     }
 
-    /// Returns whether the given event should be ignored and
-    /// the event loop be terminated. This would be the case
-    /// if the tag of the event is later than the projected
-    /// shutdown time. Such 'late' events may be emitted by
-    /// the shutdown wave.
-    fn is_after_shutdown(&self, t: EventTag) -> bool {
-        self.shutdown_time.map(|shutdown_t| shutdown_t < t).unwrap_or(false)
+    /// Admission control of physical events, needed if either
+    /// an admission policy or a budget that rejects physical
+    /// events is set.
+    fn admission_control(policy: Option<AdmissionPolicy>, budget: Option<EventBudget>) -> Option<Arc<AdmissionControl>> {
+        let max_events = budget
+            .filter(|b| b.policy == BudgetPolicy::RejectPhysical)
+            .map(|b| b.max_events);
+        if policy.is_none() && max_events.is_none() {
+            return None;
+        }
+        Some(Arc::new(AdmissionControl::new(policy, max_events)))
+    }
+
+    /// Drop the latest events if the event budget is exceeded
+    /// and its policy says so. Returns whether the program should
+    /// shut down because of the budget.
+    fn enforce_event_budget(&mut self) -> bool {
+        let budget = match self.event_budget {
+            Some(budget) if self.event_queue.len() > budget.max_events => budget,
+            _ => return false,
+        };
+        match budget.policy {
+            BudgetPolicy::RejectPhysical => false,
+            BudgetPolicy::Shutdown => true,
+            BudgetPolicy::DropLatest => {
+                while self.event_queue.len() > budget.max_events {
+                    match self.event_queue.pop_latest() {
+                        Some(evt) => {
+                            warn!(
+                                "Event budget exceeded, dropping event {}",
+                                debug_info!(self).display_event(&evt)
+                            );
+                            #[cfg(feature = "metrics")]
+                            if let Some(metrics) = &self.metrics {
+                                metrics.record_dropped_event();
+                            }
+                        }
+                        None => break,
+                    }
+                }
+                false
+            }
+        }
+    }
+
+    /// Apply the changes made through the [SchedulerControl]
+    /// handle, if any. Returns whether there were any.
+    fn apply_control(&mut self) -> bool {
+        let changes = match self.control.as_ref().and_then(|c| c.take_changes()) {
+            Some(changes) => changes,
+            None => return false,
+        };
+        if let Some(change) = changes.timeout {
+            let shutdown_time = match change {
+                TimeoutChange::Set(timeout) => timeout.map(|t| EventTag::ORIGIN.successor(t)),
+                TimeoutChange::Extend(by) => self.shutdown_time.map(|t| EventTag::offset(t.offset_from_t0 + by, 0)),
+            };
+            // we cannot shut down before the latest processed tag
+            self.shutdown_time = shutdown_time.map(|t| match self.latest_processed_tag {
+                Some(latest) if t <= latest => latest.next_microstep(),
+                _ => t,
+            });
+            match self.shutdown_time {
+                Some(t) => info!("Timeout changed, will shut down at most at tag {}", t),
+                None => info!("Timeout removed"),
+            }
+        }
+        if let Some(enabled) = changes.anomaly_detection {
+            self.anomaly_detection_enabled = enabled;
+        }
+        #[cfg(feature = "metrics")]
+        if let (Some(period), Some(metrics)) = (changes.metrics_period, &self.metrics) {
+            metrics.set_export_period(period);
+        }
+        true
+    }
+
+    /// Receive the physical events that are already in the channel.
+    fn flush_physical_events(&mut self) {
+        for evt in self.rx.try_iter() {
+            mark_received!(self, evt);
+            if is_discarded!(self, evt) {
+                continue;
+            }
+            let evt = evt.make_executable(self.dataflow);
+            push_event!(self, evt);
+        }
+    }
+
+    /// Block while the execution is [paused](SchedulerControl::pause).
+    /// Physical events are received meanwhile, so that a request
+    /// to stop, or the timeout, can end the pause.
+    fn wait_while_paused(&mut self) {
+        /// Granularity at which physical events are received while
+        /// paused, as only resuming and stepping wake us up.
+        const POLL_PERIOD: Duration = Duration::from_millis(10);
+        let control = match &self.control {
+            Some(control) => control.clone(),
+            None => return,
+        };
+        while !self.stop_requested && control.wait_while_paused(POLL_PERIOD) {
+            self.apply_control();
+            if self
+                .shutdown_time
+                .map_or(false, |t| Instant::now() >= t.to_logical_time(self.initial_time))
+            {
+                debug!("Timeout reached while paused, resuming to shut down");
+                return;
+            }
+            self.flush_physical_events();
+            if self.stop_requested {
+                debug!("Stop requested while paused, resuming to shut down");
+                return;
+            }
+            #[cfg(feature = "introspection")]
+            control.publish_pending_tags(self.event_queue.pending_tags());
+        }
     }
 
     /// Wait for an asynchronous event for as long as we can
@@ -330,9 +1335,23 @@ impl<'x> SyncScheduler<'x> {
         }
     }
 
+    /// Physical time at which the given tag may be processed,
+    /// never before its logical time.
+    fn release_time(&mut self, tag: EventTag) -> Instant {
+        let logical = tag.to_logical_time(self.initial_time);
+        match &mut self.policy {
+            Some(policy) => policy.release_time(tag, self.initial_time).max(logical),
+            None => logical,
+        }
+    }
+
     /// Sleep/wait until the given time OR an asynchronous
     /// event is received first.
     fn catch_up_physical_time(&mut self, target: Instant) -> Result<(), PhysicalEvent> {
+        #[cfg(feature = "test-harness")]
+        if self.fast {
+            return Ok(());
+        }
         let now = Instant::now();
 
         if now < target {
@@ -396,13 +1415,13 @@ impl<'x> SyncScheduler<'x> {
     }
 
     #[inline]
-    pub(super) fn debug(&self) -> DebugInfoProvider {
+    pub(super) fn debug(&self) -> DebugInfoProvider<'_> {
         debug_info!(self)
     }
 
     /// Actually process a tag. The provided reactions are the
     /// root reactions that startup the "wave".
-    fn process_tag(&mut self, is_shutdown: bool, tag: EventTag, mut reactions: ReactionPlan<'x>) {
+    fn process_tag(&mut self, is_shutdown: bool, tag: EventTag, mut reactions: ReactionPlan<'x>, triggers: &[TriggerId]) {
         if cfg!(debug_assertions) {
             if let Some(latest) = self.latest_processed_tag {
                 debug_assert!(tag > latest, "Tag ordering mismatch")
@@ -415,21 +1434,69 @@ impl<'x> SyncScheduler<'x> {
             return;
         }
 
+        let wave_start = Instant::now();
         let mut ctx = self.new_reaction_ctx(tag, None, &self.rx, debug_info!(self), &self.was_terminated, is_shutdown);
+        #[cfg(feature = "visualization")]
+        let view_sampled = self.live_view.as_ref().map_or(false, |v| v.samples_next_tag());
+        #[cfg(not(feature = "visualization"))]
+        let view_sampled = false;
+        let sampled = self.tracer.as_ref().map_or(false, |t| t.samples_next_tag())
+            || self.divergence.as_ref().map_or(false, |d| d.is_active())
+            || view_sampled;
+        ctx.record_timings = (self.anomaly_detector.is_some() && self.anomaly_detection_enabled)
+            || sampled
+            || self.event_recorder.is_some()
+            || self.replay.is_some();
+        ctx.record_present = sampled;
+        ctx.record_spans = self.perf_trace.is_some();
+        ctx.admission = self.admission.as_ref();
+        ctx.channel = self.channel.as_ref();
+        ctx.physical_tag_window = self.physical_tag_window;
+        ctx.seed = self.seed;
+        ctx.physical_tags = self.physical_tags.as_ref();
+        ctx.sleepers = Some(&self.sleepers);
+        ctx.track_injections = self.microstep_guard.is_some();
+        ctx.shutdown_reason = if is_shutdown { self.shutdown_reason } else { None };
+        #[cfg(feature = "fault-injection")]
+        {
+            ctx.faults = self.faults.as_ref();
+        }
 
         while let Some((level_no, batch)) = next_level {
             let level_no = level_no.cloned();
             trace!("  - Level {}", level_no);
+            #[cfg(feature = "metrics")]
+            if let Some(metrics) = &self.metrics {
+                metrics.record_reactions(batch.len());
+            }
             ctx.cur_level = level_no.key;
+            if let Some(dump) = &mut self.crash_dump {
+                dump.record_batch(tag, batch.iter());
+            }
 
             /// Minimum number of reactions (inclusive) required
             /// to parallelize reactions.
             /// TODO experiment with tweaking this
             const PARALLEL_THRESHOLD: usize = 3;
 
+            let ordered = self.policy.as_mut().map(|policy| {
+                let mut ordered = batch.iter().collect::<Vec<_>>();
+                policy.order_level(tag, &mut ordered);
+                ordered
+            });
+
             if cfg!(feature = "parallel-runtime") && batch.len() >= PARALLEL_THRESHOLD {
                 #[cfg(feature = "parallel-runtime")]
-                parallel_rt_impl::process_batch(&mut ctx, &mut self.reactors, batch);
+                match ordered {
+                    // workers start the reactions in this order
+                    Some(ordered) => parallel_rt_impl::process_batch(&mut ctx, &mut self.reactors, ordered.into_iter()),
+                    None => parallel_rt_impl::process_batch(&mut ctx, &mut self.reactors, batch.iter()),
+                }
+            } else if let Some(ordered) = ordered {
+                for reaction_id in ordered {
+                    let reactor = &mut self.reactors[reaction_id.0.container()];
+                    ctx.execute(reactor, reaction_id);
+                }
             } else {
                 // the impl for non-parallel runtime
                 for reaction_id in batch {
@@ -443,11 +1510,74 @@ impl<'x> SyncScheduler<'x> {
         }
 
         for evt in ctx.insides.future_events.drain(..) {
+            if let Some(replay) = &mut self.replay {
+                // physical actions scheduled by reactions
+                for trigger in &evt.triggers {
+                    replay.received(evt.tag, *trigger);
+                }
+            }
             push_event!(self, evt)
         }
+        for evt in ctx.insides.timer_events.drain(..) {
+            trace!("Pushing timer event {}", self.debug().display_event(&evt));
+            self.event_queue.push_timer(evt);
+        }
+
+        if let Some(perf) = &mut self.perf_trace {
+            let spans = std::mem::take(&mut ctx.insides.reaction_spans);
+            perf.observe_tag(tag, self.initial_time, wave_start, spans, self.event_queue.len());
+        }
+
+        if let Some(guard) = &mut self.microstep_guard {
+            guard.record(tag, ctx.insides.injections.drain(..));
+        }
+
+        if let Some(tracer) = &mut self.tracer {
+            let present = triggers.iter().chain(&ctx.insides.present).copied();
+            tracer.observe_tag(tag, &ctx.insides.reaction_timings, present);
+        }
+
+        #[cfg(feature = "visualization")]
+        if let Some(view) = self.live_view.as_ref().filter(|_| view_sampled) {
+            let present = triggers.iter().chain(&ctx.insides.present).copied();
+            view.observe_tag(
+                tag,
+                self.event_queue.len(),
+                &ctx.insides.reaction_timings,
+                present,
+                &debug_info!(self),
+            );
+        }
+
+        if let Some(divergence) = self.divergence.as_mut().filter(|d| d.is_active()) {
+            let reactions = ctx.insides.reaction_timings.iter().map(|(r, _)| *r);
+            let present = triggers.iter().chain(&ctx.insides.present).copied();
+            divergence.observe_tag(tag, reactions, present, &debug_info!(self));
+        }
+
+        if let Some(recorder) = &mut self.event_recorder {
+            recorder.observe_tag(tag, ctx.insides.reaction_timings.iter().map(|(r, _)| *r));
+        }
+
+        if let Some(replay) = &mut self.replay {
+            let reactions = ctx.insides.reaction_timings.iter().map(|(r, _)| *r);
+            replay.observe_tag(tag, reactions, &debug_info!(self));
+        }
+
+        if let Some(detector) = self.anomaly_detector.as_mut().filter(|_| self.anomaly_detection_enabled) {
+            let timings = std::mem::take(&mut ctx.insides.reaction_timings);
+            detector.observe_tag(tag, wave_start.elapsed(), timings, &debug_info!(self));
+        }
+
+        for export in &mut self.exports {
+            export.publish(tag);
+        }
 
         // cleanup tag-specific resources, eg clear port values
-        let ctx = CleanupCtx { tag };
+        let ctx = CleanupCtx {
+            tag,
+            displaced: std::mem::take(&mut self.displaced),
+        };
         // TODO measure performance of cleaning up all reactors w/ virtual dispatch like this.
         //   see also efforts in the C runtime to  avoid this
         for reactor in &mut self.reactors {
@@ -456,19 +1586,51 @@ impl<'x> SyncScheduler<'x> {
     }
 }
 
+/// The unsafe impl is safe if scheduler instances
+/// are only sent between threads like in [SyncScheduler::run_main]
+/// (their Rc internals are not copied).
+/// So long as the framework entirely controls the lifetime
+/// of SyncScheduler instances, this is enforceable.
+#[cfg(feature = "parallel-runtime")]
+unsafe impl Send for SyncScheduler<'_> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_start_time_alignment() {
+        let now = UNIX_EPOCH + Duration::from_millis(10_250);
+        assert_eq!(
+            StartTime::AlignTo(Duration::from_secs(1)).delay_from(now),
+            Duration::from_millis(750)
+        );
+        assert_eq!(StartTime::AlignTo(Duration::from_millis(250)).delay_from(now), Duration::ZERO);
+        assert_eq!(
+            StartTime::At(now + Duration::from_secs(2)).delay_from(now),
+            Duration::from_secs(2)
+        );
+        assert_eq!(StartTime::At(now - Duration::from_secs(2)).delay_from(now), Duration::ZERO);
+    }
+}
+
 #[cfg(feature = "parallel-runtime")]
 mod parallel_rt_impl {
     use rayon::prelude::*;
 
     use super::*;
-    use crate::scheduler::dependencies::Level;
 
-    pub(super) fn process_batch(ctx: &mut ReactionCtx<'_, '_>, reactors: &mut ReactorVec<'_>, batch: &Level) {
+    /// Execute the reactions of a level in parallel. Workers
+    /// take the reactions in the order of the iterator.
+    pub(super) fn process_batch(
+        ctx: &mut ReactionCtx<'_, '_>,
+        reactors: &mut ReactorVec<'_>,
+        batch: impl Iterator<Item = GlobalReactionId> + Send,
+    ) {
         let reactors_mut = UnsafeSharedPointer(reactors.raw.as_mut_ptr());
 
         ctx.insides.absorb(
             batch
-                .iter()
                 .par_bridge()
                 .fold_with(CloneableCtx(ctx.fork()), |CloneableCtx(mut ctx), reaction_id| {
                     // capture the newtype instead of capturing its field, which is not Send
@@ -0,0 +1,149 @@
+//! Wall-clock budget for the startup of a program, see [StartupBudget].
+
+use std::fmt::{Display, Formatter};
+
+use crate::*;
+
+/// Number of subtrees shown by the [Display] impl of [StartupReport].
+const SHOWN_SUBTREES: usize = 10;
+
+type StartupCallback = Box<dyn FnOnce(&StartupReport) + Send>;
+
+/// A deadline for the startup of the program, that is, the
+/// assembly of the reactor tree and the analysis of its
+/// dependency graph. Both are done before any reaction is
+/// executed, and may take long in programs with large banks
+/// or generated topologies.
+///
+/// The budget does not abort startup. If it is exceeded,
+/// a warning is logged as soon as it is noticed, so that a
+/// slow startup is not mistaken for a hang. Once startup is
+/// done, a [StartupReport] is logged and passed to the
+/// callback, if any.
+///
+/// Install it with [SchedulerOptions::startup_budget].
+pub struct StartupBudget {
+    budget: Duration,
+    callback: Option<StartupCallback>,
+}
+
+impl StartupBudget {
+    pub fn new(budget: Duration) -> Self {
+        Self { budget, callback: None }
+    }
+
+    /// Pass the report to the given callback if the
+    /// budget is exceeded, eg to fail a CI job.
+    pub fn on_exceeded(mut self, callback: impl FnOnce(&StartupReport) + Send + 'static) -> Self {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+    pub fn budget(&self) -> Duration {
+        self.budget
+    }
+}
+
+/// Where the time of startup was spent, produced
+/// when a [StartupBudget] is exceeded.
+#[derive(Clone, Debug)]
+pub struct StartupReport {
+    pub budget: Duration,
+    /// Time from the start of assembly to the
+    /// end of the dependency analysis.
+    pub elapsed: Duration,
+    /// Time spent assembling each reactor, including
+    /// its children, slowest first. Reactors are
+    /// identified by their path, eg `/child/`.
+    pub subtrees: Vec<(String, Duration)>,
+    /// Time spent in each pass of startup, in order:
+    /// `assembly`, then the passes of the analysis.
+    pub passes: Vec<(&'static str, Duration)>,
+}
+
+impl Display for StartupReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "startup took {} ms, over its budget of {} ms",
+            self.elapsed.as_millis(),
+            self.budget.as_millis()
+        )?;
+        for (pass, time) in &self.passes {
+            writeln!(f, "  pass {}: {} ms", pass, time.as_millis())?;
+        }
+        for (path, time) in self.subtrees.iter().take(SHOWN_SUBTREES) {
+            writeln!(f, "  subtree {}: {} ms", path, time.as_millis())?;
+        }
+        if self.subtrees.len() > SHOWN_SUBTREES {
+            writeln!(f, "  ... and {} faster subtrees", self.subtrees.len() - SHOWN_SUBTREES)?;
+        }
+        Ok(())
+    }
+}
+
+/// Measures startup against a [StartupBudget].
+pub(super) struct StartupProfiler {
+    budget: StartupBudget,
+    started: Instant,
+    subtrees: Vec<(ReactorId, Duration)>,
+    passes: Vec<(&'static str, Duration)>,
+    warned: bool,
+}
+
+impl StartupProfiler {
+    pub(super) fn new(budget: StartupBudget) -> Self {
+        Self {
+            budget,
+            started: Instant::now(),
+            subtrees: Vec::new(),
+            passes: Vec::new(),
+            warned: false,
+        }
+    }
+
+    /// Record that the given reactor and its children were
+    /// assembled, and warn if the budget is now exceeded.
+    pub(super) fn subtree_done(&mut self, reactor: ReactorId, elapsed: Duration, debug: &DebugInfoRegistry) {
+        self.subtrees.push((reactor, elapsed));
+        if !self.warned && self.started.elapsed() > self.budget.budget {
+            self.warned = true;
+            warn!(
+                "Startup exceeded its budget of {} ms while assembling {}, still going",
+                self.budget.budget.as_millis(),
+                debug.get_debug_info(reactor)
+            );
+        }
+    }
+
+    /// Record that the given pass, started at the given
+    /// instant, is done.
+    pub(super) fn pass_done(&mut self, pass: &'static str, since: Instant) {
+        self.passes.push((pass, since.elapsed()));
+    }
+
+    /// Report where the time was spent, if
+    /// the budget was exceeded.
+    pub(super) fn finish(self, debug: &DebugInfoRegistry) {
+        let elapsed = self.started.elapsed();
+        if elapsed <= self.budget.budget {
+            return;
+        }
+        let mut subtrees = self
+            .subtrees
+            .into_iter()
+            .map(|(id, time)| (debug.get_debug_info(id).to_string(), time))
+            .collect::<Vec<_>>();
+        subtrees.sort_by_key(|&(_, time)| std::cmp::Reverse(time));
+        let report = StartupReport {
+            budget: self.budget.budget,
+            elapsed,
+            subtrees,
+            passes: self.passes,
+        };
+        warn!("{}", report);
+        if let Some(callback) = self.budget.callback {
+            callback(&report);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Detection of reactions that keep the logical time from advancing.
+
+use std::collections::HashMap;
+
+use super::DebugInfoProvider;
+use crate::*;
+
+/// Bounds the number of consecutive microsteps at a same time
+/// point. Reactions that schedule logical actions without delay
+/// inject work at the next microstep. If they keep doing so,
+/// logical time never advances, and events at later time points
+/// starve. See [SchedulerOptions::max_microsteps](crate::SchedulerOptions::max_microsteps).
+pub(super) struct MicrostepGuard {
+    max_microsteps: MicroStep,
+    /// Time point of the current run of microsteps.
+    time_point: Duration,
+    /// Number of events each reaction has scheduled at
+    /// the current time point.
+    injections: HashMap<GlobalReactionId, u32>,
+}
+
+impl MicrostepGuard {
+    pub(super) fn new(max_microsteps: u32) -> Self {
+        Self {
+            max_microsteps: MicroStep::new(max_microsteps),
+            time_point: Duration::ZERO,
+            injections: HashMap::new(),
+        }
+    }
+
+    /// Record the reactions that scheduled an event at the
+    /// time point of the given tag, while processing that tag.
+    pub(super) fn record(&mut self, tag: EventTag, injectors: impl IntoIterator<Item = GlobalReactionId>) {
+        if tag.offset_from_t0 != self.time_point {
+            self.time_point = tag.offset_from_t0;
+            self.injections.clear();
+        }
+        for reaction in injectors {
+            *self.injections.entry(reaction).or_default() += 1;
+        }
+    }
+
+    /// Whether the tag is beyond the limit.
+    pub(super) fn is_exceeded(&self, tag: EventTag) -> bool {
+        tag.microstep > self.max_microsteps
+    }
+
+    /// Describes the reactions that kept scheduling events
+    /// at the time point of the given tag, from the one that
+    /// did it most often.
+    pub(super) fn diagnostic(&self, tag: EventTag, debug: &DebugInfoProvider<'_>) -> String {
+        let mut injections = if tag.offset_from_t0 == self.time_point {
+            self.injections.iter().collect::<Vec<_>>()
+        } else {
+            Vec::new()
+        };
+        injections.sort_by(|(r1, n1), (r2, n2)| n2.cmp(n1).then(r1.cmp(r2)));
+
+        let mut msg = format!(
+            "Logical time is stuck: exceeded {} microsteps at {}",
+            self.max_microsteps, tag
+        );
+        if !injections.is_empty() {
+            let list = injections
+                .into_iter()
+                .map(|(r, n)| format!("{} ({} times)", debug.display_reaction(*r), n))
+                .collect::<Vec<_>>();
+            msg += "; reactions that keep scheduling events at this time: ";
+            msg += &list.join(", ");
+        }
+        msg
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tag(ms: u64, microstep: u32) -> EventTag {
+        EventTag::offset(Duration::from_millis(ms), microstep)
+    }
+
+    #[test]
+    fn test_injections_are_reset_when_time_advances() {
+        let r0 = GlobalReactionId::new(ReactorId::new(0), LocalReactionId::new(0));
+        let r1 = GlobalReactionId::new(ReactorId::new(0), LocalReactionId::new(1));
+
+        let mut guard = MicrostepGuard::new(2);
+        guard.record(tag(0, 0), vec![r0]);
+        guard.record(tag(5, 0), vec![r1]);
+        guard.record(tag(5, 1), vec![r0, r1]);
+        assert!(!guard.is_exceeded(tag(5, 2)));
+        assert!(guard.is_exceeded(tag(5, 3)));
+        assert_eq!(guard.injections.get(&r0), Some(&1));
+        assert_eq!(guard.injections.get(&r1), Some(&2));
+    }
+}
@@ -0,0 +1,206 @@
+//! Storage for the events produced by timers.
+
+use std::collections::VecDeque;
+
+use super::Event;
+use crate::*;
+
+/// Number of bits of the tick consumed by each level.
+const SLOT_BITS: usize = 6;
+/// Number of slots of each level.
+const SLOTS: usize = 1 << SLOT_BITS;
+/// Number of levels. With 1 ms ticks, the wheel spans
+/// 64^6 ms, that is about two years.
+const LEVELS: usize = 6;
+/// Duration of a tick in nanoseconds.
+const TICK_NANOS: u128 = 1_000_000;
+
+/// A hierarchical timer wheel, which stores the events
+/// produced by timers. Programs with many periodic timers
+/// produce many events at distinct tags, which makes insertion
+/// into the sorted [EventQueue](super::EventQueue) costly.
+/// The wheel inserts events in constant time instead.
+///
+/// Logical time is divided into ticks. Level `l` has 64 slots
+/// that each span 64^l ticks. An event is stored in the lowest
+/// level whose slot can tell its tick apart from the current
+/// tick. When the current tick advances to a slot of a higher
+/// level, the events of that slot cascade to lower levels.
+/// Events of the current tick are kept sorted by tag in
+/// [Self::ready], where events with the same tag are coalesced.
+///
+/// The current tick only ever advances to the tick of the
+/// earliest event, so events inserted later at an earlier tick
+/// (eg by a reaction executing before that) go to [Self::ready].
+pub(super) struct TimerWheel<'x> {
+    /// Current tick. All slots contain events of a later tick.
+    now: u64,
+    levels: Vec<Vec<Vec<Event<'x>>>>,
+    /// Bitset of the non-empty slots of each level.
+    occupied: [u64; LEVELS],
+    /// Events that are too far in the future for the wheel.
+    overflow: Vec<Event<'x>>,
+    /// Events at or before the current tick, sorted by tag.
+    ready: VecDeque<Event<'x>>,
+    /// Number of events in the slots and in the overflow list.
+    pending: usize,
+}
+
+impl<'x> TimerWheel<'x> {
+    pub(super) fn insert(&mut self, evt: Event<'x>) {
+        let tick = tick_of(evt.tag);
+        if tick <= self.now {
+            self.insert_ready(evt);
+            return;
+        }
+
+        self.pending += 1;
+        let level = (63 - (tick ^ self.now).leading_zeros() as usize) / SLOT_BITS;
+        if level >= LEVELS {
+            self.overflow.push(evt);
+            return;
+        }
+        let slot = slot_index(tick, level);
+        self.levels[level][slot].push(evt);
+        self.occupied[level] |= 1 << slot;
+    }
+
+    fn insert_ready(&mut self, evt: Event<'x>) {
+        match self.ready.binary_search_by_key(&evt.tag, |e| e.tag) {
+            Ok(idx) => self.ready[idx].absorb(evt),
+            Err(idx) => self.ready.insert(idx, evt),
+        }
+    }
+
+    /// Returns the tag of the earliest event.
+    pub(super) fn peek_tag(&mut self) -> Option<EventTag> {
+        self.advance();
+        self.ready.front().map(|e| e.tag)
+    }
+
+    /// Removes and returns the earliest event.
+    pub(super) fn take_earliest(&mut self) -> Option<Event<'x>> {
+        self.advance();
+        self.ready.pop_front()
+    }
+
+    /// Number of pending events. Events of a same tag are
+    /// only counted once if they have been coalesced.
+    pub(super) fn len(&self) -> usize {
+        self.ready.len() + self.pending
+    }
+
+    /// Add the tags of the pending events to the vector, in
+    /// no particular order, and possibly with duplicates.
+    #[cfg(feature = "introspection")]
+    pub(super) fn collect_tags(&self, tags: &mut Vec<EventTag>) {
+        let slotted = self.levels.iter().flatten().flatten();
+        tags.extend(self.ready.iter().chain(slotted).chain(&self.overflow).map(|e| e.tag))
+    }
+
+    /// Advance the current tick until some events are ready.
+    fn advance(&mut self) {
+        while self.ready.is_empty() && self.pending > 0 {
+            let events = match self.next_occupied_slot() {
+                Some((level, slot)) => {
+                    // move to the start of the slot
+                    let shift = level * SLOT_BITS;
+                    let above = shift + SLOT_BITS;
+                    self.now = (self.now >> above << above) | ((slot as u64) << shift);
+                    self.occupied[level] &= !(1 << slot);
+                    std::mem::take(&mut self.levels[level][slot])
+                }
+                None => {
+                    // only far away events remain
+                    self.now = self.overflow.iter().map(|e| tick_of(e.tag)).min().unwrap();
+                    std::mem::take(&mut self.overflow)
+                }
+            };
+            self.pending -= events.len();
+            for evt in events {
+                self.insert(evt);
+            }
+        }
+    }
+
+    /// Find the earliest non-empty slot.
+    fn next_occupied_slot(&self) -> Option<(usize, usize)> {
+        (0..LEVELS).find_map(|level| {
+            let current = slot_index(self.now, level);
+            // slots after the current one
+            let later = self.occupied[level] & (!0u64 << current << 1);
+            (later != 0).then(|| (level, later.trailing_zeros() as usize))
+        })
+    }
+}
+
+impl Default for TimerWheel<'_> {
+    fn default() -> Self {
+        Self {
+            now: 0,
+            levels: (0..LEVELS).map(|_| (0..SLOTS).map(|_| Vec::new()).collect()).collect(),
+            occupied: [0; LEVELS],
+            overflow: Vec::new(),
+            ready: VecDeque::new(),
+            pending: 0,
+        }
+    }
+}
+
+fn tick_of(tag: EventTag) -> u64 {
+    (tag.offset_from_t0.as_nanos() / TICK_NANOS) as u64
+}
+
+fn slot_index(tick: u64, level: usize) -> usize {
+    ((tick >> (level * SLOT_BITS)) as usize) & (SLOTS - 1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tag(nanos: u64, microstep: crate::time::MS) -> EventTag {
+        EventTag::offset(Duration::from_nanos(nanos), microstep)
+    }
+
+    fn drain(wheel: &mut TimerWheel<'_>) -> Vec<EventTag> {
+        std::iter::from_fn(|| wheel.take_earliest()).map(|e| e.tag).collect()
+    }
+
+    #[test]
+    fn test_events_come_out_sorted() {
+        let mut wheel = TimerWheel::default();
+        // spans all levels and the overflow list
+        let mut tags = (0..2000u64)
+            .map(|i| tag(i.wrapping_mul(0x9e37_79b9_7f4a_7c15) % (1 << 50), (i % 3) as _))
+            .collect::<Vec<_>>();
+        for t in &tags {
+            wheel.insert(Event::terminate_at(*t));
+        }
+        assert_eq!(wheel.len(), tags.len());
+
+        tags.sort();
+        tags.dedup();
+        assert_eq!(drain(&mut wheel), tags);
+        assert_eq!(wheel.len(), 0);
+    }
+
+    #[test]
+    fn test_same_tag_is_coalesced() {
+        let mut wheel = TimerWheel::default();
+        let ms = 1_000_000;
+        wheel.insert(Event::terminate_at(tag(5 * ms, 0)));
+        wheel.insert(Event::terminate_at(tag(100 * ms, 0)));
+        wheel.insert(Event::terminate_at(tag(100 * ms, 0)));
+        wheel.insert(Event::terminate_at(tag(100 * ms + 1, 0)));
+
+        assert_eq!(wheel.take_earliest().map(|e| e.tag), Some(tag(5 * ms, 0)));
+        // inserted after the wheel has advanced, but before the next event
+        wheel.insert(Event::terminate_at(tag(50 * ms, 0)));
+        assert_eq!(wheel.peek_tag(), Some(tag(50 * ms, 0)));
+        assert_eq!(
+            drain(&mut wheel),
+            vec![tag(50 * ms, 0), tag(100 * ms, 0), tag(100 * ms + 1, 0)]
+        );
+    }
+}
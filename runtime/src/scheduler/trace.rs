@@ -0,0 +1,338 @@
+//! Sampled traces of the execution of reactions.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::triggers::TriggerId;
+use crate::*;
+
+/// Execution of a reaction, as recorded by a [TraceSampler].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReactionExecution {
+    pub tag: EventTag,
+    pub reaction: GlobalReactionId,
+    /// Wall-clock time taken by the reaction.
+    pub elapsed: Duration,
+}
+
+/// The triggers that were present at a tag, as recorded by
+/// a [TraceSampler]. Together with the reactions executed at
+/// that tag, this describes the inputs of the whole wave.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PresentTriggers {
+    pub tag: EventTag,
+    /// The actions, timers and ports that were present, including
+    /// [TriggerId::STARTUP] and [TriggerId::SHUTDOWN]. Sorted.
+    pub triggers: Vec<TriggerId>,
+}
+
+/// Which executions a [TraceSampler] records.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TraceSampling {
+    /// Record all the reactions executed at every Nth processed
+    /// tag, starting with the first one. Reactions are only timed
+    /// at those tags, so this has little overhead for large N.
+    /// When the trace is full, the oldest records are dropped.
+    EveryNthTag(u32),
+    /// Keep a uniform random sample of all the reaction
+    /// executions of the run (reservoir sampling). All
+    /// reactions are timed.
+    Reservoir,
+}
+
+/// Names of the components that a trace refers to, so that
+/// tools can render it without the program that produced it.
+/// Each list is sorted by id.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SymbolTable {
+    /// Path of each reactor, eg `/main/child/`.
+    pub reactors: Vec<(ReactorId, String)>,
+    pub reactions: Vec<(GlobalReactionId, String)>,
+    pub triggers: Vec<(TriggerId, String)>,
+}
+
+impl SymbolTable {
+    /// Build the table of the given reactions and triggers,
+    /// and of the reactors that contain them.
+    pub(super) fn new(
+        debug: &DebugInfoRegistry,
+        reactions: impl IntoIterator<Item = GlobalReactionId>,
+        triggers: impl IntoIterator<Item = TriggerId>,
+    ) -> Self {
+        fn sorted<K: Ord + Copy>(ids: impl IntoIterator<Item = K>, name: impl Fn(K) -> String) -> Vec<(K, String)> {
+            let mut ids = ids.into_iter().collect::<Vec<_>>();
+            ids.sort();
+            ids.dedup();
+            ids.into_iter().map(|id| (id, name(id))).collect()
+        }
+        let reactions = sorted(reactions, |r| debug.fmt_reaction(r).to_string());
+        let triggers = sorted(triggers, |t| debug.fmt_component(t).to_string());
+        let reactors = reactions
+            .iter()
+            .map(|(r, _)| r.0.container())
+            .chain(triggers.iter().filter_map(|(t, _)| debug.get_trigger_container(*t)))
+            .collect::<Vec<_>>();
+        let reactors = sorted(reactors, |r| debug.get_debug_info(r).to_string());
+        Self { reactors, reactions, triggers }
+    }
+
+    pub fn reactor(&self, id: ReactorId) -> Option<&str> {
+        lookup(&self.reactors, id)
+    }
+
+    pub fn reaction(&self, id: GlobalReactionId) -> Option<&str> {
+        lookup(&self.reactions, id)
+    }
+
+    pub fn trigger(&self, id: TriggerId) -> Option<&str> {
+        lookup(&self.triggers, id)
+    }
+}
+
+fn lookup<K: Ord>(table: &[(K, String)], id: K) -> Option<&str> {
+    let ix = table.binary_search_by(|(k, _)| k.cmp(&id)).ok()?;
+    Some(&table[ix].1)
+}
+
+/// The trace produced by a [TraceSampler] at shutdown.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SampledTrace {
+    /// Number of tags processed.
+    pub tags: u64,
+    /// Number of tags whose executions were candidates for
+    /// the sample.
+    pub sampled_tags: u64,
+    /// Number of reaction executions that were candidates for
+    /// the sample, ie all executions for [TraceSampling::Reservoir],
+    /// or those of the sampled tags for [TraceSampling::EveryNthTag].
+    pub executions: u64,
+    /// The sampled executions, ordered by tag.
+    pub records: Vec<ReactionExecution>,
+    /// The triggers present at each tag of [Self::records],
+    /// ordered by tag.
+    pub present: Vec<PresentTriggers>,
+    /// Names of the reactions and triggers of the records.
+    pub symbols: SymbolTable,
+}
+
+impl SampledTrace {
+    /// Whether the trace records all the reactions executed
+    /// at every tag of the run, ie it was sampled at every tag,
+    /// and no record was dropped.
+    pub fn is_complete(&self) -> bool {
+        self.sampled_tags == self.tags && self.executions == self.records.len() as u64
+    }
+}
+
+type TraceCallback = Box<dyn FnOnce(&SampledTrace) + Send>;
+
+/// Records a sample of the reaction executions of a program,
+/// in bounded memory, so that long runs can keep a lightweight
+/// trace for performance analysis. Install it with
+/// [SchedulerOptions::trace].
+pub struct TraceSampler {
+    sampling: TraceSampling,
+    /// Max number of records.
+    capacity: usize,
+    records: VecDeque<ReactionExecution>,
+    /// Present triggers of the tags that have records, with
+    /// the number of those records.
+    present: HashMap<EventTag, (usize, Vec<TriggerId>)>,
+    tags: u64,
+    sampled_tags: u64,
+    executions: u64,
+    /// State of the xorshift generator used for reservoir sampling.
+    rng: u64,
+    callback: Option<TraceCallback>,
+}
+
+impl TraceSampler {
+    /// Create a sampler that keeps at most `capacity` records,
+    /// and calls the given function with the trace when the
+    /// program shuts down.
+    pub fn new(sampling: TraceSampling, capacity: usize, callback: impl FnOnce(&SampledTrace) + Send + 'static) -> Self {
+        Self {
+            sampling,
+            capacity,
+            records: VecDeque::with_capacity(capacity.min(1024)),
+            present: HashMap::new(),
+            tags: 0,
+            sampled_tags: 0,
+            executions: 0,
+            rng: 0x9e37_79b9_7f4a_7c15,
+            callback: Some(Box::new(callback)),
+        }
+    }
+
+    /// Whether the reactions of the next tag should be timed.
+    pub(super) fn samples_next_tag(&self) -> bool {
+        match self.sampling {
+            TraceSampling::EveryNthTag(n) => self.tags % u64::from(n.max(1)) == 0,
+            TraceSampling::Reservoir => true,
+        }
+    }
+
+    /// Record the reactions executed at a tag, with their
+    /// execution time, and the triggers that were present.
+    pub(super) fn observe_tag(
+        &mut self,
+        tag: EventTag,
+        timings: &[(GlobalReactionId, Duration)],
+        present: impl IntoIterator<Item = TriggerId>,
+    ) {
+        let sampled = self.samples_next_tag();
+        self.tags += 1;
+        if !sampled {
+            return;
+        }
+        self.sampled_tags += 1;
+        let mut present = present.into_iter().collect::<Vec<_>>();
+        present.sort();
+        present.dedup();
+
+        for &(reaction, elapsed) in timings {
+            let record = ReactionExecution { tag, reaction, elapsed };
+            self.executions += 1;
+            let evicted = match self.sampling {
+                _ if self.capacity == 0 => continue,
+                TraceSampling::EveryNthTag(_) => {
+                    let evicted = if self.records.len() == self.capacity {
+                        self.records.pop_front()
+                    } else {
+                        None
+                    };
+                    self.records.push_back(record);
+                    evicted
+                }
+                TraceSampling::Reservoir => {
+                    if self.records.len() < self.capacity {
+                        self.records.push_back(record);
+                        None
+                    } else {
+                        let ix = self.next_random() % self.executions;
+                        match self.records.get_mut(ix as usize) {
+                            Some(slot) => Some(std::mem::replace(slot, record)),
+                            None => continue,
+                        }
+                    }
+                }
+            };
+            self.present.entry(tag).or_insert_with(|| (0, present.clone())).0 += 1;
+            if let Some(evicted) = evicted {
+                self.release_present(evicted.tag);
+            }
+        }
+    }
+
+    /// Forget the present triggers of the tag once
+    /// it has no records anymore.
+    fn release_present(&mut self, tag: EventTag) {
+        if let Some((count, _)) = self.present.get_mut(&tag) {
+            *count -= 1;
+            if *count == 0 {
+                self.present.remove(&tag);
+            }
+        }
+    }
+
+    fn next_random(&mut self) -> u64 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng
+    }
+
+    pub(super) fn trace(&self) -> SampledTrace {
+        let mut records = self.records.iter().copied().collect::<Vec<_>>();
+        records.sort_by_key(|r| r.tag);
+        let mut present = self
+            .present
+            .iter()
+            .map(|(tag, (_, triggers))| PresentTriggers { tag: *tag, triggers: triggers.clone() })
+            .collect::<Vec<_>>();
+        present.sort_by_key(|p| p.tag);
+        SampledTrace {
+            tags: self.tags,
+            sampled_tags: self.sampled_tags,
+            executions: self.executions,
+            records,
+            present,
+            symbols: Default::default(),
+        }
+    }
+
+    /// Pass the trace to the callback, with the
+    /// names of the components it refers to.
+    pub(super) fn finish(&mut self, debug: &DebugInfoRegistry) {
+        if let Some(callback) = self.callback.take() {
+            let mut trace = self.trace();
+            let reactions = trace.records.iter().map(|r| r.reaction);
+            let triggers = trace.present.iter().flat_map(|p| p.triggers.iter().copied());
+            trace.symbols = SymbolTable::new(debug, reactions, triggers);
+            callback(&trace)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run(sampling: TraceSampling, capacity: usize, tags: u32) -> SampledTrace {
+        let reaction = GlobalReactionId::new(ReactorId::new(0), LocalReactionId::new(0));
+        let mut sampler = TraceSampler::new(sampling, capacity, |_| {});
+        for i in 0..tags {
+            let tag = EventTag::offset(Duration::from_millis(i.into()), 0);
+            let timings = if sampler.samples_next_tag() {
+                vec![(reaction, Duration::from_micros(1)); 2]
+            } else {
+                vec![]
+            };
+            // the tag number identifies a present trigger
+            let trigger = TriggerId::new((i + 2) as _);
+            let present = [trigger, TriggerId::SHUTDOWN, trigger];
+            sampler.observe_tag(tag, &timings, present);
+        }
+        sampler.trace()
+    }
+
+    /// Present triggers are kept for exactly the tags of the records.
+    fn check_present(trace: &SampledTrace) {
+        let mut tags = trace.records.iter().map(|r| r.tag).collect::<Vec<_>>();
+        tags.dedup();
+        assert_eq!(trace.present.iter().map(|p| p.tag).collect::<Vec<_>>(), tags);
+        for p in &trace.present {
+            let i = p.tag.offset_from_t0.as_millis();
+            let expected = vec![TriggerId::SHUTDOWN, TriggerId::new((i + 2) as _)];
+            assert_eq!(p.triggers, expected);
+        }
+    }
+
+    #[test]
+    fn test_every_nth_tag_keeps_latest_records() {
+        let trace = run(TraceSampling::EveryNthTag(10), 4, 100);
+        assert_eq!(trace.tags, 100);
+        assert_eq!(trace.executions, 20);
+        let tags = trace
+            .records
+            .iter()
+            .map(|r| r.tag.offset_from_t0.as_millis())
+            .collect::<Vec<_>>();
+        assert_eq!(tags, vec![80, 80, 90, 90]);
+        check_present(&trace);
+    }
+
+    #[test]
+    fn test_reservoir_is_bounded() {
+        let trace = run(TraceSampling::Reservoir, 50, 1000);
+        assert_eq!(trace.executions, 2000);
+        assert_eq!(trace.records.len(), 50);
+        assert!(trace.records.windows(2).all(|w| w[0].tag <= w[1].tag));
+        // the sample is not just the first executions
+        assert!(trace.records.last().unwrap().tag.offset_from_t0 > Duration::from_millis(25));
+        check_present(&trace);
+    }
+}
@@ -0,0 +1,114 @@
+//! Checks of the structure of a program, which are run
+//! after assembly by [SyncScheduler::validate_with](crate::SyncScheduler::validate_with).
+
+use super::dependencies::DepGraph;
+pub use super::dependencies::EdgeKind;
+use crate::assembly::*;
+use crate::*;
+
+/// A component or a reaction of a [ProgramGraph].
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum ProgramNode {
+    /// A port, action or timer, or the startup and shutdown triggers.
+    Trigger(TriggerId),
+    Reaction(GlobalReactionId),
+}
+
+impl ProgramNode {
+    pub(crate) fn path(self, debug: &DebugInfoRegistry) -> String {
+        match self {
+            ProgramNode::Trigger(TriggerId::STARTUP) => "startup".to_string(),
+            ProgramNode::Trigger(TriggerId::SHUTDOWN) => "shutdown".to_string(),
+            ProgramNode::Trigger(id) => debug.fmt_component(id).to_string(),
+            ProgramNode::Reaction(id) => debug.fmt_reaction(id).to_string(),
+        }
+    }
+}
+
+/// Read-only view of the dependency graph of an assembled
+/// program, which is inspected by [ValidationRule]s.
+pub struct ProgramGraph<'a> {
+    graph: &'a DepGraph,
+    debug: &'a DebugInfoRegistry,
+}
+
+impl<'a> ProgramGraph<'a> {
+    pub(super) fn new(graph: &'a DepGraph, debug: &'a DebugInfoRegistry) -> Self {
+        Self { graph, debug }
+    }
+
+    /// All the components and reactions of the program.
+    pub fn nodes(&self) -> impl Iterator<Item = ProgramNode> + '_ {
+        self.graph.nodes()
+    }
+
+    /// All the dependencies of the program, as `(from, to, kind)`.
+    /// See [EdgeKind] for the direction of each kind of edge.
+    pub fn dependencies(&self) -> impl Iterator<Item = (ProgramNode, ProgramNode, EdgeKind)> + '_ {
+        self.graph.edges()
+    }
+
+    /// Path of the node, eg `/main/child.out`.
+    pub fn path(&self, node: ProgramNode) -> String {
+        node.path(self.debug)
+    }
+
+    /// The reactor that contains the node. This is None
+    /// for the startup and shutdown triggers.
+    pub fn container(&self, node: ProgramNode) -> Option<ReactorId> {
+        match node {
+            ProgramNode::Trigger(id) => self.debug.get_trigger_container(id),
+            ProgramNode::Reaction(id) => Some(id.0.container()),
+        }
+    }
+}
+
+/// A check of the structure of a program, which reports its
+/// findings as errors or warnings of the report. Project-specific
+/// rules, like naming conventions or connections that are
+/// forbidden between subsystems, can be passed to
+/// [SyncScheduler::validate_with](crate::SyncScheduler::validate_with).
+/// Closures with the signature of [Self::check] are rules too.
+pub trait ValidationRule {
+    fn check(&self, program: &ProgramGraph<'_>, report: &mut ValidationReport);
+}
+
+impl<F: Fn(&ProgramGraph<'_>, &mut ValidationReport)> ValidationRule for F {
+    fn check(&self, program: &ProgramGraph<'_>, report: &mut ValidationReport) {
+        self(program, report)
+    }
+}
+
+/// Rules checked for every program. Mismatched widths are
+/// reported when binding multiports and banks.
+pub(super) const BUILTIN_RULES: &[&dyn ValidationRule] = &[&NoCycles, &NoUntriggeredReaction, &SingleWriter];
+
+/// Instantaneous dependencies must not be cyclic.
+struct NoCycles;
+
+impl ValidationRule for NoCycles {
+    fn check(&self, program: &ProgramGraph<'_>, report: &mut ValidationReport) {
+        report.errors.extend(program.graph.cycle_diagnostics(program.debug));
+    }
+}
+
+/// Reactions without trigger are never executed.
+struct NoUntriggeredReaction;
+
+impl ValidationRule for NoUntriggeredReaction {
+    fn check(&self, program: &ProgramGraph<'_>, report: &mut ValidationReport) {
+        report
+            .warnings
+            .extend(program.graph.untriggered_reaction_diagnostics(program.debug));
+    }
+}
+
+/// A port is set by the reactions of a single reactor,
+/// or bound to a single upstream port.
+struct SingleWriter;
+
+impl ValidationRule for SingleWriter {
+    fn check(&self, program: &ProgramGraph<'_>, report: &mut ValidationReport) {
+        report.errors.extend(program.graph.multiple_writer_diagnostics(program.debug));
+    }
+}
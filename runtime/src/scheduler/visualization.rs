@@ -0,0 +1,291 @@
+//! Live visualization of a running program, served over HTTP.
+//! This module is only available with feature `visualization`.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use super::DebugInfoProvider;
+use crate::triggers::TriggerId;
+use crate::util::json_string;
+use crate::*;
+
+/// Min delay between two samples of the execution.
+const SAMPLE_PERIOD: Duration = Duration::from_millis(100);
+
+/// Timing of a reaction, as shown by the visualization.
+#[derive(Debug, Default)]
+struct ReactionStats {
+    executions: u64,
+    last: Duration,
+    max: Duration,
+}
+
+/// State of the program at the latest sampled tag.
+#[derive(Debug, Default)]
+struct LiveState {
+    tag: Option<EventTag>,
+    queue_depth: usize,
+    /// Names of the triggers that were present at the tag.
+    present: Vec<String>,
+    /// Timings of the reactions, by name, over all sampled tags.
+    reactions: HashMap<String, ReactionStats>,
+}
+
+/// What the visualization server shows, shared between
+/// the scheduler and the server thread.
+#[derive(Debug)]
+pub(super) struct LiveView {
+    /// The dependency graph, see [DepGraph::format_json](super::dependencies::DepGraph::format_json).
+    graph_json: String,
+    state: Mutex<LiveState>,
+    last_sample: Mutex<Option<Instant>>,
+}
+
+impl LiveView {
+    pub(super) fn new(graph_json: String) -> Self {
+        Self {
+            graph_json,
+            state: Default::default(),
+            last_sample: Default::default(),
+        }
+    }
+
+    /// Whether the next tag should be sampled. Tags are sampled
+    /// at most every [SAMPLE_PERIOD], to keep the overhead low.
+    pub(super) fn samples_next_tag(&self) -> bool {
+        let last = self.last_sample.lock().unwrap();
+        last.map_or(true, |t| t.elapsed() >= SAMPLE_PERIOD)
+    }
+
+    /// Record a sampled tag.
+    pub(super) fn observe_tag(
+        &self,
+        tag: EventTag,
+        queue_depth: usize,
+        timings: &[(GlobalReactionId, Duration)],
+        present: impl IntoIterator<Item = TriggerId>,
+        debug: &DebugInfoProvider<'_>,
+    ) {
+        *self.last_sample.lock().unwrap() = Some(Instant::now());
+        let mut present = present.into_iter().collect::<Vec<_>>();
+        present.sort();
+        present.dedup();
+
+        let mut state = self.state.lock().unwrap();
+        state.tag = Some(tag);
+        state.queue_depth = queue_depth;
+        state.present = present
+            .into_iter()
+            .map(|t| match t {
+                // as in the graph
+                TriggerId::STARTUP => "startup".to_string(),
+                TriggerId::SHUTDOWN => "shutdown".to_string(),
+                t => debug.id_registry.fmt_component(t).to_string(),
+            })
+            .collect();
+        for &(reaction, elapsed) in timings {
+            let stats = state
+                .reactions
+                .entry(debug.display_reaction(reaction).to_string())
+                .or_default();
+            stats.executions += 1;
+            stats.last = elapsed;
+            stats.max = stats.max.max(elapsed);
+        }
+    }
+
+    /// Format the latest sample as JSON.
+    fn render_live(&self) -> String {
+        let state = self.state.lock().unwrap();
+        let mut json = String::new();
+        let tag = state.tag.map_or("null".to_string(), |t| json_string(&t.to_string()));
+        write!(json, "{{\"tag\":{},\"queue_depth\":{},\"present\":[", tag, state.queue_depth).unwrap();
+        join_to!(&mut json, state.present.iter(), ",", "", "", |t| json_string(t)).unwrap();
+        json += "],\"reactions\":{";
+        let mut reactions = state.reactions.iter().collect::<Vec<_>>();
+        reactions.sort_by_key(|(name, _)| *name);
+        join_to!(&mut json, reactions.iter(), ",", "", "", |(name, stats)| format!(
+            "{}:{{\"executions\":{},\"last_us\":{},\"max_us\":{}}}",
+            json_string(name),
+            stats.executions,
+            stats.last.as_micros(),
+            stats.max.as_micros()
+        ))
+        .unwrap();
+        json += "}}";
+        json
+    }
+}
+
+/// Start a thread that serves the visualization until
+/// the scheduler terminates.
+pub(super) fn spawn_server(addr: SocketAddr, view: Arc<LiveView>, was_terminated: Arc<AtomicBool>) -> JoinHandle<()> {
+    /// Granularity at which the server checks for termination.
+    const POLL_PERIOD: Duration = Duration::from_millis(50);
+
+    std::thread::spawn(move || {
+        let listener = match TcpListener::bind(addr).and_then(|l| l.set_nonblocking(true).map(|_| l)) {
+            Ok(listener) => listener,
+            Err(e) => {
+                warn!("Cannot serve visualization on {}: {}", addr, e);
+                return;
+            }
+        };
+        info!("Serving visualization on http://{}", addr);
+        while !was_terminated.load(Ordering::SeqCst) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    if let Err(e) = serve(stream, &view) {
+                        warn!("Error while serving visualization: {}", e);
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => std::thread::sleep(POLL_PERIOD),
+                Err(e) => warn!("Error while serving visualization: {}", e),
+            }
+        }
+    })
+}
+
+/// Response to a request for the given path: status, content type and body.
+fn route(path: &str, view: &LiveView) -> (&'static str, &'static str, String) {
+    match path {
+        "/" | "/index.html" => ("200 OK", "text/html; charset=utf-8", INDEX_HTML.to_string()),
+        "/graph.json" => ("200 OK", "application/json", view.graph_json.clone()),
+        "/live.json" => ("200 OK", "application/json", view.render_live()),
+        _ => ("404 Not Found", "text/plain", "not found".to_string()),
+    }
+}
+
+fn serve(mut stream: TcpStream, view: &LiveView) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    // eg "GET /live.json HTTP/1.1"
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+
+    let (status, content_type, body) = route(path, view);
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// The page of the visualization. It lays out the dependency
+/// graph in columns by depth, and polls the live state to
+/// highlight present triggers and show reaction timings.
+const INDEX_HTML: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Reactor program</title>
+<style>
+  body { font-family: sans-serif; margin: 1em; }
+  #status { margin-bottom: 1em; }
+  svg text { font-size: 11px; }
+  .node rect { fill: #eef; stroke: #669; }
+  .node.Reaction rect { fill: #efe; stroke: #696; }
+  .node.present rect { fill: #fc6; }
+  line { stroke: #999; }
+  line.Use, line.Delayed { stroke-dasharray: 4 2; }
+</style>
+</head>
+<body>
+<div id="status">Loading...</div>
+<svg id="graph"></svg>
+<script>
+const W = 220, H = 28;
+let nodes = [];
+
+function layout(graph) {
+  const depth = graph.nodes.map(() => 0);
+  // longest path layering, the graph may have delayed cycles
+  for (let i = 0; i < graph.nodes.length; i++) {
+    for (const e of graph.edges) {
+      if (e.kind !== "Delayed") depth[e.to] = Math.max(depth[e.to], Math.min(depth[e.from] + 1, graph.nodes.length));
+    }
+  }
+  const rows = {};
+  const svg = document.getElementById("graph");
+  nodes = graph.nodes.map((n, i) => {
+    const row = rows[depth[i]] = (rows[depth[i]] || 0) + 1;
+    return Object.assign({ x: 10 + depth[i] * (W + 40), y: 10 + (row - 1) * (H + 12) }, n);
+  });
+  svg.setAttribute("width", Math.max(...nodes.map(n => n.x)) + W + 10);
+  svg.setAttribute("height", Math.max(...nodes.map(n => n.y)) + H + 10);
+  let html = "";
+  for (const e of graph.edges) {
+    const a = nodes[e.from], b = nodes[e.to];
+    html += `<line class="${e.kind}" x1="${a.x + W}" y1="${a.y + H / 2}" x2="${b.x}" y2="${b.y + H / 2}"/>`;
+  }
+  nodes.forEach((n, i) => {
+    html += `<g class="node ${n.kind}" id="n${i}"><title></title><rect x="${n.x}" y="${n.y}" width="${W}" height="${H}" rx="4"/>` +
+      `<text x="${n.x + 6}" y="${n.y + 18}"></text></g>`;
+  });
+  svg.innerHTML = html;
+  nodes.forEach((n, i) => {
+    const g = document.getElementById("n" + i);
+    g.querySelector("text").textContent = n.label;
+    g.querySelector("title").textContent = n.kind + " " + n.label;
+  });
+}
+
+async function refresh() {
+  try {
+    const live = await (await fetch("/live.json")).json();
+    document.getElementById("status").textContent =
+      `Tag ${live.tag ?? "-"}, ${live.queue_depth} pending events`;
+    const present = new Set(live.present);
+    nodes.forEach((n, i) => {
+      const g = document.getElementById("n" + i);
+      g.classList.toggle("present", present.has(n.label));
+      const stats = live.reactions[n.label];
+      if (stats) {
+        g.querySelector("title").textContent =
+          `${n.label}: ${stats.executions} sampled executions, last ${stats.last_us} µs, max ${stats.max_us} µs`;
+      }
+    });
+  } catch (e) {
+    document.getElementById("status").textContent = "Program terminated";
+    return;
+  }
+  setTimeout(refresh, 500);
+}
+
+fetch("/graph.json").then(r => r.json()).then(graph => { layout(graph); refresh(); });
+</script>
+</body>
+</html>
+"##;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_json_string_is_escaped() {
+        assert_eq!(json_string("main/a\"b\\c\n"), r#""main/a\"b\\c\u000a""#);
+    }
+
+    #[test]
+    fn test_routes() {
+        let view = LiveView::new("{\"nodes\":[],\"edges\":[]}".to_string());
+        assert_eq!(route("/graph.json", &view).2, "{\"nodes\":[],\"edges\":[]}");
+        assert_eq!(
+            route("/live.json", &view).2,
+            "{\"tag\":null,\"queue_depth\":0,\"present\":[],\"reactions\":{}}"
+        );
+        assert!(route("/", &view).2.contains("/live.json"));
+        assert_eq!(route("/other", &view).0, "404 Not Found");
+    }
+}
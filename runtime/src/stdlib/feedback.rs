@@ -0,0 +1,109 @@
+//! Connections with an initial value, which make zero-delay
+//! feedback loops well-defined.
+
+use crate::assembly::*;
+use crate::*;
+
+/// Parameters of a [FeedbackDelay] reactor.
+pub struct FeedbackDelayParams<T> {
+    /// Value presented on the output at the startup tag.
+    pub initial: T,
+}
+
+impl<T> FeedbackDelayParams<T> {
+    pub fn new(initial: T) -> Self {
+        Self { initial }
+    }
+}
+
+/// A connection that presents an initial value to its
+/// downstream port at startup, then forwards every input
+/// value one microstep later.
+///
+/// A loop of reactions connected by ports without delay is
+/// rejected as a cyclic dependency at assembly. Routing one
+/// of the connections of the loop through this reactor breaks
+/// the cycle, as there is no instantaneous dependency between
+/// its input and its output. The downstream of the connection
+/// reads the initial value at startup, which gives the loop
+/// a well-defined first iteration:
+/// ```ignore
+/// __ctx.with_child::<FeedbackDelay<u32>, _>("feedback", FeedbackDelayParams::new(0), |mut __ctx, feedback| {
+///     // ...
+///     __assembler.bind_ports(&mut a.out, &mut feedback.input)?;
+///     __assembler.bind_ports(&mut feedback.output, &mut b.inp)?;
+/// })
+/// ```
+pub struct FeedbackDelay<T: Sync + Clone + 'static> {
+    id: ReactorId,
+    pub input: Port<T>,
+    pub output: Port<T>,
+    forward: LogicalAction<T>,
+    /// Taken at startup.
+    initial: Option<T>,
+}
+
+impl<T: Sync + Clone + 'static> ReactorInitializer for FeedbackDelay<T> {
+    type Wrapped = Self;
+    type Params = FeedbackDelayParams<T>;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble(params: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        input: cc.new_port("input", PortKind::Input),
+                        output: cc.new_port("output", PortKind::Output),
+                        forward: cc.new_logical_action("forward", None),
+                        initial: Some(params.initial),
+                    })
+                },
+                2,
+                [Some("on_forward"), Some("on_input")],
+                // The reaction that sets the output must come first, otherwise
+                // the priority edge between both reactions would create an
+                // instantaneous dependency from the input to the output.
+                |decl, this, [on_forward, on_input]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_forward: triggers(startup, forward) effects(output);
+                        on_input: triggers(input) effects(forward);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl<T: Sync + Clone + 'static> ReactorBehavior for FeedbackDelay<T> {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        match rid.raw() {
+            0 => {
+                // the action is never present at startup
+                let v = match self.initial.take() {
+                    Some(initial) => Some(initial),
+                    None => ctx.use_ref_opt(&self.forward, T::clone),
+                };
+                ctx.set_opt(&mut self.output, v)
+            }
+            1 => {
+                let v = ctx.use_ref_opt(&self.input, T::clone);
+                ctx.schedule_with_v(&mut self.forward, v, Offset::Asap)
+            }
+            _ => invalid_reaction!(rid, Self),
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.input);
+        ctx.cleanup_port(&mut self.output);
+        ctx.cleanup_logical_action(&mut self.forward);
+    }
+}
@@ -0,0 +1,218 @@
+//! Bounded history of the values of a reactor's state.
+
+use std::ops::{Bound, RangeBounds};
+
+use crate::*;
+
+/// Records the last `N` samples of a value with the tag at
+/// which they were observed, in constant memory. Reactions
+/// can keep this in their reactor's state, eg to implement
+/// estimators or controllers that need past values:
+///
+/// ```
+/// # use reactor_rt::prelude::*;
+/// # use reactor_rt::stdlib::history::History;
+/// # fn react(ctx: &mut ReactionCtx, history: &mut History<f64, 16>, reading: f64) {
+/// history.record(ctx.get_tag(), reading);
+/// let since = EventTag::offset(ctx.get_elapsed_logical_time().saturating_sub(delay!(100 ms)), 0);
+/// let (sum, count) = history.window(since..).fold((0.0, 0), |(s, n), (_, v)| (s + v, n + 1));
+/// let average = sum / count as f64;
+/// # }
+/// ```
+///
+/// Samples must be recorded in increasing tag order, which
+/// is always the case if they are recorded by the same reactor.
+/// When the history is full, recording a sample evicts the oldest one.
+#[derive(Clone, Debug)]
+pub struct History<T, const N: usize> {
+    /// Ring buffer, the oldest sample is at index `start`.
+    samples: [Option<(EventTag, T)>; N],
+    start: usize,
+    len: usize,
+}
+
+impl<T, const N: usize> History<T, N> {
+    pub fn new() -> Self {
+        assert!(N > 0, "History must have a non-zero capacity");
+        Self { samples: [(); N].map(|_| None), start: 0, len: 0 }
+    }
+
+    /// Record a value at the given tag. If a sample was
+    /// already recorded for this tag, it is replaced.
+    ///
+    /// Panics if the tag is older than the latest sample.
+    pub fn record(&mut self, tag: EventTag, value: T) {
+        if let Some((latest, _)) = self.latest() {
+            assert!(
+                latest <= tag,
+                "Samples must be recorded in order, {} is older than {}",
+                tag,
+                latest
+            );
+            if latest == tag {
+                let last = self.physical_index(self.len - 1);
+                self.samples[last] = Some((tag, value));
+                return;
+            }
+        }
+
+        if self.len < N {
+            let ix = self.physical_index(self.len);
+            self.samples[ix] = Some((tag, value));
+            self.len += 1;
+        } else {
+            self.samples[self.start] = Some((tag, value));
+            self.start = (self.start + 1) % N;
+        }
+    }
+
+    /// Number of samples currently recorded.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Max number of samples.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Forget all samples.
+    pub fn clear(&mut self) {
+        self.samples.iter_mut().for_each(|s| *s = None);
+        self.start = 0;
+        self.len = 0;
+    }
+
+    /// Returns the most recent sample.
+    pub fn latest(&self) -> Option<(EventTag, &T)> {
+        self.len.checked_sub(1).map(|i| self.get(i))
+    }
+
+    /// Returns the oldest sample that is still recorded.
+    pub fn oldest(&self) -> Option<(EventTag, &T)> {
+        (self.len > 0).then(|| self.get(0))
+    }
+
+    /// Returns the sample that was current at the given tag,
+    /// ie the latest sample recorded at or before it. Returns
+    /// None if the tag is before the oldest recorded sample.
+    pub fn at_or_before(&self, tag: EventTag) -> Option<(EventTag, &T)> {
+        let num_before = self.partition_point(|t| t <= tag);
+        num_before.checked_sub(1).map(|i| self.get(i))
+    }
+
+    /// Iterate over all samples, from oldest to newest.
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (EventTag, &T)> + ExactSizeIterator + '_ {
+        (0..self.len).map(move |i| self.get(i))
+    }
+
+    /// Iterate over the samples whose tag is within the given
+    /// range, from oldest to newest. This can be used to aggregate
+    /// the values over a time window, eg
+    /// `history.window(since..).map(|(_, v)| v).max()`.
+    pub fn window(&self, range: impl RangeBounds<EventTag>) -> impl DoubleEndedIterator<Item = (EventTag, &T)> + '_ {
+        let from = match range.start_bound() {
+            Bound::Included(t) => self.partition_point(|x| x < *t),
+            Bound::Excluded(t) => self.partition_point(|x| x <= *t),
+            Bound::Unbounded => 0,
+        };
+        let to = match range.end_bound() {
+            Bound::Included(t) => self.partition_point(|x| x <= *t),
+            Bound::Excluded(t) => self.partition_point(|x| x < *t),
+            Bound::Unbounded => self.len,
+        };
+        (from..to.max(from)).map(move |i| self.get(i))
+    }
+
+    /// Number of samples (from the oldest) whose tag satisfies
+    /// the predicate, which must be monotonic.
+    fn partition_point(&self, pred: impl Fn(EventTag) -> bool) -> usize {
+        let (mut lo, mut hi) = (0, self.len);
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            if pred(self.get(mid).0) {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+
+    /// Returns the i-th sample, starting from the oldest.
+    fn get(&self, i: usize) -> (EventTag, &T) {
+        let (tag, value) = self.samples[self.physical_index(i)].as_ref().unwrap();
+        (*tag, value)
+    }
+
+    fn physical_index(&self, i: usize) -> usize {
+        (self.start + i) % N
+    }
+}
+
+impl<T, const N: usize> Default for History<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tag(ms: u64) -> EventTag {
+        EventTag::offset(Duration::from_millis(ms), 0)
+    }
+
+    fn values<'a, I: Iterator<Item = (EventTag, &'a u32)>>(it: I) -> Vec<u32> {
+        it.map(|(_, v)| *v).collect()
+    }
+
+    #[test]
+    fn test_eviction() {
+        let mut h = History::<u32, 3>::new();
+        assert!(h.latest().is_none());
+        for i in 0..5 {
+            h.record(tag(i * 10), i as u32);
+        }
+        assert_eq!(h.len(), 3);
+        assert_eq!(values(h.iter()), vec![2, 3, 4]);
+        assert_eq!(h.oldest(), Some((tag(20), &2)));
+        assert_eq!(h.latest(), Some((tag(40), &4)));
+
+        // same tag replaces the latest sample
+        h.record(tag(40), 5);
+        assert_eq!(values(h.iter()), vec![2, 3, 5]);
+    }
+
+    #[test]
+    fn test_at_or_before() {
+        let mut h = History::<u32, 4>::new();
+        for i in 1..7 {
+            h.record(tag(i * 10), i as u32);
+        }
+        // oldest is now 30
+        assert_eq!(h.at_or_before(tag(29)), None);
+        assert_eq!(h.at_or_before(tag(30)), Some((tag(30), &3)));
+        assert_eq!(h.at_or_before(tag(45)), Some((tag(40), &4)));
+        assert_eq!(h.at_or_before(tag(1000)), Some((tag(60), &6)));
+    }
+
+    #[test]
+    fn test_window() {
+        let mut h = History::<u32, 8>::new();
+        for i in 0..10 {
+            h.record(tag(i * 10), i as u32);
+        }
+        assert_eq!(values(h.window(tag(35)..)), vec![4, 5, 6, 7, 8, 9]);
+        assert_eq!(values(h.window(tag(40)..tag(60))), vec![4, 5]);
+        assert_eq!(values(h.window(tag(40)..=tag(60))), vec![4, 5, 6]);
+        assert_eq!(values(h.window(..tag(30))), vec![2]);
+        assert_eq!(h.window(tag(60)..tag(40)).count(), 0);
+        assert_eq!(h.window(tag(40)..).map(|(_, v)| *v).sum::<u32>(), 39);
+    }
+}
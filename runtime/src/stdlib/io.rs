@@ -0,0 +1,138 @@
+//! Input of the program read from the terminal or other streams.
+//!
+//! A [StdinLines] reactor emits each line typed by the user
+//! on its output, at the physical time it was entered, and
+//! shuts the program down at the end of the input:
+//! ```ignore
+//! __ctx.with_child::<StdinLines, _>("stdin", LinesParams::stdin(), |mut __ctx, stdin| {
+//!     // ...
+//!     __assembler.bind_ports(&mut stdin.line, &mut game.command)?;
+//! })
+//! ```
+//! The scheduler waits for the input even when no logical event
+//! is pending, see [SchedulerOptions::keep_alive].
+
+use std::io::BufRead;
+
+use crate::assembly::*;
+use crate::*;
+
+type Reader = Box<dyn BufRead + Send>;
+
+/// Parameters of a [StdinLines] reactor.
+pub struct LinesParams {
+    reader: Reader,
+}
+
+impl LinesParams {
+    /// Read the lines of the standard input.
+    pub fn stdin() -> Self {
+        Self::from_reader(std::io::BufReader::new(std::io::stdin()))
+    }
+
+    /// Read the lines of the given reader instead, eg a
+    /// socket or a pipe.
+    pub fn from_reader(reader: impl BufRead + Send + 'static) -> Self {
+        Self { reader: Box::new(reader) }
+    }
+}
+
+impl Default for LinesParams {
+    fn default() -> Self {
+        Self::stdin()
+    }
+}
+
+/// Emits the lines of its input, without their line terminator,
+/// each at the physical time it was read. Lines are read from a
+/// thread started at startup, and go through a physical action.
+/// At the end of the input, or if it cannot be read, the
+/// reactor requests shutdown.
+///
+/// The thread is blocked while it waits for a line, so it
+/// may outlive the program if it shuts down for another reason.
+pub struct StdinLines {
+    id: ReactorId,
+    pub line: Port<String>,
+    /// Carries a line, or no value at the end of the input.
+    read: PhysicalActionRef<String>,
+    reader: Option<Reader>,
+}
+
+impl ReactorInitializer for StdinLines {
+    type Wrapped = Self;
+    type Params = LinesParams;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble(params: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        line: cc.new_port("line", PortKind::Output),
+                        read: cc.new_physical_action("read", None),
+                        reader: Some(params.reader),
+                    })
+                },
+                2,
+                [Some("on_startup"), Some("on_read")],
+                |decl, this, [on_startup, on_read]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(read);
+                        on_read: triggers(read) effects(line);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl StdinLines {
+    /// Start the thread that reads the input.
+    fn start(&mut self, ctx: &mut ReactionCtx) {
+        let reader = self.reader.take().expect("started twice");
+        let action = self.read.clone();
+        let id = self.id;
+        ctx.spawn_physical_thread(move |link| {
+            for line in reader.lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(e) => {
+                        error!("Lines {}: cannot read input: {}", id, e);
+                        break;
+                    }
+                };
+                if link.schedule_physical_with_v(&action, Some(line), Offset::Asap).is_err() {
+                    return;
+                }
+            }
+            // the end of the input
+            let _ = link.schedule_physical(&action, Offset::Asap);
+        });
+    }
+}
+
+impl ReactorBehavior for StdinLines {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        match rid.raw() {
+            0 => self.start(ctx),
+            1 => match ctx.use_ref_opt(&self.read, String::clone) {
+                Some(line) => ctx.set(&mut self.line, line),
+                None => ctx.request_stop(Offset::Asap),
+            },
+            _ => invalid_reaction!(rid, Self),
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.line);
+        ctx.cleanup_physical_action(&mut self.read);
+    }
+}
@@ -0,0 +1,279 @@
+//! Readiness of sockets as program input.
+//!
+//! An [IoDriver] polls the sockets that reactions register
+//! with it, from a single thread, and schedules a physical
+//! action for each readiness event. This lets a network server
+//! be written as reactors, without one thread per connection:
+//! ```ignore
+//! fn on_startup(&mut self, ctx: &mut ReactionCtx) -> std::io::Result<()> {
+//!     let token = self.driver.register(ctx, &mut self.listener, Interest::READABLE, &self.ready)?;
+//!     // ...
+//! }
+//!
+//! fn on_ready(&mut self, ctx: &mut ReactionCtx) {
+//!     let event = ctx.get(&self.ready).unwrap();
+//!     if event.token == self.listener_token && event.readable {
+//!         // accept connections until it would block
+//!     }
+//! }
+//! ```
+//! Like with [mio], on which the driver is built, readiness
+//! is edge-triggered: once a socket is reported readable, the
+//! reaction should read until the read would block, or it may
+//! not be reported again. The driver therefore does not lose
+//! the events that the scheduler rejects, unless told to with
+//! a [RejectionPolicy], which is chosen for each source.
+//!
+//! The scheduler waits for sockets even when no logical event
+//! is pending, see [SchedulerOptions::keep_alive].
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use mio::event::Source;
+pub use mio::{net, Interest};
+use mio::{Events, Poll, Registry, Token};
+
+use crate::*;
+
+/// How long the driver thread waits for events before it
+/// checks whether the program has shut down.
+const POLL_TIMEOUT: Duration = Duration::from_millis(50);
+/// How long the driver thread waits for events before it
+/// sends again the events that were rejected.
+const RETRY_PERIOD: Duration = Duration::from_millis(1);
+
+/// Identifies a source registered with an [IoDriver].
+pub type IoToken = usize;
+
+/// The readiness of a registered source, see [IoDriver::register].
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub struct IoEvent {
+    /// The token returned when the source was registered.
+    pub token: IoToken,
+    pub readable: bool,
+    pub writable: bool,
+    /// The peer has closed its writing half, or the connection.
+    pub read_closed: bool,
+    /// The peer has closed its reading half, or the connection.
+    pub write_closed: bool,
+    pub error: bool,
+    /// Number of events of the source that were dropped since
+    /// the previous one that was sent, see [RejectionPolicy::Drop].
+    pub missed: usize,
+}
+
+impl IoEvent {
+    fn new(event: &mio::event::Event) -> Self {
+        Self {
+            token: event.token().0,
+            readable: event.is_readable(),
+            writable: event.is_writable(),
+            read_closed: event.is_read_closed(),
+            write_closed: event.is_write_closed(),
+            error: event.is_error(),
+            missed: 0,
+        }
+    }
+
+    /// Add the readiness of a later event of the same source.
+    fn merge(&mut self, later: IoEvent) {
+        self.readable |= later.readable;
+        self.writable |= later.writable;
+        self.read_closed |= later.read_closed;
+        self.write_closed |= later.write_closed;
+        self.error |= later.error;
+    }
+}
+
+/// What an [IoDriver] does with an event of a source that
+/// the scheduler rejects, eg because the [channel of physical events](SchedulerOptions::physical_channel)
+/// is full, or because of [admission control](SchedulerOptions::admission).
+/// This is chosen for each source, see [IoDriver::register_with_policy].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RejectionPolicy {
+    /// The event is sent again shortly after, merged with the
+    /// later readiness of the source. No readiness is lost, but
+    /// it may be reported late while the scheduler is overloaded.
+    Retry,
+    /// The event is dropped, and counted in [IoEvent::missed]
+    /// of the next event of the source. As readiness is edge-triggered,
+    /// the source is not reported again until it becomes ready
+    /// again, so this suits sources that do so often, eg sensors
+    /// that stream readings, of which the latest matter most.
+    Drop,
+}
+
+impl Default for RejectionPolicy {
+    fn default() -> Self {
+        RejectionPolicy::Retry
+    }
+}
+
+/// A handle on the thread that polls registered sources.
+/// Create it before the program starts, and pass clones of it
+/// to the reactors that use it as a parameter. The thread is
+/// started by the first registration, and stops at shutdown.
+#[derive(Clone)]
+pub struct IoDriver {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    registry: Registry,
+    /// Taken by the thread when it starts.
+    poll: Mutex<Option<Poll>>,
+    registrations: Mutex<HashMap<Token, Registration>>,
+    next_token: AtomicUsize,
+}
+
+/// A registered source.
+struct Registration {
+    /// The action to schedule for the events of the source.
+    action: PhysicalActionRef<IoEvent>,
+    policy: RejectionPolicy,
+    /// Number of events dropped since the previous one was sent.
+    missed: usize,
+}
+
+impl IoDriver {
+    pub fn new() -> io::Result<Self> {
+        let poll = Poll::new()?;
+        Ok(Self {
+            shared: Arc::new(Shared {
+                registry: poll.registry().try_clone()?,
+                poll: Mutex::new(Some(poll)),
+                registrations: Default::default(),
+                next_token: AtomicUsize::new(0),
+            }),
+        })
+    }
+
+    /// Register interest in the readiness of the source. Its
+    /// events are then carried by the action, at the physical
+    /// time they are polled, and identified by the returned
+    /// token.
+    ///
+    /// An event that the scheduler rejects is sent again shortly
+    /// after, merged with the later readiness of its source, see
+    /// [RejectionPolicy::Retry]. Several sources may share the same
+    /// action: the action is made to [reject](CollisionPolicy::Reject)
+    /// events at a tag at which it is already scheduled, so that
+    /// the events of different sources are carried at different tags.
+    pub fn register<S: Source + ?Sized>(
+        &self,
+        ctx: &mut ReactionCtx,
+        source: &mut S,
+        interest: Interest,
+        action: &PhysicalActionRef<IoEvent>,
+    ) -> io::Result<IoToken> {
+        self.register_with_policy(ctx, source, interest, action, RejectionPolicy::default())
+    }
+
+    /// Like [Self::register], but events of the source that the
+    /// scheduler rejects are handled according to the policy.
+    pub fn register_with_policy<S: Source + ?Sized>(
+        &self,
+        ctx: &mut ReactionCtx,
+        source: &mut S,
+        interest: Interest,
+        action: &PhysicalActionRef<IoEvent>,
+        policy: RejectionPolicy,
+    ) -> io::Result<IoToken> {
+        let token = Token(self.shared.next_token.fetch_add(1, Ordering::Relaxed));
+        let action = action.clone().with_collision_policy(CollisionPolicy::Reject);
+        let registration = Registration { action, policy, missed: 0 };
+        self.shared.registrations.lock().unwrap().insert(token, registration);
+        if let Err(e) = self.shared.registry.register(source, token, interest) {
+            self.shared.registrations.lock().unwrap().remove(&token);
+            return Err(e);
+        }
+        self.start(ctx);
+        Ok(token.0)
+    }
+
+    /// Change the interest of a registered source.
+    pub fn reregister<S: Source + ?Sized>(&self, source: &mut S, token: IoToken, interest: Interest) -> io::Result<()> {
+        self.shared.registry.reregister(source, Token(token), interest)
+    }
+
+    /// Stop polling the source. It should be deregistered
+    /// before it is dropped, eg when a connection is closed.
+    pub fn deregister<S: Source + ?Sized>(&self, source: &mut S, token: IoToken) -> io::Result<()> {
+        self.shared.registrations.lock().unwrap().remove(&Token(token));
+        self.shared.registry.deregister(source)
+    }
+
+    /// Start the thread, unless it is already started.
+    fn start(&self, ctx: &mut ReactionCtx) {
+        let poll = match self.shared.poll.lock().unwrap().take() {
+            Some(poll) => poll,
+            None => return,
+        };
+        let shared = self.shared.clone();
+        ctx.spawn_physical_thread(move |link| shared.run(poll, link));
+    }
+}
+
+impl Shared {
+    /// Send the event of the source. Returns whether it
+    /// must be sent again.
+    fn send(&self, link: &mut AsyncCtx, token: Token, event: &mut IoEvent) -> bool {
+        // the source may have been deregistered meanwhile
+        let action = match self.registrations.lock().unwrap().get(&token) {
+            Some(registration) => {
+                event.missed = registration.missed;
+                registration.action.clone()
+            }
+            None => return false,
+        };
+        let sent = link.schedule_physical_with_v(&action, Some(*event), Offset::Asap).is_ok();
+        let mut registrations = self.registrations.lock().unwrap();
+        let registration = match registrations.get_mut(&token) {
+            Some(registration) => registration,
+            None => return false,
+        };
+        if sent {
+            registration.missed = 0;
+            return false;
+        }
+        match registration.policy {
+            RejectionPolicy::Retry => {
+                trace!("IO driver: event of token {} was rejected, it will be sent again", token.0);
+                true
+            }
+            RejectionPolicy::Drop => {
+                trace!("IO driver: event of token {} was rejected, dropping it", token.0);
+                registration.missed += 1;
+                false
+            }
+        }
+    }
+
+    /// Body of the driver thread.
+    fn run(&self, mut poll: Poll, link: &mut AsyncCtx) {
+        let mut events = Events::with_capacity(256);
+        // at most one event per source, which is not sent yet
+        let mut pending: HashMap<Token, IoEvent> = HashMap::new();
+        while !link.was_terminated() {
+            let timeout = if pending.is_empty() { POLL_TIMEOUT } else { RETRY_PERIOD };
+            if let Err(e) = poll.poll(&mut events, Some(timeout)) {
+                if e.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                error!("IO driver: cannot poll sources: {}", e);
+                return;
+            }
+            for event in events.iter() {
+                let event = IoEvent::new(event);
+                pending
+                    .entry(Token(event.token))
+                    .and_modify(|earlier| earlier.merge(event))
+                    .or_insert(event);
+            }
+            pending.retain(|token, event| self.send(link, *token, event));
+        }
+    }
+}
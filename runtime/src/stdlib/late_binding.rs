@@ -0,0 +1,133 @@
+//! Reaction bodies provided at runtime, for prototyping and testing.
+
+use std::sync::{Arc, Mutex};
+
+use crate::*;
+
+/// Body of a reaction provided as a closure. It is passed
+/// the part of the reactor that reactions may access, of type `S`.
+pub type ReactionBody<S> = Box<dyn FnMut(&mut ReactionCtx, &mut S) + Send>;
+
+/// A table of reaction bodies bound at runtime, before startup.
+/// This lets prototypes and tests patch the behavior of a reactor
+/// without writing or generating a new reactor type. The reactor
+/// declares its reactions and their dependencies as usual, takes
+/// a table as construction parameter, and dispatches to it from
+/// [ReactorBehavior::react]:
+///
+/// ```ignore
+/// fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+///     // self.state contains the ports and state variables
+///     if !self.bodies.react(ctx, &mut self.state, rid) {
+///         // default behavior of reactions without a bound body
+///     }
+/// }
+/// ```
+///
+/// Bodies must only use the components that their reaction
+/// declared at assembly time, like any other reaction.
+///
+/// Bodies can also be swapped while the program runs, through
+/// a [BodySwapper], eg to iterate on a reaction without restarting
+/// the program and losing the state of the reactor.
+pub struct ReactionBodies<S> {
+    /// Indexed by local reaction id.
+    bodies: Vec<Option<ReactionBody<S>>>,
+    /// Bodies to bind at the next tag, sent by a [BodySwapper].
+    swaps: PendingSwaps<S>,
+    /// Tag of the latest reaction executed through this table.
+    current_tag: Option<EventTag>,
+}
+
+type PendingSwaps<S> = Arc<Mutex<Vec<(LocalReactionId, ReactionBody<S>)>>>;
+
+/// Handle to swap the bodies of a [ReactionBodies] table
+/// from another thread while the program runs. Swaps take
+/// effect at the next tag boundary, so that all the reactions
+/// executed at a tag use the same bodies.
+pub struct BodySwapper<S> {
+    swaps: PendingSwaps<S>,
+}
+
+impl<S> BodySwapper<S> {
+    /// Bind the body of a reaction from the next tag on.
+    /// If this is called several times for the same reaction
+    /// before that tag, the last body wins.
+    pub fn swap(&self, rid: LocalReactionId, body: impl FnMut(&mut ReactionCtx, &mut S) + Send + 'static) {
+        self.swaps.lock().unwrap().push((rid, Box::new(body)));
+    }
+}
+
+impl<S> Clone for BodySwapper<S> {
+    fn clone(&self) -> Self {
+        Self { swaps: self.swaps.clone() }
+    }
+}
+
+impl<S> ReactionBodies<S> {
+    pub fn new() -> Self {
+        Self {
+            bodies: Vec::new(),
+            swaps: Default::default(),
+            current_tag: None,
+        }
+    }
+
+    /// A handle to swap the bodies of this table at runtime.
+    pub fn swapper(&self) -> BodySwapper<S> {
+        BodySwapper { swaps: self.swaps.clone() }
+    }
+
+    /// Bind the body of a reaction, replacing any
+    /// body that was previously bound to it.
+    pub fn bind(mut self, rid: LocalReactionId, body: impl FnMut(&mut ReactionCtx, &mut S) + Send + 'static) -> Self {
+        self.set_body(rid, Box::new(body));
+        self
+    }
+
+    fn set_body(&mut self, rid: LocalReactionId, body: ReactionBody<S>) {
+        let ix = rid.index();
+        if self.bodies.len() <= ix {
+            self.bodies.resize_with(ix + 1, || None);
+        }
+        self.bodies[ix] = Some(body);
+    }
+
+    /// Bind the bodies sent by a [BodySwapper], if this
+    /// is the first reaction executed at a new tag.
+    fn apply_swaps(&mut self, tag: EventTag) {
+        if self.current_tag == Some(tag) {
+            return;
+        }
+        self.current_tag = Some(tag);
+        let swaps = std::mem::take(&mut *self.swaps.lock().unwrap());
+        for (rid, body) in swaps {
+            debug!("Swapping the body of reaction {}", rid);
+            self.set_body(rid, body);
+        }
+    }
+
+    /// Whether a body was bound to the given reaction.
+    pub fn is_bound(&self, rid: LocalReactionId) -> bool {
+        matches!(self.bodies.get(rid.index()), Some(Some(_)))
+    }
+
+    /// Execute the body bound to the given reaction, if any.
+    /// Returns false if there is none.
+    pub fn react(&mut self, ctx: &mut ReactionCtx, state: &mut S, rid: LocalReactionId) -> bool {
+        self.apply_swaps(ctx.get_tag());
+        match self.bodies.get_mut(rid.index()) {
+            Some(Some(body)) => {
+                body(ctx, state);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<S> Default for ReactionBodies<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,26 @@
+//! Reusable reactors for common patterns, which can be
+//! instantiated as children of generated or hand-written
+//! reactors.
+
+macro_rules! invalid_reaction {
+    ($rid:expr, $S:ty) => {
+        panic!(
+            "Invalid reaction ID: {} should be < {}",
+            $rid,
+            <$S as ReactorInitializer>::MAX_REACTION_ID
+        )
+    };
+}
+
+pub mod feedback;
+pub mod history;
+pub mod io;
+#[cfg(feature = "io-driver")]
+pub mod io_driver;
+pub mod late_binding;
+pub mod monitor;
+pub mod physical;
+pub mod replay;
+#[cfg(all(feature = "signals", unix))]
+pub mod signals;
+pub mod timing;
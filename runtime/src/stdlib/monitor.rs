@@ -0,0 +1,243 @@
+//! Reactors that check invariants of a program while it runs.
+
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::RangeInclusive;
+
+use crate::assembly::*;
+use crate::*;
+
+/// What a [Monitor] observes at a tag where any of its
+/// inputs is present.
+pub struct Observation<'a, T> {
+    pub tag: EventTag,
+    /// Delay between the logical time of the tag and the
+    /// physical time at which the monitor observed it.
+    pub lag: Duration,
+    /// Value of each input of the monitor, in the order
+    /// they were bound. Absent inputs are None.
+    pub values: &'a [Option<T>],
+}
+
+type InvariantCheck<T> = Box<dyn FnMut(&Observation<'_, T>) -> bool + Send>;
+
+/// Called by a [Monitor] with its summary at shutdown.
+pub type SummaryCallback = Box<dyn FnOnce(&MonitorSummary) + Send>;
+
+/// A named condition that must hold at every observation
+/// of a [Monitor].
+pub struct Invariant<T> {
+    name: String,
+    check: InvariantCheck<T>,
+    violations: u64,
+    first_violation: Option<EventTag>,
+}
+
+impl<T> Invariant<T> {
+    pub fn new(name: impl Into<String>, check: impl FnMut(&Observation<'_, T>) -> bool + Send + 'static) -> Self {
+        Self {
+            name: name.into(),
+            check: Box::new(check),
+            violations: 0,
+            first_violation: None,
+        }
+    }
+
+    /// Holds if values are observed at most `bound` after
+    /// their logical time.
+    pub fn max_lag(bound: Duration) -> Self {
+        Self::new(format!("lag <= {} µs", bound.as_micros()), move |obs| obs.lag <= bound)
+    }
+
+    fn observe(&mut self, obs: &Observation<'_, T>) {
+        if !(self.check)(obs) {
+            self.violations += 1;
+            self.first_violation.get_or_insert(obs.tag);
+        }
+    }
+}
+
+impl<T: PartialOrd + Debug + Send + 'static> Invariant<T> {
+    /// Holds if all present values are within the range.
+    pub fn value_range(range: RangeInclusive<T>) -> Self {
+        Self::new(format!("value in {:?}", range), move |obs| {
+            obs.values.iter().flatten().all(|v| range.contains(v))
+        })
+    }
+}
+
+/// Violations of an invariant over the execution of a program.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InvariantViolations {
+    pub invariant: String,
+    /// Number of observations that violated the invariant.
+    pub count: u64,
+    pub first: EventTag,
+}
+
+/// Produced by a [Monitor] at shutdown.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MonitorSummary {
+    /// Number of tags at which the monitor observed its inputs.
+    pub observations: u64,
+    /// Invariants that were violated at least once.
+    pub violations: Vec<InvariantViolations>,
+}
+
+impl MonitorSummary {
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+impl Display for MonitorSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} observations", self.observations)?;
+        if self.is_ok() {
+            return write!(f, ", no invariant violated");
+        }
+        join_to!(f, self.violations.iter(), ", ", ", violated: ", "", |v| format!(
+            "{} ({} times, first at {})",
+            v.invariant, v.count, v.first
+        ))
+    }
+}
+
+/// Parameters of a [Monitor] reactor.
+pub struct MonitorParams<T> {
+    /// Number of ports the monitor can observe.
+    pub width: usize,
+    pub invariants: Vec<Invariant<T>>,
+    /// Called with the summary at shutdown.
+    pub on_summary: Option<SummaryCallback>,
+}
+
+impl<T> MonitorParams<T> {
+    pub fn new(width: usize) -> Self {
+        Self { width, invariants: Vec::new(), on_summary: None }
+    }
+
+    pub fn with_invariant(mut self, invariant: Invariant<T>) -> Self {
+        self.invariants.push(invariant);
+        self
+    }
+
+    pub fn on_summary(mut self, callback: impl FnOnce(&MonitorSummary) + Send + 'static) -> Self {
+        self.on_summary = Some(Box::new(callback));
+        self
+    }
+}
+
+/// Observes a set of ports read-only, and checks invariants
+/// at every tag where any of them is present. The values of
+/// the ports are cloned for each observation. At shutdown,
+/// a summary of the violations is logged, and passed to the
+/// [callback](MonitorParams::on_summary) if any.
+///
+/// The inputs of the monitor are usually connected with
+/// [DependencyDeclarator::bind_matching], which selects
+/// ports by the pattern of their path:
+/// ```ignore
+/// let params = MonitorParams::new(4).with_invariant(Invariant::value_range(0.0..=100.0));
+/// __ctx.with_child::<Monitor<f64>, _>("monitor", params, |mut __ctx, monitor| {
+///     // ...
+///     let ports = sensors.iter_mut().map(|s| &mut s.reading);
+///     __assembler.bind_matching("main/sensors*/reading", ports, &mut monitor.inputs)?;
+/// })
+/// ```
+pub struct Monitor<T: Sync + Clone + 'static> {
+    id: ReactorId,
+    pub inputs: Multiport<T>,
+    invariants: Vec<Invariant<T>>,
+    observations: u64,
+    on_summary: Option<SummaryCallback>,
+}
+
+impl<T: Sync + Clone + 'static> Monitor<T> {
+    fn summary(&self) -> MonitorSummary {
+        MonitorSummary {
+            observations: self.observations,
+            violations: self
+                .invariants
+                .iter()
+                .filter_map(|inv| {
+                    inv.first_violation.map(|first| InvariantViolations {
+                        invariant: inv.name.clone(),
+                        count: inv.violations,
+                        first,
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<T: Sync + Clone + 'static> ReactorInitializer for Monitor<T> {
+    type Wrapped = Self;
+    type Params = MonitorParams<T>;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble(params: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        inputs: cc.new_multiport("inputs", PortKind::Input, params.width)?,
+                        invariants: params.invariants,
+                        observations: 0,
+                        on_summary: params.on_summary,
+                    })
+                },
+                2,
+                [Some("observe"), Some("report")],
+                |decl, this, [observe, report]| {
+                    declare_reactions! {
+                        (decl, this)
+                        observe: triggers(inputs);
+                        report: triggers(shutdown);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl<T: Sync + Clone + 'static> ReactorBehavior for Monitor<T> {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        match rid.raw() {
+            0 => {
+                let values = self.inputs.iter().map(|p| ctx.use_ref_opt(p, T::clone)).collect::<Vec<_>>();
+                let obs = Observation {
+                    tag: ctx.get_tag(),
+                    lag: ctx.get_physical_time().saturating_duration_since(ctx.get_logical_time()),
+                    values: &values,
+                };
+                for invariant in &mut self.invariants {
+                    invariant.observe(&obs);
+                }
+                self.observations += 1;
+            }
+            1 => {
+                let summary = self.summary();
+                if summary.is_ok() {
+                    info!("Monitor {}: {}", self.id, summary);
+                } else {
+                    warn!("Monitor {}: {}", self.id, summary);
+                }
+                if let Some(callback) = self.on_summary.take() {
+                    callback(&summary)
+                }
+            }
+            _ => invalid_reaction!(rid, Self),
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_multiport(&mut self.inputs);
+    }
+}
@@ -0,0 +1,84 @@
+//! Connections that decouple the timing of their two sides.
+
+use crate::assembly::*;
+use crate::*;
+
+/// A connection whose downstream side receives each value at
+/// the physical time at which it crossed the connection, rather
+/// than at the logical time at which it was sent. The value
+/// goes through a physical action internally.
+///
+/// This decouples two timing domains inside one program: the
+/// downstream side is not held to the logical timeline of the
+/// upstream side, as if it received the value from an external
+/// input. The delay between both tags is not deterministic.
+/// ```ignore
+/// __ctx.with_child::<PhysicalConnection<u32>, _>("conn", (), |mut __ctx, conn| {
+///     // ...
+///     __assembler.bind_ports(&mut sensor.out, &mut conn.input)?;
+///     __assembler.bind_ports(&mut conn.output, &mut logger.inp)?;
+/// })
+/// ```
+pub struct PhysicalConnection<T: Sync + Clone + 'static> {
+    id: ReactorId,
+    pub input: Port<T>,
+    pub output: Port<T>,
+    forward: PhysicalActionRef<T>,
+}
+
+impl<T: Sync + Clone + 'static> ReactorInitializer for PhysicalConnection<T> {
+    type Wrapped = Self;
+    type Params = ();
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble(_: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        input: cc.new_port("input", PortKind::Input),
+                        output: cc.new_port("output", PortKind::Output),
+                        forward: cc.new_physical_action("forward", None),
+                    })
+                },
+                2,
+                [Some("on_input"), Some("on_forward")],
+                |decl, this, [on_input, on_forward]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_input: triggers(input) effects(forward);
+                        on_forward: triggers(forward) effects(output);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl<T: Sync + Clone + 'static> ReactorBehavior for PhysicalConnection<T> {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        match rid.raw() {
+            0 => {
+                let v = ctx.use_ref_opt(&self.input, T::clone);
+                ctx.schedule_with_v(&mut self.forward, v, Offset::Asap);
+            }
+            1 => {
+                let v = ctx.use_ref_opt(&self.forward, T::clone);
+                ctx.set_opt(&mut self.output, v);
+            }
+            _ => invalid_reaction!(rid, Self),
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.input);
+        ctx.cleanup_port(&mut self.output);
+        ctx.cleanup_physical_action(&mut self.forward);
+    }
+}
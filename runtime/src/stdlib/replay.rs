@@ -0,0 +1,170 @@
+//! Replay of recorded, time-stamped data as program input.
+//!
+//! A [ReplaySource] emits recorded values on its output
+//! port at the logical time they were recorded at, which
+//! lets downstream reactors be tested offline against eg
+//! recorded sensor logs, with exact timing:
+//! ```ignore
+//! let params = ReplayParams::from_csv_file("sensors.csv")?;
+//! __ctx.with_child::<ReplaySource<f64>, _>("replay", params, |mut __ctx, replay| {
+//!     // ...
+//!     __assembler.bind_ports(&mut replay.output, &mut filter.input)?;
+//! })
+//! ```
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::assembly::*;
+use crate::*;
+
+/// A recorded value, with its logical time relative to startup.
+pub type ReplayRecord<T> = (Duration, T);
+
+type Records<T> = Box<dyn Iterator<Item = ReplayRecord<T>> + Send>;
+
+/// Parameters of a [ReplaySource] reactor.
+pub struct ReplayParams<T> {
+    records: Records<T>,
+}
+
+impl<T> ReplayParams<T> {
+    /// Replay the given records, which should be sorted by time.
+    /// They are only pulled from the iterator as the replay
+    /// progresses, so large recordings need not fit in memory.
+    /// Any format can be replayed by converting it to such
+    /// an iterator.
+    pub fn new<I>(records: I) -> Self
+    where
+        I: IntoIterator<Item = ReplayRecord<T>>,
+        I::IntoIter: Send + 'static,
+    {
+        Self { records: Box::new(records.into_iter()) }
+    }
+}
+
+impl<T: FromStr + 'static> ReplayParams<T> {
+    /// Replay records read from CSV lines of the form
+    /// `<nanoseconds since startup>,<value>`. The value is the
+    /// rest of the line, parsed with [FromStr]. Empty lines and
+    /// lines starting with `#` are ignored. Malformed lines are
+    /// logged and skipped.
+    pub fn from_csv(reader: impl BufRead + Send + 'static) -> Self {
+        let records = reader.lines().enumerate().filter_map(|(i, line)| {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => {
+                    error!("Replay: cannot read line {}: {}", i + 1, e);
+                    return None;
+                }
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+            let record = line.split_once(',').and_then(|(nanos, value)| {
+                let nanos = nanos.trim().parse::<u64>().ok()?;
+                let value = value.trim().parse::<T>().ok()?;
+                Some((Duration::from_nanos(nanos), value))
+            });
+            if record.is_none() {
+                error!("Replay: skipping malformed line {}: {:?}", i + 1, line);
+            }
+            record
+        });
+        Self::new(records)
+    }
+
+    /// Replay the CSV file at the given path, see [Self::from_csv].
+    pub fn from_csv_file(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(Self::from_csv(BufReader::new(File::open(path)?)))
+    }
+}
+
+/// Emits recorded values on its output, each at the logical
+/// time it was recorded at, relative to startup. Values recorded
+/// at the same time are emitted at successive microsteps of that
+/// time, in the order of the recording, so that none is lost.
+/// Records that are older than a previous one are logged and
+/// skipped.
+pub struct ReplaySource<T: Sync + Send + Clone + 'static> {
+    id: ReactorId,
+    pub output: Port<T>,
+    next: LogicalAction<T>,
+    records: Records<T>,
+}
+
+impl<T: Sync + Send + Clone + 'static> ReplaySource<T> {
+    /// Schedule the emission of the next record, if any.
+    fn schedule_next(&mut self, ctx: &mut ReactionCtx) {
+        let now = ctx.get_elapsed_logical_time();
+        for (time, value) in &mut self.records {
+            if time < now {
+                warn!(
+                    "Replay {}: skipping record at {} ms, which is older than the previous one",
+                    self.id,
+                    time.as_millis()
+                );
+                continue;
+            }
+            ctx.schedule_with_v(&mut self.next, Some(value), Offset::After(time - now));
+            return;
+        }
+    }
+}
+
+impl<T: Sync + Send + Clone + 'static> ReactorInitializer for ReplaySource<T> {
+    type Wrapped = Self;
+    type Params = ReplayParams<T>;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble(params: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        output: cc.new_port("output", PortKind::Output),
+                        next: cc.new_logical_action("next", None),
+                        records: params.records,
+                    })
+                },
+                2,
+                [Some("on_startup"), Some("on_next")],
+                |decl, this, [on_startup, on_next]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(next);
+                        on_next: triggers(next) effects(output, next);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl<T: Sync + Send + Clone + 'static> ReactorBehavior for ReplaySource<T> {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        match rid.raw() {
+            0 => self.schedule_next(ctx),
+            1 => {
+                let v = ctx.use_ref_opt(&self.next, T::clone);
+                ctx.set_opt(&mut self.output, v);
+                self.schedule_next(ctx);
+            }
+            _ => invalid_reaction!(rid, Self),
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.output);
+        ctx.cleanup_logical_action(&mut self.next);
+    }
+}
@@ -0,0 +1,165 @@
+//! Termination signals of the operating system as program input.
+//!
+//! A [SignalSource] receives SIGINT (eg Ctrl-C) and SIGTERM,
+//! which would otherwise abort the process with events still
+//! pending. By default, it shuts the program down gracefully,
+//! so that shutdown reactions execute:
+//! ```ignore
+//! __ctx.with_child::<SignalSource, _>("signals", SignalMode::Shutdown, |mut __ctx, signals| {
+//!     // optional, to react to the signal before shutdown
+//!     __assembler.bind_ports(&mut signals.received, &mut logger.inp)?;
+//! })
+//! ```
+//! The scheduler waits for signals even when no logical event
+//! is pending, see [SchedulerOptions::keep_alive].
+
+use signal_hook::consts::{SIGINT, SIGTERM};
+use signal_hook::iterator::{Handle, Signals};
+
+use crate::assembly::*;
+use crate::*;
+
+/// A termination signal, see [SignalSource].
+#[derive(Copy, Clone, Debug, Hash, Eq, PartialEq)]
+pub enum Signal {
+    /// SIGINT, sent eg when the user presses Ctrl-C.
+    Interrupt,
+    /// SIGTERM, sent eg by a service manager.
+    Terminate,
+}
+
+impl Signal {
+    fn from_raw(signal: i32) -> Option<Self> {
+        match signal {
+            SIGINT => Some(Signal::Interrupt),
+            SIGTERM => Some(Signal::Terminate),
+            _ => None,
+        }
+    }
+}
+
+/// What a [SignalSource] does when it receives a signal.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SignalMode {
+    /// Output the signal, then request shutdown on the
+    /// next microstep. Shutdown is also requested by the
+    /// thread that receives the signal, so that it happens
+    /// even if the execution is [paused](SchedulerControl::pause).
+    Shutdown,
+    /// Only output the signal, the program decides whether
+    /// to shut down.
+    Forward,
+}
+
+impl Default for SignalMode {
+    fn default() -> Self {
+        SignalMode::Shutdown
+    }
+}
+
+/// Outputs the termination signals received by the process,
+/// through a physical action. The signals are registered at
+/// startup, and replace the default behavior of the process,
+/// which is to terminate immediately. That behavior is not
+/// restored at shutdown: signals received after shutdown
+/// starts are ignored.
+pub struct SignalSource {
+    id: ReactorId,
+    /// Present at the tag at which a signal was received.
+    pub received: Port<Signal>,
+    signal: PhysicalActionRef<Signal>,
+    mode: SignalMode,
+    handle: Option<Handle>,
+}
+
+impl ReactorInitializer for SignalSource {
+    type Wrapped = Self;
+    type Params = SignalMode;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(3);
+
+    fn assemble(mode: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        received: cc.new_port("received", PortKind::Output),
+                        signal: cc.new_physical_action("signal", None),
+                        mode,
+                        handle: None,
+                    })
+                },
+                3,
+                [Some("on_startup"), Some("on_signal"), Some("on_shutdown")],
+                |decl, this, [on_startup, on_signal, on_shutdown]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(signal);
+                        on_signal: triggers(signal) effects(received);
+                        on_shutdown: triggers(shutdown);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl SignalSource {
+    /// Register the signals, and forward them from a thread.
+    /// They are registered in the reaction, so that they are
+    /// not missed once startup is over.
+    fn listen(&mut self, ctx: &mut ReactionCtx) {
+        let mut signals = match Signals::new([SIGINT, SIGTERM]) {
+            Ok(signals) => signals,
+            Err(e) => {
+                error!("Signals: cannot register SIGINT and SIGTERM: {}", e);
+                return;
+            }
+        };
+        self.handle = Some(signals.handle());
+        let (action, mode) = (self.signal.clone(), self.mode);
+        ctx.spawn_physical_thread(move |link| {
+            // this ends when the handle is closed
+            for signal in signals.forever().filter_map(Signal::from_raw) {
+                if link.schedule_physical_with_v(&action, Some(signal), Offset::Asap).is_err() {
+                    break;
+                }
+                // after the signal, so that the reaction to it executes first
+                if mode == SignalMode::Shutdown && link.request_stop(Offset::Asap).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+impl ReactorBehavior for SignalSource {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        match rid.raw() {
+            0 => self.listen(ctx),
+            1 => {
+                let signal = ctx.get(&self.signal);
+                ctx.set_opt(&mut self.received, signal);
+                if self.mode == SignalMode::Shutdown {
+                    ctx.request_stop(Offset::Asap);
+                }
+            }
+            2 => {
+                if let Some(handle) = self.handle.take() {
+                    handle.close();
+                }
+            }
+            _ => invalid_reaction!(rid, Self),
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.received);
+        ctx.cleanup_physical_action(&mut self.signal);
+    }
+}
@@ -0,0 +1,277 @@
+//! Reactors that reshape a stream of values in time.
+//!
+//! All reactors of this module have an `input` and an `output`
+//! port of the same type, which can be bound like the ports of
+//! any other child reactor, eg:
+//! ```ignore
+//! __ctx.with_child::<Debounce<u32>, _>("debounce", DebounceParams::new(delay!(10 ms)), |mut __ctx, debounce| {
+//!     // ...
+//!     __assembler.bind_ports(&mut source.__out, &mut debounce.input)?;
+//! })
+//! ```
+
+use crate::assembly::*;
+use crate::*;
+
+/// Parameters of a [Debounce] reactor.
+pub struct DebounceParams {
+    /// How long the input must stay absent before the latest value is emitted.
+    pub quiet_period: Duration,
+}
+
+impl DebounceParams {
+    pub fn new(quiet_period: Duration) -> Self {
+        Self { quiet_period }
+    }
+}
+
+/// Emits the latest input value once no new value has been
+/// received for a given quiet period. A burst of inputs hence
+/// produces a single output, at the logical time of the last
+/// input plus the quiet period.
+pub struct Debounce<T: Sync + Clone + 'static> {
+    id: ReactorId,
+    pub input: Port<T>,
+    pub output: Port<T>,
+    /// Carries the generation of the input that scheduled it.
+    quiet: LogicalAction<u64>,
+    quiet_period: Duration,
+    /// Latest received value, not yet emitted.
+    pending: Option<T>,
+    /// Incremented on every input, to recognize stale
+    /// occurrences of the action.
+    generation: u64,
+}
+
+impl<T: Sync + Clone + 'static> ReactorInitializer for Debounce<T> {
+    type Wrapped = Self;
+    type Params = DebounceParams;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble(params: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        input: cc.new_port("input", PortKind::Input),
+                        output: cc.new_port("output", PortKind::Output),
+                        quiet: cc.new_logical_action("quiet", None),
+                        quiet_period: params.quiet_period,
+                        pending: None,
+                        generation: 0,
+                    })
+                },
+                2,
+                [Some("on_input"), Some("on_quiet")],
+                |decl, this, [on_input, on_quiet]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_input: triggers(input) effects(quiet);
+                        on_quiet: triggers(quiet) effects(output);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl<T: Sync + Clone + 'static> ReactorBehavior for Debounce<T> {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        match rid.raw() {
+            0 => {
+                if let Some(v) = ctx.use_ref_opt(&self.input, T::clone) {
+                    self.pending = Some(v);
+                    self.generation += 1;
+                    ctx.schedule_with_v(&mut self.quiet, Some(self.generation), Offset::After(self.quiet_period));
+                }
+            }
+            1 => {
+                if ctx.get(&self.quiet) == Some(self.generation) {
+                    ctx.set_opt(&mut self.output, self.pending.take());
+                }
+            }
+            _ => invalid_reaction!(rid, Self),
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.input);
+        ctx.cleanup_port(&mut self.output);
+        ctx.cleanup_logical_action(&mut self.quiet);
+    }
+}
+
+/// Parameters of a [Throttle] reactor.
+pub struct ThrottleParams {
+    /// Minimum logical time between two outputs.
+    pub window: Duration,
+}
+
+impl ThrottleParams {
+    pub fn new(window: Duration) -> Self {
+        Self { window }
+    }
+}
+
+/// Forwards at most one value per time window. The first value
+/// received is forwarded immediately, then values are dropped
+/// until the window (measured in logical time) has elapsed.
+pub struct Throttle<T: Sync + Clone + 'static> {
+    id: ReactorId,
+    pub input: Port<T>,
+    pub output: Port<T>,
+    window: Duration,
+    /// Logical time at which the last value was forwarded.
+    last_emission: Option<Instant>,
+}
+
+impl<T: Sync + Clone + 'static> ReactorInitializer for Throttle<T> {
+    type Wrapped = Self;
+    type Params = ThrottleParams;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(1);
+
+    fn assemble(params: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        input: cc.new_port("input", PortKind::Input),
+                        output: cc.new_port("output", PortKind::Output),
+                        window: params.window,
+                        last_emission: None,
+                    })
+                },
+                1,
+                [Some("on_input")],
+                |decl, this, [on_input]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_input: triggers(input) effects(output);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl<T: Sync + Clone + 'static> ReactorBehavior for Throttle<T> {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        match rid.raw() {
+            0 => {
+                let now = ctx.get_logical_time();
+                let window_elapsed = self.last_emission.map_or(true, |last| now >= last + self.window);
+                if window_elapsed {
+                    if let Some(v) = ctx.use_ref_opt(&self.input, T::clone) {
+                        ctx.set(&mut self.output, v);
+                        self.last_emission = Some(now);
+                    }
+                }
+            }
+            _ => invalid_reaction!(rid, Self),
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.input);
+        ctx.cleanup_port(&mut self.output);
+    }
+}
+
+/// Parameters of a [SampleAndHold] reactor.
+pub struct SampleAndHoldParams {
+    /// Logical time of the first sample, relative to startup.
+    pub offset: Delay,
+    /// Time between two samples.
+    pub period: Period,
+}
+
+impl SampleAndHoldParams {
+    pub fn new(offset: Delay, period: Period) -> Self {
+        Self { offset, period }
+    }
+}
+
+/// Emits the latest value received on a periodic timer. Nothing
+/// is emitted until a first value has been received, after which
+/// that value is emitted on every sample until it is replaced.
+/// A value received at the same tag as a sample is emitted by
+/// that sample.
+pub struct SampleAndHold<T: Sync + Clone + 'static> {
+    id: ReactorId,
+    pub input: Port<T>,
+    pub output: Port<T>,
+    sample: Timer,
+    held: Option<T>,
+}
+
+impl<T: Sync + Clone + 'static> ReactorInitializer for SampleAndHold<T> {
+    type Wrapped = Self;
+    type Params = SampleAndHoldParams;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(4);
+
+    fn assemble(params: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        input: cc.new_port("input", PortKind::Input),
+                        output: cc.new_port("output", PortKind::Output),
+                        sample: cc.new_periodic_timer("sample", params.offset, params.period),
+                        held: None,
+                    })
+                },
+                // the last two reactions are synthetic, they manage the timer
+                2,
+                [Some("on_input"), Some("on_sample"), None, None],
+                |decl, this, [on_input, on_sample, bootstrap_timer, reschedule_timer]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_input: triggers(input);
+                        on_sample: triggers(sample) effects(output);
+                        bootstrap_timer: triggers(startup) effects(sample);
+                        reschedule_timer: triggers(sample);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl<T: Sync + Clone + 'static> ReactorBehavior for SampleAndHold<T> {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        match rid.raw() {
+            0 => {
+                if let Some(v) = ctx.use_ref_opt(&self.input, T::clone) {
+                    self.held = Some(v);
+                }
+            }
+            1 => ctx.set_opt(&mut self.output, self.held.clone()),
+            2 => ctx.bootstrap_timer(&mut self.sample),
+            3 => ctx.reschedule_timer(&mut self.sample),
+            _ => invalid_reaction!(rid, Self),
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.input);
+        ctx.cleanup_port(&mut self.output);
+    }
+}
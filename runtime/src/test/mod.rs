@@ -23,5 +23,46 @@
  */
 
 pub mod stuff_that_must_compile;
+pub mod test_actions;
+#[cfg(feature = "async")]
+pub mod test_async;
+pub mod test_backpressure;
+pub mod test_banks;
+pub mod test_bench;
+pub mod test_bundles;
+#[cfg(feature = "checkpoint")]
+pub mod test_checkpoint;
+pub mod test_deadlines;
+pub mod test_downstream;
+pub mod test_dyn_children;
+pub mod test_event_budget;
+#[cfg(feature = "fault-injection")]
+pub mod test_faults;
+pub mod test_feedback;
+#[cfg(feature = "test-harness")]
+pub mod test_harness;
+pub mod test_io;
+#[cfg(feature = "io-driver")]
+pub mod test_io_driver;
+pub mod test_late_binding;
+pub mod test_lifecycle;
+pub mod test_logging;
+pub mod test_monitor;
+pub mod test_panics;
+pub mod test_physical_batching;
+pub mod test_policy;
 pub mod test_ports;
+pub mod test_priorities;
+pub mod test_random;
+pub mod test_reactor_program;
+pub mod test_replay;
+#[cfg(feature = "serde")]
+pub mod test_serde;
+pub mod test_shutdown;
+#[cfg(all(feature = "signals", unix))]
+pub mod test_signals;
+pub mod test_startup;
+pub mod test_timing_reactors;
+pub mod test_validation;
+pub mod test_watchdog;
 pub mod testutil;
@@ -29,7 +29,7 @@
 
 use crate::assembly::{AssemblyCtx, ReactorInitializer};
 use crate::prelude::*;
-use crate::{CleanupCtx, Port};
+use crate::{Capability, CleanupCtx, Owned, Port};
 
 fn actions_get(ctx: &mut ReactionCtx, act_mut: &mut LogicalAction<u32>, act: &LogicalAction<u32>) {
     assert!(ctx.get(act_mut).is_some());
@@ -125,3 +125,14 @@ fn cleanup(
     ctx.cleanup_physical_action(phys_action);
     ctx.cleanup_port(port);
 }
+
+fn owned_port<S>(ctx: &mut ReactionCtx, cap: &Capability<S>, port: &mut Owned<S, Port<u32>>) {
+    assert!(ctx.get(port).is_some());
+    assert!(ctx.is_present(port));
+    ctx.set(port.get_mut(cap), 3);
+}
+
+fn owned_action<S>(ctx: &mut ReactionCtx, cap: &Capability<S>, action: &mut Owned<S, LogicalAction<u32>>) {
+    assert!(ctx.use_ref(action, |v| v.is_some()));
+    ctx.schedule(action.get_mut(cap), Asap);
+}
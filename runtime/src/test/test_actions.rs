@@ -0,0 +1,337 @@
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::*;
+use crate::*;
+
+/// What [ReactionCtx::schedule_if_absent] returned at startup,
+/// and the elapsed time of the occurrences of the action.
+type Kicks = Arc<Mutex<(Vec<bool>, Vec<Duration>)>>;
+
+/// Kicks its action several times at startup.
+struct Kicker {
+    id: ReactorId,
+    kick: LogicalAction<()>,
+    kicks: Kicks,
+}
+
+impl ReactorInitializer for Kicker {
+    type Wrapped = Self;
+    type Params = Kicks;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble(kicks: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        kick: cc.new_logical_action("kick", None),
+                        kicks,
+                    })
+                },
+                2,
+                [Some("on_startup"), Some("on_kick")],
+                |decl, this, [on_startup, on_kick]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(kick);
+                        on_kick: triggers(kick);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Kicker {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        let mut kicks = self.kicks.lock().unwrap();
+        if rid.raw() == 0 {
+            for offset in [10, 10, 3, 10] {
+                let scheduled = ctx.schedule_if_absent(&mut self.kick, Offset::After(Duration::from_millis(offset)));
+                kicks.0.push(scheduled);
+            }
+        } else {
+            kicks.1.push(ctx.get_elapsed_logical_time());
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_logical_action(&mut self.kick);
+    }
+}
+
+#[test]
+fn test_schedule_if_absent_does_not_pile_up() {
+    let kicks: Kicks = Default::default();
+    SyncScheduler::run_main::<Kicker>(Default::default(), kicks.clone());
+    let (scheduled, occurrences) = std::mem::take(&mut *kicks.lock().unwrap());
+    // the kick at 10 ms does not prevent an earlier one
+    assert_eq!(scheduled, vec![true, false, true, false]);
+    assert_eq!(occurrences, vec![Duration::from_millis(3), Duration::from_millis(10)]);
+}
+
+/// Values of the occurrences of the action of [Spaced],
+/// with the elapsed logical time.
+type Occurrences = Arc<Mutex<Vec<(Duration, u32)>>>;
+
+/// Schedules its physical action three times in a row,
+/// from a physical thread.
+struct Spaced {
+    id: ReactorId,
+    act: PhysicalActionRef<u32>,
+    occurrences: Occurrences,
+}
+
+impl ReactorInitializer for Spaced {
+    type Wrapped = Self;
+    type Params = (SpacingPolicy, Occurrences);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble((policy, occurrences): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        act: cc
+                            .new_physical_action("act", None)
+                            .with_min_spacing(Duration::from_millis(10), policy),
+                        occurrences,
+                    })
+                },
+                2,
+                [Some("on_startup"), Some("on_act")],
+                |decl, this, [on_startup, on_act]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(act);
+                        on_act: triggers(act);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Spaced {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        if rid.raw() == 0 {
+            let act = self.act.clone();
+            ctx.spawn_physical_thread(move |link| {
+                for i in 1..=3 {
+                    link.schedule_physical_with_v(&act, Some(i), Offset::After(Duration::from_millis(5)))
+                        .unwrap();
+                }
+            });
+        } else {
+            let value = ctx.get(&self.act).unwrap();
+            let elapsed = ctx.get_elapsed_logical_time();
+            self.occurrences.lock().unwrap().push((elapsed, value));
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_physical_action(&mut self.act);
+    }
+}
+
+fn spaced_occurrences(policy: SpacingPolicy) -> Vec<(Duration, u32)> {
+    let occurrences: Occurrences = Default::default();
+    let options = SchedulerOptions {
+        keep_alive: true,
+        timeout: Some(Duration::from_millis(100)),
+        ..Default::default()
+    };
+    SyncScheduler::run_main::<Spaced>(options, (policy, occurrences.clone()));
+    let result = std::mem::take(&mut *occurrences.lock().unwrap());
+    result
+}
+
+#[test]
+fn test_min_spacing_policies() {
+    let values = |occurrences: &[(Duration, u32)]| occurrences.iter().map(|(_, v)| *v).collect::<Vec<_>>();
+
+    assert_eq!(values(&spaced_occurrences(SpacingPolicy::Drop)), vec![1]);
+    assert_eq!(values(&spaced_occurrences(SpacingPolicy::Replace)), vec![3]);
+
+    let deferred = spaced_occurrences(SpacingPolicy::Defer);
+    assert_eq!(values(&deferred), vec![1, 2, 3]);
+    for pair in deferred.windows(2) {
+        assert_eq!(pair[1].0 - pair[0].0, Duration::from_millis(10), "{:?}", deferred);
+    }
+}
+
+/// Occurrences of the action of [Aligned], and the
+/// errors returned when scheduling it in the past.
+type Alignments = Arc<Mutex<(Vec<(EventTag, u32)>, Vec<ScheduleError>)>>;
+
+/// Schedules its action at absolute tags and instants.
+struct Aligned {
+    id: ReactorId,
+    act: LogicalAction<u32>,
+    observed: Alignments,
+}
+
+impl ReactorInitializer for Aligned {
+    type Wrapped = Self;
+    type Params = Alignments;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble(observed: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        act: cc.new_logical_action("act", None),
+                        observed,
+                    })
+                },
+                2,
+                [Some("on_startup"), Some("on_act")],
+                |decl, this, [on_startup, on_act]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(act);
+                        on_act: triggers(act) effects(act);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Aligned {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        let mut observed = self.observed.lock().unwrap();
+        if rid.raw() == 0 {
+            ctx.schedule_with_v(&mut self.act, Some(1), Offset::AfterTag(tag!(T0 + 5 ms)));
+            let past = ctx.try_schedule_with_v(&mut self.act, Some(0), Offset::AfterTag(tag!(T0)));
+            observed.1.extend(past.err());
+            return;
+        }
+        let value = ctx.get(&self.act).unwrap();
+        observed.0.push((ctx.get_tag(), value));
+        if value == 1 {
+            let start = ctx.get_start_time();
+            ctx.schedule_with_v(&mut self.act, Some(2), Offset::AtPhysicalTime(start + delay!(20 ms)));
+            let past = ctx.try_schedule_with_v(&mut self.act, Some(0), Offset::AtPhysicalTime(start));
+            observed.1.extend(past.err());
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_logical_action(&mut self.act);
+    }
+}
+
+#[test]
+fn test_schedule_at_absolute_tags() {
+    let observed: Alignments = Default::default();
+    SyncScheduler::run_main::<Aligned>(Default::default(), observed.clone());
+    let (occurrences, errors) = std::mem::take(&mut *observed.lock().unwrap());
+    assert_eq!(occurrences, vec![(tag!(T0 + 5 ms), 1), (tag!(T0 + 20 ms), 2)]);
+    assert_eq!(
+        errors,
+        vec![
+            ScheduleError::NotInTheFuture { requested: tag!(T0), current: tag!(T0) },
+            ScheduleError::NotInTheFuture { requested: tag!(T0), current: tag!(T0 + 5 ms) },
+        ]
+    );
+}
+
+/// Values of the action of [Collider], and the errors
+/// returned when scheduling it.
+type Collisions = Arc<Mutex<(Vec<u32>, Vec<ScheduleError>)>>;
+
+/// Schedules its logical action three times at the same tag.
+struct Collider {
+    id: ReactorId,
+    act: LogicalAction<u32>,
+    collisions: Collisions,
+}
+
+impl ReactorInitializer for Collider {
+    type Wrapped = Self;
+    type Params = (CollisionPolicy, Collisions);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble((policy, collisions): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        act: cc.new_logical_action("act", None).with_collision_policy(policy),
+                        collisions,
+                    })
+                },
+                2,
+                [Some("on_startup"), Some("on_act")],
+                |decl, this, [on_startup, on_act]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(act);
+                        on_act: triggers(act);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Collider {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        let mut collisions = self.collisions.lock().unwrap();
+        if rid.raw() == 0 {
+            for i in 1..=3 {
+                if let Err(e) = ctx.try_schedule_with_v(&mut self.act, Some(i), after!(5 ms)) {
+                    collisions.1.push(e);
+                }
+            }
+        } else {
+            collisions.0.push(ctx.get(&self.act).unwrap());
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_logical_action(&mut self.act);
+    }
+}
+
+fn collisions(policy: CollisionPolicy) -> (Vec<u32>, Vec<ScheduleError>) {
+    let collisions: Collisions = Default::default();
+    SyncScheduler::run_main::<Collider>(Default::default(), (policy, collisions.clone()));
+    let result = std::mem::take(&mut *collisions.lock().unwrap());
+    result
+}
+
+#[test]
+fn test_collision_policies() {
+    assert_eq!(collisions(CollisionPolicy::Replace), (vec![3], vec![]));
+    assert_eq!(collisions(CollisionPolicy::KeepFirst), (vec![1], vec![]));
+
+    let rejected = ScheduleError::AlreadyScheduled { tag: tag!(T0 + 5 ms) };
+    assert_eq!(collisions(CollisionPolicy::Reject), (vec![1], vec![rejected, rejected]));
+}
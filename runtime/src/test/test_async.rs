@@ -0,0 +1,85 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::runtime::{Handle, Runtime};
+
+use crate::assembly::*;
+use crate::*;
+
+type Received = Arc<Mutex<Vec<u32>>>;
+
+/// At startup, spawns a task whose output schedules the
+/// action, and another that schedules it through a link.
+struct Spawner {
+    id: ReactorId,
+    act: PhysicalActionRef<u32>,
+    runtime: Handle,
+    received: Received,
+}
+
+impl ReactorInitializer for Spawner {
+    type Wrapped = Self;
+    type Params = (Handle, Received);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble((runtime, received): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        act: cc.new_physical_action("act", None),
+                        runtime,
+                        received,
+                    })
+                },
+                2,
+                [Some("on_startup"), Some("on_act")],
+                |decl, this, [on_startup, on_act]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(act);
+                        on_act: triggers(act);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Spawner {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        if rid.raw() == 0 {
+            let link = ctx.async_link();
+            let act = self.act.clone();
+            let first = ctx.spawn_task(&self.runtime, &self.act, async { 1 });
+            self.runtime.spawn(async move {
+                // schedule at a later tag than the first task
+                first.await.unwrap().unwrap();
+                link.schedule_physical_with_v(&act, Some(2), Offset::Asap).unwrap();
+            });
+        } else {
+            self.received.lock().unwrap().push(ctx.get(&self.act).unwrap());
+        }
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+#[test]
+fn test_tasks_schedule_physical_actions() {
+    let runtime = Runtime::new().unwrap();
+    let received: Received = Default::default();
+    let options = SchedulerOptions {
+        keep_alive: true,
+        timeout: Some(Duration::from_secs(2)),
+        ..Default::default()
+    };
+    // returns once the tasks have dropped their links
+    SyncScheduler::run_main::<Spawner>(options, (runtime.handle().clone(), received.clone()));
+    assert_eq!(*received.lock().unwrap(), vec![1, 2]);
+}
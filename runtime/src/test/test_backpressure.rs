@@ -0,0 +1,110 @@
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::*;
+use crate::*;
+
+/// Values received by the reactor, and the number
+/// of sends that failed.
+type Observed = Arc<Mutex<(Vec<u32>, usize)>>;
+
+/// Sends a burst of physical events while its startup
+/// reaction is still running, and records the values
+/// it receives.
+struct Burst {
+    id: ReactorId,
+    act: PhysicalActionRef<u32>,
+    observed: Observed,
+}
+
+impl ReactorInitializer for Burst {
+    type Wrapped = Self;
+    type Params = Observed;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble(observed: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        act: cc.new_physical_action("act", None),
+                        observed,
+                    })
+                },
+                2,
+                [Some("on_startup"), Some("on_act")],
+                |decl, this, [on_startup, on_act]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(act);
+                        on_act: triggers(act);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Burst {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        if rid.raw() == 0 {
+            let act = self.act.clone();
+            let observed = self.observed.clone();
+            ctx.spawn_physical_thread(move |link| {
+                for i in 1..=10 {
+                    if link.schedule_physical_with_v(&act, Some(i), Offset::Asap).is_err() {
+                        observed.lock().unwrap().1 += 1;
+                    }
+                }
+            });
+            // the scheduler does not receive events while this runs
+            std::thread::sleep(Duration::from_millis(50));
+        } else {
+            let value = ctx.get(&self.act).unwrap();
+            self.observed.lock().unwrap().0.push(value);
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_physical_action(&mut self.act);
+    }
+}
+
+fn run_burst(policy: OverflowPolicy) -> (Vec<u32>, usize) {
+    let observed: Observed = Default::default();
+    let options = SchedulerOptions {
+        keep_alive: true,
+        timeout: Some(Duration::from_millis(200)),
+        physical_channel: Some(ChannelBound { capacity: 3, policy }),
+        ..Default::default()
+    };
+    SyncScheduler::run_main::<Burst>(options, observed.clone());
+    let observed = observed.lock().unwrap();
+    observed.clone()
+}
+
+#[test]
+fn test_bounded_channel_drops_newest() {
+    let (values, failed) = run_burst(OverflowPolicy::DropNewest);
+    assert_eq!(values, vec![1, 2, 3]);
+    assert_eq!(failed, 7);
+}
+
+#[test]
+fn test_bounded_channel_drops_oldest() {
+    let (values, failed) = run_burst(OverflowPolicy::DropOldest);
+    assert_eq!(values, vec![8, 9, 10]);
+    assert_eq!(failed, 0);
+}
+
+#[test]
+fn test_bounded_channel_blocks() {
+    let (values, failed) = run_burst(OverflowPolicy::Block);
+    assert_eq!(values, (1..=10).collect::<Vec<_>>());
+    assert_eq!(failed, 0);
+}
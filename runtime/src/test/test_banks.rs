@@ -0,0 +1,92 @@
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::*;
+use crate::*;
+
+/// Bank indices and parameters seen by the members at startup.
+type Seen = Arc<Mutex<Vec<(Option<usize>, u32)>>>;
+
+/// Records its bank index and its parameter at startup.
+struct Member {
+    id: ReactorId,
+    bank_index: Option<usize>,
+    scale: u32,
+    seen: Seen,
+}
+
+impl ReactorInitializer for Member {
+    type Wrapped = Self;
+    type Params = (u32, Seen);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(1);
+
+    fn assemble((scale, seen): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| Ok(Self { id, bank_index: cc.bank_index(), scale, seen }),
+                1,
+                [Some("on_startup")],
+                |decl, _, [on_startup]| decl.triggered_by_startup(on_startup),
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Member {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, _ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        self.seen.lock().unwrap().push((self.bank_index, self.scale));
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+/// A single member, and a bank whose members are
+/// scaled by the given factors, in order.
+struct Parent {
+    id: ReactorId,
+}
+
+impl ReactorInitializer for Parent {
+    type Wrapped = Self;
+    type Params = (Vec<u32>, Seen);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(0);
+
+    fn assemble((scales, seen): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        assert_eq!(ctx.bank_index(), None);
+        let mut scales = scales.into_iter();
+        ctx.assemble(|ctx| {
+            ctx.with_child::<Member, _>("single", (0, seen.clone()), |ctx, _| {
+                ctx.with_child_bank::<Member, _, _>(
+                    "bank",
+                    scales.len(),
+                    |i| (scales.next().unwrap() * i as u32, seen.clone()),
+                    |ctx, _| ctx.assemble_self(|_, id| Ok(Self { id }), 0, [], |_, _, []| Ok(())),
+                )
+            })
+        })
+    }
+}
+
+impl ReactorBehavior for Parent {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, _ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        unreachable!()
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+#[test]
+fn test_bank_members_know_their_index() {
+    let seen: Seen = Default::default();
+    SyncScheduler::run_main::<Parent>(Default::default(), (vec![10, 20, 30], seen.clone()));
+    let mut seen = std::mem::take(&mut *seen.lock().unwrap());
+    seen.sort();
+    assert_eq!(seen, vec![(None, 0), (Some(0), 0), (Some(1), 20), (Some(2), 60)]);
+}
@@ -0,0 +1,50 @@
+use crate::bench::*;
+use crate::test::testutil::*;
+use crate::*;
+
+reactor_program! {
+    struct Scripted(params: (Vec<(Duration, u32)>, Recording<u32>));
+    instances {
+        source: ScriptedSource<u32> = params.0,
+        recorder: Recorder<u32> = params.1,
+    }
+    connections {
+        source.output -> recorder.input;
+    }
+}
+
+#[test]
+fn test_harness_measures_each_run() {
+    let ms = Duration::from_millis;
+    let recording: Recording<u32> = Default::default();
+    let report = Harness::new("scripted")
+        .warmup(1)
+        .iterations(3)
+        .run::<Scripted>(Default::default, || {
+            (vec![(ms(0), 1), (ms(2), 2), (ms(4), 3)], recording.clone())
+        });
+    // the warmup ran too
+    assert_eq!(recording.lock().unwrap().len(), 12);
+    assert_eq!(report.iterations.len(), 3);
+    for it in &report.iterations {
+        // startup, then one tag per value
+        assert_eq!(it.tags, 4, "{:?}", it);
+        assert!(it.reactions >= 6, "{:?}", it);
+        assert!(it.wall_time >= ms(4), "{:?}", it);
+        assert!(it.mean_lag <= it.max_lag);
+    }
+
+    let mut csv = Vec::new();
+    report.write_csv(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    assert_eq!(csv.lines().count(), 4);
+    assert!(csv.lines().nth(3).unwrap().starts_with("scripted,2,"), "{}", csv);
+
+    let mut json = Vec::new();
+    report.write_json(&mut json).unwrap();
+    for line in String::from_utf8(json).unwrap().lines() {
+        let value: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert_eq!(value["name"], "scripted");
+        assert_eq!(value["tags"], 4);
+    }
+}
@@ -0,0 +1,185 @@
+use super::testutil::*;
+use crate::assembly::*;
+use crate::*;
+
+port_bundle! {
+    /// A request and its response.
+    struct Rpc {
+        out request: u32,
+        in response: u32,
+    }
+}
+
+/// Sends each value it receives as a request, and
+/// outputs the response.
+struct Client {
+    id: ReactorId,
+    trigger: Port<u32>,
+    result: Port<u32>,
+    rpc: Rpc,
+}
+
+impl ReactorInitializer for Client {
+    type Wrapped = Self;
+    type Params = ();
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble(_: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        trigger: cc.new_port("trigger", PortKind::Input),
+                        result: cc.new_port("result", PortKind::Output),
+                        rpc: cc.new_bundle("rpc", BundleSide::Initiator),
+                    })
+                },
+                2,
+                [Some("send"), Some("receive")],
+                |decl, this, [send, receive]| {
+                    declare_reactions! {
+                        (decl, this)
+                        send: triggers(trigger) effects((this.rpc.request));
+                        receive: triggers((this.rpc.response)) effects(result);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Client {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        if rid.index() == 0 {
+            let value = ctx.get(&self.trigger);
+            ctx.set_opt(&mut self.rpc.request, value);
+        } else {
+            let value = ctx.get(&self.rpc.response);
+            ctx.set_opt(&mut self.result, value);
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.trigger);
+        ctx.cleanup_port(&mut self.result);
+        ctx.cleanup_port(&mut self.rpc.request);
+        ctx.cleanup_port(&mut self.rpc.response);
+    }
+}
+
+/// Answers each request with its successor.
+struct Server {
+    id: ReactorId,
+    rpc: Rpc,
+}
+
+impl ReactorInitializer for Server {
+    type Wrapped = Self;
+    type Params = ();
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(1);
+
+    fn assemble(_: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        rpc: cc.new_bundle("rpc", BundleSide::Responder),
+                    })
+                },
+                1,
+                [Some("answer")],
+                |decl, this, [answer]| {
+                    declare_reactions! {
+                        (decl, this)
+                        answer: triggers((this.rpc.request)) effects((this.rpc.response));
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Server {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        let value = ctx.get(&self.rpc.request).map(|v| v + 1);
+        ctx.set_opt(&mut self.rpc.response, value);
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.rpc.request);
+        ctx.cleanup_port(&mut self.rpc.response);
+    }
+}
+
+/// Connects a client to a server with a single call.
+struct Main {
+    id: ReactorId,
+}
+
+impl ReactorInitializer for Main {
+    type Wrapped = Self;
+    type Params = Recording<u32>;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(0);
+
+    fn assemble(recording: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        let script = vec![(Duration::ZERO, 1), (Duration::from_millis(2), 5)];
+        ctx.assemble(|ctx| {
+            ctx.with_child::<ScriptedSource<u32>, _>("source", script, |ctx, source| {
+                ctx.with_child::<Client, _>("client", (), |ctx, client| {
+                    ctx.with_child::<Server, _>("server", (), |ctx, server| {
+                        ctx.with_child::<Recorder<u32>, _>("recorder", recording, |ctx, recorder| {
+                            ctx.assemble_self(
+                                |_, id| Ok(Self { id }),
+                                0,
+                                [],
+                                |decl, _, []| {
+                                    decl.bind_ports(&mut source.output, &mut client.trigger)?;
+                                    decl.bind_ports(&mut client.result, &mut recorder.input)?;
+                                    decl.connect_bundles(&mut client.rpc, &mut server.rpc)
+                                },
+                            )
+                        })
+                    })
+                })
+            })
+        })
+    }
+}
+
+impl ReactorBehavior for Main {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, _ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        unreachable!()
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+#[test]
+fn test_bundle_round_trip() {
+    let recording: Recording<u32> = Default::default();
+    let options = SchedulerOptions {
+        timeout: Some(Duration::from_millis(10)),
+        ..Default::default()
+    };
+    SyncScheduler::run_main::<Main>(options, recording.clone());
+    assert_eq!(
+        *recording.lock().unwrap(),
+        vec![(Duration::ZERO, 2), (Duration::from_millis(2), 6)]
+    );
+}
@@ -0,0 +1,112 @@
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::*;
+use crate::*;
+
+/// The values of the ticks, and their elapsed logical
+/// time, and the number of times startup was executed.
+type Log = Arc<Mutex<(Vec<(u32, Duration)>, usize)>>;
+
+/// Ticks every 10 ms, with the number of the tick as value.
+struct Counter {
+    id: ReactorId,
+    count: u32,
+    tick: LogicalAction<u32>,
+    log: Log,
+}
+
+impl ReactorInitializer for Counter {
+    type Wrapped = Self;
+    type Params = Log;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble(log: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        count: 0,
+                        tick: cc.new_logical_action("tick", None),
+                        log,
+                    })
+                },
+                2,
+                [Some("on_startup"), Some("on_tick")],
+                |decl, this, [on_startup, on_tick]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(tick);
+                        on_tick: triggers(tick) effects(tick);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Counter {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        let mut log = self.log.lock().unwrap();
+        if rid.raw() == 0 {
+            log.1 += 1;
+        } else {
+            self.count += 1;
+            let value = ctx.get(&self.tick).unwrap();
+            log.0.push((value, ctx.get_elapsed_logical_time()));
+        }
+        ctx.schedule_with_v(&mut self.tick, Some(self.count + 1), Offset::After(Duration::from_millis(10)));
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_logical_action(&mut self.tick);
+    }
+
+    fn serialize_state(&self) -> Result<Option<ReactorState>, CheckpointError> {
+        ReactorState::of(&(self.count, self.tick.pending_values())).map(Some)
+    }
+
+    fn deserialize_state(&mut self, state: &ReactorState) -> Result<(), CheckpointError> {
+        let (count, pending) = state.get()?;
+        self.count = count;
+        self.tick.restore_pending_values(pending);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_resume_from_checkpoint() {
+    let ms = Duration::from_millis;
+    let path = std::env::temp_dir().join(format!("reactor_rt_checkpoint_{}.json", std::process::id()));
+
+    let log: Log = Default::default();
+    let options = SchedulerOptions {
+        timeout: Some(ms(35)),
+        checkpoint: Some(CheckpointPolicy::every(ms(20), &path)),
+        ..Default::default()
+    };
+    SyncScheduler::run_main::<Counter>(options, log.clone());
+    assert_eq!(*log.lock().unwrap(), (vec![(1, ms(10)), (2, ms(20)), (3, ms(30))], 1));
+
+    // the checkpoint is taken at the end of the tag at 20 ms
+    let checkpoint = Checkpoint::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(checkpoint.tag, EventTag::offset(ms(20), 0));
+    assert_eq!(checkpoint.events.len(), 1, "{:?}", checkpoint.events);
+    assert_eq!(checkpoint.events[0].tag, EventTag::offset(ms(30), 0));
+
+    let log: Log = Default::default();
+    let options = SchedulerOptions {
+        timeout: Some(ms(45)),
+        resume_from: Some(checkpoint),
+        ..Default::default()
+    };
+    SyncScheduler::run_main::<Counter>(options, log.clone());
+    // startup is not executed again, and the tick at 30 ms keeps its value
+    assert_eq!(*log.lock().unwrap(), (vec![(3, ms(30)), (4, ms(40))], 0));
+}
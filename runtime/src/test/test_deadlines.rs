@@ -0,0 +1,120 @@
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::*;
+use crate::*;
+
+type Violations = Arc<Mutex<Vec<bool>>>;
+
+/// Takes 20 ms to set its output at startup.
+struct Slow {
+    id: ReactorId,
+    out: Port<u32>,
+}
+
+impl ReactorInitializer for Slow {
+    type Wrapped = Self;
+    type Params = ();
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(1);
+
+    fn assemble(_: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| Ok(Self { id, out: cc.new_port("out", PortKind::Output) }),
+                1,
+                [Some("work")],
+                |decl, this, [work]| {
+                    declare_reactions! {
+                        (decl, this)
+                        work: triggers(startup) effects(out);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Slow {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        assert!(!ctx.deadline_violated());
+        std::thread::sleep(Duration::from_millis(20));
+        ctx.set(&mut self.out, 1);
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.out);
+    }
+}
+
+/// Has a tight and a loose deadline on reactions
+/// triggered by its input.
+struct Checker {
+    id: ReactorId,
+    inp: Port<u32>,
+    violations: Violations,
+}
+
+impl ReactorInitializer for Checker {
+    type Wrapped = Self;
+    type Params = Violations;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble(violations: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        inp: cc.new_port("inp", PortKind::Input),
+                        violations,
+                    })
+                },
+                2,
+                [Some("tight"), Some("loose")],
+                |decl, this, [tight, loose]| {
+                    declare_reactions! {
+                        (decl, this)
+                        tight: triggers(inp) deadline(Duration::from_millis(5));
+                        loose: triggers(inp) deadline(Duration::from_secs(10));
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Checker {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        self.violations.lock().unwrap().push(ctx.deadline_violated());
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+reactor_program! {
+    struct Program(violations: Violations);
+    instances {
+        slow: Slow = (),
+        checker: Checker = violations.clone(),
+    }
+    connections {
+        slow.out -> checker.inp;
+    }
+}
+
+#[test]
+fn test_deadline_violation() {
+    let violations: Violations = Default::default();
+    SyncScheduler::run_main::<Program>(Default::default(), violations.clone());
+    // reactions of a reactor execute in order
+    assert_eq!(*violations.lock().unwrap(), vec![true, false]);
+}
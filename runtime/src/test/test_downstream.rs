@@ -0,0 +1,122 @@
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::*;
+use crate::*;
+
+type Counts = Arc<Mutex<Vec<usize>>>;
+
+/// Records the number of reactions downstream of
+/// each of its outputs at startup.
+struct Probe {
+    id: ReactorId,
+    out: Port<u32>,
+    diagnostics: Port<u32>,
+    counts: Counts,
+}
+
+impl ReactorInitializer for Probe {
+    type Wrapped = Self;
+    type Params = Counts;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(1);
+
+    fn assemble(counts: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        out: cc.new_port("out", PortKind::Output),
+                        diagnostics: cc.new_port("diagnostics", PortKind::Output),
+                        counts,
+                    })
+                },
+                1,
+                [Some("probe")],
+                |decl, this, [probe]| {
+                    declare_reactions! {
+                        (decl, this)
+                        probe: triggers(startup) effects(out, diagnostics);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Probe {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        let mut counts = self.counts.lock().unwrap();
+        counts.push(ctx.downstream_count(&self.out));
+        counts.push(ctx.downstream_count(&self.diagnostics));
+        assert!(ctx.has_downstream(&self.out));
+        assert!(!ctx.has_downstream(&self.diagnostics));
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+/// Triggered by its input.
+struct Reader {
+    id: ReactorId,
+    inp: Port<u32>,
+}
+
+impl ReactorInitializer for Reader {
+    type Wrapped = Self;
+    type Params = ();
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(1);
+
+    fn assemble(_: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| Ok(Self { id, inp: cc.new_port("inp", PortKind::Input) }),
+                1,
+                [Some("read")],
+                |decl, this, [read]| {
+                    declare_reactions! {
+                        (decl, this)
+                        read: triggers(inp);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Reader {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, _ctx: &mut ReactionCtx, _rid: LocalReactionId) {}
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+reactor_program! {
+    /// A probe whose output is read by two readers,
+    /// and whose diagnostics port is not connected.
+    struct Program(counts: Counts);
+    instances {
+        probe: Probe = counts.clone(),
+        r1: Reader = (),
+        r2: Reader = (),
+    }
+    connections {
+        probe.out -> r1.inp;
+        probe.out -> r2.inp;
+    }
+}
+
+#[test]
+fn test_downstream_count() {
+    let counts: Counts = Default::default();
+    SyncScheduler::run_main::<Program>(Default::default(), counts.clone());
+    assert_eq!(*counts.lock().unwrap(), vec![2, 0]);
+}
@@ -0,0 +1,143 @@
+use super::testutil::*;
+use crate::assembly::*;
+use crate::*;
+
+/// The interface through which the main reactor sees its filter.
+trait Filter {
+    fn ports(&mut self) -> (&mut Port<u32>, &mut Port<u32>);
+}
+
+/// Multiplies its input by `K`.
+struct Scale<const K: u32> {
+    id: ReactorId,
+    input: Port<u32>,
+    output: Port<u32>,
+}
+
+impl<const K: u32> ReactorInitializer for Scale<K> {
+    type Wrapped = Self;
+    type Params = ();
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(1);
+
+    fn assemble(_: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        input: cc.new_port("input", PortKind::Input),
+                        output: cc.new_port("output", PortKind::Output),
+                    })
+                },
+                1,
+                [Some("scale")],
+                |decl, this, [scale]| {
+                    declare_reactions! {
+                        (decl, this)
+                        scale: triggers(input) effects(output);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl<const K: u32> ReactorBehavior for Scale<K> {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        let value = ctx.get(&self.input).map(|v| v * K);
+        ctx.set_opt(&mut self.output, value);
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.input);
+        ctx.cleanup_port(&mut self.output);
+    }
+}
+
+impl<const K: u32> Filter for Scale<K> {
+    fn ports(&mut self) -> (&mut Port<u32>, &mut Port<u32>) {
+        (&mut self.input, &mut self.output)
+    }
+}
+
+/// Looks up a filter by name, like a plugin registry would.
+fn filter_named(name: &str) -> Box<dyn DynReactorInitializer<dyn Filter>> {
+    match name {
+        "double" => DynReactor::<Scale<2>, dyn Filter>::boxed((), |r| r),
+        "triple" => DynReactor::<Scale<3>, dyn Filter>::boxed((), |r| r),
+        _ => panic!("unknown filter {}", name),
+    }
+}
+
+/// Feeds a script into a filter chosen by name.
+struct Configured {
+    id: ReactorId,
+}
+
+impl ReactorInitializer for Configured {
+    type Wrapped = Self;
+    type Params = (&'static str, Recording<u32>);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(0);
+
+    fn assemble((filter, recording): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        let script = vec![(Duration::ZERO, 1), (Duration::from_millis(2), 5)];
+        ctx.assemble(|ctx| {
+            ctx.with_child::<ScriptedSource<u32>, _>("source", script, |ctx, source| {
+                ctx.with_child_dyn("filter", filter_named(filter), |ctx, filter| {
+                    ctx.with_child::<Recorder<u32>, _>("recorder", recording, |ctx, recorder| {
+                        ctx.assemble_self(
+                            |_, id| Ok(Self { id }),
+                            0,
+                            [],
+                            |decl, _, []| {
+                                let (input, output) = filter.ports();
+                                decl.bind_ports(&mut source.output, input)?;
+                                decl.bind_ports(output, &mut recorder.input)
+                            },
+                        )
+                    })
+                })
+            })
+        })
+    }
+}
+
+impl ReactorBehavior for Configured {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, _ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        unreachable!()
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+fn run_with_filter(filter: &'static str) -> Vec<(Duration, u32)> {
+    let recording: Recording<u32> = Default::default();
+    let options = SchedulerOptions {
+        timeout: Some(Duration::from_millis(10)),
+        ..Default::default()
+    };
+    SyncScheduler::run_main::<Configured>(options, (filter, recording.clone()));
+    let result = recording.lock().unwrap().clone();
+    result
+}
+
+#[test]
+fn test_child_chosen_at_runtime() {
+    assert_eq!(
+        run_with_filter("double"),
+        vec![(Duration::ZERO, 2), (Duration::from_millis(2), 10)]
+    );
+    assert_eq!(
+        run_with_filter("triple"),
+        vec![(Duration::ZERO, 3), (Duration::from_millis(2), 15)]
+    );
+}
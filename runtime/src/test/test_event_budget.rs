@@ -0,0 +1,103 @@
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::*;
+use crate::*;
+
+type Observed = Arc<Mutex<(Vec<u128>, Option<ShutdownReason>)>>;
+
+/// Schedules an action at each of the first 10 ms at
+/// startup, and records the times at which it is triggered.
+struct Burst {
+    id: ReactorId,
+    tick: LogicalAction<()>,
+    observed: Observed,
+}
+
+impl ReactorInitializer for Burst {
+    type Wrapped = Self;
+    type Params = Observed;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(3);
+
+    fn assemble(observed: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        tick: cc.new_logical_action("tick", None),
+                        observed,
+                    })
+                },
+                3,
+                [Some("on_startup"), Some("on_tick"), Some("on_shutdown")],
+                |decl, this, [on_startup, on_tick, on_shutdown]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(tick);
+                        on_tick: triggers(tick);
+                        on_shutdown: triggers(shutdown);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Burst {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        match rid.raw() {
+            0 => {
+                for ms in 1..=10 {
+                    ctx.schedule(&mut self.tick, Offset::After(Duration::from_millis(ms)));
+                }
+            }
+            1 => self
+                .observed
+                .lock()
+                .unwrap()
+                .0
+                .push(ctx.get_elapsed_logical_time().as_millis()),
+            _ => self.observed.lock().unwrap().1 = ctx.shutdown_reason(),
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_logical_action(&mut self.tick);
+    }
+}
+
+fn run_with_budget(policy: BudgetPolicy) -> (Vec<u128>, Option<ShutdownReason>) {
+    let observed: Observed = Default::default();
+    let options = SchedulerOptions {
+        event_budget: Some(EventBudget { max_events: 4, policy }),
+        ..Default::default()
+    };
+    SyncScheduler::run_main::<Burst>(options, observed.clone());
+    let result = observed.lock().unwrap().clone();
+    result
+}
+
+#[test]
+fn test_budget_drops_latest_events() {
+    let (ticks, reason) = run_with_budget(BudgetPolicy::DropLatest);
+    assert_eq!(ticks, vec![1, 2, 3, 4]);
+    assert_eq!(reason, Some(ShutdownReason::EventQueueEmpty));
+}
+
+#[test]
+fn test_budget_shuts_down() {
+    let (ticks, reason) = run_with_budget(BudgetPolicy::Shutdown);
+    assert_eq!(ticks, Vec::<u128>::new());
+    assert_eq!(reason, Some(ShutdownReason::EventBudgetExceeded));
+}
+
+#[test]
+fn test_budget_accepts_logical_events() {
+    let (ticks, _) = run_with_budget(BudgetPolicy::RejectPhysical);
+    assert_eq!(ticks.len(), 10);
+}
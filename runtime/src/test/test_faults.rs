@@ -0,0 +1,218 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::*;
+use crate::*;
+
+/// Strong counts of the token, observed at each check.
+type Counts = Arc<Mutex<Vec<usize>>>;
+
+/// Sends copies of a token through a physical action at
+/// startup, then checks twice how many copies are alive.
+struct Sender {
+    id: ReactorId,
+    value: PhysicalActionRef<Arc<()>>,
+    check: LogicalAction<()>,
+    token: Arc<()>,
+    counts: Counts,
+}
+
+impl ReactorInitializer for Sender {
+    type Wrapped = Self;
+    type Params = Counts;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(3);
+
+    fn assemble(counts: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        value: cc.new_physical_action("value", None),
+                        check: cc.new_logical_action("check", None),
+                        token: Arc::new(()),
+                        counts,
+                    })
+                },
+                3,
+                [Some("on_startup"), Some("on_value"), Some("on_check")],
+                |decl, this, [on_startup, on_value, on_check]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(value, check);
+                        on_value: triggers(value);
+                        on_check: triggers(check) effects(check);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Sender {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        match rid.raw() {
+            0 => {
+                let action = self.value.clone();
+                let token = self.token.clone();
+                ctx.spawn_physical_thread(move |link| {
+                    for _ in 0..3 {
+                        let _ = link.schedule_physical_with_v(&action, Some(token.clone()), Offset::Asap);
+                        std::thread::sleep(Duration::from_millis(1));
+                    }
+                });
+                ctx.schedule(&mut self.check, Offset::After(Duration::from_millis(30)));
+            }
+            1 => panic!("the event should have been dropped"),
+            _ => {
+                let mut counts = self.counts.lock().unwrap();
+                counts.push(Arc::strong_count(&self.token));
+                if counts.len() == 1 {
+                    ctx.schedule(&mut self.check, Offset::After(Duration::from_millis(10)));
+                }
+            }
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_physical_action(&mut self.value);
+        ctx.cleanup_logical_action(&mut self.check);
+    }
+}
+
+#[test]
+fn test_values_of_dropped_events_are_released() {
+    let counts: Counts = Default::default();
+    let options = SchedulerOptions {
+        faults: Some(FaultInjector { physical_drop_rate: 1.0, ..Default::default() }),
+        ..Default::default()
+    };
+    SyncScheduler::run_main::<Sender>(options, counts.clone());
+    // the values are released at the end of the first check
+    assert_eq!(counts.lock().unwrap().last(), Some(&1));
+}
+
+/// Lag of each tick behind physical time, and whether
+/// the deadline of the tick was violated.
+type Ticks = Arc<Mutex<Vec<(Duration, bool)>>>;
+
+/// Records its ticks, which have a deadline of 5 ms.
+struct Ticker {
+    id: ReactorId,
+    tick: Timer,
+    ticks: Ticks,
+}
+
+impl ReactorInitializer for Ticker {
+    type Wrapped = Self;
+    type Params = Ticks;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble(ticks: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        tick: cc.new_timer("tick", Duration::from_millis(30), Duration::from_millis(30)),
+                        ticks,
+                    })
+                },
+                2,
+                [Some("on_startup"), Some("on_tick")],
+                |decl, this, [on_startup, on_tick]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(tick);
+                        on_tick: triggers(tick) deadline(Duration::from_millis(5));
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Ticker {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        if rid.raw() == 0 {
+            ctx.bootstrap_timer(&mut self.tick);
+            return;
+        }
+        ctx.reschedule_timer(&mut self.tick);
+        let lag = ctx.get_physical_time().saturating_duration_since(ctx.get_logical_time());
+        self.ticks.lock().unwrap().push((lag, ctx.deadline_violated()));
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+fn run_ticker(faults: FaultInjector) -> Ticks {
+    let ticks: Ticks = Default::default();
+    let options = SchedulerOptions {
+        timeout: Some(Duration::from_millis(310)),
+        faults: Some(faults),
+        ..Default::default()
+    };
+    SyncScheduler::run_main::<Ticker>(options, ticks.clone());
+    ticks
+}
+
+/// Indices of the ticks that lag more than 8 ms behind physical time.
+fn delayed_ticks(ticks: &Ticks) -> Vec<usize> {
+    let ticks = ticks.lock().unwrap();
+    assert_eq!(ticks.len(), 10);
+    (0..ticks.len()).filter(|&i| ticks[i].0 > Duration::from_millis(8)).collect()
+}
+
+#[test]
+fn test_random_reaction_delay() {
+    let faults = FaultInjector {
+        // with this seed, each delay is either zero or above 15 ms
+        seed: 12,
+        reaction_delay: Some((0.5, Duration::from_millis(25))),
+        ..Default::default()
+    };
+    let delayed = delayed_ticks(&run_ticker(faults.clone()));
+    assert!(!delayed.is_empty() && delayed.len() < 10, "delayed {:?}", delayed);
+    // the same seed delays the same reactions
+    assert_eq!(delayed_ticks(&run_ticker(faults)), delayed);
+}
+
+#[test]
+fn test_forced_deadline_miss() {
+    let ticks = run_ticker(FaultInjector {
+        seed: 1,
+        reaction_delay: Some((1.0, Duration::from_millis(50))),
+        ..Default::default()
+    });
+    let ticks = ticks.lock().unwrap();
+    // with this seed, every delay exceeds the deadline of 5 ms
+    assert!(ticks.iter().all(|&(_, violated)| violated), "{:?}", ticks);
+}
+
+#[test]
+fn test_injected_reaction_failure() {
+    let ticks: Ticks = Default::default();
+    let options = SchedulerOptions {
+        faults: Some(FaultInjector {
+            reaction_failures: vec![ReactionFailure { reaction: "/1".into(), tag: tag!(T0 + 90 ms) }],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let payload = catch_unwind(AssertUnwindSafe(|| SyncScheduler::run_main::<Ticker>(options, ticks.clone()))).unwrap_err();
+    let message = payload.downcast_ref::<String>().unwrap();
+    assert!(message.contains("Injected failure of reaction /1@on_tick"), "{}", message);
+    // the ticks before the failure were executed
+    assert_eq!(ticks.lock().unwrap().len(), 2);
+}
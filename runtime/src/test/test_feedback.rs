@@ -0,0 +1,128 @@
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::*;
+use crate::stdlib::feedback::*;
+use crate::*;
+
+type Trace = Arc<Mutex<Vec<(EventTag, u32)>>>;
+
+/// Increments its input until it reaches 5.
+struct Counter {
+    id: ReactorId,
+    inp: Port<u32>,
+    out: Port<u32>,
+    trace: Trace,
+}
+
+impl ReactorInitializer for Counter {
+    type Wrapped = Self;
+    type Params = Trace;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(1);
+
+    fn assemble(trace: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        inp: cc.new_port("inp", PortKind::Input),
+                        out: cc.new_port("out", PortKind::Output),
+                        trace,
+                    })
+                },
+                1,
+                [Some("count")],
+                |decl, this, [count]| {
+                    declare_reactions! {
+                        (decl, this)
+                        count: triggers(inp) effects(out);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Counter {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        let v = ctx.get(&self.inp).unwrap();
+        self.trace.lock().unwrap().push((ctx.get_tag(), v));
+        if v < 5 {
+            ctx.set(&mut self.out, v + 1);
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.inp);
+        ctx.cleanup_port(&mut self.out);
+    }
+}
+
+/// A counter whose output is fed back into its input,
+/// either through a [FeedbackDelay], or directly.
+struct Loop {
+    id: ReactorId,
+}
+
+impl ReactorInitializer for Loop {
+    type Wrapped = Self;
+    /// Whether the connection has an initial value.
+    type Params = (Trace, bool);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(0);
+
+    fn assemble((trace, with_initial_value): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.with_child::<Counter, _>("counter", trace, |ctx, counter| {
+                ctx.with_child::<FeedbackDelay<u32>, _>("feedback", FeedbackDelayParams::new(0), |ctx, feedback| {
+                    ctx.assemble_self(
+                        |_, id| Ok(Self { id }),
+                        0,
+                        [],
+                        |decl, _, []| {
+                            if with_initial_value {
+                                decl.bind_ports(&mut counter.out, &mut feedback.input)?;
+                                decl.bind_ports(&mut feedback.output, &mut counter.inp)
+                            } else {
+                                decl.bind_ports(&mut counter.out, &mut counter.inp)
+                            }
+                        },
+                    )
+                })
+            })
+        })
+    }
+}
+
+impl ReactorBehavior for Loop {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, _ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        unreachable!()
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+#[test]
+fn test_feedback_loop_with_initial_value() {
+    let trace: Trace = Default::default();
+    assert!(SyncScheduler::validate::<Loop>((trace.clone(), true)).is_ok());
+
+    SyncScheduler::run_main::<Loop>(Default::default(), (trace.clone(), true));
+    let expected = (0..=5).map(|i| (EventTag::offset(Duration::ZERO, i), i)).collect::<Vec<_>>();
+    assert_eq!(*trace.lock().unwrap(), expected);
+}
+
+#[test]
+fn test_feedback_loop_without_initial_value_is_a_cycle() {
+    let report = SyncScheduler::validate::<Loop>((Default::default(), false));
+    assert_eq!(report.errors.len(), 1, "{}", report);
+    assert!(report.errors[0].message.starts_with("Cyclic dependency"));
+}
@@ -0,0 +1,38 @@
+use std::time::Instant;
+
+use crate::test::testutil::*;
+use crate::testing::*;
+use crate::*;
+
+reactor_program! {
+    struct Scripted(params: (Vec<(Duration, u32)>, Probed<u32>));
+    instances {
+        source: ScriptedSource<u32> = params.0,
+        probe: Probe<u32> = params.1,
+    }
+    connections {
+        source.output -> probe.input;
+    }
+}
+
+#[test]
+fn test_harness_runs_in_logical_time() {
+    let secs = Duration::from_secs;
+    let probed = Probed::default();
+    let start = Instant::now();
+    let run = TestHarness::new()
+        .timeout(secs(5))
+        .run::<Scripted>((vec![(secs(1), 1), (secs(3), 2)], probed.clone()));
+    // nothing waits for the 5 seconds of logical time
+    assert!(start.elapsed() < secs(1), "{:?}", start.elapsed());
+
+    let at = |s| EventTag::offset(secs(s), 0);
+    assert_eq!(run.tags(), vec![at(0), at(1), at(3)]);
+    assert_eq!(probed.values(), vec![(at(1), 1), (at(3), 2)]);
+    probed.assert_value_at(at(3), 2);
+    assert_eq!(probed.value_at(at(5)), None);
+
+    run.assert_present_at(at(0), "startup");
+    run.assert_present_at(at(1), "/source/emit");
+    assert_eq!(run.tags_of("/source/output"), vec![at(1), at(3)]);
+}
@@ -0,0 +1,63 @@
+use std::io::Cursor;
+
+use super::testutil::*;
+use crate::assembly::*;
+use crate::stdlib::io::*;
+use crate::*;
+
+struct Main {
+    id: ReactorId,
+}
+
+impl ReactorInitializer for Main {
+    type Wrapped = Self;
+    type Params = (&'static str, Recording<String>);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(0);
+
+    fn assemble((input, recording): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        let params = LinesParams::from_reader(Cursor::new(input));
+        ctx.assemble(|ctx| {
+            ctx.with_child::<StdinLines, _>("stdin", params, |ctx, stdin| {
+                ctx.with_child::<Recorder<String>, _>("recorder", recording, |ctx, recorder| {
+                    ctx.assemble_self(
+                        |_, id| Ok(Self { id }),
+                        0,
+                        [],
+                        |decl, _, []| decl.bind_ports(&mut stdin.line, &mut recorder.input),
+                    )
+                })
+            })
+        })
+    }
+}
+
+impl ReactorBehavior for Main {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, _ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        unreachable!()
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+#[test]
+fn test_lines_are_emitted_until_end_of_input() {
+    let recording: Recording<String> = Default::default();
+    let options = SchedulerOptions {
+        keep_alive: true,
+        timeout: Some(Duration::from_secs(10)),
+        ..Default::default()
+    };
+    let start = Instant::now();
+    SyncScheduler::run_main::<Main>(options, ("left\nright\r\n\nquit", recording.clone()));
+
+    assert!(
+        start.elapsed() < Duration::from_secs(5),
+        "the program should stop at the end of the input"
+    );
+    let lines: Vec<String> = recording.lock().unwrap().iter().map(|(_, l)| l.clone()).collect();
+    assert_eq!(lines, vec!["left", "right", "", "quit"]);
+}
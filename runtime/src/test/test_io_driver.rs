@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+use std::io::{ErrorKind, Read, Write};
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::*;
+use crate::stdlib::io_driver::*;
+use crate::*;
+
+type Received = Arc<Mutex<Vec<u8>>>;
+
+/// Accepts the given number of connections, which share
+/// an action, and reads them until they are closed.
+struct Server {
+    id: ReactorId,
+    driver: IoDriver,
+    ready: PhysicalActionRef<IoEvent>,
+    listener: Option<(net::TcpListener, IoToken)>,
+    clients: usize,
+    connections: HashMap<IoToken, net::TcpStream>,
+    closed: usize,
+    received: Received,
+}
+
+impl ReactorInitializer for Server {
+    type Wrapped = Self;
+    type Params = (IoDriver, usize, Received);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble((driver, clients, received): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        driver,
+                        ready: cc.new_physical_action("ready", None),
+                        listener: None,
+                        clients,
+                        connections: HashMap::new(),
+                        closed: 0,
+                        received,
+                    })
+                },
+                2,
+                [Some("on_startup"), Some("on_ready")],
+                |decl, this, [on_startup, on_ready]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(ready);
+                        on_ready: triggers(ready);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl Server {
+    fn listen(&mut self, ctx: &mut ReactionCtx) {
+        let mut listener = net::TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let token = self
+            .driver
+            .register(ctx, &mut listener, Interest::READABLE, &self.ready)
+            .unwrap();
+        let addr = listener.local_addr().unwrap();
+        self.listener = Some((listener, token));
+        for _ in 0..self.clients {
+            std::thread::spawn(move || {
+                let mut client = std::net::TcpStream::connect(addr).unwrap();
+                client.write_all(b"ping").unwrap();
+            });
+        }
+    }
+
+    fn on_ready(&mut self, ctx: &mut ReactionCtx) {
+        let event = ctx.get(&self.ready).unwrap();
+        match &mut self.listener {
+            Some((listener, token)) if *token == event.token => {
+                while let Ok((mut stream, _)) = listener.accept() {
+                    let token = self
+                        .driver
+                        .register(ctx, &mut stream, Interest::READABLE, &self.ready)
+                        .unwrap();
+                    self.connections.insert(token, stream);
+                }
+                return;
+            }
+            _ => {}
+        }
+        let stream = self.connections.get_mut(&event.token).unwrap();
+        let mut buf = [0; 64];
+        loop {
+            match stream.read(&mut buf) {
+                Ok(0) => {
+                    self.driver.deregister(stream, event.token).unwrap();
+                    self.connections.remove(&event.token);
+                    self.closed += 1;
+                    if self.closed == self.clients {
+                        ctx.request_stop(Offset::Asap);
+                    }
+                    return;
+                }
+                Ok(n) => self.received.lock().unwrap().extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return,
+                Err(e) => panic!("{}", e),
+            }
+        }
+    }
+}
+
+impl ReactorBehavior for Server {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        if rid.raw() == 0 {
+            self.listen(ctx)
+        } else {
+            self.on_ready(ctx)
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_physical_action(&mut self.ready);
+    }
+}
+
+#[test]
+fn test_server_reads_connection_until_closed() {
+    let received: Received = Default::default();
+    let options = SchedulerOptions {
+        keep_alive: true,
+        timeout: Some(Duration::from_secs(10)),
+        ..Default::default()
+    };
+    let start = Instant::now();
+    SyncScheduler::run_main::<Server>(options, (IoDriver::new().unwrap(), 1, received.clone()));
+
+    assert!(
+        start.elapsed() < Duration::from_secs(5),
+        "the program should stop once the connection is closed"
+    );
+    assert_eq!(*received.lock().unwrap(), b"ping");
+}
+
+#[test]
+fn test_events_at_the_same_tag_are_not_lost() {
+    let received: Received = Default::default();
+    let options = SchedulerOptions {
+        keep_alive: true,
+        timeout: Some(Duration::from_secs(10)),
+        // the events of the connections share a tag, and an action
+        physical_tag_window: Some(Duration::from_millis(50)),
+        ..Default::default()
+    };
+    let start = Instant::now();
+    SyncScheduler::run_main::<Server>(options, (IoDriver::new().unwrap(), 3, received.clone()));
+
+    assert!(
+        start.elapsed() < Duration::from_secs(5),
+        "the program should stop once the connections are closed"
+    );
+    assert_eq!(received.lock().unwrap().len(), 12);
+}
+
+/// The data read on each event of the connection, with
+/// the number of events that were missed before it.
+type Readings = Arc<Mutex<Vec<(Vec<u8>, usize)>>>;
+
+/// Accepts one connection, whose events are dropped when
+/// they are rejected, and reads it until it is closed.
+struct Sensor {
+    id: ReactorId,
+    driver: IoDriver,
+    ready: PhysicalActionRef<IoEvent>,
+    listener: net::TcpListener,
+    connection: Option<net::TcpStream>,
+    readings: Readings,
+}
+
+impl ReactorInitializer for Sensor {
+    type Wrapped = Self;
+    type Params = (IoDriver, net::TcpListener, Readings);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble((driver, listener, readings): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        driver,
+                        ready: cc.new_physical_action("ready", None),
+                        listener,
+                        connection: None,
+                        readings,
+                    })
+                },
+                2,
+                [Some("on_startup"), Some("on_ready")],
+                |decl, this, [on_startup, on_ready]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(ready);
+                        on_ready: triggers(ready);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl Sensor {
+    fn on_ready(&mut self, ctx: &mut ReactionCtx) {
+        let event = ctx.get(&self.ready).unwrap();
+        let stream = match &mut self.connection {
+            Some(stream) => stream,
+            None => {
+                let (mut stream, _) = self.listener.accept().unwrap();
+                self.driver
+                    .register_with_policy(ctx, &mut stream, Interest::READABLE, &self.ready, RejectionPolicy::Drop)
+                    .unwrap();
+                self.connection = Some(stream);
+                return;
+            }
+        };
+        let mut data = Vec::new();
+        let mut buf = [0; 64];
+        loop {
+            match stream.read(&mut buf) {
+                Ok(0) => {
+                    ctx.request_stop(Offset::Asap);
+                    break;
+                }
+                Ok(n) => data.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => panic!("{}", e),
+            }
+        }
+        self.readings.lock().unwrap().push((data, event.missed));
+    }
+}
+
+impl ReactorBehavior for Sensor {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        if rid.raw() == 0 {
+            self.driver
+                .register(ctx, &mut self.listener, Interest::READABLE, &self.ready)
+                .unwrap();
+        } else {
+            self.on_ready(ctx)
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_physical_action(&mut self.ready);
+    }
+}
+
+#[test]
+fn test_rejected_events_are_dropped_and_counted() {
+    let ms = Duration::from_millis;
+    let readings: Readings = Default::default();
+    let listener = net::TcpListener::bind("127.0.0.1:0".parse().unwrap()).unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        let mut client = std::net::TcpStream::connect(addr).unwrap();
+        for (message, pause) in [(b"a", 300), (b"b", 50), (b"c", 250)] {
+            std::thread::sleep(ms(pause));
+            client.write_all(message).unwrap();
+        }
+        std::thread::sleep(ms(250));
+    });
+    let options = SchedulerOptions {
+        keep_alive: true,
+        timeout: Some(Duration::from_secs(10)),
+        // the events of "a" and "b" share a tag, so the second is rejected
+        physical_tag_window: Some(ms(200)),
+        ..Default::default()
+    };
+    SyncScheduler::run_main::<Sensor>(options, (IoDriver::new().unwrap(), listener, readings.clone()));
+
+    let readings = readings.lock().unwrap();
+    let data: Vec<u8> = readings.iter().flat_map(|(data, _)| data.clone()).collect();
+    assert_eq!(data, b"abc");
+    assert_eq!(readings.iter().map(|&(_, missed)| missed).sum::<usize>(), 1, "{:?}", readings);
+}
@@ -0,0 +1,180 @@
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::*;
+use crate::stdlib::late_binding::*;
+use crate::*;
+
+/// Components accessible to the reactions of [Doubler].
+struct DoublerState {
+    out: Port<u32>,
+    count: u32,
+}
+
+/// Sets its output at startup and records it. The first
+/// reaction doubles a counter, unless its body is rebound.
+struct Doubler {
+    id: ReactorId,
+    state: DoublerState,
+    bodies: ReactionBodies<DoublerState>,
+    trace: Arc<Mutex<Vec<u32>>>,
+}
+
+impl ReactorInitializer for Doubler {
+    type Wrapped = Self;
+    type Params = (ReactionBodies<DoublerState>, Arc<Mutex<Vec<u32>>>);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble((bodies, trace): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    let state = DoublerState {
+                        out: cc.new_port("out", PortKind::Output),
+                        count: 21,
+                    };
+                    Ok(Self { id, state, bodies, trace })
+                },
+                2,
+                [Some("emit"), Some("record")],
+                |decl, this, [emit, record]| {
+                    declare_reactions! {
+                        (decl, this)
+                        emit: triggers(startup) effects((this.state.out));
+                        record: triggers((this.state.out));
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Doubler {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        if self.bodies.react(ctx, &mut self.state, rid) {
+            return;
+        }
+        match rid.raw() {
+            0 => ctx.set(&mut self.state.out, self.state.count * 2),
+            _ => self.trace.lock().unwrap().push(ctx.get(&self.state.out).unwrap()),
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.state.out);
+    }
+}
+
+fn run(bodies: ReactionBodies<DoublerState>) -> Vec<u32> {
+    let trace = Arc::new(Mutex::new(Vec::new()));
+    SyncScheduler::run_main::<Doubler>(Default::default(), (bodies, trace.clone()));
+    let values = trace.lock().unwrap().clone();
+    values
+}
+
+#[test]
+fn test_unbound_reactions_use_default_body() {
+    assert_eq!(run(ReactionBodies::new()), vec![42]);
+}
+
+#[test]
+fn test_bound_body_replaces_default() {
+    let bodies = ReactionBodies::new().bind(LocalReactionId::new(0), |ctx, state: &mut DoublerState| {
+        state.count += 1;
+        ctx.set(&mut state.out, state.count)
+    });
+    assert!(bodies.is_bound(LocalReactionId::new(0)));
+    assert!(!bodies.is_bound(LocalReactionId::new(1)));
+    assert_eq!(run(bodies), vec![22]);
+}
+
+/// Components accessible to the reactions of [Ticker].
+struct TickerState {
+    count: u32,
+}
+
+/// Runs its first reaction at three successive tags, and
+/// records the counter after each of them.
+struct Ticker {
+    id: ReactorId,
+    tick: LogicalAction<()>,
+    state: TickerState,
+    bodies: ReactionBodies<TickerState>,
+    trace: Arc<Mutex<Vec<u32>>>,
+}
+
+impl ReactorInitializer for Ticker {
+    type Wrapped = Self;
+    type Params = (ReactionBodies<TickerState>, Arc<Mutex<Vec<u32>>>);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble((bodies, trace): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        tick: cc.new_logical_action("tick", None),
+                        state: TickerState { count: 0 },
+                        bodies,
+                        trace,
+                    })
+                },
+                2,
+                [Some("step"), Some("record")],
+                |decl, this, [step, record]| {
+                    declare_reactions! {
+                        (decl, this)
+                        step: triggers(startup, tick);
+                        record: triggers(startup, tick) effects(tick);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Ticker {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        if self.bodies.react(ctx, &mut self.state, rid) {
+            return;
+        }
+        match rid.raw() {
+            0 => self.state.count += 1,
+            _ => {
+                let mut trace = self.trace.lock().unwrap();
+                trace.push(self.state.count);
+                if trace.len() < 3 {
+                    ctx.schedule(&mut self.tick, Offset::After(Duration::from_millis(1)));
+                }
+            }
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_logical_action(&mut self.tick);
+    }
+}
+
+#[test]
+fn test_swapped_body_applies_from_next_tag() {
+    let bodies = ReactionBodies::new();
+    let swapper = bodies.swapper();
+    let bodies = bodies.bind(LocalReactionId::new(0), move |_, state: &mut TickerState| {
+        state.count += 1;
+        swapper.swap(LocalReactionId::new(0), |_, state: &mut TickerState| state.count += 10);
+    });
+    let trace = Arc::new(Mutex::new(Vec::new()));
+    SyncScheduler::run_main::<Ticker>(Default::default(), (bodies, trace.clone()));
+    // the state of the reactor is kept across the swap
+    assert_eq!(*trace.lock().unwrap(), vec![1, 11, 21]);
+}
@@ -0,0 +1,97 @@
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::*;
+use crate::*;
+
+type Log = Arc<Mutex<Vec<String>>>;
+
+/// Logs its lifecycle hooks, and its startup
+/// and shutdown reactions.
+struct Device {
+    id: ReactorId,
+    name: &'static str,
+    log: Log,
+}
+
+impl Device {
+    fn log(&self, event: &str) {
+        self.log.lock().unwrap().push(format!("{} {}", self.name, event));
+    }
+}
+
+impl ReactorInitializer for Device {
+    type Wrapped = Self;
+    type Params = (&'static str, Log);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble((name, log): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |_, id| Ok(Self { id, name, log }),
+                2,
+                [Some("on_startup"), Some("on_shutdown")],
+                |decl, _, [on_startup, on_shutdown]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup);
+                        on_shutdown: triggers(shutdown);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Device {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, _ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        self.log(if rid.raw() == 0 { "startup" } else { "shutdown" });
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+
+    fn on_startup_sync(&mut self) {
+        self.log("open");
+    }
+
+    fn on_shutdown_sync(&mut self) {
+        self.log("close");
+    }
+}
+
+reactor_program! {
+    struct Devices(log: Log);
+    instances {
+        a: Device = ("a", log.clone()),
+        b: Device = ("b", log),
+    }
+    connections {}
+}
+
+#[test]
+fn test_lifecycle_hooks_run_outside_of_logical_time() {
+    let log: Log = Default::default();
+    SyncScheduler::run_main::<Devices>(Default::default(), log.clone());
+    let log = log.lock().unwrap();
+    assert_eq!(log.len(), 8, "{:?}", log);
+    // in the order of ids, then in the reverse order
+    assert_eq!(log[..2], ["a open", "b open"], "{:?}", log);
+    assert_eq!(log[6..], ["b close", "a close"], "{:?}", log);
+    assert!(log[2..4].iter().all(|e| e.ends_with("startup")), "{:?}", log);
+    assert!(log[4..6].iter().all(|e| e.ends_with("shutdown")), "{:?}", log);
+}
+
+#[test]
+fn test_dry_run_does_not_run_lifecycle_hooks() {
+    let log: Log = Default::default();
+    SyncScheduler::dry_run::<Devices>(log.clone());
+    assert!(log
+        .lock()
+        .unwrap()
+        .iter()
+        .all(|e| !e.ends_with("open") && !e.ends_with("close")));
+}
@@ -0,0 +1,65 @@
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::*;
+use crate::*;
+
+type Prefixes = Arc<Mutex<Vec<String>>>;
+
+/// Records the prefix of its log messages.
+struct Greeter {
+    id: ReactorId,
+    prefixes: Prefixes,
+}
+
+impl ReactorInitializer for Greeter {
+    type Wrapped = Self;
+    type Params = Prefixes;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(1);
+
+    fn assemble(prefixes: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |_, id| Ok(Self { id, prefixes }),
+                1,
+                [Some("greet")],
+                |decl, _, [greet]| {
+                    declare_reactions! {
+                        (decl, this)
+                        greet: triggers(startup);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Greeter {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        log_info!(ctx, "hello {}", "world");
+        log_debug!(ctx, "debug");
+        self.prefixes.lock().unwrap().push(ctx.log_prefix().to_string());
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+reactor_program! {
+    struct Greetings(prefixes: Prefixes);
+    instances {
+        greeter: Greeter = prefixes,
+    }
+    connections {}
+}
+
+#[test]
+fn test_log_prefix_names_the_tag_and_reaction() {
+    let prefixes: Prefixes = Default::default();
+    SyncScheduler::run_main::<Greetings>(Default::default(), prefixes.clone());
+    let prefixes = prefixes.lock().unwrap();
+    assert_eq!(*prefixes, vec![format!("[{}] /greeter/0@greet:", EventTag::ORIGIN)]);
+}
@@ -0,0 +1,127 @@
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::*;
+use crate::stdlib::monitor::*;
+use crate::*;
+
+/// Emits a reading at startup.
+struct Sensor {
+    id: ReactorId,
+    value: u32,
+    out: Port<u32>,
+}
+
+impl ReactorInitializer for Sensor {
+    type Wrapped = Self;
+    type Params = u32;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(1);
+
+    fn assemble(value: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        value,
+                        out: cc.new_port("out", PortKind::Output),
+                    })
+                },
+                1,
+                [Some("emit")],
+                |decl, this, [emit]| {
+                    declare_reactions! {
+                        (decl, this)
+                        emit: triggers(startup) effects(out);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Sensor {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        ctx.set(&mut self.out, self.value);
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.out);
+    }
+}
+
+type Summary = Arc<Mutex<Option<MonitorSummary>>>;
+
+/// Two sensors observed by a monitor, and one that is not.
+struct Plant {
+    id: ReactorId,
+}
+
+impl ReactorInitializer for Plant {
+    type Wrapped = Self;
+    type Params = (Summary, Arc<Mutex<usize>>);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(0);
+
+    fn assemble((summary, num_bound): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        let params = MonitorParams::new(2)
+            .with_invariant(Invariant::value_range(0..=10))
+            .with_invariant(Invariant::max_lag(Duration::from_secs(10)))
+            .on_summary(move |s| *summary.lock().unwrap() = Some(s.clone()));
+        ctx.assemble(|ctx| {
+            ctx.with_child::<Sensor, _>("sensor_a", 5, |ctx, a| {
+                ctx.with_child::<Sensor, _>("sensor_b", 50, |ctx, b| {
+                    ctx.with_child::<Sensor, _>("other", 500, |ctx, other| {
+                        ctx.with_child::<Monitor<u32>, _>("monitor", params, |ctx, monitor| {
+                            ctx.assemble_self(
+                                |_, id| Ok(Self { id }),
+                                0,
+                                [],
+                                |decl, _, []| {
+                                    let ports = [&mut a.out, &mut b.out, &mut other.out];
+                                    *num_bound.lock().unwrap() =
+                                        decl.bind_matching("*/sensor_*/out", ports, &mut monitor.inputs)?;
+                                    Ok(())
+                                },
+                            )
+                        })
+                    })
+                })
+            })
+        })
+    }
+}
+
+impl ReactorBehavior for Plant {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, _ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        unreachable!()
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+#[test]
+fn test_monitor_reports_violations() {
+    let summary: Summary = Default::default();
+    let num_bound = Arc::new(Mutex::new(0));
+    SyncScheduler::run_main::<Plant>(Default::default(), (summary.clone(), num_bound.clone()));
+
+    assert_eq!(*num_bound.lock().unwrap(), 2);
+    let summary = summary.lock().unwrap().take().unwrap();
+    assert_eq!(summary.observations, 1);
+    assert_eq!(
+        summary.violations,
+        vec![InvariantViolations {
+            invariant: "value in 0..=10".into(),
+            count: 1,
+            first: EventTag::ORIGIN
+        }]
+    );
+}
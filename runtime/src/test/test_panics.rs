@@ -0,0 +1,138 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use super::testutil::ScriptedSource;
+use crate::assembly::*;
+use crate::*;
+
+/// Panics at startup if asked to.
+struct Panicker {
+    id: ReactorId,
+    panics: bool,
+}
+
+impl ReactorInitializer for Panicker {
+    type Wrapped = Self;
+    type Params = bool;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(1);
+
+    fn assemble(panics: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |_, id| Ok(Self { id, panics }),
+                1,
+                [Some("on_startup")],
+                |decl, _this, [on_startup]| {
+                    declare_reactions! {
+                        (decl, _this)
+                        on_startup: triggers(startup);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Panicker {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, _ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        if self.panics {
+            panic!("boom")
+        }
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+reactor_program! {
+    /// Enough reactions at the same level to execute them
+    /// in parallel with feature `parallel-runtime`.
+    struct Program;
+    instances {
+        a: Panicker = false,
+        b: Panicker = true,
+        c: Panicker = false,
+        d: Panicker = false,
+    }
+    connections {}
+}
+
+#[test]
+fn test_panic_is_propagated() {
+    let payload = catch_unwind(|| SyncScheduler::run_main::<Program>(Default::default(), ())).unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"boom"));
+}
+
+/// Panics when it receives a 2.
+struct Fuse {
+    id: ReactorId,
+    input: Port<u32>,
+}
+
+impl ReactorInitializer for Fuse {
+    type Wrapped = Self;
+    type Params = ();
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(1);
+
+    fn assemble(_: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| Ok(Self { id, input: cc.new_port("input", PortKind::Input) }),
+                1,
+                [Some("on_input")],
+                |decl, this, [on_input]| decl.declare_triggers(this.input.get_id(), on_input),
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Fuse {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        if ctx.get(&self.input) == Some(2) {
+            panic!("blown")
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.input);
+    }
+}
+
+reactor_program! {
+    /// Has an event pending when it panics.
+    struct Pending;
+    instances {
+        source: ScriptedSource<u32> = vec![(Duration::from_millis(1), 1), (Duration::from_millis(2), 2), (Duration::from_millis(5), 3)],
+        fuse: Fuse = (),
+    }
+    connections {
+        source.output -> fuse.input;
+    }
+}
+
+#[test]
+fn test_crash_dump_is_written_on_panic() {
+    let path = std::env::temp_dir().join(format!("reactor-rt-crash-{}.txt", std::process::id()));
+    let options = SchedulerOptions {
+        crash_dump: Some(CrashDump::to_file(&path)),
+        ..Default::default()
+    };
+    let payload = catch_unwind(AssertUnwindSafe(|| SyncScheduler::run_main::<Pending>(options, ()))).unwrap_err();
+    assert_eq!(payload.downcast_ref::<&str>(), Some(&"blown"));
+
+    let dump = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(path);
+    assert!(dump.contains("panicked at "), "{}", dump);
+    assert!(dump.contains(": blown\n"), "{}", dump);
+    assert!(dump.contains("current tag: (T0 + 2000000 ns = 2 ms, 0)"), "{}", dump);
+    assert!(dump.contains("/fuse/0@on_input"), "{}", dump);
+    assert!(dump.contains("pending events (1):"), "{}", dump);
+    assert!(dump.contains("/source/emit"), "{}", dump);
+}
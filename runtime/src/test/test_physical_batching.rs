@@ -0,0 +1,102 @@
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::*;
+use crate::*;
+
+type Observed = Arc<Mutex<Vec<(EventTag, u32)>>>;
+
+/// Sends a burst of physical events at startup, and
+/// records the tag and value of each one it receives.
+struct Burst {
+    id: ReactorId,
+    act: PhysicalActionRef<u32>,
+    observed: Observed,
+}
+
+impl ReactorInitializer for Burst {
+    type Wrapped = Self;
+    type Params = Observed;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble(observed: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        act: cc.new_physical_action("act", None),
+                        observed,
+                    })
+                },
+                2,
+                [Some("on_startup"), Some("on_act")],
+                |decl, this, [on_startup, on_act]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(act);
+                        on_act: triggers(act);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Burst {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        if rid.raw() == 0 {
+            let act = self.act.clone();
+            ctx.spawn_physical_thread(move |link| {
+                for i in 1..=5 {
+                    link.schedule_physical_with_v(&act, Some(i), Offset::Asap).unwrap();
+                }
+            });
+        } else {
+            let value = ctx.get(&self.act).unwrap();
+            self.observed.lock().unwrap().push((ctx.get_tag(), value));
+        }
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+#[test]
+fn test_physical_events_share_a_tag_within_window() {
+    let window = Duration::from_millis(100);
+    let observed: Observed = Default::default();
+    let options = SchedulerOptions {
+        keep_alive: true,
+        timeout: Some(Duration::from_millis(300)),
+        physical_tag_window: Some(window),
+        ..Default::default()
+    };
+    SyncScheduler::run_main::<Burst>(options, observed.clone());
+
+    let observed = observed.lock().unwrap();
+    // the burst may straddle the end of a window
+    assert!(!observed.is_empty() && observed.len() <= 2, "{:?}", observed);
+    for (tag, _) in observed.iter() {
+        assert_eq!(tag.offset_from_t0.as_nanos() % window.as_nanos(), 0, "{}", tag);
+    }
+    // the last value of the window is kept
+    assert_eq!(observed.last().unwrap().1, 5);
+}
+
+#[test]
+fn test_round_up_to_window() {
+    let ms = Duration::from_millis;
+    let window = ms(10);
+    assert_eq!(EventTag::offset(ms(0), 0).round_up_to(window), EventTag::offset(ms(0), 0));
+    assert_eq!(EventTag::offset(ms(1), 0).round_up_to(window), EventTag::offset(ms(10), 0));
+    assert_eq!(EventTag::offset(ms(10), 0).round_up_to(window), EventTag::offset(ms(10), 0));
+    assert_eq!(EventTag::offset(ms(10), 2).round_up_to(window), EventTag::offset(ms(20), 0));
+    assert_eq!(
+        EventTag::offset(ms(7), 1).round_up_to(Duration::ZERO),
+        EventTag::offset(ms(7), 1)
+    );
+}
@@ -0,0 +1,230 @@
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::*;
+use crate::*;
+
+/// Names of the reactors in the order their startup
+/// reaction ran, and the physical time elapsed when
+/// their delayed action was processed.
+type Log = Arc<Mutex<Vec<(&'static str, Option<Duration>)>>>;
+
+/// Logs its name at startup, and again 3 ms later.
+struct Named {
+    id: ReactorId,
+    name: &'static str,
+    later: LogicalAction<()>,
+    log: Log,
+}
+
+impl ReactorInitializer for Named {
+    type Wrapped = Self;
+    type Params = (&'static str, Log);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble((name, log): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        name,
+                        later: cc.new_logical_action("later", None),
+                        log,
+                    })
+                },
+                2,
+                [Some("on_startup"), Some("on_later")],
+                |decl, this, [on_startup, on_later]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(later);
+                        on_later: triggers(later);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Named {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        if rid.raw() == 0 {
+            self.log.lock().unwrap().push((self.name, None));
+            ctx.schedule(&mut self.later, Offset::After(Duration::from_millis(3)));
+        } else {
+            let elapsed = ctx.get_elapsed_physical_time();
+            self.log.lock().unwrap().push((self.name, Some(elapsed)));
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_logical_action(&mut self.later);
+    }
+}
+
+reactor_program! {
+    struct Pair(log: Log);
+    instances {
+        first: Named = ("first", log.clone()),
+        second: Named = ("second", log.clone()),
+    }
+    connections {}
+}
+
+/// Executes the reactions of a level by decreasing reactor id.
+struct LastReactorFirst;
+
+impl SchedulingPolicy for LastReactorFirst {
+    fn order_level(&mut self, _tag: EventTag, reactions: &mut [GlobalReactionId]) {
+        reactions.sort_by_key(|r| std::cmp::Reverse(r.0.container()));
+    }
+}
+
+fn run(policy: impl SchedulingPolicy + 'static) -> Vec<(&'static str, Option<Duration>)> {
+    let log: Log = Default::default();
+    let options = SchedulerOptions {
+        scheduling_policy: Some(Box::new(policy)),
+        ..Default::default()
+    };
+    SyncScheduler::run_main::<Pair>(options, log.clone());
+    let result = std::mem::take(&mut *log.lock().unwrap());
+    result
+}
+
+#[test]
+fn test_policy_orders_reactions_of_a_level() {
+    let startup = |log: Vec<(&'static str, Option<Duration>)>| log.into_iter().take(2).map(|(n, _)| n).collect::<Vec<_>>();
+    assert_eq!(startup(run(LastReactorFirst)), vec!["second", "first"]);
+}
+
+#[test]
+fn test_periodic_release_delays_tags_to_period() {
+    let period = Duration::from_millis(30);
+    let log = run(PeriodicRelease { period });
+    let delayed = log.iter().filter_map(|(_, t)| *t).collect::<Vec<_>>();
+    assert_eq!(delayed.len(), 2, "{:?}", log);
+    for elapsed in delayed {
+        // the tag at 3 ms is released at the end of the first period
+        assert!(elapsed >= period, "{:?}", elapsed);
+    }
+
+    let t0 = Instant::now();
+    let mut policy = PeriodicRelease { period };
+    assert_eq!(policy.release_time(EventTag::ORIGIN, t0), t0);
+    assert_eq!(policy.release_time(EventTag::offset(period, 0), t0), t0 + period);
+    assert_eq!(
+        policy.release_time(EventTag::offset(period + Duration::from_nanos(1), 0), t0),
+        t0 + 2 * period
+    );
+}
+
+/// Whether the deadline of each round of a worker was missed.
+type Misses = Arc<Mutex<Vec<(&'static str, bool)>>>;
+
+/// Every 30 ms, works for some time within a deadline.
+struct Worker {
+    id: ReactorId,
+    name: &'static str,
+    work: Duration,
+    round: LogicalAction<()>,
+    rounds: u32,
+    misses: Misses,
+}
+
+impl ReactorInitializer for Worker {
+    type Wrapped = Self;
+    type Params = (&'static str, Duration, Duration, Misses);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble((name, work, deadline, misses): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        name,
+                        work,
+                        round: cc.new_logical_action("round", None),
+                        rounds: 0,
+                        misses,
+                    })
+                },
+                2,
+                [Some("on_startup"), Some("on_round")],
+                |decl, this, [on_startup, on_round]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(round);
+                        on_round: triggers(round) effects(round) deadline(deadline);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Worker {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        if rid.raw() == 1 {
+            self.misses.lock().unwrap().push((self.name, ctx.deadline_violated()));
+            std::thread::sleep(self.work);
+            self.rounds += 1;
+            if self.rounds == 5 {
+                return;
+            }
+        }
+        ctx.schedule(&mut self.round, Offset::After(Duration::from_millis(30)));
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_logical_action(&mut self.round);
+    }
+}
+
+reactor_program! {
+    struct Workers(misses: Misses);
+    instances {
+        slow: Worker = ("slow", Duration::from_millis(15), Duration::from_millis(100), misses.clone()),
+        steady: Worker = ("steady", Duration::ZERO, Duration::from_millis(100), misses.clone()),
+        urgent: Worker = ("urgent", Duration::ZERO, Duration::from_millis(8), misses.clone()),
+    }
+    connections {}
+}
+
+/// Returns the number of missed deadlines of each worker.
+fn count_misses(policy: Option<Box<dyn SchedulingPolicy>>) -> (usize, usize) {
+    let misses: Misses = Default::default();
+    let options = SchedulerOptions {
+        scheduling_policy: policy,
+        // the level of the workers is executed in parallel, by a single
+        // worker so that the order in which reactions start matters
+        #[cfg(feature = "parallel-runtime")]
+        threads: 1,
+        ..Default::default()
+    };
+    SyncScheduler::run_main::<Workers>(options, misses.clone());
+    let misses = misses.lock().unwrap();
+    assert_eq!(misses.len(), 15, "{:?}", misses);
+    let count = |name| misses.iter().filter(|(n, missed)| *n == name && *missed).count();
+    (count("slow"), count("urgent"))
+}
+
+#[test]
+fn test_edf_reduces_deadline_misses() {
+    // by default the slow worker, declared first, delays the urgent one
+    let default = count_misses(None);
+    assert_eq!(default, (0, 5));
+    let edf = count_misses(Some(Box::new(EarliestDeadlineFirst::default())));
+    assert_eq!(edf.0, 0);
+    assert!(edf.1 < default.1, "{:?}", edf);
+}
@@ -23,8 +23,10 @@
  */
 
 use std::borrow::Cow;
+use std::sync::{Arc, Mutex};
 
-use crate::assembly::{PortKind, TriggerId};
+use super::testutil::*;
+use crate::assembly::*;
 use crate::*;
 
 struct TestAssembler {
@@ -253,3 +255,470 @@ fn repeated_binding_panics() -> TestResult {
 
     test.ok()
 }
+
+#[test]
+fn multiport_channels_are_iterated_by_index() -> TestResult {
+    let mut test = TestAssembler::default();
+    let ports = (0..4).map(|_| test.new_port::<i32>("channel")).collect();
+    let id = test.cur_id.get_and_incr().unwrap();
+    let mut multiport = Multiport::new(ports, id);
+    let test = test.ready();
+
+    for i in [3, 0, 2] {
+        test.set(&mut multiport[i], i as i32 * 10)?;
+    }
+
+    let set = multiport.enumerate_set().map(|(i, _)| i).collect::<Vec<_>>();
+    assert_eq!(set, vec![0, 2, 3]);
+    let values = multiport.enumerate_values().rev().collect::<Vec<_>>();
+    assert_eq!(values, vec![(3, 30), (2, 20), (0, 0)]);
+    assert_eq!(multiport.iterate_values().next_back(), Some(30));
+    assert_eq!(multiport.enumerate().len(), 4);
+
+    test.ok()
+}
+
+#[test]
+fn bound_ports_share_the_origin_of_their_value() -> TestResult {
+    let mut test = TestAssembler::default();
+    let mut upstream = test.new_port::<i32>("up");
+    let mut middle = test.new_port("middle");
+    let mut downstream = test.new_port("down");
+    let test = test.ready();
+
+    test.bind(&mut middle, &mut downstream)?;
+    test.bind(&mut upstream, &mut middle)?;
+    assert_eq!(downstream.origin_id(), upstream.get_id());
+
+    test.set(&mut upstream, 5)?;
+    assert_eq!(downstream.take_impl(), Some(5));
+    assert_eq!(upstream.get(), None);
+
+    test.ok()
+}
+
+/// Whether each reaction of a [Taker] moved the value out.
+type Moves = Arc<Mutex<Vec<(usize, bool)>>>;
+
+/// Takes its input in two reactions, and forwards it.
+/// The second one is the last reader.
+struct Taker {
+    id: ReactorId,
+    input: Port<Vec<u8>>,
+    output: Port<Vec<u8>>,
+    moves: Moves,
+}
+
+impl ReactorInitializer for Taker {
+    type Wrapped = Self;
+    type Params = Moves;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble(moves: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        input: cc.new_port("input", PortKind::Input),
+                        output: cc.new_port("output", PortKind::Output),
+                        moves,
+                    })
+                },
+                2,
+                [Some("peek"), Some("forward")],
+                |decl, this, [peek, forward]| {
+                    declare_reactions! {
+                        (decl, this)
+                        peek: triggers(input);
+                        forward: triggers(input) effects(output);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Taker {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        // the last reader does not need the value to be Clone
+        let value = if rid.raw() == 1 {
+            ctx.take_if_last(&mut self.input)
+        } else {
+            ctx.take(&mut self.input)
+        };
+        let moved = !ctx.is_present(&self.input);
+        self.moves.lock().unwrap().push((rid.index(), moved));
+        if rid.raw() == 1 {
+            ctx.set_opt(&mut self.output, value);
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.input);
+        ctx.cleanup_port(&mut self.output);
+    }
+}
+
+impl Pipe<Vec<u8>> for Taker {
+    fn ports(&mut self) -> (&mut Port<Vec<u8>>, &mut Port<Vec<u8>>) {
+        (&mut self.input, &mut self.output)
+    }
+}
+
+#[test]
+fn only_the_last_reader_moves_a_value_out() {
+    let moves = Moves::default();
+    let script = vec![(Duration::ZERO, vec![1; 256])];
+    let out = run_pipeline::<Vec<u8>, Taker>(moves.clone(), script, Duration::from_millis(10));
+
+    assert_eq!(out, vec![(Duration::ZERO, vec![1; 256])]);
+    assert_eq!(*moves.lock().unwrap(), vec![(0, false), (1, true)]);
+}
+
+/// Forwards its input, incremented, through
+/// helpers that only get a facet of the context.
+struct Incrementer {
+    id: ReactorId,
+    input: Port<u32>,
+    output: Port<u32>,
+}
+
+fn incremented(ctx: ReadCtx, input: &Port<u32>) -> Option<u32> {
+    ctx.get(input).map(|v| v + 1)
+}
+
+fn forward(mut ctx: WriteCtx, output: &mut Port<u32>, value: Option<u32>) {
+    ctx.set_opt(output, value)
+}
+
+impl ReactorInitializer for Incrementer {
+    type Wrapped = Self;
+    type Params = ();
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(1);
+
+    fn assemble(_: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        input: cc.new_port("input", PortKind::Input),
+                        output: cc.new_port("output", PortKind::Output),
+                    })
+                },
+                1,
+                [Some("on_input")],
+                |decl, this, [on_input]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_input: triggers(input) effects(output);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Incrementer {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        let value = incremented(ctx.as_read(), &self.input);
+        forward(ctx.as_write(), &mut self.output, value);
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.input);
+        ctx.cleanup_port(&mut self.output);
+    }
+}
+
+impl Pipe<u32> for Incrementer {
+    fn ports(&mut self) -> (&mut Port<u32>, &mut Port<u32>) {
+        (&mut self.input, &mut self.output)
+    }
+}
+
+#[test]
+fn facets_read_and_write_through_the_context() {
+    let script = vec![(Duration::ZERO, 1), (Duration::from_millis(2), 5)];
+    let out = run_pipeline::<u32, Incrementer>((), script, Duration::from_millis(10));
+    assert_eq!(out, vec![(Duration::ZERO, 2), (Duration::from_millis(2), 6)]);
+}
+
+#[test]
+fn an_exported_port_sees_later_bindings() -> TestResult {
+    let mut test = TestAssembler::default();
+    let mut upstream = test.new_port::<i32>("up");
+    let mut downstream = test.new_port("down");
+    let test = test.ready();
+
+    let (handle, mut export) = downstream.export();
+    test.bind(&mut upstream, &mut downstream)?;
+    export.publish(EventTag::ORIGIN);
+    assert_eq!(handle.get(), None);
+
+    test.set(&mut upstream, 5)?;
+    export.publish(EventTag::ORIGIN);
+    upstream.clear_value();
+    export.publish(EventTag::ORIGIN.successor(Duration::from_millis(1)));
+    // the latest value is kept while the port is absent
+    assert_eq!(handle.get_tagged(), Some((EventTag::ORIGIN, 5)));
+
+    test.ok()
+}
+
+/// Forwards its input, and exports its output
+/// into the handle given as parameter.
+struct Exporter {
+    id: ReactorId,
+    input: Port<u32>,
+    output: Port<u32>,
+}
+
+impl ReactorInitializer for Exporter {
+    type Wrapped = Self;
+    type Params = Arc<Mutex<Option<ReadHandle<u32>>>>;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(1);
+
+    fn assemble(slot: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        input: cc.new_port("input", PortKind::Input),
+                        output: cc.new_port("output", PortKind::Output),
+                    })
+                },
+                1,
+                [Some("forward")],
+                |decl, this, [forward]| {
+                    declare_reactions! {
+                        (decl, this)
+                        forward: triggers(input) effects(output);
+                    }
+                    *slot.lock().unwrap() = Some(decl.export_port(&this.output));
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Exporter {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        let value = ctx.get(&self.input);
+        ctx.set_opt(&mut self.output, value);
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.input);
+        ctx.cleanup_port(&mut self.output);
+    }
+}
+
+impl Pipe<u32> for Exporter {
+    fn ports(&mut self) -> (&mut Port<u32>, &mut Port<u32>) {
+        (&mut self.input, &mut self.output)
+    }
+}
+
+#[test]
+fn a_read_handle_keeps_the_latest_value_of_the_port() {
+    let slot: Arc<Mutex<Option<ReadHandle<u32>>>> = Default::default();
+    let script = vec![(Duration::ZERO, 1), (Duration::from_millis(2), 5)];
+    run_pipeline::<u32, Exporter>(slot.clone(), script, Duration::from_millis(10));
+
+    let handle = slot.lock().unwrap().take().unwrap();
+    let (tag, value) = handle.get_tagged().unwrap();
+    assert_eq!(value, 5);
+    assert_eq!(tag.offset_from_t0, Duration::from_millis(2));
+}
+
+/// Moves its input out to forward it, and
+/// exports the input into the handle given as parameter.
+struct TakingExporter {
+    id: ReactorId,
+    input: Port<u32>,
+    output: Port<u32>,
+}
+
+impl ReactorInitializer for TakingExporter {
+    type Wrapped = Self;
+    type Params = Arc<Mutex<Option<ReadHandle<u32>>>>;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(1);
+
+    fn assemble(slot: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        input: cc.new_port("input", PortKind::Input),
+                        output: cc.new_port("output", PortKind::Output),
+                    })
+                },
+                1,
+                [Some("forward")],
+                |decl, this, [forward]| {
+                    declare_reactions! {
+                        (decl, this)
+                        forward: triggers(input) effects(output);
+                    }
+                    *slot.lock().unwrap() = Some(decl.export_port(&this.input));
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for TakingExporter {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        let value = ctx.take(&mut self.input);
+        ctx.set_opt(&mut self.output, value);
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.input);
+        ctx.cleanup_port(&mut self.output);
+    }
+}
+
+impl Pipe<u32> for TakingExporter {
+    fn ports(&mut self) -> (&mut Port<u32>, &mut Port<u32>) {
+        (&mut self.input, &mut self.output)
+    }
+}
+
+#[test]
+fn a_read_handle_keeps_values_that_readers_take() {
+    let slot: Arc<Mutex<Option<ReadHandle<u32>>>> = Default::default();
+    let script = vec![(Duration::ZERO, 1), (Duration::from_millis(2), 5)];
+    let out = run_pipeline::<u32, TakingExporter>(slot.clone(), script.clone(), Duration::from_millis(10));
+    assert_eq!(out, script);
+
+    let handle = slot.lock().unwrap().take().unwrap();
+    let (tag, value) = handle.get_tagged().unwrap();
+    assert_eq!(value, 5);
+    assert_eq!(tag.offset_from_t0, Duration::from_millis(2));
+}
+
+/// Compares values in [Dedup].
+type Compare = fn(&u32, &u32) -> bool;
+
+/// Forwards its input only when it changes, compared with
+/// the given function if any. Inputs of at least 100 are
+/// always forwarded, with [ReactionCtx::set].
+struct Dedup {
+    id: ReactorId,
+    input: Port<u32>,
+    output: Port<u32>,
+    eq: Option<Compare>,
+}
+
+impl ReactorInitializer for Dedup {
+    type Wrapped = Self;
+    type Params = Option<Compare>;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(1);
+
+    fn assemble(eq: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        input: cc.new_port("input", PortKind::Input),
+                        output: cc.new_port("output", PortKind::Output),
+                        eq,
+                    })
+                },
+                1,
+                [Some("forward")],
+                |decl, this, [forward]| {
+                    declare_reactions! {
+                        (decl, this)
+                        forward: triggers(input) effects(output);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Dedup {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        match (ctx.get(&self.input), self.eq) {
+            (Some(value), _) if value >= 100 => ctx.set(&mut self.output, value),
+            (Some(value), Some(eq)) => {
+                ctx.set_if_changed_by(&mut self.output, value, eq);
+            }
+            (Some(value), None) => {
+                ctx.set_if_changed(&mut self.output, value);
+            }
+            (None, _) => {}
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.input);
+        ctx.cleanup_port(&mut self.output);
+    }
+}
+
+impl Pipe<u32> for Dedup {
+    fn ports(&mut self) -> (&mut Port<u32>, &mut Port<u32>) {
+        (&mut self.input, &mut self.output)
+    }
+}
+
+#[test]
+fn set_if_changed_skips_repeated_values() {
+    let ms = Duration::from_millis;
+    let script = vec![(ms(0), 1), (ms(1), 1), (ms(2), 2), (ms(3), 2), (ms(4), 1)];
+    let out = run_pipeline::<u32, Dedup>(None, script, ms(10));
+
+    assert_eq!(out, vec![(ms(0), 1), (ms(2), 2), (ms(4), 1)]);
+}
+
+#[test]
+fn set_if_changed_compares_with_the_previous_tag() {
+    let ms = Duration::from_millis;
+    // 100 is set with set, so the second 1 is a change
+    let script = vec![(ms(0), 1), (ms(1), 100), (ms(2), 1), (ms(3), 1)];
+    let out = run_pipeline::<u32, Dedup>(None, script, ms(10));
+
+    assert_eq!(out, vec![(ms(0), 1), (ms(1), 100), (ms(2), 1)]);
+}
+
+#[test]
+fn set_if_changed_by_uses_the_comparator() {
+    let ms = Duration::from_millis;
+    let script = vec![(ms(0), 10), (ms(1), 12), (ms(2), 25), (ms(3), 29)];
+    let out = run_pipeline::<u32, Dedup>(Some(|a, b| a / 10 == b / 10), script, ms(10));
+
+    assert_eq!(out, vec![(ms(0), 10), (ms(2), 25)]);
+}
@@ -0,0 +1,69 @@
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::*;
+use crate::*;
+
+type Executed = Arc<Mutex<Vec<usize>>>;
+
+/// Three reactions to startup, which log their index.
+/// Some priorities may be overridden at assembly.
+struct Ordered {
+    id: ReactorId,
+    executed: Executed,
+}
+
+impl ReactorInitializer for Ordered {
+    type Wrapped = Self;
+    type Params = (Vec<(usize, u32)>, Executed);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(3);
+
+    fn assemble((priorities, executed): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |_, id| Ok(Self { id, executed }),
+                3,
+                [Some("a"), Some("b"), Some("c")],
+                |decl, _, reactions| {
+                    for r in reactions {
+                        decl.triggered_by_startup(r)?;
+                    }
+                    for (i, priority) in priorities {
+                        decl.set_priority(reactions[i], priority)?;
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Ordered {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, _ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        self.executed.lock().unwrap().push(rid.index());
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+fn execution_order(priorities: Vec<(usize, u32)>) -> Vec<usize> {
+    let executed: Executed = Default::default();
+    SyncScheduler::run_main::<Ordered>(Default::default(), (priorities, executed.clone()));
+    let result = executed.lock().unwrap().clone();
+    result
+}
+
+#[test]
+fn test_reactions_execute_in_declaration_order_by_default() {
+    assert_eq!(execution_order(vec![]), vec![0, 1, 2]);
+}
+
+#[test]
+fn test_priority_override() {
+    assert_eq!(execution_order(vec![(0, 10)]), vec![1, 2, 0]);
+    // ties keep the declaration order
+    assert_eq!(execution_order(vec![(2, 0)]), vec![0, 2, 1]);
+}
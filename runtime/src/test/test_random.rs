@@ -0,0 +1,95 @@
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::*;
+use crate::*;
+
+type Draws = Arc<Mutex<Vec<(&'static str, u64)>>>;
+
+/// Draws a number at startup, and at the next microstep.
+struct Dice {
+    id: ReactorId,
+    name: &'static str,
+    again: LogicalAction<()>,
+    draws: Draws,
+}
+
+impl ReactorInitializer for Dice {
+    type Wrapped = Self;
+    type Params = (&'static str, Draws);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble((name, draws): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        name,
+                        again: cc.new_logical_action("again", None),
+                        draws,
+                    })
+                },
+                2,
+                [Some("roll"), Some("roll_again")],
+                |decl, this, [roll, roll_again]| {
+                    declare_reactions! {
+                        (decl, this)
+                        roll: triggers(startup) effects(again);
+                        roll_again: triggers(again);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Dice {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        let value = ctx.random().next_u64();
+        // the same reaction draws the same sequence
+        assert_eq!(value, ctx.random().next_u64());
+        self.draws.lock().unwrap().push((self.name, value));
+        if rid.raw() == 0 {
+            ctx.schedule(&mut self.again, Offset::Asap);
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_logical_action(&mut self.again);
+    }
+}
+
+reactor_program! {
+    struct Casino(draws: Draws);
+    instances {
+        a: Dice = ("a", draws.clone()),
+        b: Dice = ("b", draws),
+    }
+    connections {}
+}
+
+fn draws_with_seed(seed: u64) -> Vec<(&'static str, u64)> {
+    let draws: Draws = Default::default();
+    SyncScheduler::run_main::<Casino>(SchedulerOptions { seed, ..Default::default() }, draws.clone());
+    let mut draws = draws.lock().unwrap().clone();
+    // reactions of a and b may execute in any order
+    draws.sort();
+    draws
+}
+
+#[test]
+fn test_random_is_reproducible() {
+    let draws = draws_with_seed(7);
+    assert_eq!(draws.len(), 4);
+    assert_eq!(draws, draws_with_seed(7));
+    assert_ne!(draws, draws_with_seed(8));
+
+    let mut values: Vec<u64> = draws.iter().map(|(_, v)| *v).collect();
+    values.dedup();
+    assert_eq!(values.len(), 4, "each reaction and tag draws its own numbers");
+}
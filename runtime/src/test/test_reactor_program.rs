@@ -0,0 +1,163 @@
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::*;
+use crate::*;
+
+type Trace = Arc<Mutex<Vec<(&'static str, u32)>>>;
+
+/// Sets its output at startup.
+struct Source {
+    id: ReactorId,
+    value: u32,
+    out: Port<u32>,
+}
+
+impl ReactorInitializer for Source {
+    type Wrapped = Self;
+    type Params = u32;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(1);
+
+    fn assemble(value: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        value,
+                        out: cc.new_port("out", PortKind::Output),
+                    })
+                },
+                1,
+                [Some("emit")],
+                |decl, this, [emit]| {
+                    declare_reactions! {
+                        (decl, this)
+                        emit: triggers(startup) effects(out);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Source {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        ctx.set(&mut self.out, self.value);
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.out);
+    }
+}
+
+/// Records the values it receives.
+struct Sink {
+    id: ReactorId,
+    name: &'static str,
+    inp: Port<u32>,
+    trace: Trace,
+}
+
+impl ReactorInitializer for Sink {
+    type Wrapped = Self;
+    type Params = (&'static str, Trace);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(1);
+
+    fn assemble((name, trace): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        name,
+                        inp: cc.new_port("inp", PortKind::Input),
+                        trace,
+                    })
+                },
+                1,
+                [Some("receive")],
+                |decl, this, [receive]| {
+                    declare_reactions! {
+                        (decl, this)
+                        receive: triggers(inp);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Sink {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        let v = ctx.get(&self.inp).unwrap();
+        self.trace.lock().unwrap().push((self.name, v));
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.inp);
+    }
+}
+
+reactor_program! {
+    /// Two sources connected to two sinks, and a sink that is not connected.
+    struct Program(trace: Trace);
+    instances {
+        one: Source = 1,
+        two: Source = 2,
+        a: Sink = ("a", trace.clone()),
+        b: Sink = ("b", trace.clone()),
+        unused: Sink = ("unused", trace.clone()),
+    }
+    connections {
+        one.out -> a.inp;
+        two.out -> b.inp;
+    }
+}
+
+#[test]
+fn test_reactor_program() {
+    let trace: Trace = Default::default();
+    assert!(SyncScheduler::validate::<Program>(trace.clone()).is_ok());
+    SyncScheduler::run_main::<Program>(Default::default(), trace.clone());
+
+    let mut trace = trace.lock().unwrap().clone();
+    trace.sort_unstable();
+    assert_eq!(trace, vec![("a", 1), ("b", 2)]);
+}
+
+#[test]
+fn test_dump_graph() {
+    let dir = std::env::temp_dir();
+    let json_path = dir.join(format!("reactor-rt-graph-{}.json", std::process::id()));
+    let dot_path = json_path.with_extension("dot");
+    SyncScheduler::dump_graph::<Program>(Default::default(), &json_path, GraphFormat::Json).unwrap();
+    SyncScheduler::dump_graph::<Program>(Default::default(), &dot_path, GraphFormat::Dot).unwrap();
+
+    let json: serde_json::Value = serde_json::from_slice(&std::fs::read(&json_path).unwrap()).unwrap();
+    let labels = json["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|n| n["label"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert!(labels.iter().any(|l| l.contains("/one/out")), "{:?}", labels);
+    assert!(labels.iter().any(|l| l.contains("/unused/inp")), "{:?}", labels);
+    assert!(!json["edges"].as_array().unwrap().is_empty());
+
+    let dot = std::fs::read_to_string(&dot_path).unwrap();
+    assert!(dot.starts_with("digraph"), "{}", dot);
+    assert!(dot.contains("/two/out"), "{}", dot);
+
+    let _ = std::fs::remove_file(json_path);
+    let _ = std::fs::remove_file(dot_path);
+}
@@ -0,0 +1,195 @@
+use std::io::Cursor;
+use std::sync::{Arc, Mutex};
+
+use super::testutil::*;
+use crate::assembly::*;
+use crate::stdlib::replay::*;
+use crate::*;
+
+reactor_program! {
+    /// Replays records into a recorder.
+    struct Replay(params: (ReplayParams<u32>, Recording<u32>));
+    instances {
+        replay: ReplaySource<u32> = params.0,
+        recorder: Recorder<u32> = params.1,
+    }
+    connections {
+        replay.output -> recorder.input;
+    }
+}
+
+fn replay(params: ReplayParams<u32>) -> Vec<(Duration, u32)> {
+    replay_with(params, Default::default())
+}
+
+fn replay_with(params: ReplayParams<u32>, options: SchedulerOptions) -> Vec<(Duration, u32)> {
+    let recording: Recording<u32> = Default::default();
+    SyncScheduler::run_main::<Replay>(options, (params, recording.clone()));
+    let result = recording.lock().unwrap().clone();
+    result
+}
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn test_replay_records() {
+    let records = vec![(ms(0), 1), (ms(5), 2), (ms(5), 3), (ms(3), 4), (ms(20), 5)];
+    // the record at 3 ms is out of order
+    let out = replay(ReplayParams::new(records));
+    assert_eq!(out, vec![(ms(0), 1), (ms(5), 2), (ms(5), 3), (ms(20), 5)]);
+}
+
+#[test]
+fn test_replay_csv() {
+    let csv = "# nanos,value\n1000000,1\n\n2000000, 2\nnot a record\n3000000,three\n4000000,4\n";
+    let out = replay(ReplayParams::from_csv(Cursor::new(csv)));
+    assert_eq!(out, vec![(ms(1), 1), (ms(2), 2), (ms(4), 4)]);
+}
+
+#[test]
+fn test_replay_divergence() {
+    let records = || vec![(ms(0), 1), (ms(5), 2), (ms(10), 3)];
+    let trace = Arc::new(Mutex::new(None));
+    let sink = trace.clone();
+    let options = SchedulerOptions {
+        trace: Some(TraceSampler::new(TraceSampling::EveryNthTag(1), 100, move |t| {
+            *sink.lock().unwrap() = Some(t.clone())
+        })),
+        ..Default::default()
+    };
+    replay_with(ReplayParams::new(records()), options);
+    let trace = trace.lock().unwrap().take().unwrap();
+    assert!(trace.is_complete());
+    let names = trace
+        .records
+        .iter()
+        .map(|r| trace.symbols.reaction(r.reaction))
+        .collect::<Vec<_>>();
+    assert!(names.contains(&Some("/replay/1@on_next")));
+    assert!(names.contains(&Some("/recorder/0@on_input")));
+
+    let check = |records: Vec<ReplayRecord<u32>>| {
+        let divergence = Arc::new(Mutex::new(None));
+        let sink = divergence.clone();
+        let options = SchedulerOptions {
+            divergence: DivergenceDetector::new(&trace, move |d| *sink.lock().unwrap() = Some(d.clone())),
+            ..Default::default()
+        };
+        replay_with(ReplayParams::new(records), options);
+        let result = divergence.lock().unwrap().take();
+        result
+    };
+
+    assert!(check(records()).is_none());
+    // same values, but the last one is late
+    let divergence = check(vec![(ms(0), 1), (ms(5), 2), (ms(12), 3)]).unwrap();
+    assert_eq!(divergence.last_agreed.map(|t| t.offset_from_t0), Some(ms(5)));
+    assert_eq!(divergence.expected.unwrap().tag.offset_from_t0, ms(10));
+    assert!(divergence.actual.is_none());
+}
+
+type Observed = Arc<Mutex<Vec<(EventTag, u32)>>>;
+
+/// Schedules a physical action from a thread at startup,
+/// after each of the given delays, and records the tag
+/// and value of each event it receives.
+struct Jittery {
+    id: ReactorId,
+    act: PhysicalActionRef<u32>,
+    delays: Vec<u64>,
+    observed: Observed,
+}
+
+impl ReactorInitializer for Jittery {
+    type Wrapped = Self;
+    type Params = (Vec<u64>, Observed);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble((delays, observed): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        act: cc.new_physical_action("act", None),
+                        delays,
+                        observed,
+                    })
+                },
+                2,
+                [Some("on_startup"), Some("on_act")],
+                |decl, this, [on_startup, on_act]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(act);
+                        on_act: triggers(act);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Jittery {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        if rid.raw() == 0 {
+            let act = self.act.clone();
+            let delays = std::mem::take(&mut self.delays);
+            ctx.spawn_physical_thread(move |link| {
+                for (i, delay) in delays.into_iter().enumerate() {
+                    std::thread::sleep(ms(delay));
+                    link.schedule_physical_with_v(&act, Some(i as u32), Offset::Asap).unwrap();
+                }
+            });
+        } else {
+            let value = ctx.get(&self.act).unwrap();
+            self.observed.lock().unwrap().push((ctx.get_tag(), value));
+        }
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+fn run_jittery(delays: Vec<u64>, options: SchedulerOptions) -> Vec<(EventTag, u32)> {
+    let observed: Observed = Default::default();
+    let options = SchedulerOptions { timeout: Some(ms(2000)), ..options };
+    SyncScheduler::run_main::<Jittery>(options, (delays, observed.clone()));
+    let result = observed.lock().unwrap().clone();
+    result
+}
+
+#[test]
+fn test_event_log_replay() {
+    let log = Arc::new(Mutex::new(None));
+    let sink = log.clone();
+    let options = SchedulerOptions {
+        record_events: Some(EventRecorder::new(move |l| *sink.lock().unwrap() = Some(l))),
+        ..Default::default()
+    };
+    let recorded = run_jittery(vec![5, 7, 3], options);
+    let log = log.lock().unwrap().take().unwrap();
+    assert_eq!(recorded.len(), 3);
+    assert_eq!(log.physical_events.len(), 3);
+    // startup, and one tag per event
+    assert_eq!(log.tags.len(), 4);
+
+    let mut bytes = Vec::new();
+    log.write_to(&mut bytes).unwrap();
+    let log = EventLog::read_from(bytes.as_slice()).unwrap();
+
+    // whether the live source is faster or slower, the recorded tags are reproduced
+    for delays in [vec![1, 1, 1], vec![10, 15, 10]] {
+        let options = SchedulerOptions {
+            replay_events: Some(log.clone()),
+            ..Default::default()
+        };
+        assert_eq!(run_jittery(delays, options), recorded);
+    }
+}
@@ -0,0 +1,71 @@
+use crate::assembly::TriggerId;
+use crate::*;
+
+fn round_trip<T: serde::Serialize + serde::de::DeserializeOwned>(value: &T) -> T {
+    let json = serde_json::to_string(value).unwrap();
+    serde_json::from_str(&json).unwrap()
+}
+
+#[test]
+fn test_ids_are_transparent() {
+    let rid = GlobalReactionId::new(ReactorId::new(3), LocalReactionId::new(1));
+    assert_eq!(serde_json::to_string(&ReactorId::new(3)).unwrap(), "3");
+    assert_eq!(serde_json::to_string(&rid).unwrap(), r#"{"container":3,"local":1}"#);
+    assert_eq!(round_trip(&rid), rid);
+}
+
+#[test]
+fn test_tag_round_trip() {
+    let tag = EventTag::offset(Duration::from_micros(1500), 2);
+    assert_eq!(round_trip(&tag), tag);
+    assert_eq!(round_trip(&ShutdownReason::Requested), ShutdownReason::Requested);
+
+    let anomaly = TagAnomaly {
+        tag,
+        elapsed: Duration::from_millis(3),
+        expected: Duration::from_millis(1),
+        deviation: 4.0,
+        dominant_reactions: vec![ReactionTiming {
+            reaction: GlobalReactionId::new(ReactorId::new(0), LocalReactionId::new(0)),
+            name: "main/0".to_string(),
+            elapsed: Duration::from_millis(2),
+        }],
+    };
+    let copy = round_trip(&anomaly);
+    assert_eq!(copy.tag, tag);
+    assert_eq!(copy.dominant_reactions[0].name, "main/0");
+}
+
+#[test]
+fn test_trace_embeds_symbols() {
+    let reaction = GlobalReactionId::new(ReactorId::new(0), LocalReactionId::new(0));
+    let trace = SampledTrace {
+        tags: 1,
+        sampled_tags: 1,
+        executions: 1,
+        records: vec![ReactionExecution {
+            tag: EventTag::ORIGIN,
+            reaction,
+            elapsed: Duration::ZERO,
+        }],
+        present: vec![],
+        symbols: SymbolTable {
+            reactors: vec![(ReactorId::new(0), "/".to_string())],
+            reactions: vec![(reaction, "/0@tick".to_string())],
+            triggers: vec![(TriggerId::STARTUP, "startup".to_string())],
+        },
+    };
+    let copy = round_trip(&trace);
+    assert_eq!(copy.symbols, trace.symbols);
+    assert_eq!(copy.symbols.reaction(copy.records[0].reaction), Some("/0@tick"));
+}
+
+#[test]
+fn test_time_values_are_strings() {
+    let period = TimeValue::new(250, TimeUnit::MILLI).unwrap();
+    assert_eq!(serde_json::to_string(&period).unwrap(), r#""250 msec""#);
+    assert_eq!(round_trip(&period).unit(), TimeUnit::MILLI);
+    let parsed: TimeValue = serde_json::from_str(r#""2sec""#).unwrap();
+    assert_eq!(Duration::from(parsed), Duration::from_secs(2));
+    assert!(serde_json::from_str::<TimeValue>(r#""2""#).is_err());
+}
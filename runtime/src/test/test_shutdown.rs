@@ -0,0 +1,730 @@
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+use crate::assembly::*;
+use crate::*;
+
+type Observed = Arc<Mutex<Option<ShutdownReason>>>;
+
+/// Records the shutdown reason. If it has a stop offset, it
+/// requests to stop at startup, otherwise it schedules an
+/// action 20 ms after startup to keep the program alive.
+struct ShutdownObserver {
+    id: ReactorId,
+    keep_alive: LogicalAction<()>,
+    request_stop: Option<Duration>,
+    observed: Observed,
+}
+
+impl ReactorInitializer for ShutdownObserver {
+    type Wrapped = Self;
+    type Params = (Option<Duration>, Observed);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble((request_stop, observed): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        keep_alive: cc.new_logical_action("keep_alive", None),
+                        request_stop,
+                        observed,
+                    })
+                },
+                2,
+                [Some("on_startup"), Some("on_shutdown")],
+                |decl, this, [on_startup, on_shutdown]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(keep_alive);
+                        on_shutdown: triggers(shutdown);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for ShutdownObserver {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        match rid.raw() {
+            0 => {
+                assert_eq!(ctx.shutdown_reason(), None);
+                match self.request_stop {
+                    Some(offset) => ctx.request_stop(Offset::After(offset)),
+                    None => ctx.schedule(&mut self.keep_alive, Offset::After(Duration::from_millis(20))),
+                }
+            }
+            _ => *self.observed.lock().unwrap() = ctx.shutdown_reason(),
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_logical_action(&mut self.keep_alive);
+    }
+}
+
+fn observe_shutdown(timeout: Option<Duration>, request_stop: Option<Duration>) -> Option<ShutdownReason> {
+    let observed: Observed = Default::default();
+    let options = SchedulerOptions { timeout, ..Default::default() };
+    SyncScheduler::run_main::<ShutdownObserver>(options, (request_stop, observed.clone()));
+    let reason = *observed.lock().unwrap();
+    reason
+}
+
+#[test]
+fn test_shutdown_reason() {
+    let ms = Duration::from_millis;
+    assert_eq!(observe_shutdown(Some(ms(10)), None), Some(ShutdownReason::Timeout));
+    assert_eq!(observe_shutdown(Some(ms(50)), Some(ms(10))), Some(ShutdownReason::Requested));
+    assert_eq!(observe_shutdown(None, None), Some(ShutdownReason::EventQueueEmpty));
+}
+
+/// Reschedules an action without delay forever.
+struct Spinner {
+    id: ReactorId,
+    spin: LogicalAction<()>,
+    observed: Observed,
+}
+
+impl ReactorInitializer for Spinner {
+    type Wrapped = Self;
+    type Params = Observed;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble(observed: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        spin: cc.new_logical_action("spin", None),
+                        observed,
+                    })
+                },
+                2,
+                [Some("on_spin"), Some("on_shutdown")],
+                |decl, this, [on_spin, on_shutdown]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_spin: triggers(startup, spin) effects(spin);
+                        on_shutdown: triggers(shutdown);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Spinner {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        match rid.raw() {
+            0 => ctx.schedule(&mut self.spin, Offset::Asap),
+            _ => *self.observed.lock().unwrap() = ctx.shutdown_reason(),
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_logical_action(&mut self.spin);
+    }
+}
+
+#[test]
+fn test_microstep_limit() {
+    let observed: Observed = Default::default();
+    let options = SchedulerOptions { max_microsteps: Some(100), ..Default::default() };
+    SyncScheduler::run_main::<Spinner>(options, observed.clone());
+    let reason = *observed.lock().unwrap();
+    assert_eq!(reason, Some(ShutdownReason::MicrostepLimitExceeded));
+}
+
+type Producer = Arc<Mutex<Option<JoinHandle<bool>>>>;
+
+/// Spawns a thread that waits for shutdown, then tries
+/// to schedule a physical action.
+struct LateProducer {
+    id: ReactorId,
+    act: PhysicalActionRef<u32>,
+    producer: Producer,
+}
+
+impl ReactorInitializer for LateProducer {
+    type Wrapped = Self;
+    type Params = Producer;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble(producer: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        act: cc.new_physical_action("act", None),
+                        producer,
+                    })
+                },
+                2,
+                [Some("on_startup"), Some("on_act")],
+                |decl, this, [on_startup, on_act]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(act);
+                        on_act: triggers(act);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for LateProducer {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        if rid.raw() == 0 {
+            let act = self.act.clone();
+            let handle = ctx.spawn_physical_thread(move |link| {
+                // interrupted at shutdown
+                while link.sleep(Duration::from_secs(60)) {}
+                link.schedule_physical_with_v(&act, Some(1), Offset::Asap).is_err()
+            });
+            *self.producer.lock().unwrap() = Some(handle);
+        }
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+#[test]
+fn test_schedule_after_shutdown_is_rejected() {
+    let producer: Producer = Default::default();
+    let options = SchedulerOptions {
+        timeout: Some(Duration::from_millis(10)),
+        ..Default::default()
+    };
+    SyncScheduler::run_main::<LateProducer>(options, producer.clone());
+    let handle = producer.lock().unwrap().take().unwrap();
+    assert!(handle.join().unwrap(), "scheduling should fail after shutdown");
+}
+
+type Ticks = Arc<Mutex<Vec<EventTag>>>;
+
+/// Ticks every 10 ms, and extends the timeout
+/// of the program at startup.
+struct Ticker {
+    id: ReactorId,
+    tick: LogicalAction<()>,
+    control: SchedulerControl,
+    ticks: Ticks,
+}
+
+impl ReactorInitializer for Ticker {
+    type Wrapped = Self;
+    type Params = (SchedulerControl, Ticks);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble((control, ticks): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        tick: cc.new_logical_action("tick", None),
+                        control,
+                        ticks,
+                    })
+                },
+                2,
+                [Some("on_startup"), Some("on_tick")],
+                |decl, this, [on_startup, on_tick]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(tick);
+                        on_tick: triggers(tick) effects(tick);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Ticker {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        if rid.raw() == 0 {
+            self.control.extend_timeout(Duration::from_millis(30));
+        } else {
+            self.ticks.lock().unwrap().push(ctx.get_tag());
+        }
+        ctx.schedule(&mut self.tick, Offset::After(Duration::from_millis(10)));
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_logical_action(&mut self.tick);
+    }
+}
+
+#[test]
+fn test_timeout_extended_at_runtime() {
+    let ticks: Ticks = Default::default();
+    let control = SchedulerControl::new();
+    let options = SchedulerOptions {
+        timeout: Some(Duration::from_millis(20)),
+        control: Some(control.clone()),
+        ..Default::default()
+    };
+    SyncScheduler::run_main::<Ticker>(options, (control, ticks.clone()));
+    let last = ticks.lock().unwrap().last().cloned();
+    assert_eq!(last, Some(tag!(T0 + 50 ms)));
+}
+
+#[test]
+fn test_paused_scheduler_processes_one_tag_per_step() {
+    let ms = Duration::from_millis;
+    let ticks: Ticks = Default::default();
+    let control = SchedulerControl::new();
+    control.pause();
+    let options = SchedulerOptions {
+        // the timeout would end the pause
+        timeout: Some(ms(200)),
+        control: Some(control.clone()),
+        ..Default::default()
+    };
+    let params = (control.clone(), ticks.clone());
+    let program = std::thread::spawn(move || SyncScheduler::run_main::<Ticker>(options, params));
+    let tick_count = || ticks.lock().unwrap().len();
+
+    std::thread::sleep(ms(30));
+    assert_eq!(tick_count(), 0, "no tag should be processed after startup");
+
+    control.step();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while tick_count() == 0 && Instant::now() < deadline {
+        std::thread::sleep(ms(1));
+    }
+    std::thread::sleep(ms(30));
+    assert_eq!(*ticks.lock().unwrap(), vec![tag!(T0 + 10 ms)]);
+
+    control.resume();
+    program.join().unwrap();
+    let last = ticks.lock().unwrap().last().cloned();
+    assert_eq!(last, Some(tag!(T0 + 230 ms)));
+}
+
+/// Requests to stop the program from an asynchronous
+/// thread, shortly after startup.
+struct AsyncStopper {
+    id: ReactorId,
+    stopped: Ticks,
+}
+
+impl ReactorInitializer for AsyncStopper {
+    type Wrapped = Self;
+    type Params = Ticks;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble(stopped: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |_, id| Ok(Self { id, stopped }),
+                2,
+                [Some("on_startup"), Some("on_shutdown")],
+                |decl, _, [on_startup, on_shutdown]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup);
+                        on_shutdown: triggers(shutdown);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for AsyncStopper {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        if rid.raw() == 0 {
+            ctx.spawn_physical_thread(|link| {
+                std::thread::sleep(Duration::from_millis(20));
+                link.request_stop(Offset::Asap).unwrap();
+            });
+        } else {
+            self.stopped.lock().unwrap().push(ctx.get_tag());
+        }
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+#[test]
+fn test_stop_request_ends_the_pause() {
+    let stopped: Ticks = Default::default();
+    let control = SchedulerControl::new();
+    control.pause();
+    let options = SchedulerOptions {
+        keep_alive: true,
+        control: Some(control),
+        ..Default::default()
+    };
+    let (tx, rx) = std::sync::mpsc::channel();
+    let params = stopped.clone();
+    std::thread::spawn(move || {
+        SyncScheduler::run_main::<AsyncStopper>(options, params);
+        tx.send(()).unwrap();
+    });
+    assert!(
+        rx.recv_timeout(Duration::from_secs(5)).is_ok(),
+        "the paused program should stop"
+    );
+    let stopped = stopped.lock().unwrap();
+    assert_eq!(stopped.len(), 1);
+    assert!(stopped[0] >= tag!(T0 + 20 ms));
+}
+
+#[test]
+fn test_timeout_ends_the_pause() {
+    let ticks: Ticks = Default::default();
+    let control = SchedulerControl::new();
+    control.pause();
+    let options = SchedulerOptions {
+        timeout: Some(Duration::from_millis(20)),
+        control: Some(control.clone()),
+        ..Default::default()
+    };
+    SyncScheduler::run_main::<Ticker>(options, (control, ticks.clone()));
+    // the tags up to the timeout are processed late
+    let last = ticks.lock().unwrap().last().cloned();
+    assert_eq!(last, Some(tag!(T0 + 50 ms)));
+}
+
+type Wills = Arc<Mutex<Vec<(&'static str, u32)>>>;
+
+/// Has a last will on both of its outputs, and overrides
+/// the one of `count` in its shutdown reaction.
+struct Bridge {
+    id: ReactorId,
+    status: Port<&'static str>,
+    count: Port<u32>,
+}
+
+impl ReactorInitializer for Bridge {
+    type Wrapped = Self;
+    type Params = ();
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(1);
+
+    fn assemble(_: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        status: cc.new_port("status", PortKind::Output),
+                        count: cc.new_port("count", PortKind::Output),
+                    })
+                },
+                1,
+                [Some("on_shutdown")],
+                |decl, this, [on_shutdown]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_shutdown: triggers(shutdown) effects(count);
+                    }
+                    decl.set_last_will(&this.status, "offline")?;
+                    decl.set_last_will(&this.count, 1)
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Bridge {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        ctx.set(&mut self.count, 2);
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.status);
+        ctx.cleanup_port(&mut self.count);
+    }
+}
+
+/// Records the values of its inputs.
+struct WillObserver {
+    id: ReactorId,
+    status: Port<&'static str>,
+    count: Port<u32>,
+    wills: Wills,
+}
+
+impl ReactorInitializer for WillObserver {
+    type Wrapped = Self;
+    type Params = Wills;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(1);
+
+    fn assemble(wills: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        status: cc.new_port("status", PortKind::Input),
+                        count: cc.new_port("count", PortKind::Input),
+                        wills,
+                    })
+                },
+                1,
+                [Some("observe")],
+                |decl, this, [observe]| {
+                    declare_reactions! {
+                        (decl, this)
+                        observe: triggers(status, count);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for WillObserver {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        assert!(ctx.is_shutdown());
+        let status = ctx.get(&self.status).unwrap();
+        let count = ctx.get(&self.count).unwrap();
+        self.wills.lock().unwrap().push((status, count));
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+reactor_program! {
+    struct LastWills(wills: Wills);
+    instances {
+        bridge: Bridge = (),
+        observer: WillObserver = wills.clone(),
+    }
+    connections {
+        bridge.status -> observer.status;
+        bridge.count -> observer.count;
+    }
+}
+
+#[test]
+fn test_last_wills_are_emitted_at_shutdown() {
+    let wills: Wills = Default::default();
+    SyncScheduler::run_main::<LastWills>(Default::default(), wills.clone());
+    assert_eq!(*wills.lock().unwrap(), vec![("offline", 2)]);
+}
+
+type Received = Arc<Mutex<Vec<u32>>>;
+
+/// Spawns a thread that schedules a physical
+/// action after a delay, then ends.
+struct DelayedSender {
+    id: ReactorId,
+    act: PhysicalActionRef<u32>,
+    received: Received,
+}
+
+impl ReactorInitializer for DelayedSender {
+    type Wrapped = Self;
+    type Params = Received;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble(received: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        act: cc.new_physical_action("act", None),
+                        received,
+                    })
+                },
+                2,
+                [Some("on_startup"), Some("on_act")],
+                |decl, this, [on_startup, on_act]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(act);
+                        on_act: triggers(act);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for DelayedSender {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        if rid.raw() == 0 {
+            let act = self.act.clone();
+            ctx.spawn_physical_thread(move |link| {
+                std::thread::sleep(Duration::from_millis(50));
+                let _ = link.schedule_physical_with_v(&act, Some(1), Offset::Asap);
+            });
+        } else {
+            self.received.lock().unwrap().push(ctx.get(&self.act).unwrap());
+        }
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+#[test]
+fn test_keep_alive_waits_for_physical_events() {
+    let received: Received = Default::default();
+    let options = SchedulerOptions { keep_alive: true, ..Default::default() };
+    // returns once the thread has ended
+    SyncScheduler::run_main::<DelayedSender>(options, received.clone());
+    assert_eq!(*received.lock().unwrap(), vec![1]);
+
+    // the scheduler waited for them before keep_alive existed
+    let received: Received = Default::default();
+    SyncScheduler::run_main::<DelayedSender>(Default::default(), received.clone());
+    assert_eq!(*received.lock().unwrap(), vec![1]);
+}
+
+/// Tag and physical time of the shutdown, and whether
+/// the event scheduled after the timeout was processed.
+type Timeout = Arc<Mutex<(Option<(EventTag, Duration)>, bool)>>;
+
+/// Schedules an action after 50 ms at startup.
+struct LateEvent {
+    id: ReactorId,
+    late: LogicalAction<()>,
+    observed: Timeout,
+}
+
+impl ReactorInitializer for LateEvent {
+    type Wrapped = Self;
+    type Params = Timeout;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(3);
+
+    fn assemble(observed: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        late: cc.new_logical_action("late", None),
+                        observed,
+                    })
+                },
+                3,
+                [Some("on_startup"), Some("on_late"), Some("on_shutdown")],
+                |decl, this, [on_startup, on_late, on_shutdown]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(late);
+                        on_late: triggers(late);
+                        on_shutdown: triggers(shutdown);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for LateEvent {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        match rid.raw() {
+            0 => ctx.schedule(&mut self.late, Offset::After(Duration::from_millis(50))),
+            1 => self.observed.lock().unwrap().1 = true,
+            _ => self.observed.lock().unwrap().0 = Some((ctx.get_tag(), ctx.get_elapsed_physical_time())),
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_logical_action(&mut self.late);
+    }
+}
+
+#[test]
+fn test_shutdown_waits_for_the_timeout_tag() {
+    let timeout = Duration::from_millis(20);
+    let observed: Timeout = Default::default();
+    let options = SchedulerOptions { timeout: Some(timeout), ..Default::default() };
+    SyncScheduler::run_main::<LateEvent>(options, observed.clone());
+
+    let (shutdown, late_processed) = *observed.lock().unwrap();
+    let (tag, physical) = shutdown.expect("shutdown reactions should run");
+    assert_eq!(tag, EventTag::offset(timeout, 0));
+    assert!(physical >= timeout, "shutdown ran early, at {:?}", physical);
+    assert!(!late_processed, "events after the timeout should be discarded");
+}
+
+#[test]
+#[cfg(feature = "introspection")]
+fn test_pending_tags_of_paused_scheduler() {
+    let ms = Duration::from_millis;
+    let ticks: Ticks = Default::default();
+    let control = SchedulerControl::new();
+    control.pause();
+    let options = SchedulerOptions {
+        // the timeout would end the pause
+        timeout: Some(ms(200)),
+        control: Some(control.clone()),
+        ..Default::default()
+    };
+    let params = (control.clone(), ticks.clone());
+    let program = std::thread::spawn(move || SyncScheduler::run_main::<Ticker>(options, params));
+    let wait_for_next_tag = |expected: EventTag| {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while control.next_tag() != Some(expected) && Instant::now() < deadline {
+            std::thread::sleep(ms(1));
+        }
+    };
+
+    wait_for_next_tag(tag!(T0 + 10 ms));
+    assert_eq!(control.pending_tags(), vec![tag!(T0 + 10 ms)]);
+    assert_eq!(control.queue_len(), 1);
+
+    control.step();
+    wait_for_next_tag(tag!(T0 + 20 ms));
+    assert_eq!(control.pending_tags(), vec![tag!(T0 + 20 ms)]);
+
+    control.resume();
+    program.join().unwrap();
+}
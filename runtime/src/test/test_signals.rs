@@ -0,0 +1,113 @@
+use signal_hook::consts::SIGTERM;
+
+use super::testutil::*;
+use crate::assembly::*;
+use crate::stdlib::signals::*;
+use crate::*;
+
+/// Sends SIGTERM to the process shortly after startup,
+/// once the [SignalSource] has registered it.
+struct Raiser {
+    id: ReactorId,
+    raise: LogicalAction<()>,
+}
+
+impl ReactorInitializer for Raiser {
+    type Wrapped = Self;
+    type Params = ();
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble(_: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| Ok(Self { id, raise: cc.new_logical_action("raise", None) }),
+                2,
+                [Some("on_startup"), Some("on_raise")],
+                |decl, this, [on_startup, on_raise]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(raise);
+                        on_raise: triggers(raise);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Raiser {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        if rid.raw() == 0 {
+            ctx.schedule(&mut self.raise, Offset::After(Duration::from_millis(5)));
+        } else {
+            signal_hook::low_level::raise(SIGTERM).unwrap();
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_logical_action(&mut self.raise);
+    }
+}
+
+struct Main {
+    id: ReactorId,
+}
+
+impl ReactorInitializer for Main {
+    type Wrapped = Self;
+    type Params = Recording<Signal>;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(0);
+
+    fn assemble(recording: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.with_child::<SignalSource, _>("signals", SignalMode::Shutdown, |ctx, signals| {
+                ctx.with_child::<Raiser, _>("raiser", (), |ctx, _| {
+                    ctx.with_child::<Recorder<Signal>, _>("recorder", recording, |ctx, recorder| {
+                        ctx.assemble_self(
+                            |_, id| Ok(Self { id }),
+                            0,
+                            [],
+                            |decl, _, []| decl.bind_ports(&mut signals.received, &mut recorder.input),
+                        )
+                    })
+                })
+            })
+        })
+    }
+}
+
+impl ReactorBehavior for Main {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, _ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        unreachable!()
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+#[test]
+fn test_sigterm_shuts_down_gracefully() {
+    let recording: Recording<Signal> = Default::default();
+    let options = SchedulerOptions {
+        keep_alive: true,
+        timeout: Some(Duration::from_secs(10)),
+        ..Default::default()
+    };
+    let start = Instant::now();
+    SyncScheduler::run_main::<Main>(options, recording.clone());
+
+    assert!(
+        start.elapsed() < Duration::from_secs(5),
+        "the program should stop on the signal"
+    );
+    let signals: Vec<Signal> = recording.lock().unwrap().iter().map(|(_, s)| *s).collect();
+    assert_eq!(signals, vec![Signal::Terminate]);
+}
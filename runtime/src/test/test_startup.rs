@@ -0,0 +1,326 @@
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::*;
+use crate::*;
+
+type Trace = Arc<Mutex<Vec<(EventTag, &'static str, Option<u32>)>>>;
+
+/// Derives a value from its configuration at startup.
+struct Child {
+    id: ReactorId,
+    config: Port<u32>,
+    derived: Port<u32>,
+    trace: Trace,
+}
+
+impl ReactorInitializer for Child {
+    type Wrapped = Self;
+    type Params = Trace;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble(trace: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        config: cc.new_port("config", PortKind::Input),
+                        derived: cc.new_port("derived", PortKind::Output),
+                        trace,
+                    })
+                },
+                2,
+                [Some("on_startup"), Some("on_config")],
+                |decl, this, [on_startup, on_config]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) uses(config) effects(derived);
+                        on_config: triggers(config);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Child {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        let config = ctx.get(&self.config);
+        if rid.raw() == 0 {
+            self.trace.lock().unwrap().push((ctx.get_tag(), "child startup", config));
+            ctx.set_opt(&mut self.derived, config.map(|c| c * 2));
+        } else {
+            self.trace.lock().unwrap().push((ctx.get_tag(), "child config", config));
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.config);
+        ctx.cleanup_port(&mut self.derived);
+    }
+}
+
+/// Configures its child at startup, and
+/// records the value the child derives.
+struct Parent {
+    id: ReactorId,
+    /// Bound to the config of the child.
+    config: Port<u32>,
+    /// Bound from the derived value of the child.
+    derived: Port<u32>,
+    trace: Trace,
+}
+
+impl ReactorInitializer for Parent {
+    type Wrapped = Self;
+    type Params = Trace;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble(trace: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.with_child::<Child, _>("child", trace.clone(), |ctx, child| {
+                ctx.assemble_self(
+                    |cc, id| {
+                        Ok(Self {
+                            id,
+                            config: cc.new_port("config", PortKind::Output),
+                            derived: cc.new_port("derived", PortKind::Input),
+                            trace,
+                        })
+                    },
+                    2,
+                    [Some("configure"), Some("on_derived")],
+                    |decl, this, [configure, on_derived]| {
+                        decl.bind_ports(&mut this.config, &mut child.config)?;
+                        decl.bind_ports(&mut child.derived, &mut this.derived)?;
+                        declare_reactions! {
+                            (decl, this)
+                            configure: triggers(startup) effects(config);
+                            on_derived: triggers(derived);
+                        }
+                        Ok(())
+                    },
+                )
+            })
+        })
+    }
+}
+
+impl ReactorBehavior for Parent {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        if rid.raw() == 0 {
+            self.trace.lock().unwrap().push((ctx.get_tag(), "parent startup", None));
+            ctx.set(&mut self.config, 21);
+        } else {
+            let derived = ctx.get(&self.derived);
+            self.trace.lock().unwrap().push((ctx.get_tag(), "parent derived", derived));
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.config);
+        ctx.cleanup_port(&mut self.derived);
+    }
+}
+
+#[test]
+fn test_startup_values_propagate_within_startup_wave() {
+    let trace: Trace = Default::default();
+    SyncScheduler::run_main::<Parent>(Default::default(), trace.clone());
+
+    let trace = trace.lock().unwrap();
+    let t0 = EventTag::ORIGIN;
+    assert_eq!(trace[0], (t0, "parent startup", None));
+    // the child reactions are unordered with respect to each other
+    let mut child = trace[1..3].to_vec();
+    child.sort();
+    assert_eq!(child, vec![(t0, "child config", Some(21)), (t0, "child startup", Some(21))]);
+    assert_eq!(trace[3..], [(t0, "parent derived", Some(42))]);
+}
+
+type Sent = Arc<Mutex<Vec<bool>>>;
+
+/// Arms a timer, schedules an action and a stop, and
+/// starts a thread that sends a physical event.
+struct Busy {
+    id: ReactorId,
+    tick: Timer,
+    later: LogicalAction<()>,
+    input: PhysicalActionRef<()>,
+    sent: Sent,
+}
+
+impl ReactorInitializer for Busy {
+    type Wrapped = Self;
+    type Params = Sent;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(3);
+
+    fn assemble(sent: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        tick: cc.new_timer("tick", Duration::from_millis(10), Duration::from_millis(10)),
+                        later: cc.new_logical_action("later", None),
+                        input: cc.new_physical_action("input", None),
+                        sent,
+                    })
+                },
+                3,
+                [Some("on_startup"), Some("on_event"), Some("on_tick")],
+                |decl, this, [on_startup, on_event, on_tick]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(tick, later, input);
+                        on_event: triggers(later, input);
+                        on_tick: triggers(tick);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Busy {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        if rid.raw() == 0 {
+            ctx.bootstrap_timer(&mut self.tick);
+            ctx.schedule(&mut self.later, Offset::After(Duration::from_millis(5)));
+            ctx.request_stop(Offset::After(Duration::from_secs(60)));
+            let (input, sent) = (self.input.clone(), self.sent.clone());
+            ctx.spawn_physical_thread(move |link| {
+                let ok = link.schedule_physical(&input, Offset::Asap).is_ok();
+                sent.lock().unwrap().push(ok);
+            });
+        } else if rid.raw() == 2 {
+            ctx.reschedule_timer(&mut self.tick);
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_logical_action(&mut self.later);
+    }
+}
+
+#[test]
+fn test_dry_run_reports_pending_events() {
+    let sent: Sent = Default::default();
+    let report = SyncScheduler::dry_run::<Busy>(sent.clone());
+    assert!(report.is_ok(), "{}", report);
+    assert_eq!(report.reactions, vec!["/0@on_startup".to_string()]);
+    assert_eq!(report.timers, vec![("/tick".to_string(), tag!(T0 + 10 ms))]);
+    assert_eq!(report.actions, vec![("/later".to_string(), tag!(T0 + 5 ms))]);
+    assert_eq!(report.stop_requested, Some(tag!(T0 + 60 sec)));
+
+    // the physical event was rejected
+    for _ in 0..100 {
+        if !sent.lock().unwrap().is_empty() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(*sent.lock().unwrap(), vec![false]);
+}
+
+/// Takes its parameter to assemble.
+struct Sleepy {
+    id: ReactorId,
+}
+
+impl ReactorInitializer for Sleepy {
+    type Wrapped = Self;
+    type Params = Duration;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(0);
+
+    fn assemble(delay: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        std::thread::sleep(delay);
+        ctx.assemble(|ctx| ctx.assemble_self(|_, id| Ok(Self { id }), 0, [], |_, _, []| Ok(())))
+    }
+}
+
+impl ReactorBehavior for Sleepy {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, _: &mut ReactionCtx, _: LocalReactionId) {}
+
+    fn cleanup_tag(&mut self, _: &CleanupCtx) {}
+}
+
+/// Has a fast and a slow child.
+struct Sleepers {
+    id: ReactorId,
+}
+
+impl ReactorInitializer for Sleepers {
+    type Wrapped = Self;
+    type Params = ();
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(0);
+
+    fn assemble(_: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.with_child::<Sleepy, _>("fast", Duration::ZERO, |ctx, _| {
+                ctx.with_child::<Sleepy, _>("slow", Duration::from_millis(30), |ctx, _| {
+                    ctx.assemble_self(|_, id| Ok(Self { id }), 0, [], |_, _, []| Ok(()))
+                })
+            })
+        })
+    }
+}
+
+impl ReactorBehavior for Sleepers {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, _: &mut ReactionCtx, _: LocalReactionId) {}
+
+    fn cleanup_tag(&mut self, _: &CleanupCtx) {}
+}
+
+#[test]
+fn test_startup_budget_reports_slow_subtrees() {
+    let report = Arc::new(Mutex::new(None));
+    let sink = report.clone();
+    let options = SchedulerOptions {
+        startup_budget: Some(
+            StartupBudget::new(Duration::from_millis(10)).on_exceeded(move |r| *sink.lock().unwrap() = Some(r.clone())),
+        ),
+        ..Default::default()
+    };
+    SyncScheduler::run_main::<Sleepers>(options, ());
+
+    let report = report.lock().unwrap().take().expect("budget should be exceeded");
+    assert!(report.elapsed >= Duration::from_millis(30), "{}", report);
+    // the main reactor includes its children
+    assert_eq!(report.subtrees.len(), 3, "{}", report);
+    assert_eq!(report.subtrees[1].0, "/slow/", "{}", report);
+    assert!(report.subtrees[1].1 >= Duration::from_millis(30), "{}", report);
+    let passes = report.passes.iter().map(|(p, _)| *p).collect::<Vec<_>>();
+    assert_eq!(passes, vec!["assembly", "reaction levels", "reaction plans"]);
+}
+
+#[test]
+fn test_startup_budget_is_silent_when_met() {
+    let options = SchedulerOptions {
+        startup_budget: Some(StartupBudget::new(Duration::from_secs(60)).on_exceeded(|r| panic!("{}", r))),
+        ..Default::default()
+    };
+    SyncScheduler::run_main::<Sleepers>(options, ());
+}
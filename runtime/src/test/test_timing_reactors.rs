@@ -0,0 +1,52 @@
+use super::testutil::*;
+use crate::stdlib::physical::*;
+use crate::stdlib::timing::*;
+use crate::*;
+
+macro_rules! impl_pipe {
+    ($($R:ident),*) => {
+        $(impl<T: Sync + Clone + 'static> Pipe<T> for $R<T> {
+            fn ports(&mut self) -> (&mut Port<T>, &mut Port<T>) {
+                (&mut self.input, &mut self.output)
+            }
+        })*
+    };
+}
+
+impl_pipe!(Debounce, Throttle, SampleAndHold, PhysicalConnection);
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn test_debounce() {
+    let script = vec![(ms(0), 1), (ms(5), 2), (ms(10), 3), (ms(40), 4)];
+    let out = run_pipeline::<u32, Debounce<u32>>(DebounceParams::new(ms(15)), script, ms(100));
+    assert_eq!(out, vec![(ms(25), 3), (ms(55), 4)]);
+}
+
+#[test]
+fn test_throttle() {
+    let script = vec![(ms(0), 1), (ms(5), 2), (ms(10), 3), (ms(12), 4), (ms(25), 5)];
+    let out = run_pipeline::<u32, Throttle<u32>>(ThrottleParams::new(ms(10)), script, ms(50));
+    assert_eq!(out, vec![(ms(0), 1), (ms(10), 3), (ms(25), 5)]);
+}
+
+#[test]
+fn test_sample_and_hold() {
+    let script = vec![(ms(0), 1), (ms(15), 2), (ms(32), 3)];
+    let out = run_pipeline::<u32, SampleAndHold<u32>>(SampleAndHoldParams::new(Delay::msec(5), Period::msec(10)), script, ms(50));
+    assert_eq!(out, vec![(ms(5), 1), (ms(15), 2), (ms(25), 2), (ms(35), 3), (ms(45), 3)]);
+}
+
+#[test]
+fn test_physical_connection() {
+    let script = vec![(ms(0), 1), (ms(10), 2)];
+    let out = run_pipeline::<u32, PhysicalConnection<u32>>((), script.clone(), ms(50));
+    assert_eq!(out.iter().map(|(_, v)| *v).collect::<Vec<_>>(), vec![1, 2]);
+    // values arrive at a later tag than they were sent
+    for ((sent, _), (received, _)) in script.iter().zip(&out) {
+        assert!(received > sent);
+    }
+}
@@ -0,0 +1,152 @@
+use super::testutil::*;
+use crate::assembly::*;
+use crate::*;
+
+/// Main reactor whose connections contain several mistakes.
+struct BadConnections {
+    id: ReactorId,
+}
+
+impl ReactorInitializer for BadConnections {
+    type Wrapped = Self;
+    type Params = ();
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(0);
+
+    fn assemble(_: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.with_child_bank::<ScriptedSource<u32>, _, _>(
+                "sources",
+                3,
+                |_| vec![],
+                |ctx, sources| {
+                    ctx.with_child_bank::<Recorder<u32>, _, _>(
+                        "recorders",
+                        2,
+                        |_| Default::default(),
+                        |ctx, recorders| {
+                            ctx.assemble_self(
+                                |_, id| Ok(Self { id }),
+                                0,
+                                [],
+                                |decl, _, []| {
+                                    // the last source is left unbound
+                                    decl.bind_ports_zip(
+                                        sources.iter_mut().map(|s| &mut s.output),
+                                        recorders.iter_mut().map(|r| &mut r.input),
+                                    )?;
+                                    // both recorders are already bound
+                                    decl.bind_ports(&mut sources[2].output, &mut recorders[0].input)?;
+                                    decl.bind_ports(&mut sources[0].output, &mut recorders[1].input)
+                                },
+                            )
+                        },
+                    )
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for BadConnections {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, _ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        unreachable!()
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+#[test]
+fn test_validation_reports_all_errors() {
+    let report = SyncScheduler::validate::<BadConnections>(());
+    assert!(!report.is_ok());
+
+    let paths = report.errors.iter().map(|e| e.path.as_deref()).collect::<Vec<_>>();
+    assert_eq!(paths, vec![Some("/recorders[0]/input"), Some("/recorders[1]/input")]);
+
+    assert_eq!(report.warnings.len(), 1, "{}", report);
+    assert_eq!(report.warnings[0].path.as_deref(), Some("/sources[2]/output"));
+}
+
+#[test]
+fn test_custom_validation_rule() {
+    // forbids connections that leave the sources bank
+    let no_source_outputs = |program: &ProgramGraph<'_>, report: &mut ValidationReport| {
+        for (from, _, kind) in program.dependencies() {
+            if kind == EdgeKind::Binding && program.path(from).starts_with("/sources") {
+                report.warnings.push(Diagnostic {
+                    path: Some(program.path(from)),
+                    message: "Sources must not be connected".into(),
+                });
+            }
+        }
+    };
+    let report = SyncScheduler::validate_with::<BadConnections>((), &[&no_source_outputs]);
+    let mut paths = report
+        .warnings
+        .iter()
+        .filter(|w| w.message == "Sources must not be connected")
+        .map(|w| w.path.as_deref().unwrap())
+        .collect::<Vec<_>>();
+    paths.sort_unstable();
+    // the bindings that failed are not part of the graph
+    assert_eq!(paths, vec!["/sources[0]/output", "/sources[1]/output"], "{}", report);
+}
+
+/// Main reactor with a reaction that sets the input
+/// of a recorder, which is also bound to a source.
+struct TwoWriters {
+    id: ReactorId,
+}
+
+impl ReactorInitializer for TwoWriters {
+    type Wrapped = Self;
+    type Params = ();
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(1);
+
+    fn assemble(_: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.with_child::<ScriptedSource<u32>, _>("source", vec![], |ctx, source| {
+                ctx.with_child::<Recorder<u32>, _>("recorder", Default::default(), |ctx, recorder| {
+                    ctx.assemble_self(
+                        |_, id| Ok(Self { id }),
+                        1,
+                        [Some("write")],
+                        |decl, _, [write]| {
+                            decl.declare_triggers(TriggerId::STARTUP, write)?;
+                            decl.effects_port(write, &recorder.input)?;
+                            decl.bind_ports(&mut source.output, &mut recorder.input)
+                        },
+                    )
+                })
+            })
+        })
+    }
+}
+
+impl ReactorBehavior for TwoWriters {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, _ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        unreachable!()
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+#[test]
+fn test_port_with_several_writers() {
+    let report = SyncScheduler::validate::<TwoWriters>(());
+    assert_eq!(report.errors.len(), 1, "{}", report);
+    assert_eq!(report.errors[0].path.as_deref(), Some("/recorder/input"));
+    assert!(
+        report.errors[0].message.starts_with("Port is bound to /source/output"),
+        "{}",
+        report
+    );
+}
@@ -0,0 +1,114 @@
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::*;
+use crate::*;
+
+#[derive(Debug, Default)]
+struct Observed {
+    /// Physical time of the expiry, relative to startup.
+    expired_after: Option<Duration>,
+    reason: Option<ShutdownReason>,
+}
+
+/// Arms a 20 ms watchdog at startup, and receives a
+/// heartbeat 10 ms later, which restarts or stops it.
+struct Guarded {
+    id: ReactorId,
+    watchdog: Watchdog,
+    heartbeat: LogicalAction<()>,
+    stop_on_heartbeat: bool,
+    observed: Arc<Mutex<Observed>>,
+}
+
+impl ReactorInitializer for Guarded {
+    type Wrapped = Self;
+    type Params = (bool, Arc<Mutex<Observed>>);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(4);
+
+    fn assemble((stop_on_heartbeat, observed): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        watchdog: cc.new_watchdog("watchdog"),
+                        heartbeat: cc.new_logical_action("heartbeat", None),
+                        stop_on_heartbeat,
+                        observed,
+                    })
+                },
+                4,
+                [
+                    Some("on_startup"),
+                    Some("on_heartbeat"),
+                    Some("on_expiry"),
+                    Some("on_shutdown"),
+                ],
+                |decl, this, [on_startup, on_heartbeat, on_expiry, on_shutdown]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(heartbeat);
+                        on_heartbeat: triggers(heartbeat);
+                        on_expiry: triggers(watchdog);
+                        on_shutdown: triggers(shutdown);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl ReactorBehavior for Guarded {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        match rid.raw() {
+            0 => {
+                ctx.start_watchdog(&self.watchdog, Duration::from_millis(20));
+                ctx.schedule(&mut self.heartbeat, Offset::After(Duration::from_millis(10)));
+            }
+            1 if self.stop_on_heartbeat => ctx.stop_watchdog(&self.watchdog),
+            1 => ctx.start_watchdog(&self.watchdog, Duration::from_millis(20)),
+            2 => {
+                self.observed.lock().unwrap().expired_after = Some(ctx.get_elapsed_physical_time());
+                ctx.request_stop(Offset::Asap);
+            }
+            _ => self.observed.lock().unwrap().reason = ctx.shutdown_reason(),
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_watchdog(&mut self.watchdog);
+        ctx.cleanup_logical_action(&mut self.heartbeat);
+    }
+}
+
+fn run(stop_on_heartbeat: bool) -> Observed {
+    let observed = Arc::new(Mutex::new(Observed::default()));
+    let options = SchedulerOptions {
+        timeout: Some(Duration::from_secs(1)),
+        ..Default::default()
+    };
+    SyncScheduler::run_main::<Guarded>(options, (stop_on_heartbeat, observed.clone()));
+    let result = std::mem::take(&mut *observed.lock().unwrap());
+    result
+}
+
+#[test]
+fn test_restarted_watchdog_expires() {
+    let observed = run(false);
+    let expired_after = observed.expired_after.unwrap();
+    assert!(expired_after >= Duration::from_millis(30), "{:?}", expired_after);
+    assert_eq!(observed.reason, Some(ShutdownReason::Requested));
+}
+
+#[test]
+fn test_stopped_watchdog_does_not_expire() {
+    let observed = run(true);
+    assert_eq!(observed.expired_after, None);
+    // the watchdog does not keep the program alive
+    assert_eq!(observed.reason, Some(ShutdownReason::EventQueueEmpty));
+}
@@ -24,9 +24,195 @@
 
 //! Test utilities.
 
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::*;
 use crate::*;
 
 /// Set a port to a value
 pub fn set_port<T: Sync>(port: &mut Port<T>, v: T) {
     port.set_impl(Some(v))
 }
+
+/// Reactor that outputs the values of a script at the
+/// given logical times (relative to startup).
+pub struct ScriptedSource<T: Sync + Clone + 'static> {
+    id: ReactorId,
+    pub output: Port<T>,
+    emit: LogicalAction<T>,
+    script: Vec<(Duration, T)>,
+}
+
+impl<T: Sync + Clone + 'static> ReactorInitializer for ScriptedSource<T> {
+    type Wrapped = Self;
+    type Params = Vec<(Duration, T)>;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(2);
+
+    fn assemble(script: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        output: cc.new_port("output", PortKind::Output),
+                        emit: cc.new_logical_action("emit", None),
+                        script,
+                    })
+                },
+                2,
+                [Some("on_startup"), Some("on_emit")],
+                |decl, this, [on_startup, on_emit]| {
+                    declare_reactions! {
+                        (decl, this)
+                        on_startup: triggers(startup) effects(emit);
+                        on_emit: triggers(emit) effects(output);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl<T: Sync + Clone + 'static> ReactorBehavior for ScriptedSource<T> {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, rid: LocalReactionId) {
+        match rid.raw() {
+            0 => {
+                for (t, v) in self.script.drain(..) {
+                    ctx.schedule_with_v(&mut self.emit, Some(v), Offset::After(t));
+                }
+            }
+            1 => {
+                let v = ctx.use_ref_opt(&self.emit, T::clone);
+                ctx.set_opt(&mut self.output, v)
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.output);
+        ctx.cleanup_logical_action(&mut self.emit);
+    }
+}
+
+/// Values recorded by a [Recorder], with the logical time
+/// (relative to startup) at which they were received.
+pub type Recording<T> = Arc<Mutex<Vec<(Duration, T)>>>;
+
+/// Reactor that records all values it receives.
+pub struct Recorder<T: Sync + Clone + Send + 'static> {
+    id: ReactorId,
+    pub input: Port<T>,
+    recording: Recording<T>,
+}
+
+impl<T: Sync + Clone + Send + 'static> ReactorInitializer for Recorder<T> {
+    type Wrapped = Self;
+    type Params = Recording<T>;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(1);
+
+    fn assemble(recording: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        input: cc.new_port("input", PortKind::Input),
+                        recording,
+                    })
+                },
+                1,
+                [Some("on_input")],
+                |decl, this, [on_input]| decl.declare_triggers(this.input.get_id(), on_input),
+            )
+        })
+    }
+}
+
+impl<T: Sync + Clone + Send + 'static> ReactorBehavior for Recorder<T> {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        if let Some(v) = ctx.use_ref_opt(&self.input, T::clone) {
+            self.recording.lock().unwrap().push((ctx.get_elapsed_logical_time(), v));
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.input);
+    }
+}
+
+/// A reactor with one input and one output, which can be
+/// tested with [run_pipeline].
+pub trait Pipe<T: Sync>: ReactorInitializer + 'static {
+    fn ports(&mut self) -> (&mut Port<T>, &mut Port<T>);
+}
+
+/// Main reactor of [run_pipeline].
+struct PipelineMain<P, T> {
+    id: ReactorId,
+    _phantom: PhantomData<(P, T)>,
+}
+
+impl<T: Sync + Clone + Send + 'static, P: Pipe<T>> ReactorInitializer for PipelineMain<P, T> {
+    type Wrapped = Self;
+    type Params = (Vec<(Duration, T)>, P::Params, Recording<T>);
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(0);
+
+    fn assemble((script, params, recording): Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.with_child::<ScriptedSource<T>, _>("source", script, |ctx, source| {
+                ctx.with_child::<P, _>("pipe", params, |ctx, pipe| {
+                    ctx.with_child::<Recorder<T>, _>("recorder", recording, |ctx, recorder| {
+                        ctx.assemble_self(
+                            |_, id| Ok(Self { id, _phantom: PhantomData }),
+                            0,
+                            [],
+                            |decl, _, []| {
+                                let (input, output) = pipe.ports();
+                                decl.bind_ports(&mut source.output, input)?;
+                                decl.bind_ports(output, &mut recorder.input)
+                            },
+                        )
+                    })
+                })
+            })
+        })
+    }
+}
+
+impl<P, T> ReactorBehavior for PipelineMain<P, T> {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, _ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        unreachable!()
+    }
+
+    fn cleanup_tag(&mut self, _ctx: &CleanupCtx) {}
+}
+
+/// Run a program that feeds the values of the script into
+/// the input of a reactor `P`, and returns what was observed
+/// on its output. The program stops at the given timeout.
+pub fn run_pipeline<T: Sync + Clone + Send + 'static, P: Pipe<T>>(
+    params: P::Params,
+    script: Vec<(Duration, T)>,
+    timeout: Duration,
+) -> Vec<(Duration, T)> {
+    let recording: Recording<T> = Default::default();
+    let options = SchedulerOptions { timeout: Some(timeout), ..Default::default() };
+    SyncScheduler::run_main::<PipelineMain<P, T>>(options, (script, params, recording.clone()));
+    let result = recording.lock().unwrap().clone();
+    result
+}
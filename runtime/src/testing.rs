@@ -0,0 +1,210 @@
+//! Testing reactors in logical time, with the feature `test-harness`.
+//!
+//! A [TestHarness] runs a program with [SchedulerOptions::fast],
+//! so that a test that covers seconds of logical time completes
+//! as fast as the reactions execute, and records which components
+//! were present at each tag. Values are recorded by [Probe]
+//! reactors, which the test program binds to the ports under test:
+//!
+//! ```ignore
+//! reactor_program! {
+//!     struct Bench(out: Probed<u32>);
+//!     instances {
+//!         counter: Counter = (),
+//!         probe: Probe<u32> = out,
+//!     }
+//!     connections {
+//!         counter.out -> probe.input;
+//!     }
+//! }
+//!
+//! let out = Probed::default();
+//! let run = TestHarness::new().timeout(Duration::from_secs(10)).run::<Bench>(out.clone());
+//! // the counter ticks every second
+//! let one_second = EventTag::offset(Duration::from_secs(1), 0);
+//! out.assert_value_at(one_second, 1);
+//! run.assert_present_at(one_second, "/counter/tick");
+//! ```
+
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use crate::assembly::*;
+use crate::*;
+
+/// Runs programs in logical time, see the [module docs](self).
+#[derive(Default)]
+pub struct TestHarness {
+    timeout: Option<Duration>,
+}
+
+impl TestHarness {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop the program at this logical time. This is
+    /// needed for programs that would otherwise not stop,
+    /// eg because they have a periodic timer.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Run the program with main reactor `R` to completion.
+    pub fn run<R: ReactorInitializer + 'static>(self, params: R::Params) -> TestRun {
+        let run: Arc<Mutex<TestRun>> = Default::default();
+        let sink = run.clone();
+        let options = SchedulerOptions {
+            fast: true,
+            timeout: self.timeout,
+            trace: Some(TraceSampler::new(TraceSampling::EveryNthTag(1), usize::MAX, move |trace| {
+                let present = trace.present.iter().map(|p| {
+                    let names = p.triggers.iter().map(|t| trigger_name(trace, *t)).collect();
+                    (p.tag, names)
+                });
+                sink.lock().unwrap().present = present.collect();
+            })),
+            ..Default::default()
+        };
+        SyncScheduler::run_main::<R>(options, params);
+        let mut run = run.lock().unwrap();
+        std::mem::take(&mut *run)
+    }
+}
+
+fn trigger_name(trace: &SampledTrace, trigger: TriggerId) -> String {
+    match trigger {
+        TriggerId::STARTUP => "startup".to_string(),
+        TriggerId::SHUTDOWN => "shutdown".to_string(),
+        _ => trace.symbols.trigger(trigger).unwrap_or_default().to_string(),
+    }
+}
+
+/// What happened during a run of a [TestHarness].
+#[derive(Clone, Debug, Default)]
+pub struct TestRun {
+    /// Processed tags, in order, with the paths of the actions,
+    /// timers and ports present at each one, eg `/child/out`,
+    /// and `startup`. Paths are sorted by id.
+    pub present: Vec<(EventTag, Vec<String>)>,
+}
+
+impl TestRun {
+    /// The processed tags, in order.
+    pub fn tags(&self) -> Vec<EventTag> {
+        self.present.iter().map(|(tag, _)| *tag).collect()
+    }
+
+    /// The tags at which the component with the given path was present.
+    pub fn tags_of(&self, path: &str) -> Vec<EventTag> {
+        self.present
+            .iter()
+            .filter(|(_, names)| names.iter().any(|n| n == path))
+            .map(|(tag, _)| *tag)
+            .collect()
+    }
+
+    /// Panic if the component with the given path
+    /// was not present at the given tag.
+    pub fn assert_present_at(&self, tag: EventTag, path: &str) {
+        let present = self.present.iter().find(|(t, _)| *t == tag).map(|(_, names)| names);
+        match present {
+            Some(names) if names.iter().any(|n| n == path) => {}
+            Some(names) => panic!("{} is not present at {}, present are {:?}", path, tag, names),
+            None => panic!("Tag {} was not processed, processed tags are {:?}", tag, self.tags()),
+        }
+    }
+}
+
+/// Values received by a [Probe], with their tag.
+pub struct Probed<T>(Arc<Mutex<Vec<(EventTag, T)>>>);
+
+impl<T> Default for Probed<T> {
+    fn default() -> Self {
+        Self(Default::default())
+    }
+}
+
+// Derive would require T: Clone.
+impl<T> Clone for Probed<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<T: Clone> Probed<T> {
+    /// All values received so far, in tag order.
+    pub fn values(&self) -> Vec<(EventTag, T)> {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// The value received at the given tag, if any.
+    pub fn value_at(&self, tag: EventTag) -> Option<T> {
+        let values = self.0.lock().unwrap();
+        values.iter().find(|(t, _)| *t == tag).map(|(_, v)| v.clone())
+    }
+}
+
+impl<T: Clone + PartialEq + Debug> Probed<T> {
+    /// Panic if the probe did not receive the given value at the given tag.
+    pub fn assert_value_at(&self, tag: EventTag, value: T) {
+        match self.value_at(tag) {
+            Some(v) => assert_eq!(v, value, "Unexpected value at {}", tag),
+            None => panic!("No value at {}, received {:?}", tag, self.values()),
+        }
+    }
+}
+
+/// A reactor that records the values it receives on its
+/// input into a [Probed], which the test keeps a handle to.
+pub struct Probe<T: Sync + Clone + Send + 'static> {
+    id: ReactorId,
+    pub input: Port<T>,
+    probed: Probed<T>,
+}
+
+impl<T: Sync + Clone + Send + 'static> ReactorInitializer for Probe<T> {
+    type Wrapped = Self;
+    type Params = Probed<T>;
+    const MAX_REACTION_ID: LocalReactionId = LocalReactionId::new(1);
+
+    fn assemble(probed: Self::Params, ctx: AssemblyCtx<Self>) -> AssemblyResult<FinishedReactor<Self>> {
+        ctx.assemble(|ctx| {
+            ctx.assemble_self(
+                |cc, id| {
+                    Ok(Self {
+                        id,
+                        input: cc.new_port("input", PortKind::Input),
+                        probed,
+                    })
+                },
+                1,
+                [Some("record")],
+                |decl, this, [record]| {
+                    declare_reactions! {
+                        (decl, this)
+                        record: triggers(input);
+                    }
+                    Ok(())
+                },
+            )
+        })
+    }
+}
+
+impl<T: Sync + Clone + Send + 'static> ReactorBehavior for Probe<T> {
+    fn id(&self) -> ReactorId {
+        self.id
+    }
+
+    fn react(&mut self, ctx: &mut ReactionCtx, _rid: LocalReactionId) {
+        if let Some(value) = ctx.use_ref_opt(&self.input, T::clone) {
+            self.probed.0.lock().unwrap().push((ctx.get_tag(), value));
+        }
+    }
+
+    fn cleanup_tag(&mut self, ctx: &CleanupCtx) {
+        ctx.cleanup_port(&mut self.input);
+    }
+}
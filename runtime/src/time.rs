@@ -22,14 +22,21 @@
  * THE USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
  */
 
+use std::cmp::Ordering;
 use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
 use std::ops::Add;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::util::{parse_time_value, TimeUnit};
 
 /// Private concrete type of a microstep.
 pub(crate) type MS = u32;
 
 /// Type of the microsteps of an [EventTag](crate::EventTag).
 #[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
 pub struct MicroStep(MS);
 
 impl MicroStep {
@@ -37,6 +44,9 @@ impl MicroStep {
     pub fn new(u: MS) -> Self {
         Self(u)
     }
+    pub const fn raw(self) -> MS {
+        self.0
+    }
 }
 
 impl Display for MicroStep {
@@ -52,3 +62,227 @@ impl Add<MS> for MicroStep {
         Self(self.0 + rhs)
     }
 }
+
+/// A delay in logical time, eg the offset of a timer or the
+/// minimal delay of an action. A delay may be zero. Unlike a
+/// plain [Duration], the unit is explicit at construction:
+/// ```
+/// # use reactor_rt::{Delay, Duration};
+/// assert_eq!(Delay::msec(5).as_duration(), Duration::from_millis(5));
+/// ```
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Delay(Duration);
+
+impl Delay {
+    pub const ZERO: Delay = Delay(Duration::ZERO);
+
+    pub const fn nsec(n: u64) -> Self {
+        Self(Duration::from_nanos(n))
+    }
+
+    pub const fn usec(n: u64) -> Self {
+        Self(Duration::from_micros(n))
+    }
+
+    pub const fn msec(n: u64) -> Self {
+        Self(Duration::from_millis(n))
+    }
+
+    pub const fn sec(n: u64) -> Self {
+        Self(Duration::from_secs(n))
+    }
+
+    pub const fn as_duration(self) -> Duration {
+        self.0
+    }
+}
+
+impl From<Duration> for Delay {
+    fn from(d: Duration) -> Self {
+        Self(d)
+    }
+}
+
+impl From<Delay> for Duration {
+    fn from(d: Delay) -> Self {
+        d.0
+    }
+}
+
+impl Display for Delay {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ns", self.0.as_nanos())
+    }
+}
+
+/// The period of a periodic timer, which is never zero. A
+/// zero period would make a timer trigger only once, which
+/// is rarely intended by code that asks for a period.
+///
+/// The constructors panic if the period is zero, which is a
+/// compile-time error if they are evaluated in a constant:
+/// ```compile_fail
+/// # use reactor_rt::Period;
+/// const SAMPLE: Period = Period::msec(0);
+/// let _ = SAMPLE;
+/// ```
+/// Use [Period::new] to check durations computed at runtime.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Period(Duration);
+
+impl Period {
+    /// Returns None if the duration is zero.
+    pub const fn new(d: Duration) -> Option<Self> {
+        if d.is_zero() {
+            None
+        } else {
+            Some(Self(d))
+        }
+    }
+
+    const fn non_zero(d: Duration) -> Self {
+        match Self::new(d) {
+            Some(p) => p,
+            None => panic!("A period must not be zero"),
+        }
+    }
+
+    pub const fn nsec(n: u64) -> Self {
+        Self::non_zero(Duration::from_nanos(n))
+    }
+
+    pub const fn usec(n: u64) -> Self {
+        Self::non_zero(Duration::from_micros(n))
+    }
+
+    pub const fn msec(n: u64) -> Self {
+        Self::non_zero(Duration::from_millis(n))
+    }
+
+    pub const fn sec(n: u64) -> Self {
+        Self::non_zero(Duration::from_secs(n))
+    }
+
+    pub const fn as_duration(self) -> Duration {
+        self.0
+    }
+}
+
+impl From<Period> for Duration {
+    fn from(p: Period) -> Self {
+        p.0
+    }
+}
+
+impl Display for Period {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ns", self.0.as_nanos())
+    }
+}
+
+/// A time value as written in LF, eg `100 msec`, which keeps
+/// its unit. This is the type of parameters with `time` type
+/// in generated code, and it converts into a [Duration]:
+/// ```
+/// # use reactor_rt::{Duration, TimeUnit, TimeValue};
+/// let period: TimeValue = "100 msec".parse().unwrap();
+/// assert_eq!(Duration::from(period), Duration::from_millis(100));
+/// assert_eq!(period.to_string(), "100 msec");
+/// assert_eq!(period, TimeValue::new(100, TimeUnit::MILLI).unwrap());
+/// // values are compared as durations
+/// assert_eq!(period, "100000 usecs".parse().unwrap());
+/// assert!("2 sec".parse::<TimeValue>().unwrap() > period);
+///
+/// assert_eq!("2".parse::<TimeValue>(), Err("time unit required".into()));
+/// assert_eq!("2 fortnights".parse::<TimeValue>(), Err("unknown time unit 'fortnights'".into()));
+/// ```
+/// Strings are parsed like [try_parse_duration](crate::try_parse_duration).
+/// With the feature `serde`, values are written as such strings.
+#[derive(Copy, Clone, Debug)]
+pub struct TimeValue {
+    magnitude: u64,
+    unit: TimeUnit,
+    duration: Duration,
+}
+
+impl TimeValue {
+    /// Returns None if the value does not fit into a [Duration].
+    pub fn new(magnitude: u64, unit: TimeUnit) -> Option<Self> {
+        let duration = unit.checked_duration(magnitude)?;
+        Some(Self { magnitude, unit, duration })
+    }
+
+    pub fn magnitude(&self) -> u64 {
+        self.magnitude
+    }
+
+    pub fn unit(&self) -> TimeUnit {
+        self.unit
+    }
+
+    pub fn as_duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+impl FromStr for TimeValue {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (magnitude, unit) = parse_time_value(s)?;
+        Self::new(magnitude, unit).ok_or_else(|| "time value too large".to_string())
+    }
+}
+
+impl From<TimeValue> for Duration {
+    fn from(t: TimeValue) -> Self {
+        t.duration
+    }
+}
+
+impl Display for TimeValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.magnitude, self.unit.name())
+    }
+}
+
+impl PartialEq for TimeValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.duration == other.duration
+    }
+}
+
+impl Eq for TimeValue {}
+
+impl PartialOrd for TimeValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TimeValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.duration.cmp(&other.duration)
+    }
+}
+
+impl Hash for TimeValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.duration.hash(state)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for TimeValue {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for TimeValue {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
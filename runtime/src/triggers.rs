@@ -77,6 +77,7 @@ pub trait TriggerLike {
 
 /// The ID of a trigger component.
 #[derive(Eq, PartialEq, Copy, Clone, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(transparent))]
 pub struct TriggerId(TriggerIdImpl);
 
 // Historical note: in the past, TriggerId was a newtype over a GlobalId.
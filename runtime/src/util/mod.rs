@@ -23,6 +23,7 @@
  */
 
 use std::convert::TryFrom;
+use std::fmt::Write as _;
 use std::time::Duration;
 
 #[macro_export]
@@ -62,6 +63,77 @@ pub(crate) fn do_write<X>(
     write!(f, "{}", suffix)
 }
 
+/// Quote and escape a string as a JSON string literal.
+pub(crate) fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json += "\\\"",
+            '\\' => json += "\\\\",
+            c if (c as u32) < 0x20 => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Logs a message from a reaction, prefixed with the current
+/// tag and the path of the reaction (see [ReactionCtx::log_prefix](crate::ReactionCtx::log_prefix)).
+/// Messages go through the [log] facade, so the application
+/// picks the backend, eg `env_logger`, or `tracing` with the
+/// `tracing-log` bridge. The target is the module of the caller,
+/// so that messages can be filtered as usual. The level is one
+/// of [log::Level]:
+///
+/// ```no_run
+/// # use reactor_rt::prelude::*;
+/// # use reactor_rt::log_reaction;
+/// # let ctx: &mut ReactionCtx = panic!();
+/// log_reaction!(ctx, log::Level::Info, "received {}", 42);
+/// // the same
+/// log_info!(ctx, "received {}", 42);
+/// ```
+///
+/// The prefix is only formatted if the level is enabled.
+#[macro_export]
+macro_rules! log_reaction {
+    ($ctx:expr, $lvl:expr, $($arg:tt)+) => {
+        $crate::__log::log!($lvl, "{} {}", $ctx.log_prefix(), format_args!($($arg)+))
+    };
+}
+
+/// Logs a message from a reaction at level `Error`, see [log_reaction].
+#[macro_export]
+macro_rules! log_error {
+    ($ctx:expr, $($arg:tt)+) => { $crate::log_reaction!($ctx, $crate::__log::Level::Error, $($arg)+) };
+}
+
+/// Logs a message from a reaction at level `Warn`, see [log_reaction].
+#[macro_export]
+macro_rules! log_warn {
+    ($ctx:expr, $($arg:tt)+) => { $crate::log_reaction!($ctx, $crate::__log::Level::Warn, $($arg)+) };
+}
+
+/// Logs a message from a reaction at level `Info`, see [log_reaction].
+#[macro_export]
+macro_rules! log_info {
+    ($ctx:expr, $($arg:tt)+) => { $crate::log_reaction!($ctx, $crate::__log::Level::Info, $($arg)+) };
+}
+
+/// Logs a message from a reaction at level `Debug`, see [log_reaction].
+#[macro_export]
+macro_rules! log_debug {
+    ($ctx:expr, $($arg:tt)+) => { $crate::log_reaction!($ctx, $crate::__log::Level::Debug, $($arg)+) };
+}
+
+/// Logs a message from a reaction at level `Trace`, see [log_reaction].
+#[macro_export]
+macro_rules! log_trace {
+    ($ctx:expr, $($arg:tt)+) => { $crate::log_reaction!($ctx, $crate::__log::Level::Trace, $($arg)+) };
+}
+
 /// Shorthand for using [After](crate::Offset::After) together with [delay].
 ///
 /// ```
@@ -187,7 +259,7 @@ macro_rules! tag {
 }
 
 /// A unit of time, used in LF.
-#[derive(Debug)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub enum TimeUnit {
     NANO,
     MICRO,
@@ -218,6 +290,33 @@ impl TryFrom<&str> for TimeUnit {
 }
 
 impl TimeUnit {
+    /// The name of the unit in LF, eg `msec`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            TimeUnit::NANO => "nsec",
+            TimeUnit::MICRO => "usec",
+            TimeUnit::MILLI => "msec",
+            TimeUnit::SEC => "sec",
+            TimeUnit::MIN => "min",
+            TimeUnit::HOUR => "hour",
+            TimeUnit::DAY => "day",
+        }
+    }
+
+    /// Like [Self::to_duration], but returns None on overflow.
+    pub fn checked_duration(&self, magnitude: u64) -> Option<Duration> {
+        let secs = |factor: u64| magnitude.checked_mul(factor).map(Duration::from_secs);
+        match *self {
+            TimeUnit::NANO => Some(Duration::from_nanos(magnitude)),
+            TimeUnit::MICRO => Some(Duration::from_micros(magnitude)),
+            TimeUnit::MILLI => Some(Duration::from_millis(magnitude)),
+            TimeUnit::SEC => secs(1),
+            TimeUnit::MIN => secs(60),
+            TimeUnit::HOUR => secs(60 * 60),
+            TimeUnit::DAY => secs(60 * 60 * 24),
+        }
+    }
+
     pub fn to_duration(&self, magnitude: u64) -> Duration {
         match *self {
             TimeUnit::NANO => Duration::from_nanos(magnitude),
@@ -254,10 +353,17 @@ impl TimeUnit {
 /// assert_eq!(try_parse_duration(""), Err("cannot parse empty string".into()));
 /// assert_eq!(try_parse_duration("30"), Err("time unit required".into()));
 /// assert_eq!(try_parse_duration("30000000000000000000000ns"), Err("number too large to fit in target type".into()));
+/// assert_eq!(try_parse_duration("300000000000000000 days"), Err("time value too large".into()));
 ///
 /// ```
 ///
+/// See [TimeValue](crate::TimeValue) to keep the unit.
 pub fn try_parse_duration(t: &str) -> Result<Duration, String> {
+    t.parse::<crate::TimeValue>().map(Duration::from)
+}
+
+/// Parse a magnitude and a unit, see [try_parse_duration].
+pub(crate) fn parse_time_value(t: &str) -> Result<(u64, TimeUnit), String> {
     // note: we parse this manually to avoid depending on regex
     let mut chars = t.char_indices().skip_while(|(_, c)| c.is_numeric());
 
@@ -266,11 +372,10 @@ pub fn try_parse_duration(t: &str) -> Result<Duration, String> {
 
         let unit = t[*num_end..].trim();
 
-        let duration = match TimeUnit::try_from(unit) {
-            Ok(unit) => unit.to_duration(magnitude),
-            Err(_) => return Err(format!("unknown time unit '{}'", unit)),
-        };
-        Ok(duration)
+        match TimeUnit::try_from(unit) {
+            Ok(unit) => Ok((magnitude, unit)),
+            Err(_) => Err(format!("unknown time unit '{}'", unit)),
+        }
     } else if t != "0" {
         // no unit
         if !t.is_empty() {
@@ -279,6 +384,6 @@ pub fn try_parse_duration(t: &str) -> Result<Duration, String> {
             Err("cannot parse empty string".into())
         }
     } else {
-        Ok(Duration::from_secs(0))
+        Ok((0, TimeUnit::SEC))
     }
 }
@@ -0,0 +1,105 @@
+use std::sync::{Arc, Mutex};
+use std::thread::Thread;
+
+use crate::assembly::{TriggerId, TriggerLike};
+use crate::*;
+
+/// A timeout in physical time, which triggers the reactions
+/// that declare it as a trigger unless it is restarted or
+/// stopped in time. A reaction arms it with [ReactionCtx::start_watchdog],
+/// eg each time a heartbeat is received, and a handler reaction
+/// then runs if no heartbeat was received for the given timeout.
+///
+/// Watchdogs are created at assembly time with
+/// [ComponentCreator::new_watchdog](crate::assembly::ComponentCreator::new_watchdog).
+/// While it is armed, a watchdog uses a background thread that
+/// waits for its deadline, and that sends an asynchronous event
+/// to the scheduler when it expires, like a physical action.
+/// The handler is therefore executed at the physical time of
+/// the expiry. If the watchdog is restarted while the event of
+/// an expiry is already sent, the handler still runs.
+/// Like for physical actions, the scheduler waits for the
+/// expiry while the watchdog is armed, see
+/// [SchedulerOptions::keep_alive](crate::SchedulerOptions::keep_alive).
+#[derive(Clone)]
+pub struct Watchdog {
+    action: PhysicalActionRef<()>,
+    state: Arc<Mutex<WatchdogState>>,
+}
+
+#[derive(Default)]
+struct WatchdogState {
+    /// Deadline of the watchdog if it is armed.
+    deadline: Option<Instant>,
+    /// The thread that waits for the deadline, if it is running.
+    thread: Option<Thread>,
+}
+
+impl Watchdog {
+    pub(crate) fn new(action: PhysicalActionRef<()>) -> Self {
+        Self { action, state: Default::default() }
+    }
+
+    /// Arm the watchdog, or move its deadline if it is armed.
+    pub(crate) fn start(&self, ctx: &mut ReactionCtx, timeout: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.deadline = Some(Instant::now() + timeout);
+        match &state.thread {
+            // wake it up so that it sees the new deadline
+            Some(thread) => thread.unpark(),
+            None => {
+                let this = self.clone();
+                let handle = ctx.spawn_physical_thread(move |link| this.wait_for_deadline(link));
+                state.thread = Some(handle.thread().clone());
+            }
+        }
+    }
+
+    /// Disarm the watchdog.
+    pub(crate) fn stop(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.deadline = None;
+        if let Some(thread) = &state.thread {
+            thread.unpark();
+        }
+    }
+
+    /// Body of the background thread. It stops once the
+    /// watchdog has expired or was stopped, so that it does
+    /// not keep the program alive.
+    fn wait_for_deadline(&self, link: &mut AsyncCtx) {
+        loop {
+            let mut state = self.state.lock().unwrap();
+            let deadline = match state.deadline {
+                Some(deadline) if !link.was_terminated() => deadline,
+                _ => {
+                    state.thread = None;
+                    return;
+                }
+            };
+            let now = Instant::now();
+            if now >= deadline {
+                state.deadline = None;
+                state.thread = None;
+                drop(state);
+                trace!("Watchdog {:?} expired", self.get_id());
+                // this fails if the scheduler is shutting down
+                let _ = link.schedule_physical(&self.action, Offset::Asap);
+                return;
+            }
+            drop(state);
+            // start and stop unpark this thread, as does the scheduler on shutdown
+            std::thread::park_timeout(deadline - now);
+        }
+    }
+
+    pub(crate) fn action_mut(&mut self) -> &mut PhysicalActionRef<()> {
+        &mut self.action
+    }
+}
+
+impl TriggerLike for Watchdog {
+    fn get_id(&self) -> TriggerId {
+        self.action.get_id()
+    }
+}